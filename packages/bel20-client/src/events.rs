@@ -0,0 +1,179 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::{Stream, StreamExt};
+use serde::Deserialize;
+
+use crate::{types::History, Error};
+
+/// Event of `POST /events` and `/address/{address}/events/stream`
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamEvent {
+    History(History),
+    NewBlock { height: u32, proof: String, blockhash: String },
+    /// Events above `new_height - 1` received before are rolled back
+    Reorg { blocks_count: u32, new_height: u32 },
+    TokenCompleted { tick: String, height: u32 },
+}
+
+/// Schema of the event payloads requested by the client, v1 is deprecated
+pub(crate) const EVENT_SCHEMA_HEADER: &str = "X-Event-Schema";
+pub(crate) const EVENT_SCHEMA: &str = "v2";
+
+/// Events other than token ones are tagged with `event_type`, token ones too since v2
+#[derive(Deserialize)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+enum ServiceEvent {
+    TokenEvent(History),
+    NewBlock { height: u32, proof: String, blockhash: String },
+    Reorg { blocks_count: u32, new_height: u32 },
+    TokenCompleted { tick: String, height: u32 },
+}
+
+impl StreamEvent {
+    fn parse(data: &str) -> Result<Self, Error> {
+        let value: serde_json::Value = serde_json::from_str(data)?;
+
+        if value.get("event_type").is_none() {
+            return Ok(Self::History(serde_json::from_value(value)?));
+        }
+
+        Ok(match serde_json::from_value(value)? {
+            ServiceEvent::TokenEvent(history) => Self::History(history),
+            ServiceEvent::NewBlock { height, proof, blockhash } => Self::NewBlock { height, proof, blockhash },
+            ServiceEvent::Reorg { blocks_count, new_height } => Self::Reorg { blocks_count, new_height },
+            ServiceEvent::TokenCompleted { tick, height } => Self::TokenCompleted { tick, height },
+        })
+    }
+}
+
+/// Server-sent events of a subscription, ends when the server closes the connection
+pub struct EventStream {
+    inner: Pin<Box<dyn Stream<Item = Result<StreamEvent, Error>> + Send>>,
+}
+
+impl EventStream {
+    pub(crate) fn new<B: AsRef<[u8]>>(bytes: impl Stream<Item = reqwest::Result<B>> + Send + 'static) -> Self {
+        let state = (Box::pin(bytes), SseParser::default(), VecDeque::<String>::new());
+
+        let inner = futures_util::stream::unfold(state, |(mut bytes, mut parser, mut ready)| async move {
+            loop {
+                if let Some(data) = ready.pop_front() {
+                    let event = StreamEvent::parse(&data);
+                    return Some((event, (bytes, parser, ready)));
+                }
+
+                match bytes.next().await? {
+                    Ok(chunk) => ready.extend(parser.feed(chunk.as_ref())),
+                    Err(e) => return Some((Err(e.into()), (bytes, parser, ready))),
+                }
+            }
+        });
+
+        Self { inner: Box::pin(inner) }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<StreamEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+/// Splits the `text/event-stream` body into the data of its events, chunks may end anywhere
+#[derive(Default)]
+struct SseParser {
+    line: Vec<u8>,
+    data: Option<String>,
+}
+
+impl SseParser {
+    fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut events = vec![];
+
+        for byte in chunk {
+            if *byte != b'\n' {
+                self.line.push(*byte);
+                continue;
+            }
+
+            let line = std::mem::take(&mut self.line);
+            let line = String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(&line)).into_owned();
+
+            if line.is_empty() {
+                events.extend(self.data.take());
+                continue;
+            }
+
+            // Other fields and comments (lines starting with `:`) aren't used by the server
+            if let Some(value) = line.strip_prefix("data:") {
+                let value = value.strip_prefix(' ').unwrap_or(value);
+                match self.data.as_mut() {
+                    Some(data) => {
+                        data.push('\n');
+                        data.push_str(value);
+                    }
+                    None => self.data = Some(value.to_string()),
+                }
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_split_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.feed(b"data: {\"a\"").is_empty());
+        assert!(parser.feed(b":1}\n").is_empty());
+        assert_eq!(parser.feed(b"\ndata: 2\n\n"), vec!["{\"a\":1}".to_string(), "2".to_string()]);
+    }
+
+    #[test]
+    fn sse_multiline_and_comments() {
+        let mut parser = SseParser::default();
+        let events = parser.feed(b": keep-alive\r\n\r\nevent: x\r\ndata:1\r\ndata: 2\r\n\r\n");
+        assert_eq!(events, vec!["1\n2".to_string()]);
+    }
+
+    #[test]
+    fn parse_events() {
+        let history = r#"{"id":1,"address":"addr","tick":"abcd","height":10,"type":"Mint","amt":"1000","txid":"00","vout":0}"#;
+        assert_eq!(
+            StreamEvent::parse(history).unwrap(),
+            StreamEvent::History(History {
+                id: 1,
+                address: "addr".to_string(),
+                tick: "abcd".to_string(),
+                height: 10,
+                action: crate::types::TokenAction::Mint {
+                    amt: "1000".to_string(),
+                    txid: "00".to_string(),
+                    vout: 0,
+                },
+                origin: None,
+            })
+        );
+
+        let reorg = r#"{"event_type":"reorg","blocks_count":2,"new_height":9}"#;
+        assert_eq!(StreamEvent::parse(reorg).unwrap(), StreamEvent::Reorg { blocks_count: 2, new_height: 9 });
+
+        let v2 = r#"{"schema":"v2","event_type":"token_event","id":1,"address":"addr","tick":"abcd","height":10,"type":"Mint","amt":"1000","txid":"00","vout":0}"#;
+        assert_eq!(StreamEvent::parse(v2).unwrap(), StreamEvent::parse(history).unwrap());
+
+        let reorg_v2 = r#"{"schema":"v2","event_type":"reorg","blocks_count":2,"new_height":9}"#;
+        assert_eq!(StreamEvent::parse(reorg_v2).unwrap(), StreamEvent::Reorg { blocks_count: 2, new_height: 9 });
+
+        assert!(StreamEvent::parse(r#"{"event_type":"unknown"}"#).is_err());
+    }
+}
@@ -0,0 +1,477 @@
+//! Typed async client of the BEL-20 indexer REST API (`/v1`) and its event streams
+
+mod events;
+pub mod types;
+
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub use events::{EventStream, StreamEvent};
+use events::{EVENT_SCHEMA, EVENT_SCHEMA_HEADER};
+use types::*;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// Non-2xx response, `message` is the body returned by the indexer
+    #[error("{status}: {message}")]
+    Api { status: StatusCode, message: String },
+    #[error("Invalid event: {0}")]
+    Event(#[from] serde_json::Error),
+    /// Row of an NDJSON response which can't be parsed
+    #[error("Invalid row: {0}")]
+    Row(serde_json::Error),
+}
+
+impl Error {
+    /// The request hit a rollback or the node isn't synced, it may be retried
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Api { status, .. } if *status == StatusCode::SERVICE_UNAVAILABLE)
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::Api { status, .. } if *status == StatusCode::NOT_FOUND)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+const ADMIN_OPERATOR_HEADER: &str = "X-Admin-Operator";
+const NEXT_AFTER_HEADER: &str = "X-Next-After";
+const TOTAL_ESTIMATE_HEADER: &str = "X-Total-Estimate";
+
+/// Owner of balances and history, the address routes and the Electrum-style script hash routes return the same data
+#[derive(Clone, Copy, Debug)]
+pub enum Owner<'a> {
+    Address(&'a str),
+    /// sha256 of the script_pubkey in reversed byte order
+    ScriptHash(&'a str),
+}
+
+impl Owner<'_> {
+    fn path(&self) -> String {
+        match self {
+            Owner::Address(address) => format!("/address/{address}"),
+            Owner::ScriptHash(hash) => format!("/scripthash/{hash}"),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Owner<'a> {
+    fn from(value: &'a str) -> Self {
+        Owner::Address(value)
+    }
+}
+
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    /// Without the trailing slash, e.g. `http://localhost:8000/v1`
+    base_url: String,
+    admin_token: Option<String>,
+    admin_operator: Option<String>,
+}
+
+impl Client {
+    /// `base_url` is the root of the indexer, e.g. `http://localhost:8000`
+    pub fn new(base_url: &str) -> Self {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    pub fn with_http_client(base_url: &str, http: reqwest::Client) -> Self {
+        Self {
+            http,
+            base_url: format!("{}/v1", base_url.trim_end_matches('/')),
+            admin_token: None,
+            admin_operator: None,
+        }
+    }
+
+    /// Token sent as `Authorization: Bearer` to the admin routes
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into());
+        self
+    }
+
+    /// Name recorded in the audit log of the admin requests, sent as `X-Admin-Operator`
+    pub fn with_admin_operator(mut self, operator: impl Into<String>) -> Self {
+        self.admin_operator = Some(operator.into());
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http.request(method, format!("{}{path}", self.base_url))
+    }
+
+    fn admin_request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut request = self.request(method, path);
+        if let Some(token) = &self.admin_token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(operator) = &self.admin_operator {
+            request = request.header(ADMIN_OPERATOR_HEADER, operator);
+        }
+
+        request
+    }
+
+    async fn send(request: RequestBuilder) -> Result<reqwest::Response> {
+        let response = request.send().await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(Error::Api { status, message });
+        }
+
+        Ok(response)
+    }
+
+    async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+        Ok(Self::send(request).await?.json().await?)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &(impl Serialize + ?Sized)) -> Result<T> {
+        Self::json(self.request(Method::GET, path).query(query)).await
+    }
+
+    // Address
+
+    /// Balances of the owner without transfers
+    pub async fn address_tokens(&self, owner: Owner<'_>, args: &AddressTokensArgs) -> Result<Vec<TokenBalance>> {
+        self.get(&format!("{}/tokens", owner.path()), args).await
+    }
+
+    pub async fn address_tokens_tick(&self, owner: Owner<'_>, args: &AddressTokensArgs) -> Result<Vec<String>> {
+        self.get(&format!("{}/tokens-tick", owner.path()), args).await
+    }
+
+    pub async fn address_history(&self, owner: Owner<'_>, args: &AddressHistoryArgs) -> Result<AddressHistoryPage> {
+        self.get(&format!("{}/history", owner.path()), args).await
+    }
+
+    pub async fn address_deltas(&self, owner: Owner<'_>, args: &AddressDeltasArgs) -> Result<Vec<BlockDeltas>> {
+        self.get(&format!("{}/deltas", owner.path()), args).await
+    }
+
+    /// Inscriptions of the owner which ended up in the fees, newest block first
+    pub async fn address_leaked(&self, owner: Owner<'_>, args: &LeakedArgs) -> Result<Vec<LeakedInscription>> {
+        self.get(&format!("{}/leaked", owner.path()), args).await
+    }
+
+    /// Balance of one token with a page of its transfers
+    pub async fn address_token_balance(&self, owner: Owner<'_>, tick: &str, args: &TokenBalanceArgs) -> Result<TokenBalance> {
+        self.get(&format!("{}/{tick}/balance", owner.path()), args).await
+    }
+
+    /// Balance after each block where it changed, oldest first
+    pub async fn address_token_balance_history(&self, owner: Owner<'_>, tick: &str, args: &BalanceHistoryArgs) -> Result<Vec<BalanceChange>> {
+        self.get(&format!("{}/{tick}/balance-history", owner.path()), args).await
+    }
+
+    pub async fn address_token_balance_proof(&self, owner: Owner<'_>, tick: &str, height: Option<u32>) -> Result<BalanceProof> {
+        self.get(&format!("{}/{tick}/balance-proof", owner.path()), &[("height", height)]).await
+    }
+
+    /// Outpoint in the `txid:vout` format
+    pub async fn outpoint(&self, outpoint: &str) -> Result<OutPointInfo> {
+        self.get(&format!("/outpoint/{outpoint}"), &()).await
+    }
+
+    /// Page of every known script hash with its address, `after` is `next` of the previous page
+    pub async fn addresses_export(&self, after: Option<&str>, limit: Option<usize>) -> Result<AddressesExportPage> {
+        #[derive(Serialize)]
+        struct Args<'a> {
+            after: Option<&'a str>,
+            limit: Option<usize>,
+        }
+
+        let response = Self::send(self.request(Method::GET, "/addresses/export").query(&Args { after, limit })).await?;
+
+        let header = |name| response.headers().get(name).and_then(|x| x.to_str().ok()).map(str::to_owned);
+        let next = header(NEXT_AFTER_HEADER);
+        let total_estimate = header(TOTAL_ESTIMATE_HEADER).and_then(|x| x.parse().ok()).unwrap_or_default();
+
+        let addresses = response
+            .text()
+            .await?
+            .lines()
+            .filter(|x| !x.is_empty())
+            .map(serde_json::from_str)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(Error::Row)?;
+
+        Ok(AddressesExportPage {
+            addresses,
+            next,
+            total_estimate,
+        })
+    }
+
+    // Token
+
+    pub async fn tokens(&self, args: &TokensArgs) -> Result<TokensResult> {
+        self.get("/tokens", args).await
+    }
+
+    pub async fn trending_tokens(&self, window: TrendingWindow, limit: Option<usize>) -> Result<Vec<TrendingToken>> {
+        #[derive(Serialize)]
+        struct Args {
+            window: TrendingWindow,
+            limit: Option<usize>,
+        }
+
+        self.get("/tokens/trending", &Args { window, limit }).await
+    }
+
+    pub async fn token(&self, tick: &str) -> Result<Token> {
+        self.get("/token", &[("tick", tick)]).await
+    }
+
+    pub async fn deployer_tokens(&self, address: &str) -> Result<DeployerTokens> {
+        self.get(&format!("/deployer/{address}"), &()).await
+    }
+
+    /// Supplies in the order of the ticks, fails if any of them isn't deployed
+    pub async fn token_supplies(&self, ticks: &[&str]) -> Result<Vec<Amount>> {
+        Self::json(self.request(Method::POST, "/token-supplies").json(ticks)).await
+    }
+
+    /// Transfers of the address at the outpoint, fails with 503 while the indexer is behind the node
+    pub async fn token_transfer_proof(&self, address: &str, outpoint: &str) -> Result<Vec<TokenTransferProof>> {
+        self.get(&format!("/token/proof/{address}/{outpoint}"), &()).await
+    }
+
+    pub async fn token_events(&self, tick: &str, args: &TokenEventsArgs) -> Result<Vec<AddressHistory>> {
+        self.get(&format!("/token-events/{tick}"), args).await
+    }
+
+    pub async fn token_stats(&self, tick: &str) -> Result<TokenStats> {
+        self.get(&format!("/token/{tick}/stats"), &()).await
+    }
+
+    /// Token, stats, top 10 holders and last 20 events at once
+    pub async fn token_overview(&self, tick: &str) -> Result<TokenOverview> {
+        self.get(&format!("/token/{tick}/overview"), &()).await
+    }
+
+    /// Mints of the token in the blocks from `from` to `to` (inclusive) with the `top` addresses which received the most of them
+    pub async fn mint_activity(&self, tick: &str, from: u32, to: u32, top: Option<usize>) -> Result<MintActivity> {
+        let mut query = vec![("from", from.to_string()), ("to", to.to_string())];
+        query.extend(top.map(|top| ("top", top.to_string())));
+
+        self.get(&format!("/token/{tick}/mint-activity"), &query).await
+    }
+
+    pub async fn holders(&self, args: &HoldersArgs) -> Result<Holders> {
+        self.get("/holders", args).await
+    }
+
+    /// Percent of the supply held by the top 100, next 100, next 300, next 500 holders and the rest
+    pub async fn holders_shares(&self, tick: &str) -> Result<Vec<Amount>> {
+        self.get("/holders-stats", &[("tick", tick)]).await
+    }
+
+    pub async fn holders_distribution(&self, tick: &str) -> Result<HoldersDistribution> {
+        self.get("/holders-stats", &[("tick", tick), ("distribution", "true")]).await
+    }
+
+    // Events
+
+    pub async fn events_by_height(&self, height: u32) -> Result<Vec<History>> {
+        self.get(&format!("/events/{height}"), &()).await
+    }
+
+    pub async fn txid_events(&self, txid: &str) -> Result<Vec<History>> {
+        self.get(&format!("/txid/{txid}"), &()).await
+    }
+
+    pub async fn txid_batch_summary(&self, txid: &str) -> Result<BatchSummary> {
+        self.get(&format!("/txid/{txid}/batch-summary"), &()).await
+    }
+
+    /// Events matching the subscription from the moment it's accepted, the stream doesn't reconnect
+    pub async fn subscribe(&self, subscription: &Subscription) -> Result<EventStream> {
+        let response = Self::send(self.request(Method::POST, "/events").header(EVENT_SCHEMA_HEADER, EVENT_SCHEMA).json(subscription)).await?;
+        Ok(EventStream::new(response.bytes_stream()))
+    }
+
+    /// Token events of the address and reorgs
+    pub async fn address_events(&self, address: &str) -> Result<EventStream> {
+        let request = self.request(Method::GET, &format!("/address/{address}/events/stream")).header(EVENT_SCHEMA_HEADER, EVENT_SCHEMA);
+        let response = Self::send(request).await?;
+        Ok(EventStream::new(response.bytes_stream()))
+    }
+
+    // Transactions
+
+    pub async fn broadcast(&self, args: &BroadcastArgs) -> Result<BroadcastResult> {
+        Self::json(self.request(Method::POST, "/tx/broadcast").json(args)).await
+    }
+
+    /// Transactions sending the amount from the address, reusing a transfer inscription with exactly the amount if there is one
+    pub async fn estimate_transfer(&self, args: &EstimateTransferArgs) -> Result<TransferEstimate> {
+        self.get("/estimate/transfer", args).await
+    }
+
+    pub async fn validate_inscription(&self, args: &ValidateInscriptionArgs) -> Result<InscriptionValidation> {
+        Self::json(self.request(Method::POST, "/validate/inscription").json(args)).await
+    }
+
+    // Status
+
+    pub async fn status(&self) -> Result<Status> {
+        self.get("/status", &()).await
+    }
+
+    /// Blocks from the newest one
+    pub async fn proof_of_history(&self, args: &ProofHistoryArgs) -> Result<Vec<ProofOfHistory>> {
+        self.get("/proof-of-history", args).await
+    }
+
+    pub async fn proof_of_history_spec(&self) -> Result<ProofOfHistorySpec> {
+        self.get("/proof-of-history/spec", &()).await
+    }
+
+    /// Only heights at multiples of `STATE_DIGEST_INTERVAL` of the indexer have digests
+    pub async fn state_digest(&self, height: u32) -> Result<StateDigest> {
+        self.get(&format!("/state-digest/{height}"), &()).await
+    }
+
+    // Stats
+
+    /// Block by height or hash
+    pub async fn block(&self, block: &str) -> Result<BlockDetails> {
+        self.get(&format!("/block/{block}"), &()).await
+    }
+
+    pub async fn block_inscription_stats(&self, height: u32) -> Result<InscriptionStats> {
+        self.get(&format!("/block/{height}/inscription-stats"), &()).await
+    }
+
+    pub async fn block_leaked(&self, height: u32) -> Result<Vec<LeakedInscription>> {
+        self.get(&format!("/block/{height}/leaked"), &()).await
+    }
+
+    pub async fn inscription_stats(&self, from: u32, to: u32) -> Result<InscriptionStats> {
+        self.get("/stats/inscriptions", &[("from", from), ("to", to)]).await
+    }
+
+    pub async fn fee_stats(&self, from: u32, to: u32) -> Result<FeeStats> {
+        self.get("/stats/fees", &[("from", from), ("to", to)]).await
+    }
+
+    // Inscription
+
+    /// Requires `INSCRIPTION_HISTORY` on the indexer
+    pub async fn inscription_history(&self, id: &str, args: &InscriptionHistoryArgs) -> Result<Vec<InscriptionMove>> {
+        self.get(&format!("/inscription/{id}/history"), args).await
+    }
+
+    pub async fn inscription_curses(&self, id: &str) -> Result<InscriptionCurses> {
+        self.get(&format!("/inscription/{id}/curses"), &()).await
+    }
+
+    /// Requires `INSCRIPTION_HISTORY` on the indexer
+    pub async fn inscription_location(&self, id: &str) -> Result<InscriptionLocation> {
+        self.get(&format!("/inscription/{id}/location"), &()).await
+    }
+
+    /// Locations in the order of the ids, at most 1000 at once. Requires `INSCRIPTION_HISTORY` on the indexer
+    pub async fn inscription_locations(&self, ids: &[&str]) -> Result<Vec<InscriptionLocation>> {
+        Self::json(self.request(Method::POST, "/inscriptions/locations").json(ids)).await
+    }
+
+    // Debug
+
+    pub async fn tx_trace(&self, txid: &str, height: Option<u32>) -> Result<TxTrace> {
+        self.get(&format!("/debug/tx/{txid}/trace"), &[("height", height)]).await
+    }
+
+    // Admin, requires `with_admin_token`
+
+    pub async fn set_token_extras(&self, tick: &str, extras: &serde_json::Value) -> Result<()> {
+        Self::send(self.admin_request(Method::PUT, &format!("/admin/token/{tick}/extras")).json(extras)).await?;
+        Ok(())
+    }
+
+    pub async fn remove_token_extras(&self, tick: &str) -> Result<()> {
+        Self::send(self.admin_request(Method::DELETE, &format!("/admin/token/{tick}/extras"))).await?;
+        Ok(())
+    }
+
+    pub async fn partials_stats(&self) -> Result<PartialsStats> {
+        Self::json(self.admin_request(Method::GET, "/admin/partials-stats")).await
+    }
+
+    pub async fn webhooks(&self) -> Result<Vec<Webhook>> {
+        Self::json(self.admin_request(Method::GET, "/admin/webhooks")).await
+    }
+
+    pub async fn create_webhook(&self, args: &WebhookArgs) -> Result<WebhookCreated> {
+        Self::json(self.admin_request(Method::POST, "/admin/webhooks").json(args)).await
+    }
+
+    pub async fn remove_webhook(&self, id: u64) -> Result<()> {
+        Self::send(self.admin_request(Method::DELETE, &format!("/admin/webhooks/{id}"))).await?;
+        Ok(())
+    }
+
+    /// Queues the simulation, [`Self::reorg_simulation`] returns the report once the indexer runs it
+    pub async fn simulate_reorg(&self, depth: u32) -> Result<()> {
+        Self::send(self.admin_request(Method::POST, "/admin/simulate-reorg").query(&[("depth", depth)])).await?;
+        Ok(())
+    }
+
+    pub async fn reorg_simulation(&self) -> Result<ReorgSimulation> {
+        Self::json(self.admin_request(Method::GET, "/admin/simulate-reorg")).await
+    }
+
+    /// Waits until the indexer gets to it, which is before its next block
+    pub async fn reprocess_block(&self, height: u32) -> Result<BlockReprocess> {
+        Self::json(self.admin_request(Method::POST, &format!("/admin/reprocess/{height}"))).await
+    }
+
+    /// Queues the backup, it's listed by [`Self::backups`] once the indexer creates it
+    pub async fn create_backup(&self) -> Result<()> {
+        Self::send(self.admin_request(Method::POST, "/admin/backups")).await?;
+        Ok(())
+    }
+
+    /// Newest first
+    pub async fn backups(&self) -> Result<Vec<Backup>> {
+        Self::json(self.admin_request(Method::GET, "/admin/backups")).await
+    }
+
+    pub async fn remove_backup(&self, name: &str) -> Result<()> {
+        Self::send(self.admin_request(Method::DELETE, &format!("/admin/backups/{name}"))).await?;
+        Ok(())
+    }
+
+    /// Removes all local backups but the newest `keep`
+    pub async fn prune_backups(&self, keep: usize) -> Result<PrunedBackups> {
+        Self::json(self.admin_request(Method::POST, "/admin/backups/prune").query(&[("keep", keep)])).await
+    }
+
+    /// Newest entries first
+    pub async fn audit_log(&self, args: &AuditLogArgs) -> Result<Vec<AdminAuditEntry>> {
+        Self::json(self.admin_request(Method::GET, "/admin/audit-log").query(args)).await
+    }
+
+    /// Queues a supply audit, fails with `409 Conflict` while one is pending or running
+    pub async fn request_supply_audit(&self) -> Result<()> {
+        Self::send(self.admin_request(Method::POST, "/admin/supply-audit")).await?;
+        Ok(())
+    }
+
+    /// Newest reports first
+    pub async fn supply_audits(&self, args: &SupplyAuditArgs) -> Result<Vec<SupplyAudit>> {
+        Self::json(self.admin_request(Method::GET, "/admin/supply-audit").query(args)).await
+    }
+
+    /// Deploys blocked by the tick policy of the indexer, newest first
+    pub async fn rejected_deploys(&self, args: &RejectedDeploysArgs) -> Result<Vec<RejectedDeploy>> {
+        Self::json(self.admin_request(Method::GET, "/admin/rejected-deploys").query(args)).await
+    }
+}
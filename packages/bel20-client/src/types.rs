@@ -0,0 +1,939 @@
+//! Requests and responses of the `/v1` API, field docs are in the OpenAPI definitions served at `/v1/docs`
+
+use serde::{Deserialize, Serialize};
+
+/// Decimal amount as returned by the API, e.g. `"1000.5"`, kept as a string so no precision is lost
+pub type Amount = String;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Status {
+    pub height: u32,
+    pub proof: String,
+    pub blockhash: String,
+    pub version: String,
+    pub uptime_secs: u64,
+    pub token_cache: Option<TokenCacheStats>,
+    #[serde(default)]
+    pub parser_limits: ParserLimits,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct ParserLimits {
+    pub max_partial_parts: Option<usize>,
+    pub max_inscription_bytes: Option<usize>,
+    pub max_inscriptions_per_tx: Option<usize>,
+    pub max_envelopes_per_input: Option<usize>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenCacheStats {
+    pub accounts: usize,
+    pub capacity: usize,
+    pub memory_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64,
+}
+
+/// Value of the `type` field of token events
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenEventType {
+    Deploy,
+    Mint,
+    DeployTransfer,
+    Send,
+    Receive,
+    SendReceive,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum TokenAction {
+    Deploy { max: Amount, lim: Amount, dec: u8, txid: String, vout: u32 },
+    Mint { amt: Amount, txid: String, vout: u32 },
+    DeployTransfer { amt: Amount, txid: String, vout: u32 },
+    Send { amt: Amount, recipient: String, txid: String, vout: u32 },
+    Receive { amt: Amount, sender: String, txid: String, vout: u32 },
+    SendReceive { amt: Amount, txid: String, vout: u32 },
+    /// Roll-up of compacted events, only returned by the address history
+    Checkpoint {
+        from_height: u32,
+        to_height: u32,
+        events: u64,
+        balance: Amount,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct History {
+    pub id: u64,
+    pub address: String,
+    pub tick: String,
+    pub height: u32,
+    #[serde(flatten)]
+    pub action: TokenAction,
+    /// Set by the history routes for events indexed since origins are stored
+    #[serde(default)]
+    pub origin: Option<ActionOrigin>,
+}
+
+/// How the inscription behind a token event got to its owner
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionOrigin {
+    Normal,
+    Pointer,
+    Vindicated,
+    LeakedReturn,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AddressHistory {
+    #[serde(flatten)]
+    pub history: History,
+    /// Median time past of the block, never decreases
+    pub created: u32,
+    /// Header timestamp of the block
+    pub block_time: u32,
+    pub batch_id: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AddressHistoryPage {
+    pub total: u64,
+    pub first_height: Option<u32>,
+    pub last_height: Option<u32>,
+    pub history: Vec<AddressHistory>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AddressHistoryArgs {
+    pub tick: String,
+    /// Event id of the last item from the previous page
+    pub offset: Option<u64>,
+    pub limit: Option<usize>,
+    pub from_ts: Option<u32>,
+    pub to_ts: Option<u32>,
+}
+
+impl AddressHistoryArgs {
+    pub fn new(tick: impl Into<String>) -> Self {
+        Self {
+            tick: tick.into(),
+            offset: None,
+            limit: None,
+            from_ts: None,
+            to_ts: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ExportedAddress {
+    /// sha256 of the script_pubkey in reversed byte order
+    pub scripthash: String,
+    pub address: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct AddressesExportPage {
+    pub addresses: Vec<ExportedAddress>,
+    /// Cursor of the next page, `None` on the last one
+    pub next: Option<String>,
+    /// Number of addresses estimated by the indexer
+    pub total_estimate: u64,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct AddressTokensArgs {
+    /// Tick of the last item from the previous page
+    pub offset: Option<String>,
+    pub limit: Option<usize>,
+    pub search: Option<String>,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct TokenBalanceArgs {
+    /// Outpoint of the last transfer from the previous page
+    pub offset: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct BalanceHistoryArgs {
+    /// Height of the last change from the previous page
+    pub after_height: Option<u32>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct AddressDeltasArgs {
+    pub from_height: u32,
+    pub to_height: Option<u32>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenTransfer {
+    pub outpoint: String,
+    pub amount: Amount,
+    #[serde(default)]
+    pub spent_in_mempool: Option<bool>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenBalance {
+    pub tick: String,
+    pub balance: Amount,
+    pub transferable_balance: Amount,
+    pub transfers_count: u64,
+    #[serde(default)]
+    pub transfers: Vec<TokenTransfer>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BalanceProof {
+    pub height: u32,
+    pub tick: String,
+    pub script_hash: String,
+    pub balance: Amount,
+    pub transferable_balance: Amount,
+    pub index: usize,
+    pub leaf: String,
+    pub path: Vec<BalanceProofStep>,
+    pub balance_root: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BalanceProofStep {
+    pub hash: String,
+    pub left: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BalanceChange {
+    pub height: u32,
+    /// Median time past of the block
+    pub created: u32,
+    pub block_time: u32,
+    /// Total (available + transferable) balance after the block
+    pub balance: Amount,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BlockDeltas {
+    pub height: u32,
+    /// Median time past of the block
+    pub created: u32,
+    pub block_time: u32,
+    pub deltas: Vec<TokenDelta>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenDelta {
+    pub tick: String,
+    pub received: Amount,
+    pub sent: Amount,
+    /// Signed decimal, e.g. `"-10"`
+    pub delta: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct OutPointInfo {
+    pub value: u64,
+    pub script_hash: String,
+    pub address: String,
+    pub inscription_offsets: Vec<u64>,
+    pub transfers: Vec<OutPointTransfer>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct OutPointTransfer {
+    pub offset: u64,
+    pub tick: String,
+    pub amt: Amount,
+    pub height: u32,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Default)]
+pub enum TokenSortBy {
+    DeployTimeAsc,
+    DeployTimeDesc,
+    HoldersAsc,
+    HoldersDesc,
+    TransactionsAsc,
+    #[default]
+    TransactionsDesc,
+    CompletionAsc,
+    CompletionDesc,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Default)]
+pub enum TokenFilterBy {
+    #[default]
+    All,
+    Completed,
+    InProgress,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct TokensArgs {
+    pub page_size: Option<usize>,
+    pub page: Option<usize>,
+    pub sort_by: Option<TokenSortBy>,
+    pub filter_by: Option<TokenFilterBy>,
+    pub search: Option<String>,
+    pub include_lookalikes: Option<bool>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Token {
+    pub height: u32,
+    pub created: u32,
+    pub tick: String,
+    /// Inscription id of the deploy, `<txid>i<index>`
+    pub genesis: String,
+    pub deployer: String,
+    pub transactions: u32,
+    pub mint_count: u64,
+    pub holders: u32,
+    pub supply: Amount,
+    pub mint_percent: String,
+    pub completed: bool,
+    pub completed_height: Option<u32>,
+    #[serde(default)]
+    pub first_mint: Option<FirstMint>,
+    #[serde(default)]
+    pub minted_out_in_blocks: Option<u32>,
+    pub max: Amount,
+    pub lim: Amount,
+    pub dec: u8,
+    #[serde(default)]
+    pub extras: Option<serde_json::Value>,
+    #[serde(default)]
+    pub lookalikes: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FirstMint {
+    pub height: u32,
+    pub txid: String,
+    /// Recipient of the mint
+    pub address: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokensResult {
+    pub pages: usize,
+    pub count: usize,
+    pub tokens: Vec<Token>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DeployerTokens {
+    pub deployer: String,
+    pub count: usize,
+    pub completed: usize,
+    pub holders: u64,
+    pub tokens: Vec<Token>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Default)]
+pub enum TrendingWindow {
+    #[serde(rename = "1h")]
+    Hour,
+    #[default]
+    #[serde(rename = "24h")]
+    Day,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TrendingToken {
+    pub tick: String,
+    pub queries: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenTransferProof {
+    pub amt: Amount,
+    pub tick: String,
+    pub height: u32,
+    pub tip_height: u32,
+    pub valid_as_of: String,
+    #[serde(default)]
+    pub spent_in_mempool: Option<bool>,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct TokenEventsArgs {
+    /// Event id of the last item from the previous page
+    pub offset: Option<u64>,
+    pub limit: Option<usize>,
+    /// Txid or outpoint
+    pub search: Option<String>,
+    pub from_ts: Option<u32>,
+    pub to_ts: Option<u32>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenStats {
+    pub tick: String,
+    pub mint_count: u64,
+    pub mint_fees: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenOverview {
+    pub token: Token,
+    pub stats: TokenStats,
+    pub holders: Holders,
+    pub events: Vec<AddressHistory>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MintActivity {
+    pub tick: String,
+    pub mints: u64,
+    pub amount: Amount,
+    pub minters: u64,
+    pub blocks: Vec<MintActivityBlock>,
+    pub top_minters: Vec<Minter>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MintActivityBlock {
+    pub height: u32,
+    pub mints: u32,
+    pub amount: Amount,
+    pub minters: u32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Minter {
+    pub address: String,
+    pub mints: u64,
+    pub amount: Amount,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HoldersArgs {
+    pub tick: String,
+    pub page_size: Option<usize>,
+    pub page: Option<usize>,
+    /// Holders after the block at this height instead of the current ones
+    pub height: Option<u32>,
+    /// Only holders with at least this balance
+    pub min_balance: Option<Amount>,
+    /// Only the holder with this address or holders whose address starts with it
+    pub address: Option<String>,
+}
+
+impl HoldersArgs {
+    pub fn new(tick: impl Into<String>) -> Self {
+        Self {
+            tick: tick.into(),
+            page_size: None,
+            page: None,
+            height: None,
+            min_balance: None,
+            address: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Holders {
+    pub pages: usize,
+    pub count: usize,
+    pub max_percent: String,
+    pub holders: Vec<Holder>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Holder {
+    pub rank: usize,
+    pub address: String,
+    pub balance: String,
+    pub percent: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct HoldersDistribution {
+    pub count: usize,
+    pub shares: Vec<Amount>,
+    pub top_10_percent: String,
+    pub top_100_percent: String,
+    pub gini: String,
+    pub histogram: Vec<BalanceBucket>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BalanceBucket {
+    pub min: String,
+    pub max: Option<String>,
+    pub count: usize,
+    pub percent: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BatchSummary {
+    pub txid: String,
+    pub transfers: usize,
+    pub recipients: Vec<BatchRecipient>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BatchRecipient {
+    pub tick: String,
+    pub sender: String,
+    pub recipient: String,
+    pub amount: Amount,
+    pub transfers: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BroadcastArgs {
+    /// Raw transaction in hex
+    pub hex: String,
+    pub preview: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BroadcastResult {
+    pub txid: String,
+    #[serde(default)]
+    pub preview: Option<Vec<ActionPreview>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EstimateTransferArgs {
+    pub tick: String,
+    pub amt: Amount,
+    /// Address which sends the tokens
+    pub address: String,
+    /// Confirmation target of the fee rate in blocks, 6 by default
+    pub blocks: Option<u16>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TxEstimate {
+    pub vsize: u64,
+    /// Not set if the node has no fee estimate
+    pub fee: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InscribeEstimate {
+    pub commit: TxEstimate,
+    pub reveal: TxEstimate,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TransferEstimate {
+    pub tick: String,
+    pub amt: Amount,
+    /// Existing transfer inscription with exactly `amt`
+    pub transfer: Option<TokenTransfer>,
+    /// Not set if `transfer` is
+    pub inscribe: Option<InscribeEstimate>,
+    pub send: TxEstimate,
+    /// Satoshis per vbyte
+    pub fee_rate: Option<f64>,
+    pub total_fee: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ActionPreview {
+    pub tick: String,
+    pub address: String,
+    pub action: TokenAction,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ValidateInscriptionArgs {
+    pub content_type: String,
+    pub content: Option<String>,
+    pub content_hex: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InscriptionValidation {
+    pub valid: bool,
+    #[serde(default)]
+    pub op: Option<String>,
+    #[serde(default)]
+    pub tick: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct ProofHistoryArgs {
+    /// Height of the last block from the previous page
+    pub offset: Option<u32>,
+    pub limit: Option<usize>,
+    pub links: Option<bool>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ProofOfHistory {
+    pub height: u32,
+    pub hash: String,
+    pub version: u8,
+    #[serde(default)]
+    pub balance_root: Option<String>,
+    #[serde(default)]
+    pub prev_proof: Option<String>,
+    #[serde(default)]
+    pub history_hash: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ProofOfHistorySpec {
+    pub v2_height: Option<u32>,
+    pub block_proof: String,
+    pub versions: Vec<PohVersionSpec>,
+    pub balance_tree: String,
+    pub state_digest: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct StateDigest {
+    pub height: u32,
+    pub digest: String,
+    pub accounts: u64,
+    pub tokens: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PohVersionSpec {
+    pub version: u8,
+    pub encoding: String,
+    pub fields: Vec<PohFieldSpec>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PohFieldSpec {
+    pub name: String,
+    pub encoding: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BlockDetails {
+    pub height: u32,
+    pub hash: String,
+    pub prev_hash: Option<String>,
+    pub created: u32,
+    pub median_time: Option<u32>,
+    pub tx_count: Option<u32>,
+    pub size: Option<u32>,
+    pub events: usize,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InscriptionStats {
+    pub text: u64,
+    pub json: u64,
+    pub image: u64,
+    pub other: u64,
+    pub total: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FeeStats {
+    pub txs: u64,
+    pub fees: u64,
+    pub avg_fee: u64,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct InscriptionHistoryArgs {
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InscriptionMoveKind {
+    Created,
+    Transferred,
+    Leaked,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InscriptionMove {
+    pub kind: InscriptionMoveKind,
+    pub height: u32,
+    /// `txid:vout:offset`
+    pub location: String,
+    pub address: String,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct LeakedArgs {
+    /// `height` of the last block from the previous page
+    pub offset: Option<u32>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeakedKind {
+    Creation,
+    Move,
+}
+
+/// Inscription created or moved into the fees, received by the miner in the coinbase
+#[derive(Deserialize, Clone, Debug)]
+pub struct LeakedInscription {
+    pub height: u32,
+    pub kind: LeakedKind,
+    pub txid: String,
+    pub input: u32,
+    /// `txid:vout:offset` of the spent output
+    pub from: String,
+    /// Owner of the spent output
+    pub address: String,
+    /// `txid:vout:offset` in the coinbase
+    pub location: String,
+    pub inscriptions: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InscriptionLocation {
+    pub id: String,
+    /// `txid:vout:offset`, `None` if the inscription isn't indexed
+    pub location: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InscriptionCurses {
+    pub id: String,
+    /// e.g. `duplicate_field`, `pointer` or `reinscription`
+    pub curses: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TxTrace {
+    pub txid: String,
+    pub height: u32,
+    pub jubilee: bool,
+    pub inputs: Vec<InputTrace>,
+    pub events: Vec<History>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InputTrace {
+    pub input_index: u32,
+    pub outpoint: String,
+    pub envelope: String,
+    pub inscriptions: Vec<InscriptionTrace>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InscriptionTrace {
+    pub id: String,
+    pub content_type: Option<String>,
+    pub body_size: usize,
+    pub curses: Vec<String>,
+    pub pointer: Option<u64>,
+    pub pointer_applied: bool,
+    pub leaked: bool,
+    pub location: Option<String>,
+    pub owner: Option<String>,
+    pub reinscription: bool,
+    pub skipped: bool,
+    pub token: TokenTrace,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenTrace {
+    pub parsed: bool,
+    pub op: Option<String>,
+    pub tick: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Filters of `POST /events`, every event is received if none is set
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct Subscription {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub addresses: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_types: Option<Vec<TokenEventType>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_amount: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mint_tokens: Option<Vec<String>>,
+    /// Replace `min_amount` for the events of an address, a token or both
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_amounts: Option<Vec<AmountThreshold>>,
+}
+
+/// At least one of `address` and `tick` must be set
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct AmountThreshold {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tick: Option<String>,
+    pub min_amount: Amount,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PartialsStats {
+    pub count: u64,
+    pub total_bytes: u64,
+    pub spent: u64,
+    pub oldest_height: Option<u32>,
+    pub unknown_height: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WebhookArgs {
+    pub url: String,
+    pub tick: String,
+    pub event_types: Vec<TokenEventType>,
+    pub secret: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WebhookCreated {
+    pub id: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Webhook {
+    pub id: u64,
+    pub url: String,
+    pub tick: String,
+    pub event_types: Vec<TokenEventType>,
+    pub delivered: u64,
+    pub failed: u64,
+    pub last_attempt: Option<u64>,
+    pub last_success: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_height: Option<u32>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Backup {
+    pub name: String,
+    pub height: u32,
+    pub created: u64,
+    pub size_bytes: u64,
+    pub uploaded: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PrunedBackups {
+    pub removed: Vec<String>,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct AuditLogArgs {
+    /// `id` of the last entry from the previous page
+    pub offset: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AdminAuditEntry {
+    pub id: u64,
+    pub time: u64,
+    pub operator: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    /// `secret` fields are redacted
+    pub body: Option<String>,
+    pub status: u16,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct SupplyAuditArgs {
+    /// `id` of the last report from the previous page
+    pub offset: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SupplyAudit {
+    pub id: u64,
+    pub height: u32,
+    pub created: u64,
+    pub duration_ms: u64,
+    pub tokens: u64,
+    pub discrepancies: Vec<SupplyDiscrepancy>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SupplyDiscrepancy {
+    pub tick: String,
+    pub stored: SupplyCounters,
+    pub computed: SupplyCounters,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SupplyCounters {
+    pub supply: Amount,
+    pub mint_count: u64,
+    pub transfer_count: u64,
+}
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct RejectedDeploysArgs {
+    /// `height` of the last block from the previous page
+    pub offset: Option<u32>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectedDeployReason {
+    Denied,
+    NotAllowed,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RejectedDeploy {
+    pub height: u32,
+    pub created: u32,
+    pub tick: String,
+    pub genesis: String,
+    pub deployer: String,
+    pub reason: RejectedDeployReason,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ReorgSimulation {
+    pub depth: u32,
+    pub restore_height: Option<u32>,
+    pub tip: Option<u32>,
+    pub rollback_ms: u64,
+    pub replay_ms: u64,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub checks: Vec<ReorgSimulationCheck>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BlockReprocess {
+    pub height: u32,
+    pub hash: Option<String>,
+    pub restore_ms: u64,
+    pub process_ms: u64,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub checks: Vec<ReorgSimulationCheck>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ReorgSimulationCheck {
+    pub name: String,
+    pub ok: bool,
+    pub details: Option<String>,
+}
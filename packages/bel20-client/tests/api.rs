@@ -0,0 +1,226 @@
+//! Runs against an indexer started in the test process on a temporary `DB_PATH`. It indexes a synthetic chain served by
+//! `mock-node`: a deploy, mints to two addresses and a transfer sent to a third one.
+
+use std::{
+    net::{TcpListener, TcpStream},
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use bel20_client::{types::*, Client, Owner};
+use bel20_core::{rest::run_rest, Indexer, Server};
+use mock_node::{Chain, Tx};
+
+const TICK: &str = "test";
+const ADMIN_TOKEN: &str = "bel20-client-test";
+/// Time given to the REST server to bind before the tests fail
+const REST_START_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn chain() -> Chain {
+    let mut chain = Chain::new();
+    chain.push_block(vec![Tx::Inscribe {
+        body: mock_node::deploy(TICK, 21_000, 1_000),
+        owner: 1,
+    }]);
+    chain.push_block(vec![
+        Tx::Inscribe {
+            body: mock_node::mint(TICK, 1_000),
+            owner: 1,
+        },
+        Tx::Inscribe {
+            body: mock_node::mint(TICK, 500),
+            owner: 2,
+        },
+    ]);
+    let transfer = chain.push_block(vec![Tx::Inscribe {
+        body: mock_node::transfer(TICK, 100),
+        owner: 1,
+    }]);
+    chain.push_block(vec![Tx::Send { inscription: transfer[0], to: 3 }]);
+    chain
+}
+
+/// Indexes the chain and starts the REST server, returns its URL
+fn start() -> String {
+    let chain = chain();
+    let tip = chain.height();
+    let rpc_url = chain.serve().unwrap();
+
+    let rest_addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let db_path = std::env::temp_dir().join(format!("bel20-client-test-{}", std::process::id()));
+    std::fs::remove_dir_all(&db_path).ok();
+    let db_path = db_path.to_string_lossy().into_owned();
+
+    // Read by the statics of the indexer, which are initialized by the first access
+    for (key, value) in [
+        ("BLOCKCHAIN", "bells"),
+        ("NETWORK", "testnet"),
+        ("RPC_URL", rpc_url.as_str()),
+        ("RPC_USER", "user"),
+        ("RPC_PASS", "pass"),
+        ("DB_PATH", db_path.as_str()),
+        ("SERVER_BIND_URL", rest_addr.as_str()),
+        ("ADMIN_TOKEN", ADMIN_TOKEN),
+    ] {
+        std::env::set_var(key, value);
+    }
+
+    let (_events, server) = Server::new(&db_path).unwrap();
+    let server = Arc::new(server);
+
+    // Blocks after the genesis block, like the node is synced from
+    let (tx, rx) = nint_blk::block_channel();
+    let client = server.client.clone();
+    std::thread::spawn(move || {
+        for height in 1..=tip {
+            let hash = client.get_block_hash(height).unwrap();
+            let block = client.get_block(&hash).unwrap();
+            let id = nint_blk::BlockId { height, hash };
+            tx.send(nint_blk::BlockEvent { id, block, reorg_len: 0, tip }).unwrap();
+        }
+    });
+
+    // Returns once the channel is closed after the last block
+    Indexer::new(server.clone()).run_from(rx).unwrap();
+
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_multi_thread().thread_name("rest").enable_all().build().unwrap();
+        runtime.block_on(run_rest(server)).unwrap();
+    });
+
+    let started = Instant::now();
+    while TcpStream::connect(&rest_addr).is_err() {
+        assert!(started.elapsed() < REST_START_TIMEOUT, "REST server didn't start");
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    format!("http://{rest_addr}")
+}
+
+fn client() -> Client {
+    static URL: OnceLock<String> = OnceLock::new();
+
+    // Outside of the runtime of the test, which is dropped with it
+    let url = URL.get_or_init(|| std::thread::spawn(start).join().unwrap());
+    Client::new(url).with_admin_token(ADMIN_TOKEN)
+}
+
+#[tokio::test]
+async fn status_matches_proof_of_history() {
+    let client = client();
+
+    let status = client.status().await.unwrap();
+    let proofs = client.proof_of_history(&ProofHistoryArgs { limit: Some(1), ..Default::default() }).await.unwrap();
+
+    let last = proofs.first().unwrap();
+    assert_eq!(last.height, status.height);
+
+    let block = client.block(&status.height.to_string()).await.unwrap();
+    assert_eq!(block.hash, status.blockhash);
+
+    let leaked = client.block_leaked(status.height).await.unwrap();
+    assert!(leaked.iter().all(|x| x.height == status.height));
+}
+
+#[tokio::test]
+async fn proof_of_history_links() {
+    let client = client();
+
+    let proofs = client
+        .proof_of_history(&ProofHistoryArgs {
+            limit: Some(10),
+            links: Some(true),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(proofs.len() > 1);
+
+    for pair in proofs.windows(2) {
+        assert_eq!(pair[0].prev_proof.as_ref(), Some(&pair[1].hash));
+    }
+}
+
+#[tokio::test]
+async fn tokens_and_holders() {
+    let client = client();
+
+    let tokens = client
+        .tokens(&TokensArgs {
+            page_size: Some(5),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(tokens.tokens.len(), 1);
+
+    let token = &tokens.tokens[0];
+    assert_eq!(token.tick.to_lowercase(), TICK);
+
+    assert_eq!(client.token(&token.tick).await.unwrap().genesis, token.genesis);
+    assert_eq!(client.token_supplies(&[&token.tick]).await.unwrap(), vec![token.supply.clone()]);
+
+    let holders = client.holders(&HoldersArgs::new(&token.tick)).await.unwrap();
+    let holder = holders.holders.first().unwrap();
+
+    let balance = client.address_token_balance(Owner::Address(&holder.address), &token.tick, &Default::default()).await.unwrap();
+    assert_eq!(balance.tick.to_lowercase(), token.tick.to_lowercase());
+
+    let history = client.address_history(Owner::Address(&holder.address), &AddressHistoryArgs::new(&token.tick)).await.unwrap();
+    assert!(history.total > 0);
+
+    let changes = client.address_token_balance_history(Owner::Address(&holder.address), &token.tick, &Default::default()).await.unwrap();
+    assert!(!changes.is_empty());
+    assert!(changes.windows(2).all(|x| x[0].height < x[1].height));
+}
+
+#[tokio::test]
+async fn errors_carry_status() {
+    let client = client();
+
+    let error = client.token("\u{1F600}zzz").await.unwrap_err();
+    assert!(error.is_not_found() || matches!(error, bel20_client::Error::Api { status, .. } if status.is_client_error()));
+}
+
+#[tokio::test]
+async fn admin_webhooks() {
+    let client = client();
+
+    client.partials_stats().await.unwrap();
+
+    let created = client
+        .create_webhook(&WebhookArgs {
+            url: "http://127.0.0.1:9/hook".to_string(),
+            tick: TICK.to_string(),
+            event_types: vec![TokenEventType::Mint],
+            secret: "0123456789abcdef".to_string(),
+        })
+        .await
+        .unwrap();
+
+    assert!(client.webhooks().await.unwrap().iter().any(|x| x.id == created.id));
+
+    client.remove_webhook(created.id).await.unwrap();
+    assert!(!client.webhooks().await.unwrap().iter().any(|x| x.id == created.id));
+
+    let log = client.audit_log(&AuditLogArgs { offset: None, limit: Some(100) }).await.unwrap();
+    let removed = format!("/admin/webhooks/{}", created.id);
+    assert!(log.iter().any(|x| x.method == "DELETE" && x.path.ends_with(&removed)));
+    assert!(log.iter().all(|x| !x.body.as_deref().unwrap_or_default().contains("0123456789abcdef")));
+}
+
+#[tokio::test]
+async fn addresses_export_pages() {
+    let client = client();
+
+    // The miner and the three token owners
+    let first = client.addresses_export(None, Some(2)).await.unwrap();
+    assert_eq!(first.addresses.len(), 2);
+
+    let next = first.next.unwrap();
+    assert_eq!(Some(&next), first.addresses.last().map(|x| &x.scripthash));
+
+    let second = client.addresses_export(Some(&next), Some(2)).await.unwrap();
+    assert!(!second.addresses.is_empty());
+    assert!(second.addresses.iter().all(|x| first.addresses.iter().all(|y| y.scripthash != x.scripthash)));
+}
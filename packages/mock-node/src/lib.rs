@@ -0,0 +1,442 @@
+//! Synthetic chain of BEL-20 inscriptions served like a node, so the indexer can be run in tests without one. <br/>
+//! Only the JSON-RPC calls the indexer makes while syncing are answered: `getbestblockhash`, `getblockcount`, `getblockhash`
+//! and `getblock` as hex.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+};
+
+use bitcoin_hashes::{sha256d, Hash};
+use serde_json::{json, Value};
+
+const OP_FALSE: u8 = 0x00;
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+const OP_IF: u8 = 0x63;
+const OP_ENDIF: u8 = 0x68;
+const MAX_PUSH: usize = 520;
+
+const CONTENT_TYPE: &[u8] = b"text/plain;charset=utf-8";
+/// Value of the coinbase outputs spent by the reveal transactions
+const FUNDING_VALUE: u64 = 10_000;
+/// Value of the outputs holding inscriptions, the rest of the funding is the fee
+const INSCRIPTION_VALUE: u64 = 1_000;
+const BLOCK_REWARD: u64 = 50 * 100_000_000;
+const GENESIS_TIME: u32 = 1_700_000_000;
+const BLOCK_INTERVAL: u32 = 60;
+/// Lowest difficulty, the work isn't checked by the indexer
+const BITS: u32 = 0x207f_ffff;
+/// Owner of the block rewards and funding outputs
+pub const MINER: u8 = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutPoint {
+    pub txid: sha256d::Hash,
+    pub vout: u32,
+}
+
+/// Transaction of a block after its coinbase
+pub enum Tx {
+    /// Reveals a `text/plain` inscription with the body, which is sent to `owner`
+    Inscribe { body: String, owner: u8 },
+    /// Sends the inscription on the first sat of the outpoint to `to`
+    Send { inscription: OutPoint, to: u8 },
+}
+
+/// `deploy` inscription body
+pub fn deploy(tick: &str, max: u64, lim: u64) -> String {
+    format!(r#"{{"p":"bel-20","op":"deploy","tick":"{tick}","max":"{max}","lim":"{lim}"}}"#)
+}
+
+/// `mint` inscription body
+pub fn mint(tick: &str, amt: u64) -> String {
+    format!(r#"{{"p":"bel-20","op":"mint","tick":"{tick}","amt":"{amt}"}}"#)
+}
+
+/// `transfer` inscription body
+pub fn transfer(tick: &str, amt: u64) -> String {
+    format!(r#"{{"p":"bel-20","op":"transfer","tick":"{tick}","amt":"{amt}"}}"#)
+}
+
+/// P2WPKH script of the owner, whose key hash is the owner repeated
+pub fn owner_script(owner: u8) -> Vec<u8> {
+    let mut script = vec![OP_FALSE, 20];
+    script.extend([owner; 20]);
+    script
+}
+
+struct Input {
+    prevout: OutPoint,
+    script_sig: Vec<u8>,
+    witness: Vec<Vec<u8>>,
+}
+
+struct RawTx {
+    inputs: Vec<Input>,
+    outputs: Vec<(u64, Vec<u8>)>,
+}
+
+impl RawTx {
+    fn serialize(&self, with_witness: bool) -> Vec<u8> {
+        let with_witness = with_witness && self.inputs.iter().any(|x| !x.witness.is_empty());
+
+        let mut data = 1u32.to_le_bytes().to_vec();
+        if with_witness {
+            data.extend([0x00, 0x01]);
+        }
+
+        write_varint(&mut data, self.inputs.len() as u64);
+        for input in &self.inputs {
+            data.extend(input.prevout.txid.to_byte_array());
+            data.extend(input.prevout.vout.to_le_bytes());
+            write_bytes(&mut data, &input.script_sig);
+            data.extend(u32::MAX.to_le_bytes());
+        }
+
+        write_varint(&mut data, self.outputs.len() as u64);
+        for (value, script) in &self.outputs {
+            data.extend(value.to_le_bytes());
+            write_bytes(&mut data, script);
+        }
+
+        if with_witness {
+            for input in &self.inputs {
+                write_varint(&mut data, input.witness.len() as u64);
+                for item in &input.witness {
+                    write_bytes(&mut data, item);
+                }
+            }
+        }
+
+        data.extend(0u32.to_le_bytes());
+        data
+    }
+
+    fn txid(&self) -> sha256d::Hash {
+        sha256d::Hash::hash(&self.serialize(false))
+    }
+}
+
+struct Block {
+    hash: sha256d::Hash,
+    raw: Vec<u8>,
+}
+
+/// Blocks from the genesis block on, every block is mined a minute after the previous one
+pub struct Chain {
+    blocks: Vec<Block>,
+}
+
+impl Default for Chain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chain {
+    /// Chain with only the genesis block
+    pub fn new() -> Self {
+        let mut chain = Self { blocks: vec![] };
+        chain.push_block(vec![]);
+        chain
+    }
+
+    /// Height of the best block
+    pub fn height(&self) -> u64 {
+        self.blocks.len() as u64 - 1
+    }
+
+    /// Mines a block with the transactions after its coinbase, which funds the reveals. <br/>
+    /// Returns the outpoint holding the inscription of every transaction.
+    pub fn push_block(&mut self, txs: Vec<Tx>) -> Vec<OutPoint> {
+        let height = self.blocks.len() as u32;
+
+        let reveals = txs.iter().filter(|x| matches!(x, Tx::Inscribe { .. })).count();
+        let mut outputs = vec![(FUNDING_VALUE, owner_script(MINER)); reveals];
+        outputs.push((BLOCK_REWARD, owner_script(MINER)));
+
+        // The height makes the coinbase of every block unique
+        let mut script_sig = vec![4];
+        script_sig.extend(height.to_le_bytes());
+
+        let coinbase = RawTx {
+            inputs: vec![Input {
+                prevout: OutPoint {
+                    txid: sha256d::Hash::all_zeros(),
+                    vout: u32::MAX,
+                },
+                script_sig,
+                witness: vec![],
+            }],
+            outputs,
+        };
+        let coinbase_txid = coinbase.txid();
+
+        let mut funding = 0;
+        let mut block_txs = vec![coinbase];
+        for tx in txs {
+            let (input, owner) = match tx {
+                Tx::Inscribe { body, owner } => {
+                    let prevout = OutPoint {
+                        txid: coinbase_txid,
+                        vout: funding,
+                    };
+                    funding += 1;
+
+                    // Script-path spend of the leaf with the envelope, the control block isn't checked by the indexer
+                    let mut control_block = vec![0xc0];
+                    control_block.extend([1; 32]);
+
+                    let input = Input {
+                        prevout,
+                        script_sig: vec![],
+                        witness: vec![envelope(body.as_bytes()), control_block],
+                    };
+                    (input, owner)
+                }
+                Tx::Send { inscription, to } => {
+                    let input = Input {
+                        prevout: inscription,
+                        script_sig: vec![],
+                        witness: vec![],
+                    };
+                    (input, to)
+                }
+            };
+
+            block_txs.push(RawTx {
+                inputs: vec![input],
+                outputs: vec![(INSCRIPTION_VALUE, owner_script(owner))],
+            });
+        }
+
+        let txids = block_txs.iter().map(RawTx::txid).collect::<Vec<_>>();
+
+        let mut raw = 1u32.to_le_bytes().to_vec();
+        raw.extend(self.blocks.last().map_or(sha256d::Hash::all_zeros(), |x| x.hash).to_byte_array());
+        raw.extend(merkle_root(txids.clone()).to_byte_array());
+        raw.extend((GENESIS_TIME + height * BLOCK_INTERVAL).to_le_bytes());
+        raw.extend(BITS.to_le_bytes());
+        raw.extend(0u32.to_le_bytes());
+        let hash = sha256d::Hash::hash(&raw);
+
+        write_varint(&mut raw, block_txs.len() as u64);
+        for tx in &block_txs {
+            raw.extend(tx.serialize(true));
+        }
+
+        self.blocks.push(Block { hash, raw });
+
+        txids[1..].iter().map(|txid| OutPoint { txid: *txid, vout: 0 }).collect()
+    }
+
+    /// Serves the blocks on a free local port until the process exits, returns the URL for `RPC_URL`
+    pub fn serve(self) -> io::Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}", listener.local_addr()?);
+
+        let chain = Arc::new(self);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let chain = chain.clone();
+                std::thread::spawn(move || chain.handle(stream));
+            }
+        });
+
+        Ok(url)
+    }
+
+    /// Answers the requests of a keep-alive connection until it's closed
+    fn handle(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+
+            let mut content_length = 0;
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 || line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap_or_default();
+                    }
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+
+            let response = self.respond(&body).to_string();
+            write!(
+                writer,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                response.len(),
+                response
+            )?;
+            writer.flush()?;
+        }
+    }
+
+    fn respond(&self, body: &[u8]) -> Value {
+        let request = serde_json::from_slice::<Value>(body).unwrap_or_default();
+        let params = &request["params"];
+
+        let result = match request["method"].as_str().unwrap_or_default() {
+            "getbestblockhash" => self.blocks.last().map(|x| json!(x.hash.to_string())),
+            "getblockcount" => Some(json!(self.height())),
+            "getblockhash" => params[0].as_u64().and_then(|height| self.blocks.get(height as usize)).map(|x| json!(x.hash.to_string())),
+            // The verbose formats aren't served, so the client falls back to hex
+            "getblock" if params[1].is_null() || params[1] == 0 || params[1] == false => self
+                .blocks
+                .iter()
+                .find(|x| params[0].as_str() == Some(&x.hash.to_string()))
+                .map(|x| json!(hex::encode(&x.raw))),
+            _ => None,
+        };
+
+        match result {
+            Some(result) => json!({ "result": result, "error": null, "id": request["id"] }),
+            None => json!({ "result": null, "error": { "code": -1, "message": "Not served by the mock node" }, "id": request["id"] }),
+        }
+    }
+}
+
+/// Ordinals envelope with the content type and the body
+fn envelope(body: &[u8]) -> Vec<u8> {
+    let mut script = vec![OP_FALSE, OP_IF];
+    push(&mut script, b"ord");
+    push(&mut script, &[1]);
+    push(&mut script, CONTENT_TYPE);
+    script.push(OP_FALSE);
+    for chunk in body.chunks(MAX_PUSH) {
+        push(&mut script, chunk);
+    }
+    script.push(OP_ENDIF);
+    script
+}
+
+fn push(script: &mut Vec<u8>, data: &[u8]) {
+    match data.len() {
+        len @ 0..=75 => script.push(len as u8),
+        len @ 76..=255 => script.extend([OP_PUSHDATA1, len as u8]),
+        len => {
+            script.push(OP_PUSHDATA2);
+            script.extend((len as u16).to_le_bytes());
+        }
+    }
+    script.extend(data);
+}
+
+fn write_varint(data: &mut Vec<u8>, value: u64) {
+    match value {
+        0..=0xfc => data.push(value as u8),
+        0xfd..=0xffff => {
+            data.push(0xfd);
+            data.extend((value as u16).to_le_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            data.push(0xfe);
+            data.extend((value as u32).to_le_bytes());
+        }
+        _ => {
+            data.push(0xff);
+            data.extend(value.to_le_bytes());
+        }
+    }
+}
+
+fn write_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint(data, bytes.len() as u64);
+    data.extend(bytes);
+}
+
+fn merkle_root(mut hashes: Vec<sha256d::Hash>) -> sha256d::Hash {
+    while hashes.len() > 1 {
+        hashes = hashes
+            .chunks(2)
+            .map(|pair| {
+                let mut data = pair[0].to_byte_array().to_vec();
+                data.extend(pair.last().unwrap().to_byte_array());
+                sha256d::Hash::hash(&data)
+            })
+            .collect();
+    }
+    hashes[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(url: &str, method: &str, params: Value) -> Value {
+        let mut stream = TcpStream::connect(url.trim_start_matches("http://")).unwrap();
+        let body = json!({ "method": method, "params": params, "id": 7 }).to_string();
+        write!(stream, "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0;
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                content_length = value.trim().parse().unwrap();
+            }
+            line.clear();
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        let response = serde_json::from_slice::<Value>(&body).unwrap();
+        assert_eq!(response["id"], 7);
+        response["result"].clone()
+    }
+
+    #[test]
+    fn serves_linked_blocks() {
+        let mut chain = Chain::new();
+        let deployed = chain.push_block(vec![Tx::Inscribe {
+            body: deploy("test", 1000, 10),
+            owner: 1,
+        }]);
+        let sent = chain.push_block(vec![Tx::Send { inscription: deployed[0], to: 2 }]);
+        assert_eq!(sent.len(), 1);
+        assert_ne!(sent[0], deployed[0]);
+
+        let url = chain.serve().unwrap();
+        assert_eq!(call(&url, "getblockcount", json!([])), 2);
+
+        let best = call(&url, "getbestblockhash", json!([]));
+        assert_eq!(call(&url, "getblockhash", json!([2])), best);
+
+        let raw = hex::decode(call(&url, "getblock", json!([best, 0])).as_str().unwrap()).unwrap();
+        let mut hash = sha256d::Hash::hash(&raw[..80]).to_byte_array();
+        hash.reverse();
+        assert_eq!(hex::encode(hash), best.as_str().unwrap());
+
+        // The previous block hash is stored in internal byte order
+        let mut prev = call(&url, "getblockhash", json!([1])).as_str().map(|x| hex::decode(x).unwrap()).unwrap();
+        prev.reverse();
+        assert_eq!(raw[4..36], prev);
+
+        assert!(call(&url, "getblock", json!([best, 1])).is_null());
+    }
+
+    #[test]
+    fn envelope_pushes_fields() {
+        let script = envelope(&[7; 600]);
+        assert_eq!(script[..7], [OP_FALSE, OP_IF, 3, b'o', b'r', b'd', 1]);
+        // Bodies are split into pushes of at most 520 bytes
+        let body = &script[script.len() - 600 - 3 - 2 - 1..];
+        assert_eq!(body[..3], [OP_PUSHDATA2, 0x08, 0x02]);
+        assert_eq!(body[3 + 520..3 + 520 + 2], [OP_PUSHDATA1, 80]);
+        assert_eq!(*script.last().unwrap(), OP_ENDIF);
+    }
+}
@@ -1,11 +1,35 @@
 use super::*;
 
+use std::sync::RwLock;
+
+/// Coins added with [`CoinType::register`]
+static REGISTERED: RwLock<Vec<CoinType>> = RwLock::new(Vec::new());
+
 pub struct EncoderConfig {
     pubkey_address: u8,
     script_address: u8,
     bech32: &'static str,
 }
 
+/// Hash function used for the proof of work of the block header
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowAlgorithm {
+    Sha256d,
+    Scrypt,
+}
+
+/// How the `pointer` field of an inscription changes the sat it's created on
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointerPolicy {
+    /// Inscriptions stay on the first sat of their input, like indexers which predate the field
+    IgnorePointer,
+    /// Inscriptions move to the sat the pointer points at if it's within the outputs, like ord
+    #[default]
+    OrdPointer,
+}
+
 /// Trait to specify the underlying coin of a blockchain
 /// Needs a proper magic value and a network id for address prefixes
 pub trait Coin {
@@ -13,6 +37,18 @@ pub trait Coin {
     const NAME: &'static str;
     /// Configuration for address generation
     const CONFIG: EncoderConfig;
+    /// Proof of work hash function
+    const POW: PowAlgorithm;
+    /// Message start bytes prefixing every block in blk files, `None` if it isn't known
+    const MAGIC: Option<[u8; 4]>;
+    /// Height from which inscriptions are searched in every input, `None` if the embedder decides
+    const JUBILEE_HEIGHT: Option<u64> = None;
+    /// Height of the first inscription block, `None` if the embedder decides
+    const FIRST_INSCRIPTION_HEIGHT: Option<u64> = None;
+    /// Value of `p` in token payloads, `None` if the embedder decides
+    const PROTOCOL: Option<&'static str> = None;
+    /// Handling of the inscription pointer field
+    const POINTER_POLICY: PointerPolicy = PointerPolicy::OrdPointer;
 }
 
 pub struct Bitcoin;
@@ -23,6 +59,8 @@ impl Coin for Bitcoin {
         script_address: 5,
         bech32: "bc",
     };
+    const POW: PowAlgorithm = PowAlgorithm::Sha256d;
+    const MAGIC: Option<[u8; 4]> = Some([0xf9, 0xbe, 0xb4, 0xd9]);
 }
 
 pub struct BitcoinTestnet;
@@ -33,6 +71,8 @@ impl Coin for BitcoinTestnet {
         script_address: 196,
         bech32: "tb",
     };
+    const POW: PowAlgorithm = PowAlgorithm::Sha256d;
+    const MAGIC: Option<[u8; 4]> = Some([0x0b, 0x11, 0x09, 0x07]);
 }
 
 pub struct Litecoin;
@@ -43,6 +83,8 @@ impl Coin for Litecoin {
         script_address: 5,
         bech32: "lt",
     };
+    const POW: PowAlgorithm = PowAlgorithm::Scrypt;
+    const MAGIC: Option<[u8; 4]> = Some([0xfb, 0xc0, 0xb6, 0xdb]);
 }
 
 pub struct LitecoinTestnet;
@@ -53,6 +95,8 @@ impl Coin for LitecoinTestnet {
         script_address: 196,
         bech32: "tlt",
     };
+    const POW: PowAlgorithm = PowAlgorithm::Scrypt;
+    const MAGIC: Option<[u8; 4]> = Some([0xfd, 0xd2, 0xc8, 0xf1]);
 }
 
 pub struct Dogecoin;
@@ -63,6 +107,8 @@ impl Coin for Dogecoin {
         script_address: 22,
         bech32: "dg",
     };
+    const POW: PowAlgorithm = PowAlgorithm::Scrypt;
+    const MAGIC: Option<[u8; 4]> = Some([0xc0, 0xc0, 0xc0, 0xc0]);
 }
 
 pub struct DogecoinTestnet;
@@ -73,6 +119,8 @@ impl Coin for DogecoinTestnet {
         script_address: 196,
         bech32: "tdg",
     };
+    const POW: PowAlgorithm = PowAlgorithm::Scrypt;
+    const MAGIC: Option<[u8; 4]> = Some([0xfc, 0xc1, 0xb7, 0xdc]);
 }
 
 pub struct Bellscoin;
@@ -83,6 +131,8 @@ impl Coin for Bellscoin {
         script_address: 30,
         bech32: "bel",
     };
+    const POW: PowAlgorithm = PowAlgorithm::Scrypt;
+    const MAGIC: Option<[u8; 4]> = None;
 }
 
 pub struct BellscoinTestnet;
@@ -93,6 +143,8 @@ impl Coin for BellscoinTestnet {
         script_address: 22,
         bech32: "tbel",
     };
+    const POW: PowAlgorithm = PowAlgorithm::Scrypt;
+    const MAGIC: Option<[u8; 4]> = None;
 }
 
 pub struct Pepecoin;
@@ -103,6 +155,8 @@ impl Coin for Pepecoin {
         script_address: 22,
         bech32: "pe",
     };
+    const POW: PowAlgorithm = PowAlgorithm::Scrypt;
+    const MAGIC: Option<[u8; 4]> = None;
 }
 
 pub struct PepecoinTestnet;
@@ -113,6 +167,8 @@ impl Coin for PepecoinTestnet {
         script_address: 196,
         bech32: "tpe",
     };
+    const POW: PowAlgorithm = PowAlgorithm::Scrypt;
+    const MAGIC: Option<[u8; 4]> = None;
 }
 
 #[derive(Clone, Copy)]
@@ -122,6 +178,54 @@ pub struct CoinType {
     pub pubkey_address: u8,
     pub script_address: u8,
     pub bech32: &'static str,
+    pub pow: PowAlgorithm,
+    pub magic: Option<[u8; 4]>,
+    pub jubilee_height: Option<u64>,
+    pub first_inscription_height: Option<u64>,
+    pub protocol: Option<&'static str>,
+    pub pointer_policy: PointerPolicy,
+}
+
+/// Definition of a coin which isn't built in, e.g. a fork, see [`CoinType::register`]
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct CustomCoin {
+    /// Name accepted by [`CoinType::from_str`]
+    pub name: String,
+    pub pubkey_address: u8,
+    pub script_address: u8,
+    /// Human readable part of bech32 addresses
+    pub bech32: String,
+    #[serde(default = "CustomCoin::default_pow")]
+    pub pow: PowAlgorithm,
+    /// Magic bytes in hex, e.g. `f9beb4d9`
+    #[serde(default, deserialize_with = "deserialize_magic")]
+    pub magic: Option<[u8; 4]>,
+    #[serde(default)]
+    pub jubilee_height: Option<u64>,
+    #[serde(default)]
+    pub first_inscription_height: Option<u64>,
+    #[serde(default)]
+    pub protocol: Option<String>,
+    #[serde(default)]
+    pub pointer_policy: PointerPolicy,
+}
+
+impl CustomCoin {
+    fn default_pow() -> PowAlgorithm {
+        PowAlgorithm::Scrypt
+    }
+}
+
+fn deserialize_magic<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<[u8; 4]>, D::Error> {
+    let Some(value) = <Option<String> as serde::Deserialize>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    hex::decode(value.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom("magic must be 4 bytes in hex"))
 }
 
 impl Default for CoinType {
@@ -139,10 +243,76 @@ impl<T: Coin> From<T> for CoinType {
             bech32: config.bech32,
             pubkey_address: config.pubkey_address,
             script_address: config.script_address,
+            pow: T::POW,
+            magic: T::MAGIC,
+            jubilee_height: T::JUBILEE_HEIGHT,
+            first_inscription_height: T::FIRST_INSCRIPTION_HEIGHT,
+            protocol: T::PROTOCOL,
+            pointer_policy: T::POINTER_POLICY,
         }
     }
 }
 
+impl CoinType {
+    /// Names accepted by [`CoinType::from_str`]
+    pub const NAMES: &'static [&'static str] = &[
+        "bitcoin",
+        "bitcoin-testnet",
+        "litecoin",
+        "litecoin-testnet",
+        "dogecoin",
+        "dogecoin-testnet",
+        "bellscoin",
+        "bellscoin-testnet",
+        "pepecoin",
+        "pepecoin-testnet",
+    ];
+
+    /// Names of the coins with the known magic, registered ones included
+    pub fn names_by_magic(magic: [u8; 4]) -> Vec<&'static str> {
+        Self::NAMES
+            .iter()
+            .copied()
+            .chain(Self::registered().iter().map(|coin| coin.name))
+            .filter(|name| Self::from_str(name).is_ok_and(|coin| coin.magic == Some(magic)))
+            .collect()
+    }
+
+    /// Makes the coin available to [`CoinType::from_str`] under its name, so forks don't need to patch the parser. <br/>
+    /// Strings of the definition are leaked, coins are meant to be registered once at startup.
+    pub fn register(custom: CustomCoin) -> Result<Self> {
+        if custom.name.is_empty() || custom.bech32.is_empty() {
+            anyhow::bail!("Name and bech32 of the coin must not be empty");
+        }
+
+        let mut registered = REGISTERED.write().unwrap();
+        if Self::NAMES.contains(&custom.name.as_str()) || registered.iter().any(|coin| coin.name == custom.name) {
+            anyhow::bail!("Coin `{}` is already defined", custom.name);
+        }
+
+        let coin = CoinType {
+            name: custom.name.leak(),
+            pubkey_address: custom.pubkey_address,
+            script_address: custom.script_address,
+            bech32: custom.bech32.leak(),
+            pow: custom.pow,
+            magic: custom.magic,
+            jubilee_height: custom.jubilee_height,
+            first_inscription_height: custom.first_inscription_height,
+            protocol: custom.protocol.map(|x| &*x.leak()),
+            pointer_policy: custom.pointer_policy,
+        };
+        registered.push(coin);
+
+        Ok(coin)
+    }
+
+    /// Coins added with [`CoinType::register`]
+    pub fn registered() -> Vec<CoinType> {
+        REGISTERED.read().unwrap().clone()
+    }
+}
+
 impl FromStr for CoinType {
     type Err = anyhow::Error;
     fn from_str(coin_name: &str) -> Result<Self> {
@@ -157,7 +327,10 @@ impl FromStr for CoinType {
             "bellscoin-testnet" => Ok(CoinType::from(BellscoinTestnet)),
             "pepecoin" => Ok(CoinType::from(Pepecoin)),
             "pepecoin-testnet" => Ok(CoinType::from(PepecoinTestnet)),
-            n => anyhow::bail!("There is no implementation for `{}`!", n),
+            n => match REGISTERED.read().unwrap().iter().find(|coin| coin.name == n) {
+                Some(coin) => Ok(*coin),
+                None => anyhow::bail!("There is no implementation for `{}`!", n),
+            },
         }
     }
 }
@@ -15,6 +15,7 @@ pub struct LoadBlocksArgs<'a> {
     from_height: Option<u64>,
     network: &'a str,
     reorg_len: u64,
+    validate_blocks: bool,
 }
 
 pub struct LoadBlocks {
@@ -33,6 +34,7 @@ impl LoadBlocks {
                 range: BlockHeightRange::new(from_height, None).unwrap(),
                 coin: CoinType::from_str(data.network).expect("Unsupported network"),
                 index_dir_path: data.index_dir_path.map(|path| PathBuf::from_str(path).unwrap()),
+                validate_blocks: data.validate_blocks,
             })
             .unwrap(),
             from_height,
@@ -43,7 +45,7 @@ impl LoadBlocks {
     pub fn load_blocks(&mut self) -> impl Iterator<Item = blockchain::proto::block::Block> + use<'_> {
         let max_height = self.storage.max_height() - self.reorg_len;
 
-        (self.from_height..=max_height).map(|x| self.storage.get_block(x).unwrap().unwrap())
+        (self.from_height..=max_height).map_while(|x| self.storage.get_block(x).unwrap())
     }
 
     pub fn get_block(&mut self, height: u64) -> Option<blockchain::proto::block::Block> {
@@ -6,6 +6,34 @@ use proto::block::Block;
 
 const READER_BUFSIZE: usize = 32 * 1024;
 
+/// Start of the first blk file in a directory
+#[derive(Debug)]
+pub struct BlkProbe {
+    pub path: PathBuf,
+    /// Index of the file, `blk00000.dat` starts with the genesis block unless the node is pruned
+    pub index: u64,
+    pub magic: [u8; 4],
+    pub first_block: sha256d::Hash,
+}
+
+/// Reads the magic and the first block header of the first blk file in the directory
+pub fn probe_blk_dir(path: &Path) -> Result<BlkProbe> {
+    let (index, mut blk_file) = BlkFile::from_path(path)?.into_iter().min_by_key(|(index, _)| *index).anyhow()?;
+
+    let reader = blk_file.open()?;
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    reader.read_u32::<LittleEndian>()?;
+    let header = reader.read_block_header()?;
+
+    Ok(BlkProbe {
+        path: blk_file.path.clone(),
+        index,
+        magic,
+        first_block: Hashed::double_sha256(header).hash,
+    })
+}
+
 /// Holds all necessary data about a raw blk file
 pub struct BlkFile {
     pub path: PathBuf,
@@ -41,11 +69,53 @@ impl BlkFile {
         }
     }
 
-    pub fn read_block(&mut self, offset: u64, coin: CoinType) -> Result<Block> {
+    /// `None` if the block isn't completely written yet
+    pub fn read_block(&mut self, offset: u64, coin: CoinType) -> Result<Option<Block>> {
+        let Some(block_size) = self.record_size(offset)? else {
+            return Ok(None);
+        };
+
+        self.open()?.read_block(block_size, coin).map(Some)
+    }
+
+    /// Reads the serialized block at the offset without parsing it, `None` if it isn't completely written yet
+    pub fn read_raw_block(&mut self, offset: u64) -> Result<Option<Vec<u8>>> {
+        let Some(block_size) = self.record_size(offset)? else {
+            return Ok(None);
+        };
+
+        let mut bytes = vec![0; block_size as usize];
+        self.open()?.read_exact(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    /// Reads the size of the block record at the offset and leaves the reader at the start of the block. <br/>
+    /// The node may still be writing the file, so the record is incomplete if it ends after the end of the file
+    /// or its size isn't written yet (blk files are preallocated with zeros).
+    fn record_size(&mut self, offset: u64) -> Result<Option<u32>> {
+        if offset < 4 {
+            anyhow::bail!("Invalid block offset {} in {}", offset, self.path.display());
+        }
+
+        let len = fs::metadata(&self.path)?.len();
+        if offset > len {
+            return Ok(None);
+        }
+
         let reader = self.open()?;
         reader.seek(SeekFrom::Start(offset - 4))?;
         let block_size = reader.read_u32::<LittleEndian>()?;
-        reader.read_block(block_size, coin)
+
+        if block_size == 0 || offset + block_size as u64 > len {
+            return Ok(None);
+        }
+
+        Ok(Some(block_size))
+    }
+
+    /// Same file with its own handle, which isn't opened yet
+    pub fn reopen(&self) -> BlkFile {
+        BlkFile::new(self.path.clone(), self.xor_key.clone())
     }
 
     /// Collects all blk*.dat paths in the given directory
@@ -127,3 +197,31 @@ impl BlkFile {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_size_of_truncated_blocks() {
+        let path = std::env::temp_dir().join(format!("blk-record-size-{}.dat", std::process::id()));
+
+        // Complete record of 3 bytes, record of 8 bytes with 2 of them written and a preallocated zero tail
+        let mut data = vec![0xfa, 0xbf, 0xb5, 0xda, 3, 0, 0, 0, 1, 2, 3];
+        data.extend([0xfa, 0xbf, 0xb5, 0xda, 8, 0, 0, 0, 1, 2]);
+        fs::write(&path, &data).unwrap();
+
+        let mut blk_file = BlkFile::new(path.clone(), None);
+        assert_eq!(blk_file.record_size(8).unwrap(), Some(3));
+        assert_eq!(blk_file.record_size(19).unwrap(), None);
+        assert_eq!(blk_file.record_size(100).unwrap(), None);
+
+        data.truncate(11);
+        data.extend([0; 16]);
+        fs::write(&path, &data).unwrap();
+        blk_file.close();
+        assert_eq!(blk_file.record_size(19).unwrap(), None);
+
+        fs::remove_file(path).unwrap();
+    }
+}
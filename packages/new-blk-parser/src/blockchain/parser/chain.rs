@@ -7,10 +7,59 @@ use itertools::Itertools;
 use parser::index::ChainIndex;
 use proto::block::Block;
 
+/// Serialized block read ahead by [`ChainStorage::prefetch`]
+pub struct RawBlock {
+    pub height: u64,
+    blk_index: u64,
+    data_offset: u64,
+    bytes: Vec<u8>,
+}
+
+/// Why the block of a chain index entry can't be used, see [`ChainStorage::scan`]
+#[derive(Debug)]
+pub enum BlkScanProblem {
+    MissingFile,
+    /// The record ends after the end of the file, usually the node is still writing it
+    Truncated,
+    Unreadable(String),
+    HashMismatch(sha256d::Hash),
+}
+
+#[derive(Debug)]
+pub struct BlkScanIssue {
+    pub height: u64,
+    pub blk_index: u64,
+    pub data_offset: u64,
+    pub expected_hash: sha256d::Hash,
+    pub problem: BlkScanProblem,
+}
+
+impl fmt::Display for BlkScanIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "height {} in blk{:05}.dat at offset {}: ", self.height, self.blk_index, self.data_offset)?;
+
+        match &self.problem {
+            BlkScanProblem::MissingFile => write!(f, "blk file not found"),
+            BlkScanProblem::Truncated => write!(f, "block isn't completely written"),
+            BlkScanProblem::Unreadable(e) => write!(f, "unreadable block: {}", e),
+            BlkScanProblem::HashMismatch(hash) => write!(f, "expected block {}, found {}", self.expected_hash, hash),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BlkScanReport {
+    /// Number of entries in the chain index
+    pub blocks: u64,
+    /// Entries whose block can't be used, ascending by height
+    pub issues: Vec<BlkScanIssue>,
+}
+
 /// Manages the index and data of longest valid chain
 pub struct ChainStorage {
     pub chain_index: ChainIndex,
     coin: CoinType,
+    validate_blocks: bool,
     blk_files: Option<HashMap<u64, BlkFile>>, // maps blk_index to BlkFile
 }
 
@@ -18,12 +67,13 @@ impl ChainStorage {
     pub fn new(options: &ChainOptions) -> Result<Self> {
         Ok(Self {
             coin: options.coin,
+            validate_blocks: options.validate_blocks,
             chain_index: ChainIndex::new(options)?,
             blk_files: options.blockchain_dir.as_ref().map(|x| BlkFile::from_path(x.as_path())).transpose()?,
         })
     }
 
-    /// Returns the block at the given height
+    /// Returns the block at the given height, `None` if it isn't indexed or the node is still writing it
     pub fn get_block(&mut self, height: u64) -> Result<Option<Block>> {
         // Read block
         let block_meta = match self.chain_index.get(height) {
@@ -34,7 +84,14 @@ impl ChainStorage {
         let Some(blk_files) = &mut self.blk_files else { return Ok(None) };
 
         let blk_file = blk_files.get_mut(&block_meta.blk_index).anyhow_with("Block file for block not found")?;
-        let block = blk_file.read_block(block_meta.data_offset, self.coin).anyhow_with("Unable to read block")?;
+        let Some(block) = blk_file.read_block(block_meta.data_offset, self.coin).anyhow_with("Unable to read block")? else {
+            warn_truncated(height, block_meta.blk_index, block_meta.data_offset);
+            return Ok(None);
+        };
+
+        if self.validate_blocks {
+            Self::validate(&block, self.coin, height, block_meta.blk_index, block_meta.data_offset)?;
+        }
 
         // Check if blk file can be closed
         if height >= self.chain_index.max_height_by_blk(block_meta.blk_index) {
@@ -44,19 +101,160 @@ impl ChainStorage {
         Ok(Some(block))
     }
 
+    /// Reads blocks from `from_height` in a background thread, up to `blocks` ahead of the consumer, so disk IO overlaps with parsing. <br/>
+    /// Blocks are sent in the order of the chain index, the channel is closed after the last indexed block, the first block
+    /// which isn't completely written yet or the first error.
+    pub fn prefetch(&self, from_height: u64, blocks: usize) -> kanal::Receiver<Result<RawBlock>> {
+        let (tx, rx) = kanal::bounded::<Result<RawBlock>>(blocks);
+
+        let Some(blk_files) = &self.blk_files else {
+            return rx;
+        };
+
+        let mut blk_files: HashMap<u64, BlkFile> = blk_files.iter().map(|(index, blk_file)| (*index, blk_file.reopen())).collect();
+        let metas = (from_height..=self.max_height())
+            .map_while(|height| self.chain_index.get(height).map(|meta| (height, meta.blk_index, meta.data_offset)))
+            .map(|(height, blk_index, data_offset)| (height, blk_index, data_offset, height >= self.chain_index.max_height_by_blk(blk_index)))
+            .collect_vec();
+
+        std::thread::spawn(move || {
+            for (height, blk_index, data_offset, last_in_file) in metas {
+                let raw = blk_files
+                    .get_mut(&blk_index)
+                    .anyhow_with("Block file for block not found")
+                    .and_then(|blk_file| {
+                        let bytes = blk_file.read_raw_block(data_offset).anyhow_with("Unable to read block")?;
+                        if last_in_file {
+                            blk_file.close();
+                        }
+
+                        Ok(bytes.map(|bytes| RawBlock {
+                            height,
+                            blk_index,
+                            data_offset,
+                            bytes,
+                        }))
+                    })
+                    .transpose();
+
+                let Some(raw) = raw else {
+                    warn_truncated(height, blk_index, data_offset);
+                    return;
+                };
+
+                let failed = raw.is_err();
+                if tx.send(raw).is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        rx
+    }
+
+    /// Parses the block read by [`ChainStorage::prefetch`]
+    pub fn parse_block(&self, raw: RawBlock) -> Result<(u64, Block)> {
+        let size = raw.bytes.len() as u32;
+        let block = Cursor::new(raw.bytes).read_block(size, self.coin).anyhow_with("Unable to read block")?;
+
+        if self.validate_blocks {
+            Self::validate(&block, self.coin, raw.height, raw.blk_index, raw.data_offset)?;
+        }
+
+        Ok((raw.height, block))
+    }
+
+    fn validate(block: &Block, coin: CoinType, height: u64, blk_index: u64, data_offset: u64) -> Result<()> {
+        block.validate(coin).map_err(|e| {
+            anyhow::anyhow!(
+                "Invalid block {} at height {} in blk{:05}.dat at offset {}: {}",
+                block.header.hash,
+                height,
+                blk_index,
+                data_offset,
+                e
+            )
+        })
+    }
+
+    /// Decodes the block of every chain index entry and compares its hash with the indexed one, blk files are scanned in parallel
+    pub fn scan(&self) -> BlkScanReport {
+        let Some(blk_files) = &self.blk_files else {
+            return BlkScanReport::default();
+        };
+
+        let by_file = self
+            .chain_index
+            .block_index
+            .iter()
+            .map(|(height, meta)| (meta.blk_index, (*height, meta.data_offset, meta.block_hash)))
+            .into_group_map();
+
+        let mut issues = by_file
+            .into_par_iter()
+            .flat_map(|(blk_index, blocks)| {
+                let mut blk_file = blk_files.get(&blk_index).map(BlkFile::reopen);
+
+                blocks
+                    .into_iter()
+                    .sorted_unstable_by_key(|(_, data_offset, _)| *data_offset)
+                    .filter_map(|(height, data_offset, expected_hash)| {
+                        let problem = match blk_file.as_mut() {
+                            None => BlkScanProblem::MissingFile,
+                            // Malformed data may panic deep in the decoder, which is exactly what the scan has to report
+                            Some(blk_file) => match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| blk_file.read_block(data_offset, self.coin))) {
+                                Ok(Ok(Some(block))) if block.header.hash == expected_hash => return None,
+                                Ok(Ok(Some(block))) => BlkScanProblem::HashMismatch(block.header.hash),
+                                Ok(Ok(None)) => BlkScanProblem::Truncated,
+                                Ok(Err(e)) => BlkScanProblem::Unreadable(e.to_string()),
+                                Err(_) => BlkScanProblem::Unreadable("decoder panicked".to_string()),
+                            },
+                        };
+
+                        Some(BlkScanIssue {
+                            height,
+                            blk_index,
+                            data_offset,
+                            expected_hash,
+                            problem,
+                        })
+                    })
+                    .collect_vec()
+            })
+            .collect::<Vec<_>>();
+
+        issues.sort_unstable_by_key(|x| x.height);
+
+        BlkScanReport {
+            blocks: self.chain_index.block_index.len() as u64,
+            issues,
+        }
+    }
+
     #[inline]
     pub(crate) const fn max_height(&self) -> u64 {
         self.chain_index.max_height()
     }
 
-    pub fn complete(self) -> Option<CheckPoint> {
+    /// Checkpoint of the indexed blocks below `until_height`, the following ones weren't read from blk files
+    pub fn complete(self, until_height: u64) -> Option<CheckPoint> {
         let iterator = self
             .chain_index
             .block_index
             .into_iter()
+            .filter(|(height, _)| *height < until_height)
             .sorted_unstable_by_key(|x| x.0)
             .map(|(k, v)| BlockId { hash: v.block_hash, height: k });
 
         CheckPoint::from_block_ids(iterator).ok()
     }
 }
+
+/// The index of the node may point past the end of a blk file which is still being written
+fn warn_truncated(height: u64, blk_index: u64, data_offset: u64) {
+    warn!(
+        target: "blkfile",
+        "Block at height {} in blk{:05}.dat at offset {} isn't completely written yet, stopping at the previous block",
+        height, blk_index, data_offset
+    );
+}
@@ -5,7 +5,8 @@ mod chain;
 mod index;
 mod reader;
 
-pub use chain::ChainStorage;
+pub use blk_file::{BlkProbe, probe_blk_dir};
+pub use chain::{BlkScanIssue, BlkScanProblem, BlkScanReport, ChainStorage};
 pub use reader::BlockchainRead;
 
 pub struct ChainOptions {
@@ -13,10 +14,12 @@ pub struct ChainOptions {
     pub range: crate::utils::BlockHeightRange,
     pub coin: CoinType,
     pub index_dir_path: Option<PathBuf>,
+    /// Check merkle root and proof of work of every block read from blk files
+    pub validate_blocks: bool,
 }
 
 impl ChainOptions {
-    pub fn new(path: Option<&str>, index_dir_path: Option<&str>, coin: CoinType, last_height: u32) -> Self {
+    pub fn new(path: Option<&str>, index_dir_path: Option<&str>, coin: CoinType, last_height: u32, validate_blocks: bool) -> Self {
         let dir = path.map(|path| PathBuf::from_str(path).expect("Invalid path"));
         let index_dir_path = index_dir_path.map(|index_dir_path| PathBuf::from_str(index_dir_path).expect("Invalid INDEX_DIR path"));
         let range = crate::utils::BlockHeightRange::new(last_height as u64, None).unwrap();
@@ -26,6 +29,7 @@ impl ChainOptions {
             coin,
             range,
             index_dir_path,
+            validate_blocks,
         }
     }
 }
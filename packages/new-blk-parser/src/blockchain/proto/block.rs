@@ -1,7 +1,8 @@
 use super::*;
 
+use bellscoin::{BlockHash, CompactTarget, Target};
 use blockchain::proto::{
-    Hashed, MerkleBranch,
+    Hashed, MerkleBranch, ToRaw,
     header::BlockHeader,
     tx::{EvaluatedTx, RawTx},
     varuint::VarUint,
@@ -62,6 +63,30 @@ impl Block {
             anyhow::bail!("{}", msg);
         }
     }
+
+    /// Checks merkle root and proof of work of the header.
+    /// For AuxPoW blocks the work is checked on the parent block, which must commit to this block.
+    pub fn validate(&self, coin: CoinType) -> Result<()> {
+        self.verify_merkle_root()?;
+
+        let pow_header = match &self.aux_pow_extension {
+            Some(aux_pow) => {
+                aux_pow.verify(self.header.hash)?;
+                &aux_pow.parent_block
+            }
+            None => &self.header.value,
+        };
+
+        let bits = self.header.value.bits;
+        let target = Target::from_compact(CompactTarget::from_consensus(bits));
+        let pow_hash = BlockHash::from_byte_array(pow_header.pow_hash(coin.pow));
+
+        if !target.is_met_by(pow_hash) {
+            anyhow::bail!("Proof of work {} doesn't meet the target of bits {:#010x}", pow_hash, bits);
+        }
+
+        Ok(())
+    }
 }
 
 impl fmt::Debug for Block {
@@ -82,3 +107,30 @@ pub struct AuxPowExtension {
     pub blockchain_branch: MerkleBranch,
     pub parent_block: BlockHeader,
 }
+
+impl AuxPowExtension {
+    /// Checks that the parent coinbase commits to the aux block and is included in the parent block
+    pub fn verify(&self, block_hash: sha256d::Hash) -> Result<()> {
+        let coinbase_txid = sha256d::Hash::hash(&self.coinbase_tx.to_bytes());
+        let parent_merkle_root = self.coinbase_branch.root(coinbase_txid);
+
+        if parent_merkle_root != self.parent_block.merkle_root {
+            anyhow::bail!(
+                "Invalid AuxPoW coinbase branch!\n  -> expected: {}\n  -> got: {}\n",
+                self.parent_block.merkle_root,
+                parent_merkle_root
+            );
+        }
+
+        // Chain merkle root is stored in the coinbase script in reversed byte order
+        let mut chain_root = self.blockchain_branch.root(block_hash).to_byte_array();
+        chain_root.reverse();
+
+        let script_sig = &self.coinbase_tx.inputs.first().anyhow_with("AuxPoW coinbase has no inputs")?.script_sig;
+        if !script_sig.windows(chain_root.len()).any(|x| x == chain_root) {
+            anyhow::bail!("AuxPoW coinbase doesn't commit to the block {}", block_hash);
+        }
+
+        Ok(())
+    }
+}
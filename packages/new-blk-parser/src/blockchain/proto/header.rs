@@ -1,5 +1,6 @@
 use super::*;
 
+use blockchain::coins::PowAlgorithm;
 use proto::ToRaw;
 
 /// Block Header definition. Exact 80 bytes long
@@ -13,6 +14,24 @@ pub struct BlockHeader {
     pub nonce: u32,
 }
 
+impl BlockHeader {
+    /// Hash of the header which is checked against the target
+    pub fn pow_hash(&self, pow: PowAlgorithm) -> [u8; 32] {
+        let bytes = self.to_bytes();
+
+        match pow {
+            PowAlgorithm::Sha256d => sha256d::Hash::hash(&bytes).to_byte_array(),
+            PowAlgorithm::Scrypt => {
+                // Litecoin-style scrypt: N=1024, r=1, p=1, header is both password and salt
+                let params = scrypt::Params::new(10, 1, 1, 32).expect("Valid scrypt params");
+                let mut hash = [0u8; 32];
+                scrypt::scrypt(&bytes, &bytes, &params, &mut hash).expect("Valid scrypt output length");
+                hash
+            }
+        }
+    }
+}
+
 impl ToRaw for BlockHeader {
     fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::with_capacity(80);
@@ -52,4 +52,21 @@ impl MerkleBranch {
     pub fn new(hashes: Vec<[u8; 32]>, side_mask: u32) -> Self {
         Self { hashes, side_mask }
     }
+
+    /// Computes merkle root for the leaf hash using this branch
+    pub fn root(&self, leaf: sha256d::Hash) -> sha256d::Hash {
+        let mut hash = leaf;
+        let mut side_mask = self.side_mask;
+
+        for branch_hash in &self.hashes {
+            hash = if side_mask & 1 == 1 {
+                sha256d::Hash::hash(&[&branch_hash[..], &hash[..]].concat())
+            } else {
+                sha256d::Hash::hash(&[&hash[..], &branch_hash[..]].concat())
+            };
+            side_mask >>= 1;
+        }
+
+        hash
+    }
 }
@@ -238,7 +238,12 @@ pub fn address_to_fullhash(address: &str, script_type: ScriptType, coin: CoinTyp
             let payload = address_to_payload(address, coin)?.script_pubkey();
             Ok(sha256::Hash::hash(payload.as_bytes()))
         }
-        ScriptType::ScriptHash => sha256::Hash::from_slice(&hex::decode(address).anyhow_with("Invalid hex")?).anyhow_with("Invalid script hash length"),
+        // Electrum convention: sha256 of the script_pubkey, hex-encoded in reversed byte order
+        ScriptType::ScriptHash => {
+            let mut bytes = hex::decode(address).anyhow_with("Invalid hex")?;
+            bytes.reverse();
+            sha256::Hash::from_slice(&bytes).anyhow_with("Invalid script hash length")
+        }
     }
 }
 
@@ -86,6 +86,24 @@ impl From<RawTx> for EvaluatedTx {
     }
 }
 
+impl ToRaw for RawTx {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity((4 + self.in_count.value + self.out_count.value + 4) as usize);
+
+        bytes.extend(&self.version.to_le_bytes());
+        bytes.extend(&self.in_count.to_bytes());
+        for i in &self.inputs {
+            bytes.extend(&i.to_bytes());
+        }
+        bytes.extend(&self.out_count.to_bytes());
+        for o in &self.outputs {
+            bytes.extend(&o.to_bytes());
+        }
+        bytes.extend(&self.locktime.to_le_bytes());
+        bytes
+    }
+}
+
 impl ToRaw for EvaluatedTx {
     fn to_bytes(&self) -> Vec<u8> {
         let mut bytes =
@@ -33,15 +33,32 @@ mod blockchain;
 mod utils;
 
 pub use blockchain::{
-    BlockId, CoinType, LoadBlocks, LoadBlocksArgs,
+    BlockId, CoinType, CustomCoin, LoadBlocks, LoadBlocksArgs, PointerPolicy, PowAlgorithm,
+    parser::{BlkProbe, BlkScanIssue, BlkScanProblem, BlkScanReport, probe_blk_dir},
     proto::{self, ScriptType},
 };
-pub use utils::{Auth, Client};
+pub use utils::{Auth, BlockFormat, Client};
 
 const BOUNDED_CHANNEL_SIZE: usize = 30;
 
+/// Checks that every block of the chain index in `index_dir` can be read from the blk files in `blk_dir`
+pub fn scan_blk_files(blk_dir: &str, index_dir: &str, coin: CoinType) -> Result<BlkScanReport> {
+    let chain = ChainStorage::new(&ChainOptions::new(Some(blk_dir), Some(index_dir), coin, 0, false))?;
+    Ok(chain.scan())
+}
+
 type Result<T> = std::result::Result<T, anyhow::Error>;
 
+/// Sending half of the channel of [`BlockEvent`]s, for feeding blocks which aren't parsed by [`Indexer::parse_blocks`]
+pub type BlockSender = kanal::Sender<BlockEvent>;
+/// Blocks in chain order, each one following the previous or rolling back `reorg_len` blocks first
+pub type BlockReceiver = kanal::Receiver<BlockEvent>;
+
+/// Bounded like the channel of [`Indexer::parse_blocks`], so the producer waits for the consumer
+pub fn block_channel() -> (BlockSender, BlockReceiver) {
+    kanal::bounded(BOUNDED_CHANNEL_SIZE)
+}
+
 pub struct BlockEvent {
     pub id: BlockId,
     pub block: blockchain::proto::block::Block,
@@ -57,6 +74,9 @@ pub struct Indexer {
     pub last_block: BlockId,
     pub reorg_max_len: usize,
     pub client: Arc<Client>,
+    pub validate_blocks: bool,
+    /// Blocks read ahead from blk files while the previous ones are parsed, disabled if zero
+    pub prefetch_blocks: usize,
 }
 
 trait SendChecked {
@@ -79,8 +99,8 @@ fn check_ordering(last_sent_hash: &mut sha256d::Hash, header: &Hashed<proto::hea
 }
 
 impl Indexer {
-    pub fn parse_blocks(self: Arc<Self>) -> kanal::Receiver<BlockEvent> {
-        let (tx, rx) = kanal::bounded::<BlockEvent>(BOUNDED_CHANNEL_SIZE);
+    pub fn parse_blocks(self: Arc<Self>) -> BlockReceiver {
+        let (tx, rx) = block_channel();
 
         std::thread::spawn(move || {
             let mut last_height = {
@@ -94,20 +114,15 @@ impl Indexer {
                 self.index_dir_path.as_deref(),
                 self.coin,
                 self.last_block.height as u32,
+                self.validate_blocks,
             ))
             .unwrap();
 
             let max_height = chain.max_height();
+            // First height which isn't sent from blk files, the node is asked for the following blocks
+            let mut next_height = last_height;
 
-            for height in last_height..=max_height {
-                if self.token.is_cancelled() {
-                    return;
-                }
-
-                let Some(block) = chain.get_block(height).unwrap() else {
-                    break;
-                };
-
+            let mut send = |height: u64, block: blockchain::proto::block::Block| {
                 let event = BlockEvent {
                     id: BlockId { height, hash: block.header.hash },
                     block,
@@ -115,12 +130,43 @@ impl Indexer {
                     tip: max_height,
                 };
 
-                if tx.send_checked(event, &mut last_hash).is_err() {
-                    return;
-                };
+                tx.send_checked(event, &mut last_hash).is_ok()
+            };
+
+            if self.prefetch_blocks > 0 {
+                for raw in chain.prefetch(last_height, self.prefetch_blocks) {
+                    if self.token.is_cancelled() {
+                        return;
+                    }
+
+                    let (height, block) = raw.and_then(|raw| chain.parse_block(raw)).unwrap();
+                    if !send(height, block) {
+                        return;
+                    }
+                    next_height = height + 1;
+                }
+            } else {
+                for height in last_height..=max_height {
+                    if self.token.is_cancelled() {
+                        return;
+                    }
+
+                    let Some(block) = chain.get_block(height).unwrap() else {
+                        break;
+                    };
+
+                    if !send(height, block) {
+                        return;
+                    }
+                    next_height = height + 1;
+                }
             }
 
-            let mut checkpoint = match chain.complete() {
+            if next_height <= max_height {
+                info!("Loading blocks from height {} from the node", next_height);
+            }
+
+            let mut checkpoint = match chain.complete(next_height) {
                 Some(v) => v,
                 None => {
                     last_height = last_height.saturating_sub(1);
@@ -141,29 +187,22 @@ impl Indexer {
                 let best_hash = self.client.get_best_block_hash().unwrap();
 
                 if best_hash != checkpoint.hash() {
+                    // Height and hashes by height are available on every node, unlike the verbose `getblock`, see `BlockFormat`
+                    let best_height = self.client.get_block_count().unwrap();
+
                     loop {
                         if reorg_counter > self.reorg_max_len {
                             panic!("Reorg chain is too long");
                         }
 
-                        let hash = checkpoint.hash();
-                        match self.client.get_block_info(&hash) {
-                            Ok(v) if v.confirmations < 0 => {
-                                reorg_counter += 1;
-                                checkpoint = checkpoint.prev().unwrap();
-                                last_hash = checkpoint.hash();
-                                continue;
-                            }
-                            Err(_) => {
-                                reorg_counter += 1;
-                                checkpoint = checkpoint.prev().unwrap();
-                                last_hash = checkpoint.hash();
-                                continue;
-                            }
-                            _ => {}
-                        };
-
-                        let best_height = self.client.get_block_info(&best_hash).unwrap().height as u64;
+                        // Blocks above the best height or with another hash at their height were reorged out
+                        let height = checkpoint.height();
+                        if height > best_height || self.client.get_block_hash(height).unwrap() != checkpoint.hash() {
+                            reorg_counter += 1;
+                            checkpoint = checkpoint.prev().unwrap();
+                            last_hash = checkpoint.hash();
+                            continue;
+                        }
 
                         while checkpoint.height() < best_height {
                             let next_height = checkpoint.height() + 1;
@@ -281,4 +320,17 @@ mod tests {
 
         assert_eq!(checkpoint.height(), best_block_id.height);
     }
+
+    #[test]
+    fn test_merkle_branch_root() {
+        let leaves = (0..4u8).map(|x| sha256d::Hash::from_byte_array([x; 32])).collect::<Vec<_>>();
+        let root = utils::merkle_root(leaves.clone());
+
+        // Branch of the third leaf: its right sibling, then the hash of the left pair
+        let left_pair = sha256d::Hash::hash(&[&leaves[0][..], &leaves[1][..]].concat());
+        let branch = proto::MerkleBranch::new(vec![leaves[3].to_byte_array(), left_pair.to_byte_array()], 0b10);
+
+        assert_eq!(branch.root(leaves[2]), root);
+        assert_eq!(proto::MerkleBranch::new(vec![], 0).root(leaves[0]), leaves[0]);
+    }
 }
@@ -1,8 +1,13 @@
 use crate::blockchain::parser::BlockchainRead;
-use crate::blockchain::proto::block::Block;
+use crate::blockchain::proto::{Hashed, block::Block, tx::EvaluatedTx};
 
 use super::*;
 
+use std::{
+    sync::{Mutex, OnceLock, RwLock, RwLockReadGuard},
+    time::SystemTime,
+};
+
 /// The different authentication methods for the client.
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub enum Auth {
@@ -33,7 +38,20 @@ pub enum Error {
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// JSON-RPC error code of unknown methods, e.g. `gettxspendingprevout` of nodes before Bitcoin Core 24
+const RPC_METHOD_NOT_FOUND: i32 = -32601;
+
 impl Auth {
+    /// Cookie authentication with the `.cookie` file written by the node, `path` may also be the datadir which contains it
+    pub fn cookie(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Auth::CookieFile(path.join(".cookie"))
+        } else {
+            Auth::CookieFile(path.to_path_buf())
+        }
+    }
+
     /// Convert into the arguments that jsonrpc::Client needs.
     pub fn get_user_pass(self) -> Result<(Option<String>, Option<String>)> {
         match self {
@@ -51,11 +69,56 @@ impl Auth {
     }
 }
 
+/// Cookie file of the client and its modification time when it was read. <br/>
+/// The node writes a new cookie on every restart, so it is read again once the file changes.
+struct Cookie {
+    path: PathBuf,
+    modified: Mutex<Option<SystemTime>>,
+}
+
+/// How `getblock` of the node is called, forks differ in its parameters. Detected by [`Client::probe_block_format`]
+#[derive(Clone, Copy, Debug, Default, Hash, Eq, PartialEq)]
+pub enum BlockFormat {
+    /// Integer `verbosity`: `0` for hex, `1` for the header fields (Bitcoin Core 0.15+)
+    #[default]
+    Verbosity,
+    /// Boolean `verbose`: `false` for hex, `true` for the header fields (older forks)
+    Verbose,
+    /// Only the hash parameter and always hex
+    HexOnly,
+}
+
+impl BlockFormat {
+    fn hex_args(self, hash: &sha256d::Hash) -> Result<Vec<serde_json::Value>> {
+        let hash = serde_json::to_value(hash)?;
+        Ok(match self {
+            BlockFormat::Verbosity => vec![hash, 0.into()],
+            BlockFormat::Verbose => vec![hash, false.into()],
+            BlockFormat::HexOnly => vec![hash],
+        })
+    }
+
+    /// `None` if the node can't return the header fields
+    fn info_args(self, hash: &sha256d::Hash) -> Result<Option<Vec<serde_json::Value>>> {
+        let hash = serde_json::to_value(hash)?;
+        Ok(match self {
+            BlockFormat::Verbosity => Some(vec![hash, 1.into()]),
+            BlockFormat::Verbose => Some(vec![hash, true.into()]),
+            BlockFormat::HexOnly => None,
+        })
+    }
+}
+
 /// Client for the Bitcoin Core daemon or compatible APIs.
 pub struct Client {
-    client: jsonrpc::client::Client,
+    url: String,
+    client: RwLock<jsonrpc::client::Client>,
+    cookie: Option<Cookie>,
     coin: CoinType,
     token: WaitToken,
+    format: OnceLock<BlockFormat>,
+    /// Whether the node has `gettxspendingprevout`, detected by the first [`Client::is_spent_in_mempool`]
+    spending_prevout: OnceLock<bool>,
 }
 
 impl fmt::Debug for Client {
@@ -69,16 +132,58 @@ impl Client {
     ///
     /// Can only return [Err] when using cookie authentication.
     pub fn new(url: &str, auth: Auth, coin: CoinType, token: WaitToken) -> Result<Self> {
+        let cookie = match &auth {
+            Auth::CookieFile(path) => Some(Cookie {
+                path: path.clone(),
+                modified: Mutex::new(fs::metadata(path)?.modified().ok()),
+            }),
+            _ => None,
+        };
+
         let (user, pass) = auth.get_user_pass()?;
         jsonrpc::client::Client::simple_http(url, user, pass)
             .map(|client| Client {
-                client,
+                url: url.to_string(),
+                client: RwLock::new(client),
+                cookie,
                 coin,
                 token,
+                format: OnceLock::new(),
+                spending_prevout: OnceLock::new(),
             })
             .map_err(|e| e.into())
     }
 
+    /// JSON-RPC client with the current credentials, see [`Client::reload_cookie`]
+    fn rpc(&self) -> RwLockReadGuard<'_, jsonrpc::client::Client> {
+        if let Err(e) = self.reload_cookie() {
+            tracing::warn!("Failed to reload RPC cookie: {e}");
+        }
+
+        self.client.read().unwrap()
+    }
+
+    /// Recreates the JSON-RPC client if the cookie file was modified since it was read
+    fn reload_cookie(&self) -> Result<()> {
+        let Some(cookie) = &self.cookie else {
+            return Ok(());
+        };
+
+        let modified = fs::metadata(&cookie.path)?.modified().ok();
+        let mut last_modified = cookie.modified.lock().unwrap();
+        if modified == *last_modified {
+            return Ok(());
+        }
+
+        let (user, pass) = Auth::CookieFile(cookie.path.clone()).get_user_pass()?;
+        *self.client.write().unwrap() = jsonrpc::client::Client::simple_http(&self.url, user, pass)?;
+        *last_modified = modified;
+
+        tracing::info!("RPC cookie reloaded from {}", cookie.path.display());
+
+        Ok(())
+    }
+
     /// Call an `cmd` rpc with given `args` list
     fn call<T: serde::de::DeserializeOwned>(
         &self,
@@ -88,8 +193,10 @@ impl Client {
         let raw = serde_json::value::to_raw_value(args).unwrap();
 
         for _ in 0..10 {
-            let req = self.client.build_request(cmd, Some(&*raw));
-            let resp = self.client.send_request(req);
+            let client = self.rpc();
+            let req = client.build_request(cmd, Some(&*raw));
+            let resp = client.send_request(req);
+            drop(client);
 
             match resp {
                 Ok(resp) => match resp.result() {
@@ -113,17 +220,123 @@ impl Client {
         Err(Error::Cancelled)
     }
 
+    /// Sends a single request without retries to check connectivity and credentials
+    pub fn ping(&self) -> Result<sha256d::Hash> {
+        let client = self.rpc();
+        let req = client.build_request("getbestblockhash", None);
+        Ok(client.send_request(req)?.result()?)
+    }
+
+    /// Hash of the genesis block, sent once without retries like [`Client::ping`]
+    pub fn get_genesis_hash(&self) -> Result<sha256d::Hash> {
+        let raw = serde_json::value::to_raw_value(&[0])?;
+        let client = self.rpc();
+        let req = client.build_request("getblockhash", Some(&*raw));
+        Ok(client.send_request(req)?.result()?)
+    }
+
+    /// Detects how `getblock` of the node is called by requesting the best block as hex with each [`BlockFormat`],
+    /// the first one whose block decodes is used by the client from then on. Sent once without retries like [`Client::ping`]
+    pub fn probe_block_format(&self) -> Result<BlockFormat> {
+        let hash = self.ping()?;
+
+        let mut format = BlockFormat::Verbosity;
+        let mut result = self.try_get_block_as(format, &hash);
+        for fallback in [BlockFormat::Verbose, BlockFormat::HexOnly] {
+            if result.is_ok() {
+                break;
+            }
+            format = fallback;
+            result = self.try_get_block_as(format, &hash);
+        }
+
+        result?;
+        Ok(*self.format.get_or_init(|| format))
+    }
+
+    /// Format detected by [`Client::probe_block_format`], [`BlockFormat::Verbosity`] until it's probed
+    pub fn block_format(&self) -> BlockFormat {
+        self.format.get().copied().unwrap_or_default()
+    }
+
     pub fn get_block(&self, hash: &sha256d::Hash) -> Result<Block> {
-        let block_hex: String = self.call("getblock", &[serde_json::to_value(hash)?, 0.into()])?;
+        let block_hex: String = self.call("getblock", &self.block_format().hex_args(hash)?)?;
+        self.decode_block(block_hex)
+    }
+
+    /// Same as [`Client::get_block`], but sent once without retries, so a failure doesn't stop the indexer
+    pub fn try_get_block(&self, hash: &sha256d::Hash) -> Result<Block> {
+        self.try_get_block_as(self.block_format(), hash)
+    }
+
+    fn try_get_block_as(&self, format: BlockFormat, hash: &sha256d::Hash) -> Result<Block> {
+        let raw = serde_json::value::to_raw_value(&format.hex_args(hash)?)?;
+        let client = self.rpc();
+        let req = client.build_request("getblock", Some(&*raw));
+        let block_hex: String = client.send_request(req)?.result()?;
+        drop(client);
+
+        self.decode_block(block_hex)
+    }
+
+    /// Transaction by txid, confirmed ones require `txindex` on the node. Sent once without retries like [`Client::try_get_block`]
+    pub fn try_get_raw_transaction(&self, txid: &sha256d::Hash) -> Result<Hashed<EvaluatedTx>> {
+        let raw = serde_json::value::to_raw_value(&[serde_json::to_value(txid)?])?;
+        let client = self.rpc();
+        let req = client.build_request("getrawtransaction", Some(&*raw));
+        let tx_hex: String = client.send_request(req)?.result()?;
+        drop(client);
+
+        self.parse_tx(&hex::decode(tx_hex)?)
+    }
+
+    fn decode_block(&self, block_hex: String) -> Result<Block> {
         let block_bytes = hex::decode(block_hex)?;
+        let size = block_bytes.len() as u32;
         let mut block_cursor = std::io::Cursor::new(block_bytes);
         block_cursor
-            .read_block(0, self.coin)
+            .read_block(size, self.coin)
             .map_err(|err| err.into())
     }
 
+    /// Deserializes a raw transaction of the client coin
+    pub fn parse_tx(&self, tx_bytes: &[u8]) -> Result<Hashed<EvaluatedTx>> {
+        let mut tx_cursor = std::io::Cursor::new(tx_bytes);
+        let tx = tx_cursor.read_tx(self.coin)?;
+        if tx_cursor.position() != tx_bytes.len() as u64 {
+            return Err(anyhow::anyhow!("Unexpected data after the transaction").into());
+        }
+
+        Ok(Hashed::double_sha256(EvaluatedTx::from(tx)))
+    }
+
+    /// Relays a raw transaction to the node. <br/>
+    /// Sent once without retries, since rejections of the transaction are final.
+    pub fn send_raw_transaction(&self, tx_hex: &str) -> Result<sha256d::Hash> {
+        let raw = serde_json::value::to_raw_value(&[tx_hex])?;
+        let client = self.rpc();
+        let req = client.build_request("sendrawtransaction", Some(&*raw));
+        Ok(client.send_request(req)?.result()?)
+    }
+
+    /// Fee rate for confirmation within `conf_target` blocks. <br/>
+    /// Sent once without retries like [`Client::try_get_block`], so a node without estimates doesn't stop the indexer.
+    pub fn estimate_smart_fee(&self, conf_target: u16) -> Result<EstimateSmartFeeResult> {
+        let raw = serde_json::value::to_raw_value(&[conf_target])?;
+        let client = self.rpc();
+        let req = client.build_request("estimatesmartfee", Some(&*raw));
+        Ok(client.send_request(req)?.result()?)
+    }
+
+    /// Header fields of the block, not available with [`BlockFormat::HexOnly`]
     pub fn get_block_info(&self, hash: &sha256d::Hash) -> Result<GetBlockResult> {
-        self.call("getblock", &[serde_json::to_value(hash)?, 1.into()])
+        let args = self.block_format().info_args(hash)?.ok_or_else(|| anyhow::anyhow!("getblock of the node returns only hex"))?;
+        self.call("getblock", &args)
+    }
+
+    /// Height of the best block
+    pub fn get_block_count(&self) -> Result<u64> {
+        self.call("getblockcount", &[])
     }
 
     /// Get block hash at a given height
@@ -134,6 +347,47 @@ impl Client {
     pub fn get_best_block_hash(&self) -> Result<sha256d::Hash> {
         self.call("getbestblockhash", &[])
     }
+
+    /// Returns `None` if the output is spent or doesn't exist
+    pub fn get_tx_out(&self, txid: &sha256d::Hash, vout: u32, include_mempool: bool) -> Result<Option<GetTxOutResult>> {
+        self.call("gettxout", &[serde_json::to_value(txid)?, vout.into(), include_mempool.into()])
+    }
+
+    /// Checks whether a confirmed output is spent by an unconfirmed transaction with `gettxspendingprevout` (Bitcoin Core 24+). <br/>
+    /// Nodes without it are asked with `gettxout` whether the output is unspent in the chain but not in the mempool, so outputs spent
+    /// by blocks or unknown to the node aren't reported. Sent once without retries like [`Client::try_get_block`], so a failed check
+    /// doesn't stop the indexer.
+    pub fn is_spent_in_mempool(&self, txid: &sha256d::Hash, vout: u32) -> Result<bool> {
+        if self.spending_prevout.get() != Some(&false) {
+            let prevouts = serde_json::json!([{ "txid": txid, "vout": vout }]);
+            match self.send_once::<Vec<SpendingPrevout>>("gettxspendingprevout", &[prevouts]) {
+                Ok(spending) => {
+                    self.spending_prevout.get_or_init(|| true);
+                    return Ok(spending.iter().any(|x| x.spendingtxid.is_some()));
+                }
+                Err(Error::FailedRequest(jsonrpc::Error::Rpc(e))) if e.code == RPC_METHOD_NOT_FOUND => {
+                    self.spending_prevout.get_or_init(|| false);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let txid = serde_json::to_value(txid)?;
+        let confirmed: Option<GetTxOutResult> = self.send_once("gettxout", &[txid.clone(), vout.into(), false.into()])?;
+        if confirmed.is_none() {
+            return Ok(false);
+        }
+
+        let unspent: Option<GetTxOutResult> = self.send_once("gettxout", &[txid, vout.into(), true.into()])?;
+        Ok(unspent.is_none())
+    }
+
+    fn send_once<T: serde::de::DeserializeOwned>(&self, cmd: &str, args: &[serde_json::Value]) -> Result<T> {
+        let raw = serde_json::value::to_raw_value(args)?;
+        let client = self.rpc();
+        let req = client.build_request(cmd, Some(&*raw));
+        Ok(client.send_request(req)?.result()?)
+    }
 }
 
 #[derive(Clone, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
@@ -154,3 +408,29 @@ pub struct GetBlockResult {
     pub previousblockhash: Option<sha256d::Hash>,
     pub nextblockhash: Option<sha256d::Hash>,
 }
+
+#[derive(Clone, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+pub struct EstimateSmartFeeResult {
+    /// Coins per kvB, not set if the node has no estimate yet
+    pub feerate: Option<f64>,
+    /// Number of blocks the estimate is for
+    pub blocks: Option<u32>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTxOutResult {
+    pub bestblock: sha256d::Hash,
+    pub confirmations: u32,
+    pub value: f64,
+    pub coinbase: bool,
+}
+
+/// Item of `gettxspendingprevout`
+#[derive(Clone, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+struct SpendingPrevout {
+    /// Unconfirmed transaction which spends the output, not set if there is none
+    spendingtxid: Option<sha256d::Hash>,
+}
@@ -3,7 +3,7 @@ use super::*;
 mod client;
 mod range;
 
-pub use client::{Auth, Client};
+pub use client::{Auth, BlockFormat, Client};
 pub use range::BlockHeightRange;
 
 pub fn arr_to_hex(data: &[u8]) -> String {
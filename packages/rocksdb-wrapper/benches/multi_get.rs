@@ -0,0 +1,97 @@
+//! Compares a history page (100 rows) resolved with one `get` per row against a single batched `multi_get`,
+//! and a block worth of prevouts (10k rows) read with a single `multi_get` against `multi_get_chunked`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use rocksdb_wrapper::{RocksDB, RocksTable};
+
+const TABLE: &str = "ADDRESSES";
+const PAGE_SIZE: usize = 100;
+const ROWS: u64 = 100_000;
+const BLOCK_INPUTS: usize = 10_000;
+const MIN_CHUNK: usize = 1_000;
+
+fn open_table(path: &std::path::Path) -> RocksTable<[u8; 32], String> {
+    let db = RocksDB::open_db(path.to_str().unwrap(), [TABLE]);
+    let table = db.table::<[u8; 32], String>(TABLE);
+
+    table.extend((0..ROWS).map(|i| (key(i), format!("address-{i}"))));
+    table.flush();
+
+    table
+}
+
+fn key(i: u64) -> [u8; 32] {
+    let mut key = [0; 32];
+    key[..8].copy_from_slice(&i.wrapping_mul(0x9E37_79B9_7F4A_7C15).to_be_bytes());
+    key
+}
+
+fn page(n: u64) -> Vec<[u8; 32]> {
+    keys(n, PAGE_SIZE)
+}
+
+fn keys(n: u64, count: usize) -> Vec<[u8; 32]> {
+    (0..count as u64).map(|i| key((n * count as u64 + i) % ROWS)).collect()
+}
+
+fn bench_history_page(c: &mut Criterion) {
+    let path = std::env::temp_dir().join(format!("rocksdb-wrapper-bench-{}", std::process::id()));
+    let table = open_table(&path);
+    let mut group = c.benchmark_group("history_page_100_rows");
+    let mut n = 0;
+
+    group.bench_function("get_per_row", |b| {
+        b.iter_batched(
+            || {
+                n += 1;
+                page(n)
+            },
+            |keys| keys.iter().map(|k| table.get(k)).collect::<Vec<_>>(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function("single_multi_get", |b| {
+        b.iter_batched(
+            || {
+                n += 1;
+                page(n)
+            },
+            |keys| table.multi_get(keys.iter()),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+
+    let mut group = c.benchmark_group("block_inputs_10k_rows");
+
+    group.bench_function("single_multi_get", |b| {
+        b.iter_batched(
+            || {
+                n += 1;
+                keys(n, BLOCK_INPUTS)
+            },
+            |keys| table.multi_get(keys.iter()),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("multi_get_chunked", |b| {
+        b.iter_batched(
+            || {
+                n += 1;
+                keys(n, BLOCK_INPUTS)
+            },
+            |keys| table.multi_get_chunked(&keys, MIN_CHUNK, rayon::current_num_threads()),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+    drop(table);
+    let _ = std::fs::remove_dir_all(path);
+}
+
+criterion_group!(benches, bench_history_page);
+criterion_main!(benches);
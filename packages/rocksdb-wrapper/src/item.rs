@@ -12,6 +12,27 @@ pub trait Pebble {
     }
 }
 
+/// Size of the stack buffer used by [`FixedPebble::with_fixed_bytes`]
+pub const MAX_FIXED_PEBBLE_SIZE: usize = 128;
+
+/// Extension of [`Pebble`] for types with a constant encoded size. <br/>
+/// Encodes into a caller-provided stack buffer instead of allocating a `Vec` for every key.
+/// Implementors are expected to forward `Pebble::get_bytes_borrowing` to [`FixedPebble::with_fixed_bytes`].
+pub trait FixedPebble: Pebble {
+    const SIZE: usize;
+
+    /// `dest` is always exactly `Self::SIZE` bytes long
+    fn write_fixed(v: &Self::Inner, dest: &mut [u8]);
+
+    fn with_fixed_bytes<R>(v: &Self::Inner, f: impl FnOnce(&[u8]) -> R) -> R {
+        const { assert!(Self::SIZE <= MAX_FIXED_PEBBLE_SIZE, "FixedPebble::SIZE exceeds stack buffer") };
+        let mut buf = [0u8; MAX_FIXED_PEBBLE_SIZE];
+        let buf = &mut buf[..Self::SIZE];
+        Self::write_fixed(v, buf);
+        (f)(buf)
+    }
+}
+
 impl Pebble for () {
     const FIXED_SIZE: Option<usize> = Some(0);
     type Inner = Self;
@@ -52,6 +73,7 @@ impl Pebble for String {
 
 thread_local! {
     static POSTCARD_BUFFER: RefCell<Vec<Vec<u8>>> = Default::default();
+    static CONSENSUS_BUFFER: RefCell<Vec<Vec<u8>>> = Default::default();
 }
 
 /// Provides rocksdb support for types that implements serde::Serialize and serde::Deserialize (via derive). <br/>
@@ -141,6 +163,15 @@ where
     fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
         bellscoin::consensus::Decodable::consensus_decode(&mut std::io::Cursor::new(&v)).anyhow()
     }
+
+    fn get_bytes_borrowing<R>(v: &Self::Inner, f: impl FnOnce(&[u8]) -> R) -> R {
+        let mut buf = CONSENSUS_BUFFER.with_borrow_mut(|buf| buf.pop()).unwrap_or_default();
+        buf.clear();
+        v.consensus_encode(&mut buf).unwrap();
+        let x = (f)(&buf);
+        CONSENSUS_BUFFER.with_borrow_mut(|x| x.push(buf));
+        x
+    }
 }
 
 /// Wrapper for a type with align of 1
@@ -162,6 +193,10 @@ macro_rules! impl_pebble {
             fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
                 Ok(Self::from_be_bytes((&*v).try_into().anyhow()?))
             }
+
+            fn get_bytes_borrowing<R>(v: &Self::Inner, f: impl FnOnce(&[u8]) -> R) -> R {
+                (f)(&v.to_be_bytes())
+            }
         }
     };
 
@@ -18,6 +18,6 @@ mod storage;
 mod utils;
 
 use internal::{DbInfo, TableInfo};
-pub use item::{Pebble, UsingConsensus, UsingSerde};
-pub use storage::{RocksDB, RocksTable};
+pub use item::{FixedPebble, Pebble, UsingConsensus, UsingSerde, MAX_FIXED_PEBBLE_SIZE};
+pub use storage::{BackgroundThreads, RocksDB, RocksTable, Snapshot};
 use utils::RcUtils;
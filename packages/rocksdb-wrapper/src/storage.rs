@@ -1,31 +1,274 @@
 use std::cmp::Ordering;
+use std::path::Path;
+use std::sync::atomic::{self, AtomicU64};
+use std::sync::OnceLock;
+
+use rayon::{iter::ParallelIterator, slice::ParallelSlice};
 
 use super::*;
+use utils::prefix_successor;
+
+/// Directory of the cold instance inside checkpoints of a tiered database
+const COLD_CHECKPOINT_DIR: &str = "cold";
+/// File in the main instance with the path of the cold one, so the database is opened whole without the configuration
+const COLD_TIER_FILE: &str = "COLD_TIER";
+/// Keys copied in one write batch when a table is moved to the cold instance
+const MOVE_BATCH_LEN: usize = 10_000;
+
+/// Set by [`RocksDB::set_background_threads`], instances opened before keep the default limits
+static BACKGROUND_THREADS: OnceLock<BackgroundThreads> = OnceLock::new();
+
+thread_local! {
+    /// Writes collected by [`RocksDB::atomic`] running on the thread, one batch per instance
+    static PENDING: RefCell<Option<Vec<(RocksDB, WriteBatchWithTransaction<true>)>>> = const { RefCell::new(None) };
+}
+
+/// Drops the writes collected by [`RocksDB::atomic`] if its closure panics
+struct PendingGuard;
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        PENDING.set(None);
+    }
+}
+
+/// Thread pools of the RocksDB environment, which is shared by all instances of the process
+#[derive(Clone, Copy, Debug)]
+pub struct BackgroundThreads {
+    /// Threads of the high priority pool, which flushes memtables
+    pub flush: u32,
+    /// Threads of the low priority pool, which compacts SST files
+    pub compaction: u32,
+    /// Lowers CPU and IO priority of the compaction threads, so they yield to the other threads of the process
+    pub lower_compaction_priority: bool,
+}
 
 #[derive(Clone)]
 pub struct RocksDB {
     pub db: Arc<rocksdb::OptimisticTransactionDB>,
+    cfs: Arc<Vec<String>>,
+    written_bytes: Arc<AtomicU64>,
+    /// Second instance with the tables kept on slower storage, see [`RocksDB::open_tiered`]
+    cold: Option<Arc<RocksDB>>,
 }
 
 impl RocksDB {
+    /// Opens the cold instance too if the database has one, either inside a checkpoint or recorded by [`RocksDB::open_tiered`]
     pub fn open_db(path: &str, tables: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        match Self::cold_path(path) {
+            Some(cold_path) => Self::open_tiered(path, tables, &cold_path, &[] as &[&str]),
+            None => Self::open_instance(path, tables.into_iter().map(|x| x.as_ref().to_string()).collect(), Default::default()),
+        }
+    }
+
+    /// Keeps the `cold` tables in a second instance at `cold_path`, e.g. on a cheaper and slower disk. A batch can't span
+    /// both instances, so [`RocksDB::atomic`] writes the cold one first and [`RocksTable::set_with`] needs both tables in one. <br/>
+    /// Cold tables which still have data in the main instance are moved first, so a tier can be added to an existing database.
+    /// Tables are never moved back, a table removed from `cold` stays in the cold instance.
+    pub fn open_tiered(path: &str, tables: impl IntoIterator<Item = impl AsRef<str>>, cold_path: &str, cold: &[impl AsRef<str>]) -> Self {
+        // A checkpoint (e.g. a restored backup) must be paired with its own cold instance, not the live one
+        let checkpoint = Path::new(path).join(COLD_CHECKPOINT_DIR);
+        let cold_path = if checkpoint.is_dir() { checkpoint.to_string_lossy().into_owned() } else { cold_path.to_string() };
+
+        let opts = rocksdb::Options::default();
+        let cold_existing = rocksdb::DB::list_cf(&opts, &cold_path).unwrap_or_default();
+        let (cold_tables, hot_tables): (Vec<_>, Vec<_>) = tables
+            .into_iter()
+            .map(|x| x.as_ref().to_string())
+            .partition(|x| cold.iter().any(|cf| cf.as_ref() == x) || cold_existing.contains(x));
+
+        let existing = rocksdb::DB::list_cf(&opts, path).unwrap_or_default();
+        let moved = cold_tables.iter().filter(|x| existing.contains(x)).cloned().collect::<Vec<_>>();
+
+        let written_bytes = Arc::<AtomicU64>::default();
+        let mut db = Self::open_instance(path, hot_tables.iter().chain(&moved).cloned().collect(), written_bytes.clone());
+        let cold_db = Self::open_instance(&cold_path, cold_tables, written_bytes);
+
+        for cf in &moved {
+            db.move_table(&cold_db, cf);
+        }
+
+        std::fs::write(Path::new(path).join(COLD_TIER_FILE), &cold_path).unwrap();
+        db.cfs = hot_tables.arc();
+        db.cold = Some(cold_db.arc());
+        db
+    }
+
+    /// Sizes the thread pools, must be called before any instance is opened. Only the first call has effect. <br/>
+    /// Each instance may run as many background jobs at once as there are threads in both pools, RocksDB splits them
+    /// between flushes and compactions and grows a pool which is too small for its share.
+    pub fn set_background_threads(threads: BackgroundThreads) {
+        if BACKGROUND_THREADS.set(threads).is_err() {
+            return;
+        }
+
+        // The default environment is a singleton, so the pools outlive the handle
+        let mut env = rocksdb::Env::new().unwrap();
+        env.set_high_priority_background_threads(threads.flush as i32);
+        env.set_low_priority_background_threads(threads.compaction as i32);
+        if threads.lower_compaction_priority {
+            env.lower_thread_pool_cpu_priority();
+            env.lower_thread_pool_io_priority();
+        }
+    }
+
+    fn open_instance(path: &str, cfs: Vec<String>, written_bytes: Arc<AtomicU64>) -> Self {
         let mut opts = rocksdb::Options::default();
         opts.create_if_missing(true);
         opts.create_missing_column_families(true);
+        if let Some(threads) = BACKGROUND_THREADS.get() {
+            opts.set_max_background_jobs((threads.flush + threads.compaction) as i32);
+        }
+
+        let db = rocksdb::OptimisticTransactionDB::open_cf(&opts, path, &cfs).unwrap().arc();
+        Self {
+            db,
+            cfs: cfs.arc(),
+            written_bytes,
+            cold: None,
+        }
+    }
+
+    fn cold_path(path: &str) -> Option<String> {
+        let checkpoint = Path::new(path).join(COLD_CHECKPOINT_DIR);
+        if checkpoint.is_dir() {
+            return Some(checkpoint.to_string_lossy().into_owned());
+        }
+
+        std::fs::read_to_string(Path::new(path).join(COLD_TIER_FILE)).ok().map(|x| x.trim().to_string())
+    }
+
+    /// Copies the table to the cold instance and drops it here, an interrupted move is repeated on the next open
+    fn move_table(&self, cold: &RocksDB, cf: &str) {
+        tracing::info!("Moving table {cf} to the cold instance");
+
+        let (from, to) = (self.db.cf_handle(cf).unwrap(), cold.db.cf_handle(cf).unwrap());
+        let mut w = WriteBatchWithTransaction::<true>::default();
+        for (k, v) in self.db.iterator_cf(&from, rocksdb::IteratorMode::Start).map(Result::unwrap) {
+            w.put_cf(&to, k, v);
+            if w.len() >= MOVE_BATCH_LEN {
+                cold.db.write(std::mem::take(&mut w)).unwrap();
+            }
+        }
+        cold.db.write(w).unwrap();
+        cold.db.flush_cf(&to).unwrap();
+
+        drop(from);
+        self.db.drop_cf(cf).unwrap();
+    }
+
+    /// Size of all writes since the database was opened
+    pub fn written_bytes(&self) -> u64 {
+        self.written_bytes.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Database wide integer property, e.g. `rocksdb.actual-delayed-write-rate`, summed with the cold instance
+    pub fn property_int(&self, name: &str) -> u64 {
+        let cold = self.cold.as_ref().map(|cold| cold.property_int(name)).unwrap_or_default();
+        self.db.property_int_value(name).ok().flatten().unwrap_or_default() + cold
+    }
+
+    /// Sum of a column family integer property over all column families, e.g. `rocksdb.estimate-pending-compaction-bytes`
+    pub fn property_int_sum(&self, name: &str) -> u64 {
+        let cold = self.cold.as_ref().map(|cold| cold.property_int_sum(name)).unwrap_or_default();
+        self.cfs
+            .iter()
+            .filter_map(|cf| self.db.cf_handle(cf))
+            .filter_map(|cf| self.db.property_int_value_cf(&cf, name).ok().flatten())
+            .sum::<u64>()
+            + cold
+    }
+
+    /// Syncs the write-ahead log to disk
+    pub fn sync_wal(&self) {
+        self.db.flush_wal(true).unwrap();
+        if let Some(cold) = &self.cold {
+            cold.sync_wal();
+        }
+    }
+
+    /// Consistent copy of the database at `path`, which must not exist. SST files are hard linked when `path` is on the same filesystem.
+    /// The cold instance is checkpointed into the `cold` directory of the copy, so it's copied if it's on another filesystem.
+    pub fn checkpoint(&self, path: &str) -> anyhow::Result<()> {
+        rocksdb::checkpoint::Checkpoint::new(&*self.db)?.create_checkpoint(path)?;
+        if let Some(cold) = &self.cold {
+            cold.checkpoint(&Path::new(path).join(COLD_CHECKPOINT_DIR).to_string_lossy())?;
+        }
+        Ok(())
+    }
+
+    /// Collects the writes of all tables made by `f` on the current thread and writes them in one batch per instance when it returns,
+    /// so after a crash either all or none of them are in the instance. Reads in `f` see the state before it. <br/>
+    /// The batch of the cold instance is written before the one of this instance. A nested call joins the outer one,
+    /// nothing is written if `f` panics.
+    pub fn atomic<T>(&self, f: impl FnOnce() -> T) -> T {
+        if PENDING.with_borrow(Option::is_some) {
+            return f();
+        }
+
+        PENDING.set(Some(vec![]));
+        let guard = PendingGuard;
+        let result = f();
+        let mut batches = PENDING.take().unwrap_or_default();
+        drop(guard);
 
-        let db = rocksdb::OptimisticTransactionDB::open_cf(&opts, path, tables).unwrap().arc();
-        Self { db }
+        batches.sort_by_key(|(db, _)| Arc::ptr_eq(&db.db, &self.db));
+        for (db, w) in batches {
+            db.count_written(w.size_in_bytes());
+            db.db.write(w).unwrap();
+        }
+
+        result
     }
 
+    fn count_written(&self, bytes: usize) {
+        self.written_bytes.fetch_add(bytes as u64, atomic::Ordering::Relaxed);
+    }
+
+    /// Tables of the cold instance are bound to it, so they're read and written there
     pub fn table<K: Pebble, V: Pebble>(&self, cf: impl ToString) -> RocksTable<K, V> {
-        RocksTable {
-            db: self.clone(),
-            cf: cf.to_string(),
-            __marker: PhantomData,
+        let cf = cf.to_string();
+        let db = match &self.cold {
+            Some(cold) if cold.cfs.contains(&cf) => RocksDB::clone(cold),
+            _ => self.clone(),
+        };
+
+        RocksTable { db, cf, __marker: PhantomData }
+    }
+
+    /// The cold instance is snapshotted right after this one, so both have the same writes only if none are made meanwhile
+    pub fn snapshot(&self) -> Snapshot<'_> {
+        Snapshot {
+            db: self,
+            snapshot: self.db.snapshot(),
+            cold: self.cold.as_deref().map(|cold| Box::new(cold.snapshot())),
         }
     }
 }
 
+/// Reads all tables as of the moment it was taken, while the database is written
+pub struct Snapshot<'a> {
+    db: &'a RocksDB,
+    snapshot: rocksdb::SnapshotWithThreadMode<'a, rocksdb::OptimisticTransactionDB>,
+    cold: Option<Box<Snapshot<'a>>>,
+}
+
+impl Snapshot<'_> {
+    /// Visits the raw rows of the table in key order, tables of the cold instance are read there
+    pub fn for_each_row(&self, cf: &str, mut f: impl FnMut(&[u8], &[u8]) -> anyhow::Result<()>) -> anyhow::Result<()> {
+        if let Some(cold) = self.cold.as_ref().filter(|cold| cold.db.cfs.iter().any(|x| x == cf)) {
+            return cold.for_each_row(cf, f);
+        }
+
+        let cf = self.db.db.cf_handle(cf).anyhow_with(format!("Table {cf} doesn't exist"))?;
+        for row in self.snapshot.iterator_cf(&cf, rocksdb::IteratorMode::Start) {
+            let (k, v) = row?;
+            f(&k, &v)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 pub struct RocksTable<K: Pebble, V: Pebble> {
     pub db: RocksDB,
@@ -52,10 +295,13 @@ impl<K: Pebble, V: Pebble> RocksTable<K, V> {
         self.db.db.cf_handle(&self.cf).unwrap()
     }
 
+    /// Number of keys estimated by RocksDB without a scan, overwritten and removed keys may still be counted
+    pub fn estimate_len(&self) -> u64 {
+        self.db.db.property_int_value_cf(&self.cf(), "rocksdb.estimate-num-keys").ok().flatten().unwrap_or_default()
+    }
+
     pub fn get(&self, k: impl Borrow<K::Inner>) -> Option<V::Inner> {
-        self.db
-            .db
-            .get_cf(&self.cf(), K::get_bytes(k.borrow()))
+        K::get_bytes_borrowing(k.borrow(), |k| self.db.db.get_cf(&self.cf(), k))
             .unwrap()
             .map(|x| V::from_bytes(Cow::Owned(x)))
             .map(|x| x.unwrap_or_else(|e| _panic("get", &self.cf, e)))
@@ -74,6 +320,28 @@ impl<K: Pebble, V: Pebble> RocksTable<K, V> {
             .collect()
     }
 
+    /// [`RocksTable::multi_get`] for large key sets. Keys are split into at most `parallelism` chunks of at least `min_chunk` keys,
+    /// which are read concurrently on the rayon pool. Values are in the order of the keys.
+    pub fn multi_get_chunked(&self, keys: &[K::Inner], min_chunk: usize, parallelism: usize) -> Vec<Option<V::Inner>>
+    where
+        K: Sync,
+        V: Sync,
+        K::Inner: Sync,
+        V::Inner: Send,
+    {
+        let chunk = keys.len().div_ceil(parallelism.max(1)).max(min_chunk.max(1));
+        if keys.len() <= chunk {
+            return self.multi_get(keys.iter());
+        }
+
+        keys.par_chunks(chunk)
+            .map(|chunk| self.multi_get(chunk.iter()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
     pub fn multi_get_kv<'a>(&'a self, keys: impl IntoIterator<Item = &'a K::Inner>, panic_if_not_exists: bool) -> Vec<(&'a K::Inner, V::Inner)> {
         let keys_bytes = keys.into_iter().map(|x| (x, K::get_bytes(x))).collect::<Vec<_>>();
 
@@ -97,11 +365,29 @@ impl<K: Pebble, V: Pebble> RocksTable<K, V> {
     }
 
     pub fn set(&self, k: impl Borrow<K::Inner>, v: impl Borrow<V::Inner>) {
-        self.db.db.put_cf(&self.cf(), K::get_bytes(k.borrow()), V::get_bytes(v.borrow())).unwrap();
+        self.batch(|w| K::get_bytes_borrowing(k.borrow(), |k| V::get_bytes_borrowing(v.borrow(), |v| w.put_cf(&self.cf(), k, v))));
     }
 
     pub fn remove(&self, k: impl Borrow<K::Inner>) {
-        self.db.db.delete_cf(&self.cf(), K::get_bytes(k.borrow())).unwrap();
+        self.batch(|w| K::get_bytes_borrowing(k.borrow(), |k| w.delete_cf(&self.cf(), k)));
+    }
+
+    /// Sets the key together with the key of `other` in one batch, so either both or none are written. <br/>
+    /// Both tables must be in the same instance, batches can't span the cold one.
+    pub fn set_with<K2: Pebble, V2: Pebble>(
+        &self,
+        k: impl Borrow<K::Inner>,
+        v: impl Borrow<V::Inner>,
+        other: &RocksTable<K2, V2>,
+        other_k: impl Borrow<K2::Inner>,
+        other_v: impl Borrow<V2::Inner>,
+    ) {
+        assert!(Arc::ptr_eq(&self.db.db, &other.db.db), "Tables '{}' and '{}' are in different instances", self.cf, other.cf);
+
+        self.batch(|w| {
+            K::get_bytes_borrowing(k.borrow(), |k| V::get_bytes_borrowing(v.borrow(), |v| w.put_cf(&self.cf(), k, v)));
+            K2::get_bytes_borrowing(other_k.borrow(), |k| V2::get_bytes_borrowing(other_v.borrow(), |v| w.put_cf(&other.cf(), k, v)));
+        });
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (K::Inner, V::Inner)> + '_ {
@@ -113,6 +399,45 @@ impl<K: Pebble, V: Pebble> RocksTable<K, V> {
             .map(|(k, v)| (k.unwrap_or_else(|e| _panic("iter key", &self.cf, e)), v.unwrap_or_else(|e| _panic("iter val", &self.cf, e))))
     }
 
+    /// Entries in descending key order
+    pub fn iter_rev(&self) -> impl Iterator<Item = (K::Inner, V::Inner)> + '_ {
+        self.bounded(None, None, true)
+    }
+
+    /// Entries with keys starting with the bytes of `prefix`, which is encoded like the leading fields of the key,
+    /// e.g. the address for keys starting with an address. <br/>
+    /// The bounds are passed to RocksDB, so keys outside of the prefix are never read in either direction.
+    pub fn prefix<'a, P: Pebble>(&'a self, prefix: &P::Inner, reversed: bool) -> Box<dyn Iterator<Item = (K::Inner, V::Inner)> + 'a> {
+        let lower = P::get_bytes(prefix).into_owned();
+        let upper = prefix_successor(&lower);
+
+        Box::new(self.bounded(Some(lower), upper, reversed))
+    }
+
+    fn bounded(&self, lower: Option<Vec<u8>>, upper: Option<Vec<u8>>, reversed: bool) -> impl Iterator<Item = (K::Inner, V::Inner)> + '_ {
+        let mut opts = rocksdb::ReadOptions::default();
+        if let Some(lower) = lower {
+            opts.set_iterate_lower_bound(lower);
+        }
+        if let Some(upper) = upper {
+            opts.set_iterate_upper_bound(upper);
+        }
+
+        let mode = if reversed { rocksdb::IteratorMode::End } else { rocksdb::IteratorMode::Start };
+
+        self.db
+            .db
+            .iterator_cf_opt(&self.cf(), opts, mode)
+            .flatten()
+            .map(|(k, v)| (K::from_bytes(Cow::Owned(k.into_vec())), V::from_bytes(Cow::Owned(v.into_vec()))))
+            .map(|(k, v)| {
+                (
+                    k.unwrap_or_else(|e| _panic("bounded key", &self.cf, e)),
+                    v.unwrap_or_else(|e| _panic("bounded val", &self.cf, e)),
+                )
+            })
+    }
+
     pub fn range<'a>(&'a self, range: impl RangeBounds<&'a K::Inner>, reversed: bool) -> Box<dyn Iterator<Item = (K::Inner, V::Inner)> + 'a> {
         enum Position {
             Start,
@@ -193,9 +518,6 @@ impl<K: Pebble, V: Pebble> RocksTable<K, V> {
     }
 
     pub fn retain(&self, f: impl Fn(K::Inner, V::Inner) -> bool) {
-        let mut w = WriteBatchWithTransaction::<true>::default();
-        let cf = self.cf();
-
         let iter = self
             .db
             .db
@@ -205,36 +527,190 @@ impl<K: Pebble, V: Pebble> RocksTable<K, V> {
             .map(|(k, v, x)| (!(f)(k, v), x))
             .filter(|(b, _)| *b)
             .map(|(_, x)| x);
-        for k in iter {
-            w.delete_cf(&cf, k);
-        }
 
-        self.write(w);
+        self.batch(|w| {
+            let cf = self.cf();
+            for k in iter {
+                w.delete_cf(&cf, k);
+            }
+        });
     }
 
     pub fn flush(&self) {
         self.db.db.flush_cf(&self.cf()).unwrap();
     }
 
+    /// Written at once, even inside [`RocksDB::atomic`]
     pub fn write(&self, w: WriteBatchWithTransaction<true>) {
+        self.db.count_written(w.size_in_bytes());
         self.db.db.write(w).unwrap();
     }
 
-    pub fn extend(&self, kv: impl IntoIterator<Item = (impl Borrow<K::Inner>, impl Borrow<V::Inner>)>) {
-        let mut w = WriteBatchWithTransaction::<true>::default();
-        let cf = self.cf();
-        for (k, v) in kv {
-            w.put_cf(&cf, K::get_bytes(k.borrow()), V::get_bytes(v.borrow()));
+    /// Adds the writes to the batch of the instance if [`RocksDB::atomic`] runs on the thread, otherwise writes them at once
+    fn batch(&self, f: impl FnOnce(&mut WriteBatchWithTransaction<true>)) {
+        let mut f = Some(f);
+        PENDING.with_borrow_mut(|pending| {
+            let Some(pending) = pending else {
+                return;
+            };
+
+            let i = match pending.iter().position(|(db, _)| Arc::ptr_eq(&db.db, &self.db.db)) {
+                Some(i) => i,
+                None => {
+                    pending.push((self.db.clone(), Default::default()));
+                    pending.len() - 1
+                }
+            };
+            (f.take().unwrap())(&mut pending[i].1);
+        });
+
+        if let Some(f) = f {
+            let mut w = WriteBatchWithTransaction::<true>::default();
+            f(&mut w);
+            self.write(w);
         }
-        self.write(w);
+    }
+
+    pub fn extend(&self, kv: impl IntoIterator<Item = (impl Borrow<K::Inner>, impl Borrow<V::Inner>)>) {
+        self.batch(|w| {
+            let cf = self.cf();
+            for (k, v) in kv {
+                K::get_bytes_borrowing(k.borrow(), |k| V::get_bytes_borrowing(v.borrow(), |v| w.put_cf(&cf, k, v)));
+            }
+        });
     }
 
     pub fn remove_batch(&self, k: impl IntoIterator<Item = impl Borrow<K::Inner>>) {
-        let mut w = WriteBatchWithTransaction::<true>::default();
-        let cf = self.cf();
-        for k in k {
-            w.delete_cf(&cf, K::get_bytes(k.borrow()));
+        self.batch(|w| {
+            let cf = self.cf();
+            for k in k {
+                K::get_bytes_borrowing(k.borrow(), |k| w.delete_cf(&cf, k));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Path of the database, set for the child process of [`killed_block_resumes_exactly`] which dies inside the batch
+    const KILL_ENV: &str = "ROCKSDB_WRAPPER_KILLED_DB";
+    const TABLES: [&str; 3] = ["balances", "history", "last_block"];
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rocksdb-wrapper-{name}-{}", std::process::id()));
+        std::fs::remove_dir_all(&path).ok();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// History is kept in the cold instance, so the block spans both
+    fn open(path: &str) -> RocksDB {
+        RocksDB::open_tiered(path, TABLES, &format!("{path}-cold"), &["history"])
+    }
+
+    /// Overwrites and removes balances, appends history and sets the last block. The process is aborted before the write `kill_at`.
+    fn write_block(db: &RocksDB, height: u32, kill_at: Option<u32>) {
+        let balances = db.table::<u32, u64>("balances");
+        let history = db.table::<u64, u32>("history");
+        let last_block = db.table::<(), u32>("last_block");
+
+        db.atomic(|| {
+            balances.remove_batch([height - 1]);
+            for i in 0..10 {
+                if kill_at == Some(i) {
+                    std::process::abort();
+                }
+                balances.set(height + i, u64::from(height) * 100 + u64::from(i));
+                history.extend([((u64::from(height) << 32) | u64::from(i), i)]);
+            }
+            last_block.set((), height);
+        });
+    }
+
+    fn dump(db: &RocksDB) -> Vec<Vec<(Vec<u8>, Vec<u8>)>> {
+        TABLES
+            .iter()
+            .map(|cf| {
+                db.table::<Cow<'static, [u8]>, Cow<'static, [u8]>>(cf)
+                    .iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn remove(path: &str) {
+        std::fs::remove_dir_all(path).ok();
+        std::fs::remove_dir_all(format!("{path}-cold")).ok();
+    }
+
+    #[test]
+    fn killed_block_resumes_exactly() {
+        if let Ok(path) = std::env::var(KILL_ENV) {
+            write_block(&open(&path), 2, Some(5));
+            unreachable!("the block must be killed");
         }
-        self.write(w);
+
+        let reference = temp_path("reference");
+        let expected = {
+            let db = open(&reference);
+            write_block(&db, 1, None);
+            write_block(&db, 2, None);
+            dump(&db)
+        };
+
+        let path = temp_path("killed");
+        let before = {
+            let db = open(&path);
+            write_block(&db, 1, None);
+            dump(&db)
+        };
+
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["killed_block_resumes_exactly", "--test-threads=1"])
+            .env(KILL_ENV, &path)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(!status.success(), "the child process must die inside the batch");
+
+        let db = open(&path);
+        assert_eq!(dump(&db), before, "writes of the killed block must not be visible");
+
+        write_block(&db, 2, None);
+        assert_eq!(dump(&db), expected, "resumed block must give the state of an uninterrupted run");
+
+        drop(db);
+        remove(&path);
+        remove(&reference);
+    }
+
+    #[test]
+    fn panicked_batch_writes_nothing() {
+        let path = temp_path("panicked");
+        let db = open(&path);
+        write_block(&db, 1, None);
+        let before = dump(&db);
+
+        let balances = db.table::<u32, u64>("balances");
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            db.atomic(|| {
+                balances.set(100, 1);
+                // Nested calls join the outer batch
+                db.atomic(|| balances.set(101, 1));
+                panic!("block failed");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(dump(&db), before);
+
+        // Writes outside of a batch go through at once again
+        balances.set(100, 1);
+        assert_eq!(balances.get(100), Some(1));
+
+        drop((balances, db));
+        remove(&path);
     }
 }
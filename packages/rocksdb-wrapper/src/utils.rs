@@ -8,6 +8,19 @@ pub trait RcUtils: Sized {
 
 impl<T: Sized> RcUtils for T {}
 
+/// Smallest key which is greater than all keys starting with the prefix, `None` if the prefix is empty or all `0xff`
+pub fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut upper = prefix.to_vec();
+    while let Some(last) = upper.pop() {
+        if last != u8::MAX {
+            upper.push(last + 1);
+            return Some(upper);
+        }
+    }
+
+    None
+}
+
 #[macro_export]
 macro_rules! generate_db_code {
     ($($name:ident: $key_type:ty => $value_type:ty),* $(,)?) => {
@@ -15,9 +28,13 @@ macro_rules! generate_db_code {
             $(
                 pub $name: super::RocksTable<$key_type, $value_type>,
             )*
+            rocks: RocksDB,
         }
 
         impl DB {
+            /// Names of the tables as declared
+            pub const TABLES: &'static [&'static str] = &[$(stringify!($name)),*];
+
             pub fn open(path: &str) -> Self {
                 let db = RocksDB::open_db(
                     path,
@@ -28,13 +45,38 @@ macro_rules! generate_db_code {
                     ],
                 );
 
+                Self::from_rocks(db)
+            }
+
+            /// Keeps the `cold` tables (declared names) in a second instance at `cold_path`, see [`RocksDB::open_tiered`]
+            pub fn open_tiered(path: &str, cold_path: &str, cold: &[impl AsRef<str>]) -> Self {
+                let db = RocksDB::open_tiered(
+                    path,
+                    [
+                        $(
+                            stringify!($name).to_uppercase().as_str(),
+                        )*
+                    ],
+                    cold_path,
+                    &cold.iter().map(|x| x.as_ref().to_uppercase()).collect::<Vec<_>>(),
+                );
+
+                Self::from_rocks(db)
+            }
+
+            fn from_rocks(db: RocksDB) -> Self {
                 Self {
                     $(
                         $name: db.table(stringify!($name).to_uppercase().as_str()),
                     )*
+                    rocks: db,
                 }
             }
 
+            pub fn rocks(&self) -> &RocksDB {
+                &self.rocks
+            }
+
             pub fn flush_all(&self) {
                 $(
                     self.$name.flush();
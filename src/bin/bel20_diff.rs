@@ -0,0 +1,3 @@
+fn main() {
+    std::process::exit(bel20_core::run_diff());
+}
@@ -1,4 +1,6 @@
-use std::str::FromStr;
+use std::{path::Path, str::FromStr};
+
+use itertools::Itertools;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Blockchain {
@@ -14,6 +16,54 @@ pub enum BlockchainParseError {
     UnknownBlockchain,
 }
 
+impl Blockchain {
+    /// Name of the `nint_blk::CoinType` indexed on the network, `None` if the combination isn't supported
+    pub fn coin_name(self, network: bellscoin::Network) -> Option<&'static str> {
+        SUPPORTED_COINS.iter().find(|(blockchain, x, _, _)| *blockchain == self && *x == network).map(|(_, _, _, coin)| *coin)
+    }
+
+    /// Value of `p` in token payloads
+    pub fn protocol(self) -> &'static str {
+        match self {
+            Blockchain::Bellscoin => "bel-20",
+            Blockchain::Dogecoin => "drc-20",
+            Blockchain::Pepecoin => "prc-20",
+            Blockchain::Litecoin => "ltc-20",
+        }
+    }
+
+    /// Inscriptions are revealed in the leaf script of a taproot script-path spend, otherwise in the script sig of a P2SH spend
+    pub fn has_taproot(self) -> bool {
+        matches!(self, Blockchain::Bellscoin | Blockchain::Litecoin)
+    }
+}
+
+/// `BLOCKCHAIN` and `NETWORK` combinations with a built-in coin: blockchain, network, value of `NETWORK`, coin name
+pub const SUPPORTED_COINS: &[(Blockchain, bellscoin::Network, &str, &str)] = &[
+    (Blockchain::Bellscoin, bellscoin::Network::Bellscoin, "mainnet", "bellscoin"),
+    (Blockchain::Bellscoin, bellscoin::Network::Testnet, "testnet", "bellscoin-testnet"),
+    (Blockchain::Dogecoin, bellscoin::Network::Bellscoin, "mainnet", "dogecoin"),
+    (Blockchain::Dogecoin, bellscoin::Network::Testnet, "testnet", "dogecoin-testnet"),
+    (Blockchain::Pepecoin, bellscoin::Network::Bellscoin, "mainnet", "pepecoin"),
+    (Blockchain::Pepecoin, bellscoin::Network::Testnet, "testnet", "pepecoin-testnet"),
+    (Blockchain::Litecoin, bellscoin::Network::Bellscoin, "mainnet", "litecoin"),
+    (Blockchain::Litecoin, bellscoin::Network::Testnet, "testnet", "litecoin-testnet"),
+];
+
+/// Human readable list of [`SUPPORTED_COINS`]
+pub fn supported_coins() -> String {
+    SUPPORTED_COINS
+        .iter()
+        .map(|(blockchain, _, network, coin)| format!("{}/{} ({})", format!("{blockchain:?}").to_lowercase(), network, coin))
+        .join(", ")
+}
+
+/// Reads the definition of a coin which isn't built into `nint_blk` from a JSON file, see `COIN_CONFIG`
+pub fn load_custom_coin(path: &Path) -> anyhow::Result<nint_blk::CustomCoin> {
+    let data = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&data)?)
+}
+
 impl FromStr for Blockchain {
     type Err = BlockchainParseError;
 
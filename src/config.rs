@@ -5,32 +5,124 @@ use std::fmt;
 #[derive(Clone, Debug)]
 pub struct Config {
     pub blk_dir: Option<String>,
+    pub validate_blocks: bool,
+    pub blk_prefetch_blocks: usize,
     pub rpc_url: String,
-    pub rpc_user: String,
-    pub rpc_pass: String,
+    pub rpc_user: Option<String>,
+    pub rpc_pass: Option<String>,
+    pub rpc_cookie_path: Option<String>,
     pub blockchain: Blockchain,
     pub index_dir: Option<String>,
     pub network: Network,
+    pub coin_config: Option<String>,
+    pub coin_override: Option<String>,
     pub jubilee_height: usize,
+    pub pointer_policy: nint_blk::PointerPolicy,
+    pub witness_scan_height: Option<u32>,
+    pub max_partial_parts: Option<usize>,
+    pub max_inscription_bytes: Option<usize>,
+    pub max_inscriptions_per_tx: Option<usize>,
+    pub max_envelopes_per_input: Option<usize>,
     pub start_height: u32,
     pub server_url: String,
+    pub api_public_url: Option<String>,
+    pub admin_token: Option<String>,
     pub db_path: String,
+    pub cold_db_path: Option<String>,
+    pub cold_tables: Vec<String>,
+    pub rocksdb_flush_threads: Option<u32>,
+    pub rocksdb_compaction_threads: Option<u32>,
+    pub rocksdb_low_priority_compaction: bool,
+    pub mempool_check: bool,
+    pub bootstrap_serve: bool,
+    pub bootstrap_peer_url: Option<String>,
+    pub inscription_history: bool,
+    pub history_compaction_depth: Option<u32>,
+    pub history_checkpoint_interval: u32,
+    pub poh_v2_height: Option<u32>,
+    pub max_write_mbps: Option<u64>,
+    pub partials_gc_depth: Option<u32>,
+    pub holders_snapshot_interval: u32,
+    pub token_cache_mb: Option<usize>,
+    pub replication_url: Option<String>,
+    pub standby: bool,
+    pub simulate_reorg: bool,
+    pub state_digest_interval: Option<u32>,
+    pub backup_dir: Option<String>,
+    pub backup_interval_hours: Option<u64>,
+    pub backup_keep: Option<usize>,
+    pub backup_s3_url: Option<String>,
+    pub backup_s3_region: String,
+    pub backup_s3_access_key: Option<String>,
+    pub backup_s3_secret_key: Option<String>,
+    pub supply_audit_interval_hours: Option<u64>,
+    pub spot_audit_interval_secs: Option<u64>,
+    pub slow_query_ms: Option<u64>,
+    pub tick_policy_file: Option<String>,
+    pub concurrent_blocks: Option<usize>,
+    pub http_cache_max_age_secs: u64,
 }
 
 impl Config {
     pub fn new() -> Self {
         Self {
             blk_dir: crate::BLK_DIR.clone(),
+            validate_blocks: *crate::VALIDATE_BLOCKS,
+            blk_prefetch_blocks: *crate::BLK_PREFETCH_BLOCKS,
             rpc_url: crate::URL.clone(),
             rpc_user: crate::USER.clone(),
             rpc_pass: crate::PASS.clone(),
+            rpc_cookie_path: crate::RPC_COOKIE_PATH.clone(),
             blockchain: crate::BLOCKCHAIN.clone(),
             index_dir: crate::INDEX_DIR.clone(),
             network: *crate::NETWORK,
+            coin_config: crate::COIN_CONFIG.clone(),
+            coin_override: crate::COIN_OVERRIDE.clone(),
             jubilee_height: *crate::JUBILEE_HEIGHT,
+            pointer_policy: crate::COIN.pointer_policy,
+            witness_scan_height: *crate::WITNESS_SCAN_HEIGHT,
+            max_partial_parts: *crate::MAX_PARTIAL_PARTS,
+            max_inscription_bytes: *crate::MAX_INSCRIPTION_BYTES,
+            max_inscriptions_per_tx: *crate::MAX_INSCRIPTIONS_PER_TX,
+            max_envelopes_per_input: *crate::MAX_ENVELOPES_PER_INPUT,
             start_height: *crate::START_HEIGHT,
             server_url: crate::SERVER_URL.clone(),
+            api_public_url: crate::API_PUBLIC_URL.clone(),
+            admin_token: crate::ADMIN_TOKEN.clone(),
             db_path: crate::DB_PATH.clone(),
+            cold_db_path: crate::COLD_DB_PATH.clone(),
+            cold_tables: crate::COLD_TABLES.clone(),
+            rocksdb_flush_threads: *crate::ROCKSDB_FLUSH_THREADS,
+            rocksdb_compaction_threads: *crate::ROCKSDB_COMPACTION_THREADS,
+            rocksdb_low_priority_compaction: *crate::ROCKSDB_LOW_PRIORITY_COMPACTION,
+            mempool_check: *crate::MEMPOOL_CHECK,
+            bootstrap_serve: *crate::BOOTSTRAP_SERVE,
+            bootstrap_peer_url: crate::BOOTSTRAP_PEER_URL.clone(),
+            inscription_history: *crate::INSCRIPTION_HISTORY,
+            history_compaction_depth: *crate::HISTORY_COMPACTION_DEPTH,
+            history_checkpoint_interval: *crate::HISTORY_CHECKPOINT_INTERVAL,
+            poh_v2_height: *crate::POH_V2_HEIGHT,
+            max_write_mbps: *crate::MAX_WRITE_MBPS,
+            partials_gc_depth: *crate::PARTIALS_GC_DEPTH,
+            holders_snapshot_interval: *crate::HOLDERS_SNAPSHOT_INTERVAL,
+            token_cache_mb: *crate::TOKEN_CACHE_MB,
+            replication_url: crate::REPLICATION_URL.clone(),
+            standby: *crate::STANDBY,
+            simulate_reorg: *crate::SIMULATE_REORG,
+            state_digest_interval: *crate::STATE_DIGEST_INTERVAL,
+            backup_dir: crate::BACKUP_DIR.clone(),
+            backup_interval_hours: *crate::BACKUP_INTERVAL_HOURS,
+            backup_keep: *crate::BACKUP_KEEP,
+            backup_s3_url: crate::BACKUP_S3_URL.clone(),
+            backup_s3_region: crate::BACKUP_S3_REGION.clone(),
+            backup_s3_access_key: crate::BACKUP_S3_ACCESS_KEY.clone(),
+            backup_s3_secret_key: crate::BACKUP_S3_SECRET_KEY.clone(),
+            supply_audit_interval_hours: *crate::SUPPLY_AUDIT_INTERVAL_HOURS,
+            spot_audit_interval_secs: *crate::SPOT_AUDIT_INTERVAL_SECS,
+            slow_query_ms: *crate::SLOW_QUERY_MS,
+            tick_policy_file: crate::TICK_POLICY_FILE.clone(),
+            concurrent_blocks: *crate::CONCURRENT_BLOCKS,
+            http_cache_max_age_secs: *crate::HTTP_CACHE_MAX_AGE_SECS,
         }
     }
 
@@ -46,16 +138,62 @@ impl fmt::Debug for RedactedConfig<'_> {
         let config = self.0;
         f.debug_struct("Config")
             .field("blk_dir", &config.blk_dir)
+            .field("validate_blocks", &config.validate_blocks)
+            .field("blk_prefetch_blocks", &config.blk_prefetch_blocks)
             .field("rpc_url", &RedactedStr(&config.rpc_url))
-            .field("rpc_user", &RedactedStr(&config.rpc_user))
-            .field("rpc_pass", &RedactedStr(&config.rpc_pass))
+            .field("rpc_user", &config.rpc_user.as_deref().map(RedactedStr))
+            .field("rpc_pass", &config.rpc_pass.as_deref().map(RedactedStr))
+            .field("rpc_cookie_path", &config.rpc_cookie_path)
             .field("blockchain", &config.blockchain)
             .field("index_dir", &config.index_dir)
             .field("network", &config.network)
+            .field("coin_config", &config.coin_config)
+            .field("coin_override", &config.coin_override)
             .field("jubilee_height", &config.jubilee_height)
+            .field("pointer_policy", &config.pointer_policy)
+            .field("witness_scan_height", &config.witness_scan_height)
+            .field("max_partial_parts", &config.max_partial_parts)
+            .field("max_inscription_bytes", &config.max_inscription_bytes)
+            .field("max_inscriptions_per_tx", &config.max_inscriptions_per_tx)
+            .field("max_envelopes_per_input", &config.max_envelopes_per_input)
             .field("start_height", &config.start_height)
             .field("server_url", &config.server_url)
+            .field("api_public_url", &config.api_public_url)
+            .field("admin_token", &config.admin_token.as_deref().map(RedactedStr))
             .field("db_path", &config.db_path)
+            .field("cold_db_path", &config.cold_db_path)
+            .field("cold_tables", &config.cold_tables)
+            .field("rocksdb_flush_threads", &config.rocksdb_flush_threads)
+            .field("rocksdb_compaction_threads", &config.rocksdb_compaction_threads)
+            .field("rocksdb_low_priority_compaction", &config.rocksdb_low_priority_compaction)
+            .field("mempool_check", &config.mempool_check)
+            .field("bootstrap_serve", &config.bootstrap_serve)
+            .field("bootstrap_peer_url", &config.bootstrap_peer_url)
+            .field("inscription_history", &config.inscription_history)
+            .field("history_compaction_depth", &config.history_compaction_depth)
+            .field("history_checkpoint_interval", &config.history_checkpoint_interval)
+            .field("poh_v2_height", &config.poh_v2_height)
+            .field("max_write_mbps", &config.max_write_mbps)
+            .field("partials_gc_depth", &config.partials_gc_depth)
+            .field("holders_snapshot_interval", &config.holders_snapshot_interval)
+            .field("token_cache_mb", &config.token_cache_mb)
+            .field("replication_url", &config.replication_url.as_deref().map(RedactedStr))
+            .field("standby", &config.standby)
+            .field("simulate_reorg", &config.simulate_reorg)
+            .field("state_digest_interval", &config.state_digest_interval)
+            .field("backup_dir", &config.backup_dir)
+            .field("backup_interval_hours", &config.backup_interval_hours)
+            .field("backup_keep", &config.backup_keep)
+            .field("backup_s3_url", &config.backup_s3_url)
+            .field("backup_s3_region", &config.backup_s3_region)
+            .field("backup_s3_access_key", &config.backup_s3_access_key.as_deref().map(RedactedStr))
+            .field("backup_s3_secret_key", &config.backup_s3_secret_key.as_deref().map(RedactedStr))
+            .field("supply_audit_interval_hours", &config.supply_audit_interval_hours)
+            .field("spot_audit_interval_secs", &config.spot_audit_interval_secs)
+            .field("slow_query_ms", &config.slow_query_ms)
+            .field("tick_policy_file", &config.tick_policy_file)
+            .field("concurrent_blocks", &config.concurrent_blocks)
+            .field("http_cache_max_age_secs", &config.http_cache_max_age_secs)
             .finish()
     }
 }
@@ -5,27 +5,564 @@ pub use structs::*;
 
 rocksdb_wrapper::generate_db_code! {
     token_to_meta: LowerCaseTokenTick => UsingSerde<TokenMetaDB>,
+    token_extras: LowerCaseTokenTick => String,
+    token_skeleton: TokenSkeletonKey => (),
+    deployer_tokens: AddressToken => (),
+    token_completion_height: LowerCaseTokenTick => u32,
     address_location_to_transfer: AddressLocation => UsingSerde<TransferProtoDB>,
     address_token_to_balance: AddressToken => UsingSerde<TokenBalance>,
+    // tokens of the address with a nonzero balance, see `TokenBalance::is_held`
+    address_held_tokens: AddressToken => (),
     address_token_to_history: AddressTokenIdDB => UsingSerde<HistoryValue>,
+    address_token_to_checkpoint: AddressTokenIdDB => UsingSerde<HistoryCheckpoint>,
+    // first block whose history isn't rolled up into checkpoints, see `HistoryCompactor`
+    history_compacted_height: () => u32,
+    address_token_history_stats: AddressToken => UsingSerde<HistoryStats>,
     block_info: u32 => BlockInfo,
+    // median of the timestamps of the block and the 10 blocks before it, not set for blocks indexed before it was stored
+    block_median_time: u32 => u32,
+    time_bucket: u32 => UsingSerde<TimeBucket>,
+    block_summary: u32 => BlockSummary,
+    block_hash_to_height: UsingConsensus<BlockHash> => u32,
     prevouts: UsingConsensus<OutPoint> => TxPrevout,
     outpoint_to_partials: UsingConsensus<OutPoint> => Partials,
+    partials_height: UsingConsensus<OutPoint> => u32,
     outpoint_to_inscription_offsets: UsingConsensus<OutPoint> => HashSet<u64>,
     last_block: () => u32,
+    // equal to `last_block`, both are written in the batch of the block, see `DB::commit_block`
+    last_committed_block_marker: () => u32,
+    // last block at the moment of a clean shutdown, removed on startup
+    shutdown_marker: () => u32,
     last_history_id: () => u64,
     proof_of_history: u32 => UsingConsensus<sha256::Hash>,
+    proof_of_history_version: u32 => u8,
+    // hash of the block events linked with the previous proof, not set for blocks indexed before it was stored
+    proof_of_history_events_hash: u32 => UsingConsensus<sha256::Hash>,
+    balance_root: u32 => UsingConsensus<sha256::Hash>,
+    block_balance_leaves: u32 => UsingSerde<Vec<BalanceLeaf>>,
+    // digest of the full token state, every `STATE_DIGEST_INTERVAL` blocks
+    state_digest: u32 => UsingSerde<StateDigestDB>,
+    address_token_balance_height: AddressTokenHeight => (),
+    holders_snapshot: TokenHeight => UsingSerde<Vec<SortedByBalance>>,
+    holders_snapshot_ticks: u32 => UsingSerde<Vec<OriginalTokenTick>>,
     block_events: u32 => Vec<AddressTokenIdDB>,
     fullhash_to_address: FullHash => String,
     outpoint_to_event: UsingConsensus<OutPoint> => AddressTokenIdDB,
+    txid_to_batch: UsingConsensus<Txid> => u32,
     token_id_to_event: TokenId => AddressTokenIdDB,
+    block_inscription_stats: u32 => UsingSerde<InscriptionStatsDB>,
+    block_fee_stats: u32 => UsingSerde<FeeStatsDB>,
+    // cumulative fees of the valid mints of the token
+    token_mint_fees: LowerCaseTokenTick => u64,
+    token_mint_activity: TokenHeight => UsingSerde<MintActivityDB>,
+    token_first_mint: LowerCaseTokenTick => UsingSerde<FirstMintDB>,
+    // deploys of the block blocked by `TICK_POLICY_FILE`, for audit
+    rejected_deploys: u32 => UsingSerde<Vec<RejectedDeployDB>>,
+    // reports of supply audits by id, see `SupplyAudits`
+    supply_audit: u64 => UsingSerde<SupplyAuditDB>,
+    reorg_journal: u32 => UsingSerde<ReorgHistoryBlock>,
+    event_outbox: u64 => UsingSerde<Vec<(AddressTokenIdDB, HistoryValue)>>,
+    outbox_offsets: String => u64,
+    token_access_stats: TokenAccessKey => u64,
+    webhooks: u64 => UsingSerde<WebhookDB>,
+    webhook_status: u64 => UsingSerde<WebhookStatus>,
+    // append-only record of the admin actions, see `rest::admin::audit_admin`
+    admin_audit: u64 => UsingSerde<AdminAuditDB>,
+    // hashes of the last blocks acknowledged by the replication sink
+    replication_acked: u32 => UsingConsensus<BlockHash>,
+    // blocks and proofs of history received from the primary by a standby, see `Standby`
+    standby_primary_hash: u32 => UsingConsensus<BlockHash>,
+    standby_primary_proof: u32 => UsingConsensus<sha256::Hash>,
+    location_to_inscriptions: Location => UsingSerde<Vec<InscriptionId>>,
+    inscription_history: InscriptionHistoryKey => UsingSerde<InscriptionHistoryValue>,
+    // current location of the inscriptions, the last one of their `inscription_history`
+    inscription_location: InscriptionId => Location,
+    // curses of the inscriptions created since the CF was added, uncursed ones aren't stored
+    inscription_curses: InscriptionId => UsingSerde<Vec<InscriptionCurse>>,
+    // inscriptions created or moved into the fees of the block, which the miner received in the coinbase
+    block_leaked: u32 => UsingSerde<Vec<LeakedInscriptionDB>>,
+    // blocks with leaked inscriptions of the address, by the owner of the spent output
+    address_leaked: AddressHeight => (),
+    // origin of the token events by id, `ActionOrigin::Normal` ones aren't stored
+    history_origin: u64 => UsingSerde<ActionOrigin>,
+    // first block whose event origins are stored, see `DB::init_history_origin_height`
+    history_origin_height: () => u32,
 }
 
 impl DB {
+    /// Tables which may be kept in the cold instance. It's written before the batch of the block in the main instance,
+    /// so rows of a block whose main batch was lost by a crash must be told apart by their height and removed on startup.
+    pub const COLD_TABLES_ALLOWED: &[&str] = &["address_token_to_history", "block_events"];
+
     pub fn load_token_accounts(&self, keys: Vec<AddressToken>) -> HashMap<AddressToken, TokenBalance> {
         self.address_token_to_balance.multi_get_kv(keys.iter(), false).into_iter().map(|(k, v)| (*k, v)).collect()
     }
 
+    /// Other deployed ticks with the same skeleton, see [`tick_skeleton`]
+    pub fn token_lookalikes(&self, tick: OriginalTokenTick) -> Vec<OriginalTokenTick> {
+        let from = TokenSkeletonKey {
+            skeleton: tick_skeleton(&tick.0),
+            tick: Default::default(),
+        };
+        let to = TokenSkeletonKey {
+            tick: [u8::MAX; 4].into(),
+            ..from.clone()
+        };
+
+        self.token_skeleton
+            .range(&from..=&to, false)
+            .map(|(key, _)| key)
+            .filter(|key| key.skeleton == from.skeleton && key.tick != tick)
+            .map(|key| key.tick)
+            .collect()
+    }
+
+    /// Indexes skeletons of tokens deployed before the index existed
+    pub fn backfill_token_skeletons(&self) {
+        if self.token_skeleton.iter().next().is_some() {
+            return;
+        }
+
+        self.token_skeleton
+            .extend(self.token_to_meta.iter().map(|(_, meta)| (TokenSkeletonKey::new(meta.proto.tick), ())));
+    }
+
+    /// Ticks deployed by the address in the order of their bytes
+    pub fn deployer_tokens(&self, deployer: FullHash) -> Vec<OriginalTokenTick> {
+        let from = AddressToken {
+            address: deployer,
+            token: Default::default(),
+        };
+        let to = AddressToken {
+            address: deployer,
+            token: [u8::MAX; 4].into(),
+        };
+
+        self.deployer_tokens.range(&from..=&to, false).map(|(key, _)| key.token).collect()
+    }
+
+    /// Keeps `address_held_tokens` in sync with written balances
+    pub fn update_held_tokens(&self, balances: &[(AddressToken, TokenBalance)]) {
+        let (held, emptied): (Vec<_>, Vec<_>) = balances.iter().partition(|(_, balance)| balance.is_held());
+        self.address_held_tokens.remove_batch(emptied.into_iter().map(|(key, _)| key));
+        self.address_held_tokens.extend(held.into_iter().map(|(key, _)| (key, ())));
+    }
+
+    /// Indexes held tokens of balances written before the index existed
+    pub fn backfill_held_tokens(&self) {
+        if self.address_held_tokens.iter().next().is_some() {
+            return;
+        }
+
+        for chunk in &self.address_token_to_balance.iter().filter(|(_, balance)| balance.is_held()).chunks(10_000) {
+            self.address_held_tokens.extend(chunk.map(|(key, _)| (key, ())));
+        }
+    }
+
+    /// Current locations of the inscriptions moved before the index existed, taken from the last row of their history
+    pub fn backfill_inscription_locations(&self) {
+        if self.inscription_location.iter().next().is_some() {
+            return;
+        }
+
+        // Rows of an inscription are ordered by height and index in the block
+        let locations = self
+            .inscription_history
+            .iter()
+            .map(|(key, value)| (key.id, value.location))
+            .coalesce(|prev, next| if prev.0 == next.0 { Ok(next) } else { Err((prev, next)) });
+
+        for chunk in &locations.chunks(10_000) {
+            self.inscription_location.extend(chunk);
+        }
+    }
+
+    /// Indexes deployers of tokens deployed before the index existed
+    pub fn backfill_deployer_tokens(&self) {
+        if self.deployer_tokens.iter().next().is_some() {
+            return;
+        }
+
+        self.deployer_tokens.extend(self.token_to_meta.iter().map(|(_, meta)| (meta.proto.deployer_token(), ())));
+    }
+
+    /// Completion heights of tokens completed before the index existed, taken from their last mint event. <br/>
+    /// Tokens whose mints were rolled up by the history compactor are left without one.
+    pub fn backfill_token_completion_heights(&self) {
+        if self.token_completion_height.iter().next().is_some() {
+            return;
+        }
+
+        let heights = self
+            .token_to_meta
+            .iter()
+            .filter(|(_, meta)| meta.is_completed())
+            .filter_map(|(tick, meta)| {
+                let from = TokenId { token: meta.proto.tick, id: 0 };
+                let to = TokenId {
+                    token: meta.proto.tick,
+                    id: u64::MAX,
+                };
+
+                let height = self
+                    .token_id_to_event
+                    .range(&from..=&to, true)
+                    .filter_map(|(_, key)| self.address_token_to_history.get(key))
+                    .find(|value| matches!(value.action, TokenHistoryDB::Mint { .. }))?
+                    .height;
+
+                Some((tick, height))
+            })
+            .collect_vec();
+
+        self.token_completion_height.extend(heights);
+    }
+
+    pub fn backfill_token_first_mints(&self) {
+        if self.token_first_mint.iter().next().is_some() {
+            return;
+        }
+
+        let first_mints = self
+            .token_to_meta
+            .iter()
+            .filter(|(_, meta)| meta.proto.mint_count > 0)
+            .filter_map(|(tick, meta)| {
+                let from = TokenId { token: meta.proto.tick, id: 0 };
+                let to = TokenId {
+                    token: meta.proto.tick,
+                    id: u64::MAX,
+                };
+
+                let (key, value) = self
+                    .token_id_to_event
+                    .range(&from..=&to, false)
+                    .filter_map(|(_, key)| self.address_token_to_history.get(&key).map(|value| (key, value)))
+                    .find(|(_, value)| matches!(value.action, TokenHistoryDB::Mint { .. }))?;
+
+                let first_mint = FirstMintDB {
+                    height: value.height,
+                    txid: value.action.outpoint().txid,
+                    minter: key.address,
+                };
+
+                Some((tick, first_mint))
+            })
+            .collect_vec();
+
+        self.token_first_mint.extend(first_mints);
+    }
+
+    pub fn add_block_time(&self, height: u32, created: u32) {
+        let bucket = created / TIME_BUCKET_SECS;
+        let value = match self.time_bucket.get(bucket) {
+            Some(x) => TimeBucket {
+                from_height: x.from_height.min(height),
+                to_height: x.to_height.max(height),
+            },
+            None => TimeBucket {
+                from_height: height,
+                to_height: height,
+            },
+        };
+
+        self.time_bucket.set(bucket, value);
+    }
+
+    /// Called for the last block only, so no other block of the bucket is above it
+    pub fn remove_block_time(&self, height: u32, created: u32) {
+        let bucket = created / TIME_BUCKET_SECS;
+        let Some(value) = self.time_bucket.get(bucket) else {
+            return;
+        };
+
+        if value.from_height >= height {
+            self.time_bucket.remove(bucket);
+        } else if value.to_height >= height {
+            self.time_bucket.set(
+                bucket,
+                TimeBucket {
+                    to_height: height - 1,
+                    ..value
+                },
+            );
+        }
+    }
+
+    /// Median of the timestamps of the block and the indexed blocks before it, which unlike header timestamps never decreases
+    pub fn median_time_past(&self, height: u32, created: u32) -> u32 {
+        let from = height.saturating_sub(MEDIAN_TIME_SPAN - 1);
+        let mut times = self.block_info.range(&from..&height, false).map(|(_, info)| info.created).chain([created]).collect_vec();
+        times.sort_unstable();
+        times[times.len() / 2]
+    }
+
+    /// Median time past and header timestamp of the indexed blocks among the heights
+    pub fn block_times(&self, heights: impl IntoIterator<Item = u32>) -> HashMap<u32, BlockTimes> {
+        let heights = heights.into_iter().collect::<BTreeSet<_>>();
+        let median_times = self.block_median_time.multi_get_kv(heights.iter(), true).into_iter().map(|(k, v)| (*k, v)).collect::<HashMap<_, _>>();
+
+        self.block_info
+            .multi_get_kv(heights.iter(), true)
+            .into_iter()
+            .map(|(height, info)| {
+                let times = BlockTimes {
+                    median_time: median_times.get(height).copied().unwrap_or(info.created),
+                    block_time: info.created,
+                };
+                (*height, times)
+            })
+            .collect()
+    }
+
+    /// Last write of every block and of every rolled back one, inside the batch of [`RocksDB::atomic`] with the data of the block.
+    /// `last_block` is set with its commit marker in one batch even outside of it, see `Server::verify_commit_marker`
+    pub fn commit_block(&self, height: u32) {
+        self.last_block.set_with((), height, &self.last_committed_block_marker, (), height);
+    }
+
+    /// First history id which isn't rolled up into checkpoints by `HistoryCompactor`, zero if the history isn't compacted
+    pub fn first_uncompacted_id(&self) -> u64 {
+        let Some(height) = self.history_compacted_height.get(()) else {
+            return 0;
+        };
+
+        let first = self.block_events.range(&height.., false).find_map(|(_, events)| events.first().map(|x| x.id));
+        first.unwrap_or(u64::MAX)
+    }
+
+    /// Origins of the events of earlier blocks aren't known, the blocks would have to be parsed again
+    pub fn init_history_origin_height(&self) {
+        if self.history_origin_height.get(()).is_none() {
+            self.history_origin_height.set((), self.last_block.get(()).map(|x| x + 1).unwrap_or_default());
+        }
+    }
+
+    /// Origin of the event, `None` if it's indexed before the origins were stored
+    pub fn history_origins(&self, events: &[(AddressTokenIdDB, HistoryValue)]) -> Vec<Option<ActionOrigin>> {
+        let from_height = self.history_origin_height.get(()).unwrap_or(u32::MAX);
+        let origins = self
+            .history_origin
+            .multi_get_kv(events.iter().map(|(key, _)| &key.id), false)
+            .into_iter()
+            .map(|(id, origin)| (*id, origin))
+            .collect::<HashMap<_, _>>();
+
+        events
+            .iter()
+            .map(|(key, value)| (value.height >= from_height).then(|| origins.get(&key.id).copied().unwrap_or_default()))
+            .collect()
+    }
+
+    /// Median times past of blocks indexed before they were stored
+    pub fn backfill_median_times(&self) {
+        if self.block_median_time.iter().next().is_some() {
+            return;
+        }
+
+        let mut window = vec![];
+        let mut median_times = vec![];
+        for (height, info) in self.block_info.iter() {
+            window.push(info.created);
+            if window.len() > MEDIAN_TIME_SPAN as usize {
+                window.remove(0);
+            }
+
+            let mut times = window.clone();
+            times.sort_unstable();
+            median_times.push((height, times[times.len() / 2]));
+        }
+
+        self.block_median_time.extend(median_times);
+    }
+
+    /// Indexes timestamps of blocks indexed before the index existed
+    pub fn backfill_time_buckets(&self) {
+        if self.time_bucket.iter().next().is_some() {
+            return;
+        }
+
+        let mut buckets = HashMap::<u32, TimeBucket>::new();
+        for (height, info) in self.block_info.iter() {
+            buckets
+                .entry(info.created / TIME_BUCKET_SECS)
+                .and_modify(|x| {
+                    x.from_height = x.from_height.min(height);
+                    x.to_height = x.to_height.max(height);
+                })
+                .or_insert(TimeBucket {
+                    from_height: height,
+                    to_height: height,
+                });
+        }
+
+        self.time_bucket.extend(buckets);
+    }
+
+    /// Event counters of the (address, tick) pairs with history indexed before the counters existed
+    pub fn backfill_history_stats(&self) {
+        if self.address_token_history_stats.iter().next().is_some() {
+            return;
+        }
+
+        let mut stats = HashMap::<AddressToken, HistoryStats>::new();
+        for (key, value) in self.address_token_to_history.iter() {
+            stats.entry(key.into()).or_insert_with(|| HistoryStats::new(value.height)).push(value.height);
+        }
+
+        self.address_token_history_stats.extend(stats);
+    }
+
+    /// Heights of the blocks with timestamps in the bounds, rounded to [`TIME_BUCKET_SECS`]
+    pub fn heights_by_time(&self, from_ts: Option<u32>, to_ts: Option<u32>) -> Option<RangeInclusive<u32>> {
+        let from = from_ts.unwrap_or_default() / TIME_BUCKET_SECS;
+        let to = to_ts.unwrap_or(u32::MAX) / TIME_BUCKET_SECS;
+        if from > to {
+            return None;
+        }
+
+        self.time_bucket
+            .range(&from..=&to, false)
+            .map(|(_, x)| x.from_height..=x.to_height)
+            .reduce(|a, b| *a.start().min(b.start())..=*a.end().max(b.end()))
+    }
+
+    /// Range of history ids of the events in blocks with timestamps in the bounds, empty if there are none. <br/>
+    /// History ids grow with the height, so time filters of history queries are applied as id bounds.
+    pub fn history_ids_by_time(&self, from_ts: Option<u32>, to_ts: Option<u32>) -> std::ops::Range<u64> {
+        if from_ts.is_none() && to_ts.is_none() {
+            return 0..u64::MAX;
+        }
+
+        let Some(heights) = self.heights_by_time(from_ts, to_ts) else {
+            return 0..0;
+        };
+
+        let (from, to) = heights.into_inner();
+        let first = self.block_events.range(&from..=&to, false).find_map(|(_, events)| events.first().map(|x| x.id));
+        let last = self.block_events.range(&from..=&to, true).find_map(|(_, events)| events.last().map(|x| x.id));
+
+        match (first, last) {
+            (Some(first), Some(last)) => first..last + 1,
+            _ => 0..0,
+        }
+    }
+
+    /// Holders of the tick after the block at `height` sorted by balance, from the last holders snapshot at or below the height
+    /// and the balance changes of the following blocks. `None` if the height is below the first snapshot.
+    pub fn holders_at(&self, tick: OriginalTokenTick, height: u32) -> Option<BTreeSet<SortedByBalance>> {
+        let (snapshot_height, _) = self.holders_snapshot_ticks.range(..=&height, true).next()?;
+
+        // Ticks are snapshotted at every snapshot height after a change, so the last snapshot of the tick is valid at `snapshot_height`
+        let from = TokenHeight { tick, height: 0 };
+        let to = TokenHeight {
+            tick,
+            height: snapshot_height,
+        };
+        let mut balances: HashMap<FullHash, Fixed128> = self
+            .holders_snapshot
+            .range(&from..=&to, true)
+            .next()
+            .map(|(_, holders)| holders.into_iter().map(|x| (x.1, x.0)).collect())
+            .unwrap_or_default();
+
+        if snapshot_height < height {
+            let from = snapshot_height + 1;
+            for (_, leaves) in self.block_balance_leaves.range(&from..=&height, false) {
+                for leaf in leaves.into_iter().filter(|leaf| leaf.token == tick) {
+                    let balance = leaf.balance + leaf.transferable_balance;
+                    if balance.is_zero() {
+                        balances.remove(&leaf.address);
+                    } else {
+                        balances.insert(leaf.address, balance);
+                    }
+                }
+            }
+        }
+
+        Some(balances.into_iter().map(|(address, balance)| SortedByBalance(balance, address)).collect())
+    }
+
+    /// Balance leaf of the (address, tick) in the block, `None` if its balance didn't change in the block
+    pub fn balance_leaf(&self, key: AddressToken, height: u32) -> Option<BalanceLeaf> {
+        let mut leaves = self.block_balance_leaves.get(height)?;
+        let index = leaves.binary_search_by_key(&(key.address, key.token), |leaf| (leaf.address, leaf.token)).ok()?;
+
+        Some(leaves.swap_remove(index))
+    }
+
+    /// Total (available + transferable) balance of the (address, tick) after each block from `from` where it changed, at most `limit` blocks. <br/>
+    /// Taken from the balance leaves of the blocks, the history of blocks indexed before the leaves were stored is replayed.
+    pub fn balance_changes(&self, key: AddressToken, from: u32, limit: usize) -> Vec<(u32, Fixed128)> {
+        // Leaves are stored for every block since the first one which has them
+        let covered = self.block_balance_leaves.iter().next().map(|(height, _)| height).unwrap_or(u32::MAX);
+
+        let mut changes = vec![];
+        let mut last = Fixed128::default();
+
+        if from < covered {
+            let replayed = self
+                .address_token_to_history
+                .prefix::<AddressToken>(&key, false)
+                .map(|(_, v)| (v.height, v.action))
+                .take_while(|(height, _)| *height < covered);
+
+            let mut balance = Fixed128::default();
+            for (height, group) in &replayed.chunk_by(|(height, _)| *height) {
+                for (_, action) in group {
+                    action.apply_to_balance(&mut balance);
+                }
+
+                if height >= from && balance != last {
+                    changes.push((height, balance));
+                    if changes.len() >= limit {
+                        return changes;
+                    }
+                }
+                last = balance;
+            }
+        } else {
+            let lower = AddressTokenHeight {
+                address: key.address,
+                token: key.token,
+                height: 0,
+            };
+            let upper = AddressTokenHeight { height: from, ..lower };
+
+            last = self
+                .address_token_balance_height
+                .range(&lower..&upper, true)
+                .next()
+                .and_then(|(k, _)| self.balance_leaf(key, k.height))
+                .map(|leaf| leaf.balance + leaf.transferable_balance)
+                .unwrap_or_default();
+        }
+
+        let lower = AddressTokenHeight {
+            address: key.address,
+            token: key.token,
+            height: from.max(covered),
+        };
+        let upper = AddressTokenHeight { height: u32::MAX, ..lower };
+
+        for (k, _) in self.address_token_balance_height.range(&lower..=&upper, false) {
+            let Some(leaf) = self.balance_leaf(key, k.height) else {
+                continue;
+            };
+
+            let balance = leaf.balance + leaf.transferable_balance;
+            if balance != last {
+                changes.push((k.height, balance));
+                if changes.len() >= limit {
+                    break;
+                }
+            }
+            last = balance;
+        }
+
+        changes
+    }
+
     pub fn load_transfers(&self, keys: &HashSet<AddressOutPoint>) -> Vec<(Location, (FullHash, TransferProtoDB))> {
         keys.iter()
             .flat_map(|x| {
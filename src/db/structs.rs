@@ -45,7 +45,42 @@ impl rocksdb_wrapper::Pebble for LowerCaseTokenTick {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Tick keyed by its skeleton to find lookalike ticks, see [`tick_skeleton`]
+#[derive(Clone, Debug)]
+pub struct TokenSkeletonKey {
+    pub skeleton: String,
+    pub tick: OriginalTokenTick,
+}
+
+impl TokenSkeletonKey {
+    pub fn new(tick: OriginalTokenTick) -> Self {
+        Self {
+            skeleton: tick_skeleton(&tick.0),
+            tick,
+        }
+    }
+}
+
+impl rocksdb_wrapper::Pebble for TokenSkeletonKey {
+    type Inner = Self;
+
+    fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
+        let mut result = Vec::with_capacity(v.skeleton.len() + 4);
+        result.extend(v.skeleton.as_bytes());
+        result.extend(v.tick.0);
+        Cow::Owned(result)
+    }
+
+    fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
+        let split = v.len().checked_sub(4).anyhow()?;
+        let skeleton = String::from_utf8(v[..split].to_vec())?;
+        let tick = OriginalTokenTick(v[split..].try_into().anyhow()?);
+
+        Ok(Self { skeleton, tick })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct TokenMetaDB {
     pub genesis: InscriptionId,
     pub proto: DeployProtoDB,
@@ -143,15 +178,11 @@ impl AddressLocation {
 
 impl rocksdb_wrapper::Pebble for AddressLocation {
     type Inner = Self;
+    const FIXED_SIZE: Option<usize> = Some(<Self as rocksdb_wrapper::FixedPebble>::SIZE);
 
     fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
-        let mut result = Vec::with_capacity(32 + 44);
-
-        result.extend(v.address);
-
-        result.extend(consensus::serialize(&v.location.outpoint));
-        result.extend(v.location.offset.to_be_bytes());
-
+        let mut result = vec![0; <Self as rocksdb_wrapper::FixedPebble>::SIZE];
+        <Self as rocksdb_wrapper::FixedPebble>::write_fixed(v, &mut result);
         Cow::Owned(result)
     }
 
@@ -165,6 +196,22 @@ impl rocksdb_wrapper::Pebble for AddressLocation {
             location: Location { outpoint, offset },
         })
     }
+
+    fn get_bytes_borrowing<R>(v: &Self::Inner, f: impl FnOnce(&[u8]) -> R) -> R {
+        <Self as rocksdb_wrapper::FixedPebble>::with_fixed_bytes(v, f)
+    }
+}
+
+impl rocksdb_wrapper::FixedPebble for AddressLocation {
+    const SIZE: usize = 32 + 36 + 8;
+
+    fn write_fixed(v: &Self::Inner, dest: &mut [u8]) {
+        // Same layout as consensus-encoded OutPoint: raw txid followed by little-endian vout
+        dest[..32].copy_from_slice(v.address.as_slice());
+        dest[32..64].copy_from_slice(v.location.outpoint.txid.as_byte_array());
+        dest[64..68].copy_from_slice(&v.location.outpoint.vout.to_le_bytes());
+        dest[68..].copy_from_slice(&v.location.offset.to_be_bytes());
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -174,7 +221,7 @@ pub struct Partials {
     pub parts: Vec<Part>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct TxPrevout {
     pub script_hash: FullHash,
     pub value: u64,
@@ -191,11 +238,11 @@ impl From<TxOut> for TxPrevout {
 
 impl rocksdb_wrapper::Pebble for TxPrevout {
     type Inner = Self;
+    const FIXED_SIZE: Option<usize> = Some(<Self as rocksdb_wrapper::FixedPebble>::SIZE);
 
     fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
-        let mut result = Vec::with_capacity(32 + 8);
-        result.extend(v.script_hash);
-        result.extend(v.value.to_be_bytes());
+        let mut result = vec![0; <Self as rocksdb_wrapper::FixedPebble>::SIZE];
+        <Self as rocksdb_wrapper::FixedPebble>::write_fixed(v, &mut result);
         Cow::Owned(result)
     }
 
@@ -205,13 +252,26 @@ impl rocksdb_wrapper::Pebble for TxPrevout {
 
         Ok(Self { script_hash, value })
     }
+
+    fn get_bytes_borrowing<R>(v: &Self::Inner, f: impl FnOnce(&[u8]) -> R) -> R {
+        <Self as rocksdb_wrapper::FixedPebble>::with_fixed_bytes(v, f)
+    }
+}
+
+impl rocksdb_wrapper::FixedPebble for TxPrevout {
+    const SIZE: usize = 32 + 8;
+
+    fn write_fixed(v: &Self::Inner, dest: &mut [u8]) {
+        dest[..32].copy_from_slice(v.script_hash.as_slice());
+        dest[32..].copy_from_slice(&v.value.to_be_bytes());
+    }
 }
 
 impl rocksdb_wrapper::Pebble for Partials {
     type Inner = Self;
 
     fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
-        let mut buff = Vec::with_capacity(4 + 32 + v.parts.len() * (1 + 4 + 1700));
+        let mut buff = Vec::with_capacity(4 + 32 + v.parts.iter().map(|x| 1 + 4 + x.script_buffer.len()).sum::<usize>());
 
         buff.extend(v.inscription_index.to_be_bytes());
         buff.extend_from_slice(&bellscoin::consensus::serialize(&v.genesis_txid));
@@ -220,7 +280,7 @@ impl rocksdb_wrapper::Pebble for Partials {
             buff.push(part.is_tapscript as u8);
             let script_len = part.script_buffer.len() as u32;
             buff.extend(script_len.to_be_bytes());
-            buff.extend(part.script_buffer.clone());
+            buff.extend_from_slice(&part.script_buffer);
         }
 
         Cow::Owned(buff)
@@ -256,6 +316,27 @@ impl rocksdb_wrapper::Pebble for Partials {
     }
 }
 
+/// Number of blocks of the median time past, the same as the node uses
+pub const MEDIAN_TIME_SPAN: u32 = 11;
+
+/// Timestamps of an indexed block
+#[derive(Clone, Copy, Debug)]
+pub struct BlockTimes {
+    /// Median time past, the header timestamp for blocks without it
+    pub median_time: u32,
+    pub block_time: u32,
+}
+
+/// Length of the timestamp buckets of the `time_bucket` CF
+pub const TIME_BUCKET_SECS: u32 = 60 * 60;
+
+/// Heights of the blocks with timestamps in a bucket, block timestamps aren't monotonic so the ranges of buckets may overlap
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct TimeBucket {
+    pub from_height: u32,
+    pub to_height: u32,
+}
+
 #[derive(Clone, Copy)]
 pub struct BlockInfo {
     pub hash: BlockHash,
@@ -286,6 +367,30 @@ impl rocksdb_wrapper::Pebble for BlockInfo {
     }
 }
 
+/// Header data of the block which isn't kept in `BlockInfo`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockSummary {
+    pub prev_hash: BlockHash,
+    pub tx_count: u32,
+    pub size: u32,
+}
+
+impl rocksdb_wrapper::Pebble for BlockSummary {
+    type Inner = Self;
+
+    fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
+        Cow::Owned([v.prev_hash.to_byte_array().as_slice(), v.tx_count.to_be_bytes().as_slice(), v.size.to_be_bytes().as_slice()].concat())
+    }
+
+    fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
+        let prev_hash = BlockHash::from_byte_array(v[0..32].try_into()?);
+        let tx_count = u32::from_be_bytes(v[32..36].try_into()?);
+        let size = u32::from_be_bytes(v[36..].try_into()?);
+
+        Ok(Self { prev_hash, tx_count, size })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
 pub struct AddressTokenIdDB {
     pub address: FullHash,
@@ -295,14 +400,11 @@ pub struct AddressTokenIdDB {
 
 impl rocksdb_wrapper::Pebble for AddressTokenIdDB {
     type Inner = Self;
-    const FIXED_SIZE: Option<usize> = Some(32 + 4 + 8);
+    const FIXED_SIZE: Option<usize> = Some(<Self as rocksdb_wrapper::FixedPebble>::SIZE);
 
     fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
-        let mut result = Vec::with_capacity(Self::FIXED_SIZE.unwrap());
-        result.extend(v.address);
-        result.extend(v.token.0);
-        result.extend(v.id.to_be_bytes());
-
+        let mut result = vec![0; <Self as rocksdb_wrapper::FixedPebble>::SIZE];
+        <Self as rocksdb_wrapper::FixedPebble>::write_fixed(v, &mut result);
         Cow::Owned(result)
     }
 
@@ -313,9 +415,23 @@ impl rocksdb_wrapper::Pebble for AddressTokenIdDB {
 
         Ok(Self { address, id, token })
     }
+
+    fn get_bytes_borrowing<R>(v: &Self::Inner, f: impl FnOnce(&[u8]) -> R) -> R {
+        <Self as rocksdb_wrapper::FixedPebble>::with_fixed_bytes(v, f)
+    }
 }
 
-#[derive(Clone, Copy)]
+impl rocksdb_wrapper::FixedPebble for AddressTokenIdDB {
+    const SIZE: usize = 32 + 4 + 8;
+
+    fn write_fixed(v: &Self::Inner, dest: &mut [u8]) {
+        dest[..32].copy_from_slice(v.address.as_slice());
+        dest[32..36].copy_from_slice(&v.token.0);
+        dest[36..].copy_from_slice(&v.id.to_be_bytes());
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct TokenId {
     pub token: OriginalTokenTick,
     pub id: u64,
@@ -371,6 +487,76 @@ impl rocksdb_wrapper::Pebble for AddressToken {
     }
 }
 
+/// Change of the (address, tick) balance in a block, keyed for lookups of the last change at or below a height
+#[derive(Clone, Copy, Debug)]
+pub struct AddressTokenHeight {
+    pub address: FullHash,
+    pub token: OriginalTokenTick,
+    pub height: u32,
+}
+
+impl rocksdb_wrapper::Pebble for AddressTokenHeight {
+    type Inner = Self;
+    const FIXED_SIZE: Option<usize> = Some(32 + 4 + 4);
+
+    fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
+        let mut result = Vec::with_capacity(32 + 4 + 4);
+        result.extend(v.address);
+        result.extend(v.token.0);
+        result.extend(v.height.to_be_bytes());
+        Cow::Owned(result)
+    }
+
+    fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
+        let address: FullHash = v[..32].try_into().anyhow()?;
+        let token = OriginalTokenTick(v[32..36].try_into().anyhow()?);
+        let height = u32::from_be_bytes(v[36..].try_into().anyhow()?);
+
+        Ok(Self { address, token, height })
+    }
+}
+
+/// Block where an inscription of the address leaked, see [`LeakedInscriptionDB`]
+#[derive(Clone, Copy, Debug)]
+pub struct AddressHeight {
+    pub address: FullHash,
+    pub height: u32,
+}
+
+impl rocksdb_wrapper::Pebble for AddressHeight {
+    type Inner = Self;
+    const FIXED_SIZE: Option<usize> = Some(32 + 4);
+
+    fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
+        Cow::Owned([v.address.as_slice(), &v.height.to_be_bytes()].concat())
+    }
+
+    fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
+        let address: FullHash = v[..32].try_into().anyhow()?;
+        let height = u32::from_be_bytes(v[32..].try_into().anyhow()?);
+
+        Ok(Self { address, height })
+    }
+}
+
+/// Digest of the full token state at the end of a block, see [`crate::poh::state_digest`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct StateDigestDB {
+    pub digest: [u8; 32],
+    /// Number of hashed non-zero balances
+    pub accounts: u64,
+    pub tokens: u64,
+}
+
+/// Balance of the (address, tick) at the end of a block, leaf of the block balance tree
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BalanceLeaf {
+    pub address: FullHash,
+    pub token: OriginalTokenTick,
+    pub balance: Fixed128,
+    pub transferable_balance: Fixed128,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct TransferProtoDB {
     pub tick: OriginalTokenTick,
@@ -397,7 +583,7 @@ impl From<TransferProtoDB> for TransferProto {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct DeployProtoDB {
     pub tick: OriginalTokenTick,
     pub max: Fixed128,
@@ -413,6 +599,14 @@ pub struct DeployProtoDB {
 }
 
 impl DeployProtoDB {
+    /// Key of the token in the deployer index
+    pub fn deployer_token(&self) -> AddressToken {
+        AddressToken {
+            address: self.deployer,
+            token: self.tick,
+        }
+    }
+
     pub fn is_completed(&self) -> bool {
         self.supply == Fixed128::from(self.max)
     }
@@ -428,7 +622,14 @@ pub struct TokenBalance {
     pub transfers_count: u64,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+impl TokenBalance {
+    /// Listed by `/address/{address}/tokens-tick`, balances of tokens which were transferred away entirely are kept with zeros
+    pub fn is_held(&self) -> bool {
+        !self.balance.is_zero() || !self.transferable_balance.is_zero()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum TokenHistoryDB {
     Deploy { max: Fixed128, lim: Fixed128, dec: u8, txid: Txid, vout: u32 },
     Mint { amt: Fixed128, txid: Txid, vout: u32 },
@@ -438,7 +639,7 @@ pub enum TokenHistoryDB {
     SendReceive { amt: Fixed128, txid: Txid, vout: u32 },
 }
 
-#[derive(Serialize, Debug, Clone, Deserialize)]
+#[derive(Serialize, Debug, Clone, Deserialize, PartialEq)]
 pub struct HistoryValue {
     pub height: u32,
     pub action: TokenHistoryDB,
@@ -475,6 +676,20 @@ impl TokenHistoryDB {
         }
     }
 
+    /// Movement of a transfer inscription between addresses
+    pub fn is_transfer(&self) -> bool {
+        matches!(self, TokenHistoryDB::Send { .. } | TokenHistoryDB::Receive { .. } | TokenHistoryDB::SendReceive { .. })
+    }
+
+    /// Applies the event to the total (available + transferable) balance of the event owner
+    pub fn apply_to_balance(&self, balance: &mut Fixed128) {
+        match self {
+            TokenHistoryDB::Mint { amt, .. } | TokenHistoryDB::Receive { amt, .. } => *balance += *amt,
+            TokenHistoryDB::Send { amt, .. } => *balance -= *amt,
+            TokenHistoryDB::Deploy { .. } | TokenHistoryDB::DeployTransfer { .. } | TokenHistoryDB::SendReceive { .. } => {}
+        }
+    }
+
     pub fn outpoint(&self) -> OutPoint {
         match self {
             TokenHistoryDB::Deploy { txid, vout, .. }
@@ -486,3 +701,381 @@ impl TokenHistoryDB {
         }
     }
 }
+
+/// Roll-up of the compacted history of the address for the token, keyed by the id of the last compacted event
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct HistoryCheckpoint {
+    pub from_height: u32,
+    pub to_height: u32,
+    /// Number of compacted events
+    pub events: u64,
+    /// Total (available + transferable) balance at the end of the period
+    pub balance: Fixed128,
+}
+
+/// Number of history events of the address for the token and heights of its first and last ones, compacted events included
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct HistoryStats {
+    pub count: u64,
+    pub first_height: u32,
+    pub last_height: u32,
+}
+
+impl HistoryStats {
+    pub fn new(height: u32) -> Self {
+        Self {
+            count: 0,
+            first_height: height,
+            last_height: height,
+        }
+    }
+
+    pub fn push(&mut self, height: u32) {
+        self.count += 1;
+        self.first_height = self.first_height.min(height);
+        self.last_height = self.last_height.max(height);
+    }
+}
+
+/// Number of inscriptions created in the block grouped by content type
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct InscriptionStatsDB {
+    pub text: u64,
+    pub json: u64,
+    pub image: u64,
+    pub other: u64,
+}
+
+impl InscriptionStatsDB {
+    pub fn add(&mut self, content_type: Option<&str>) {
+        // Skip parameters, e.g. `text/plain;charset=utf-8`
+        let mime = content_type.and_then(|x| x.split(';').next()).map(|x| x.trim().to_ascii_lowercase());
+
+        match mime.as_deref() {
+            Some("text/plain") => self.text += 1,
+            Some("application/json") => self.json += 1,
+            Some(x) if x.starts_with("image/") => self.image += 1,
+            _ => self.other += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.text += other.text;
+        self.json += other.json;
+        self.image += other.image;
+        self.other += other.other;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.text + self.json + self.image + self.other
+    }
+}
+
+/// Fees paid by the transactions which created inscriptions in the block
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct FeeStatsDB {
+    pub txs: u64,
+    pub fees: u64,
+}
+
+impl FeeStatsDB {
+    pub fn add(&mut self, fee: u64) {
+        self.txs += 1;
+        self.fees += fee;
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.txs += other.txs;
+        self.fees += other.fees;
+    }
+}
+
+/// Valid mints of a tick in a block
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MintActivityDB {
+    pub mints: u32,
+    pub amount: Fixed128,
+    /// Recipients with their number of mints and minted amount, sorted by address
+    pub minters: Vec<(FullHash, u32, Fixed128)>,
+}
+
+/// First valid mint of a token
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FirstMintDB {
+    pub height: u32,
+    pub txid: Txid,
+    pub minter: FullHash,
+}
+
+/// Deploy blocked by the tick policy, see [`TickPolicy`]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RejectedDeployDB {
+    pub tick: OriginalTokenTick,
+    pub genesis: InscriptionId,
+    pub deployer: FullHash,
+    pub created: u32,
+    pub reason: RejectedDeployReason,
+}
+
+/// Supply, mint count and transfer count of a token, see [`SupplyAudits`](crate::server::SupplyAudits)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct SupplyCounters {
+    pub supply: Fixed128,
+    pub mint_count: u64,
+    pub transfer_count: u64,
+}
+
+/// Token which counters stored in its deploy differ from the ones recomputed from its history
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SupplyDiscrepancyDB {
+    pub tick: OriginalTokenTick,
+    pub stored: SupplyCounters,
+    pub computed: SupplyCounters,
+}
+
+/// Result of a supply audit of the database at the height, the creation time is unix seconds
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SupplyAuditDB {
+    pub height: u32,
+    pub created: u64,
+    pub duration_ms: u64,
+    /// Number of audited tokens
+    pub tokens: u64,
+    pub discrepancies: Vec<SupplyDiscrepancyDB>,
+    /// Set if the audit couldn't be done
+    pub error: Option<String>,
+}
+
+/// Hourly bucket of REST queries for the tick
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
+pub struct TokenAccessKey {
+    /// Hours since UNIX epoch
+    pub hour: u32,
+    pub tick: OriginalTokenTick,
+}
+
+/// Snapshot of the holders of a tick at a height
+#[derive(Clone, Copy, Debug)]
+pub struct TokenHeight {
+    pub tick: OriginalTokenTick,
+    pub height: u32,
+}
+
+impl rocksdb_wrapper::Pebble for TokenHeight {
+    type Inner = Self;
+    const FIXED_SIZE: Option<usize> = Some(4 + 4);
+
+    fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
+        Cow::Owned([v.tick.0, v.height.to_be_bytes()].concat())
+    }
+
+    fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
+        let tick = OriginalTokenTick(v[..4].try_into().anyhow()?);
+        let height = u32::from_be_bytes(v[4..].try_into().anyhow()?);
+
+        Ok(Self { tick, height })
+    }
+}
+
+impl rocksdb_wrapper::Pebble for TokenAccessKey {
+    type Inner = Self;
+    const FIXED_SIZE: Option<usize> = Some(4 + 4);
+
+    fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
+        Cow::Owned([v.hour.to_be_bytes(), v.tick.0].concat())
+    }
+
+    fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
+        let hour = u32::from_be_bytes(v[..4].try_into().anyhow()?);
+        let tick = OriginalTokenTick(v[4..].try_into().anyhow()?);
+
+        Ok(Self { hour, tick })
+    }
+}
+
+impl rocksdb_wrapper::Pebble for Location {
+    type Inner = Self;
+    const FIXED_SIZE: Option<usize> = Some(36 + 8);
+
+    fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
+        Cow::Owned([consensus::serialize(&v.outpoint), v.offset.to_be_bytes().to_vec()].concat())
+    }
+
+    fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
+        let outpoint: OutPoint = consensus::deserialize(&v[..36])?;
+        let offset = u64::from_be_bytes(v[36..].try_into().anyhow()?);
+
+        Ok(Self { outpoint, offset })
+    }
+}
+
+/// Movement of the inscription, sorted by inscription and then by block
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub struct InscriptionHistoryKey {
+    pub id: InscriptionId,
+    pub height: u32,
+    /// Index of the movement in the block
+    pub seq: u32,
+}
+
+impl InscriptionHistoryKey {
+    pub fn search(id: InscriptionId) -> RangeInclusive<Self> {
+        Self { id, height: 0, seq: 0 }..=Self {
+            id,
+            height: u32::MAX,
+            seq: u32::MAX,
+        }
+    }
+}
+
+impl rocksdb_wrapper::Pebble for InscriptionHistoryKey {
+    type Inner = Self;
+    const FIXED_SIZE: Option<usize> = Some(32 + 4 + 4 + 4);
+
+    fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
+        Cow::Owned(
+            [
+                v.id.txid.as_byte_array().as_slice(),
+                &v.id.index.to_be_bytes(),
+                &v.height.to_be_bytes(),
+                &v.seq.to_be_bytes(),
+            ]
+            .concat(),
+        )
+    }
+
+    fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
+        let txid = Txid::from_byte_array(v[..32].try_into().anyhow()?);
+        let index = u32::from_be_bytes(v[32..36].try_into().anyhow()?);
+        let height = u32::from_be_bytes(v[36..40].try_into().anyhow()?);
+        let seq = u32::from_be_bytes(v[40..].try_into().anyhow()?);
+
+        Ok(Self {
+            id: InscriptionId { txid, index },
+            height,
+            seq,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InscriptionMoveKind {
+    Created,
+    Transferred,
+    /// Moved to the coinbase as a fee
+    Leaked,
+}
+
+/// Inscription created or moved into the fees of a transaction, which the miner receives in the coinbase
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LeakedInscriptionDB {
+    pub kind: LeakedKind,
+    /// Transaction which spent the inscription into the fees
+    pub txid: Txid,
+    pub input: u32,
+    /// Spent output and the offset of the inscription in it
+    pub from: Location,
+    /// Owner of the spent output
+    pub owner: FullHash,
+    /// Location in the coinbase transaction
+    pub location: Location,
+    /// Moved inscriptions, known only with `INSCRIPTION_HISTORY`. Leaked creations aren't indexed, so they have no ids
+    pub inscriptions: Vec<InscriptionId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeakedKind {
+    Creation,
+    Move,
+}
+
+/// How the inscription behind a token event got to its owner. New variants go to the end
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionOrigin {
+    /// Revealed into its output or moved by a transaction like any other
+    #[default]
+    Normal,
+    /// Moved into another output by the pointer field of the envelope
+    Pointer,
+    /// Cursed, but indexed since it's created from the jubilee height. Redirected ones are `Pointer`
+    Vindicated,
+    /// Transfer inscription moved into the fees, whose amount returned to the sender
+    LeakedReturn,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InscriptionHistoryValue {
+    pub kind: InscriptionMoveKind,
+    pub location: Location,
+    pub owner: FullHash,
+}
+
+impl rocksdb_wrapper::Pebble for InscriptionId {
+    type Inner = Self;
+    const FIXED_SIZE: Option<usize> = Some(32 + 4);
+
+    fn get_bytes<'a>(v: &'a Self::Inner) -> Cow<'a, [u8]> {
+        Cow::Owned([v.txid.as_byte_array().as_slice(), &v.index.to_be_bytes()].concat())
+    }
+
+    fn from_bytes(v: Cow<[u8]>) -> anyhow::Result<Self::Inner> {
+        let txid = Txid::from_byte_array(v[..32].try_into().anyhow()?);
+        let index = u32::from_be_bytes(v[32..].try_into().anyhow()?);
+
+        Ok(Self { txid, index })
+    }
+}
+
+/// Why ord would curse the inscription, kept for explorers since cursed inscriptions are still token operations here. <br/>
+/// New variants go to the end
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InscriptionCurse {
+    NotInFirstInput,
+    DuplicateField,
+    IncompleteField,
+    UnrecognizedEvenField,
+    NotAtOffsetZero,
+    Pushnum,
+    Stutter,
+    Pointer,
+    /// Created at the location of another inscription
+    Reinscription,
+}
+
+/// Token webhook registered via `/admin/webhooks`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebhookDB {
+    pub url: String,
+    pub tick: OriginalTokenTick,
+    /// All token event types are delivered if empty
+    pub event_types: Vec<rest::types::TokenEventType>,
+    /// Key of the HMAC-SHA256 signature of the requests
+    pub secret: String,
+}
+
+/// Delivery counters of a webhook, timestamps are unix seconds
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct WebhookStatus {
+    pub delivered: u64,
+    pub failed: u64,
+    pub last_attempt: Option<u64>,
+    pub last_success: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_height: Option<u32>,
+}
+
+/// Admin request which changed (or tried to change) the indexer, the time is unix seconds
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AdminAuditDB {
+    pub time: u64,
+    /// `X-Admin-Operator` header, operators share the admin token so it's declared by the caller
+    pub operator: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    /// Request body with secrets redacted
+    pub body: Option<String>,
+    pub status: u16,
+}
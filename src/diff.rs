@@ -0,0 +1,424 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use serde_json::{json, Value};
+
+use super::*;
+use crate::preflight::exit_code;
+
+const USAGE: &str = "Usage: bel20-diff [--json] [--tables balances,tokens,history] [--from-height <height>] [--to-height <height>] <db or snapshot> <db or snapshot>
+       bel20-diff dump [--tables ...] [--from-height <height>] [--to-height <height>] <db> <snapshot>";
+/// Blocks whose events are loaded and resolved at once
+const BLOCKS_PER_BATCH: usize = 1_000;
+/// Balances loaded and resolved at once
+const BALANCES_PER_BATCH: usize = 10_000;
+
+/// Rows of a compared table in the order they're read, keyed by a readable identity which doesn't depend on history ids
+type Table = Vec<(String, Value)>;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+enum TableKind {
+    /// `address_token_to_balance`
+    Balances,
+    /// `token_to_meta`
+    Tokens,
+    /// `address_token_to_history` of the blocks in the height range
+    History,
+}
+
+impl TableKind {
+    const ALL: [TableKind; 3] = [TableKind::Balances, TableKind::Tokens, TableKind::History];
+
+    fn name(self) -> &'static str {
+        match self {
+            TableKind::Balances => "balances",
+            TableKind::Tokens => "tokens",
+            TableKind::History => "history",
+        }
+    }
+}
+
+impl FromStr for TableKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|x| x.name() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown table {s}, expected {}", Self::ALL.iter().map(|x| x.name()).join(", ")))
+    }
+}
+
+/// What is compared, stored in snapshots so a database is read the same way as the snapshot was
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Selection {
+    tables: Vec<TableKind>,
+    /// Heights of the compared history (inclusive)
+    from_height: u32,
+    to_height: u32,
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Self {
+            tables: TableKind::ALL.to_vec(),
+            from_height: 0,
+            to_height: u32::MAX,
+        }
+    }
+}
+
+/// Tables of a database written by `bel20-diff dump`, for comparing a database with its earlier state or with one of another host
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    selection: Selection,
+    tables: BTreeMap<TableKind, Table>,
+}
+
+enum Source {
+    Db(DB),
+    Snapshot(Snapshot),
+}
+
+impl Source {
+    /// Directories are databases, files are snapshots
+    fn open(path: &str) -> anyhow::Result<Self> {
+        if Path::new(path).is_dir() {
+            Ok(Source::Db(DB::open(path)))
+        } else {
+            Ok(Source::Snapshot(serde_json::from_reader(BufReader::new(File::open(path)?))?))
+        }
+    }
+
+    fn tables(&self, selection: &Selection) -> anyhow::Result<BTreeMap<TableKind, Table>> {
+        selection
+            .tables
+            .iter()
+            .map(|&kind| {
+                let table = match self {
+                    Source::Db(db) => match kind {
+                        TableKind::Balances => balances(db),
+                        TableKind::Tokens => tokens(db),
+                        TableKind::History => history(db, selection.from_height, selection.to_height),
+                    },
+                    Source::Snapshot(snapshot) => snapshot
+                        .tables
+                        .get(&kind)
+                        .cloned()
+                        .ok_or_else(|| anyhow::anyhow!("Snapshot has no {} table", kind.name()))?,
+                };
+                Ok((kind, table))
+            })
+            .collect()
+    }
+}
+
+struct Args {
+    dump: bool,
+    json: bool,
+    selection: Selection,
+    paths: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let mut args = args.peekable();
+        let dump = args.next_if_eq("dump").is_some();
+
+        let mut json = false;
+        let mut selection = Selection::default();
+        let mut paths = vec![];
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| anyhow::anyhow!("{arg} needs a value"));
+            match arg.as_str() {
+                "--json" => json = true,
+                "--tables" => selection.tables = value()?.split(',').map(|x| x.trim().parse()).collect::<anyhow::Result<_>>()?,
+                "--from-height" => selection.from_height = value()?.parse()?,
+                "--to-height" => selection.to_height = value()?.parse()?,
+                x if x.starts_with("--") => anyhow::bail!("Unknown option {x}"),
+                _ => paths.push(arg),
+            }
+        }
+
+        if paths.len() != 2 {
+            anyhow::bail!("Expected two paths");
+        }
+        if selection.from_height > selection.to_height {
+            anyhow::bail!("--from-height must not be greater than --to-height");
+        }
+
+        Ok(Self { dump, json, selection, paths })
+    }
+}
+
+#[derive(Serialize)]
+struct Row {
+    key: String,
+    value: Value,
+}
+
+#[derive(Serialize)]
+struct ChangedRow {
+    key: String,
+    left: Value,
+    right: Value,
+}
+
+#[derive(Serialize, Default)]
+struct TableDiff {
+    only_left: Vec<Row>,
+    only_right: Vec<Row>,
+    changed: Vec<ChangedRow>,
+}
+
+impl TableDiff {
+    fn new(left: Table, right: Table) -> Self {
+        let left_keys = left.iter().map(|(key, _)| key.clone()).collect::<HashSet<_>>();
+        let mut right_rows = right.iter().map(|(key, value)| (key.clone(), value.clone())).collect::<HashMap<_, _>>();
+
+        let mut diff = TableDiff::default();
+        for (key, value) in left {
+            match right_rows.remove(&key) {
+                None => diff.only_left.push(Row { key, value }),
+                Some(right) if right != value => diff.changed.push(ChangedRow { key, left: value, right }),
+                Some(_) => {}
+            }
+        }
+
+        diff.only_right = right
+            .into_iter()
+            .filter(|(key, _)| !left_keys.contains(key))
+            .map(|(key, value)| Row { key, value })
+            .collect();
+
+        diff
+    }
+
+    fn is_empty(&self) -> bool {
+        self.only_left.is_empty() && self.only_right.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    left: String,
+    right: String,
+    selection: Selection,
+    tables: BTreeMap<TableKind, TableDiff>,
+}
+
+impl Report {
+    fn print(&self) {
+        println!("--- {}", self.left);
+        println!("+++ {}", self.right);
+
+        for (kind, diff) in &self.tables {
+            println!(
+                "{}: {} only in left, {} only in right, {} changed",
+                kind.name(),
+                diff.only_left.len(),
+                diff.only_right.len(),
+                diff.changed.len()
+            );
+
+            for row in &diff.only_left {
+                println!("- {}: {}", row.key, row.value);
+            }
+            for row in &diff.only_right {
+                println!("+ {}: {}", row.key, row.value);
+            }
+            for row in &diff.changed {
+                println!("~ {}: {} -> {}", row.key, row.left, row.right);
+            }
+        }
+    }
+}
+
+/// `bel20-diff` command which compares balances, token metas and history of a height range of two databases (or a database and
+/// a snapshot written by `bel20-diff dump`) and prints the differences, as JSON with `--json`. <br/>
+/// Opens the databases for writing, so it runs against stopped nodes, backups or checkpoints. Returns exit code.
+pub fn run_diff() -> i32 {
+    init_logger();
+
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("{e}\n{USAGE}");
+            return exit_code::CONFIG;
+        }
+    };
+
+    // The snapshot of `dump` is written, not read
+    let inputs = if args.dump { &args.paths[..1] } else { &args.paths[..] };
+    if let Some(path) = inputs.iter().find(|path| !Path::new(path).exists()) {
+        error!("No database or snapshot at {path}");
+        return exit_code::DB_PATH;
+    }
+
+    let sources = match inputs.iter().map(|path| Source::open(path)).collect::<anyhow::Result<Vec<_>>>() {
+        Ok(x) => x,
+        Err(e) => {
+            error!("Failed to read snapshot: {e}");
+            return exit_code::SNAPSHOT;
+        }
+    };
+
+    if args.dump {
+        return match dump(&sources[0], &args.paths[1], &args.selection) {
+            Ok(()) => {
+                info!("Written snapshot of {} to {}", args.paths[0], args.paths[1]);
+                0
+            }
+            Err(e) => {
+                error!("Failed to write snapshot: {e}");
+                exit_code::SNAPSHOT
+            }
+        };
+    }
+
+    // Databases are read like the snapshot was
+    let selection = sources
+        .iter()
+        .find_map(|x| match x {
+            Source::Snapshot(snapshot) => Some(snapshot.selection.clone()),
+            Source::Db(_) => None,
+        })
+        .unwrap_or(args.selection);
+
+    let (left, mut right) = match (sources[0].tables(&selection), sources[1].tables(&selection)) {
+        (Ok(left), Ok(right)) => (left, right),
+        (Err(e), _) | (_, Err(e)) => {
+            error!("Failed to read tables: {e}");
+            return exit_code::SNAPSHOT;
+        }
+    };
+
+    let report = Report {
+        left: args.paths[0].clone(),
+        right: args.paths[1].clone(),
+        tables: left
+            .into_iter()
+            .map(|(kind, table)| (kind, TableDiff::new(table, right.remove(&kind).unwrap_or_default())))
+            .collect(),
+        selection,
+    };
+
+    if args.json {
+        println!("{}", json!(report));
+    } else {
+        report.print();
+    }
+
+    if report.tables.values().all(TableDiff::is_empty) {
+        0
+    } else {
+        exit_code::DIFFERS
+    }
+}
+
+/// Written to a temporary file first, so an interrupted dump never leaves a partial snapshot
+fn dump(source: &Source, path: &str, selection: &Selection) -> anyhow::Result<()> {
+    if !matches!(source, Source::Db(_)) {
+        anyhow::bail!("Only a database can be dumped");
+    }
+
+    let snapshot = Snapshot {
+        selection: selection.clone(),
+        tables: source.tables(selection)?,
+    };
+
+    let tmp = format!("{path}.tmp");
+    serde_json::to_writer(BufWriter::new(File::create(&tmp)?), &snapshot)?;
+    std::fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+/// Addresses of the script hashes, hex of the hash if the address isn't known, so unknown scripts don't collide
+fn addresses(db: &DB, hashes: impl IntoIterator<Item = FullHash>) -> HashMap<FullHash, String> {
+    let hashes = hashes.into_iter().collect::<HashSet<_>>();
+    let known = db
+        .fullhash_to_address
+        .multi_get_kv(hashes.iter(), false)
+        .into_iter()
+        .map(|(k, v)| (*k, v))
+        .collect::<HashMap<_, _>>();
+
+    hashes
+        .into_iter()
+        .map(|hash| {
+            let address = known.get(&hash).cloned().unwrap_or_else(|| sha256::Hash::from_byte_array(*hash).to_string());
+            (hash, address)
+        })
+        .collect()
+}
+
+fn balances(db: &DB) -> Table {
+    let mut rows = vec![];
+
+    for chunk in &db.address_token_to_balance.iter().chunks(BALANCES_PER_BATCH) {
+        let chunk = chunk.collect_vec();
+        let addresses = addresses(db, chunk.iter().map(|(key, _)| key.address));
+
+        rows.extend(
+            chunk
+                .into_iter()
+                .map(|(key, balance)| (format!("{} {}", addresses[&key.address], key.token), json!(balance))),
+        );
+    }
+
+    rows
+}
+
+fn tokens(db: &DB) -> Table {
+    db.token_to_meta.iter().map(|(_, meta)| (meta.proto.tick.to_string(), json!(meta))).collect()
+}
+
+/// Keyed by height, outpoint, tick, owner and type, so events of both sides match even if their history ids differ
+fn history(db: &DB, from: u32, to: u32) -> Table {
+    let mut rows = vec![];
+
+    for blocks in &db.block_events.range(&from..=&to, false).chunks(BLOCKS_PER_BATCH) {
+        let keys = blocks.flat_map(|(_, keys)| keys).collect_vec();
+        let history = db.address_token_to_history.multi_get_kv(keys.iter(), true);
+        let addresses = addresses(db, history.iter().flat_map(|(key, value)| [Some(key.address), value.action.address().copied()]).flatten());
+
+        for (key, value) in history {
+            let (event_type, amt, deploy) = match &value.action {
+                TokenHistoryDB::Deploy { max, lim, dec, .. } => ("Deploy", None, Some((max, lim, *dec))),
+                TokenHistoryDB::Mint { amt, .. } => ("Mint", Some(amt), None),
+                TokenHistoryDB::DeployTransfer { amt, .. } => ("DeployTransfer", Some(amt), None),
+                TokenHistoryDB::Send { amt, .. } => ("Send", Some(amt), None),
+                TokenHistoryDB::Receive { amt, .. } => ("Receive", Some(amt), None),
+                TokenHistoryDB::SendReceive { amt, .. } => ("SendReceive", Some(amt), None),
+            };
+            let outpoint = value.action.outpoint();
+
+            let mut fields = serde_json::Map::new();
+            if let Some(amt) = amt {
+                fields.insert("amount".to_string(), json!(amt));
+            }
+            if let Some((max, lim, dec)) = deploy {
+                fields.insert("max".to_string(), json!(max));
+                fields.insert("lim".to_string(), json!(lim));
+                fields.insert("dec".to_string(), json!(dec));
+            }
+            if let Some(counterparty) = value.action.address() {
+                fields.insert("counterparty".to_string(), json!(addresses[counterparty]));
+            }
+
+            let row_key = format!(
+                "{} {}:{} {} {} {event_type}",
+                value.height, outpoint.txid, outpoint.vout, key.token, addresses[&key.address]
+            );
+            rows.push((row_key, Value::Object(fields)));
+        }
+    }
+
+    rows
+}
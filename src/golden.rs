@@ -0,0 +1,249 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    net::{Ipv4Addr, SocketAddr},
+};
+
+use serde_json::Value;
+
+use super::*;
+use crate::{preflight::exit_code, rest::run_rest};
+
+const USAGE: &str = "Usage: bel_20_node golden <golden file> [--update]";
+/// Time given to the REST server to bind before the first query fails
+const REST_START_TIMEOUT: Duration = Duration::from_secs(30);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Golden file: the replayed block range, the scripted queries and their committed checksums
+#[derive(Serialize, Deserialize)]
+struct Golden {
+    /// `START_HEIGHT` the checksums were taken with, it changes the indexed tokens
+    from_height: u32,
+    /// Last replayed block (inclusive)
+    to_height: u32,
+    queries: Vec<GoldenQuery>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GoldenQuery {
+    /// Path with the query string, e.g. `/v1/tokens?page_size=100`
+    path: String,
+    /// JSON pointers of fields which don't depend on the indexed blocks (versions, uptime), removed before hashing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ignore: Vec<String>,
+    /// Sha256 of the status and the canonicalized body, `None` until the file is updated
+    #[serde(default)]
+    checksum: Option<sha256::Hash>,
+}
+
+/// Test harness which replays `from_height..=to_height` of the golden file into an empty `DB_PATH`, walks its queries through the REST
+/// server and compares the checksums of the canonicalized responses with the committed ones, so rewrites of the token engine can prove
+/// that the API output didn't change. <br/>
+/// With `--update` the checksums are written into the golden file instead. Returns exit code.
+pub fn run_golden() -> i32 {
+    let mut path = None;
+    let mut update = false;
+    for arg in std::env::args().skip(2) {
+        match arg.as_str() {
+            "--update" => update = true,
+            _ if path.is_none() => path = Some(arg),
+            _ => {
+                error!("{USAGE}");
+                return exit_code::CONFIG;
+            }
+        }
+    }
+    let Some(path) = path else {
+        error!("{USAGE}");
+        return exit_code::CONFIG;
+    };
+
+    let mut golden = match File::open(&path)
+        .anyhow()
+        .and_then(|file| serde_json::from_reader::<_, Golden>(BufReader::new(file)).anyhow())
+    {
+        Ok(golden) => golden,
+        Err(e) => {
+            error!("Failed to read golden file {path}: {e}");
+            return exit_code::CONFIG;
+        }
+    };
+
+    if golden.from_height != *START_HEIGHT {
+        error!("Golden file is taken from height {}, but START_HEIGHT is {}", golden.from_height, *START_HEIGHT);
+        return exit_code::CONFIG;
+    }
+
+    if !golden.queries.iter().map(|x| &x.path).all_unique() {
+        error!("Golden file has duplicate queries");
+        return exit_code::CONFIG;
+    }
+
+    // The replay starts from scratch, so the checksums don't depend on a database indexed by another version
+    if std::path::Path::new(&*DB_PATH).exists() {
+        error!("Golden replay needs an empty DB_PATH, {} already exists", *DB_PATH);
+        return exit_code::DB_PATH;
+    }
+
+    let (_events, server) = match Server::new(&DB_PATH) {
+        Ok((events, server)) => (events, Arc::new(server)),
+        Err(e) => {
+            error!("Failed to start the server: {e}");
+            return exit_code::RPC;
+        }
+    };
+
+    let result = replay(&server, golden.to_height).and_then(|_| query(&server, &mut golden.queries, update));
+    server.token.cancel();
+
+    let mismatches = match result {
+        Ok(mismatches) => mismatches,
+        Err(e) => {
+            error!("Golden replay failed: {e}");
+            return exit_code::REPLAY;
+        }
+    };
+
+    if update {
+        let tmp = format!("{path}.tmp");
+        let written = File::create(&tmp)
+            .anyhow()
+            .and_then(|file| serde_json::to_writer_pretty(BufWriter::new(file), &golden).anyhow())
+            .and_then(|_| std::fs::rename(&tmp, &path).anyhow());
+        if let Err(e) = written {
+            error!("Failed to write golden file {path}: {e}");
+            return exit_code::CONFIG;
+        }
+        info!("Written checksums of {} queries to {path}", golden.queries.len());
+        return 0;
+    }
+
+    if mismatches == 0 {
+        info!("All {} queries match the golden checksums", golden.queries.len());
+        0
+    } else {
+        error!("{} of {} queries don't match the golden checksums", mismatches, golden.queries.len());
+        exit_code::DIFFERS
+    }
+}
+
+/// Indexes the blocks from the node up to `to_height`, then the indexer stops on the closed channel
+fn replay(server: &Arc<Server>, to_height: u32) -> anyhow::Result<()> {
+    let best_height = server.client.get_block_count()?;
+    if best_height < to_height as u64 {
+        anyhow::bail!("Node has {best_height} blocks, the golden file replays up to {to_height}");
+    }
+
+    let blocks = server.indexer.clone().parse_blocks();
+    let (tx, rx) = nint_blk::block_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = blocks.recv() {
+            let height = event.id.height;
+            if tx.send(event).is_err() || height >= to_height as u64 {
+                break;
+            }
+        }
+    });
+
+    info!("Replaying blocks up to {to_height}");
+    Indexer::new(server.clone()).run_from(rx)?;
+
+    let last_block = server.db.last_block.get(());
+    if last_block != Some(to_height) {
+        anyhow::bail!("Replay stopped at block {last_block:?} instead of {to_height}");
+    }
+
+    Ok(())
+}
+
+/// Runs the REST server and its queries, returns number of mismatched checksums. Checksums are replaced if `update` is set.
+fn query(server: &Arc<Server>, queries: &mut [GoldenQuery], update: bool) -> anyhow::Result<usize> {
+    let rest_server = server.clone();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_multi_thread().thread_name("rest").enable_all().build().unwrap();
+        runtime.block_on(run_rest(rest_server))
+    });
+
+    // The server binds to all interfaces by default, which can't be connected to
+    let base_url = match SocketAddr::from_str(&SERVER_URL) {
+        Ok(mut addr) => {
+            if addr.ip().is_unspecified() {
+                addr.set_ip(Ipv4Addr::LOCALHOST.into());
+            }
+            format!("http://{addr}")
+        }
+        Err(_) => format!("http://{}", *SERVER_URL),
+    };
+    let client = reqwest::blocking::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+
+    let started = Instant::now();
+    let mut mismatches = 0;
+    for query in queries {
+        let response = loop {
+            match client.get(format!("{base_url}{}", query.path)).send() {
+                Ok(response) => break response,
+                Err(e) if e.is_connect() && started.elapsed() < REST_START_TIMEOUT => std::thread::sleep(Duration::from_millis(100)),
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        let status = response.status().as_u16();
+        let checksum = checksum(status, &response.bytes()?, &query.ignore);
+
+        if update {
+            query.checksum = Some(checksum);
+        } else if query.checksum != Some(checksum) {
+            error!("{} ({}): checksum {}, golden {:?}", query.path, status, checksum, query.checksum);
+            mismatches += 1;
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Hashes the status and the body, JSON bodies are canonicalized first so the checksums only change with the data
+fn checksum(status: u16, body: &[u8], ignore: &[String]) -> sha256::Hash {
+    let body = match serde_json::from_slice::<Value>(body) {
+        Ok(mut value) => {
+            for pointer in ignore {
+                remove_pointer(&mut value, pointer);
+            }
+            serde_json::to_vec(&canonicalize(value)).unwrap()
+        }
+        // NDJSON and text routes are hashed as they are
+        Err(_) => body.to_vec(),
+    };
+
+    let mut data = status.to_be_bytes().to_vec();
+    data.extend_from_slice(&body);
+    sha256::Hash::hash(&data)
+}
+
+/// Sorts object keys, so the field order of the response structs doesn't matter
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)).map(|(k, v)| (k, canonicalize(v))).collect()),
+        Value::Array(values) => Value::Array(values.into_iter().map(canonicalize).collect()),
+        value => value,
+    }
+}
+
+/// Removes the field, array elements are replaced with `null` to keep the indexes of the following ones
+fn remove_pointer(value: &mut Value, pointer: &str) {
+    let Some((parent, key)) = pointer.rsplit_once('/') else {
+        return;
+    };
+    let key = key.replace("~1", "/").replace("~0", "~");
+
+    match value.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.remove(&key);
+        }
+        Some(Value::Array(values)) => {
+            if let Some(value) = key.parse::<usize>().ok().and_then(|index| values.get_mut(index)) {
+                *value = Value::Null;
+            }
+        }
+        _ => {}
+    }
+}
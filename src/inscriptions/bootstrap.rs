@@ -0,0 +1,403 @@
+use std::io::{BufReader, Read, Write};
+
+use super::*;
+
+/// Tables of the indexer itself (deliveries, audits, admin state, views rolled up by its own settings), which aren't in the dump
+const LOCAL_TABLES: &[&str] = &[
+    "address_token_to_checkpoint",
+    "history_compacted_height",
+    "shutdown_marker",
+    "supply_audit",
+    "event_outbox",
+    "outbox_offsets",
+    "token_access_stats",
+    "webhooks",
+    "webhook_status",
+    "admin_audit",
+    "replication_acked",
+    "standby_primary_hash",
+    "standby_primary_proof",
+];
+
+/// Tables computed by the replica while it checks the history, the last block is written at the end by [`DB::commit_block`]
+const REBUILT_TABLES: &[&str] = &[
+    "proof_of_history",
+    "proof_of_history_version",
+    "proof_of_history_events_hash",
+    "last_block",
+    "last_committed_block_marker",
+];
+
+/// Tables of `GET /bootstrap` in the order they're written, `last_block` is the last one so a truncated dump never has it
+fn dump_tables() -> impl Iterator<Item = &'static str> {
+    DB::TABLES
+        .iter()
+        .copied()
+        .filter(|table| !LOCAL_TABLES.contains(table) && !REBUILT_TABLES.contains(table))
+        .chain(["last_block"])
+}
+
+// Frames of the dump: a table name is followed by the rows of the table, names, keys and values are prefixed by their u32 BE length
+const FRAME_END: u8 = 0;
+const FRAME_TABLE: u8 = 1;
+const FRAME_ROW: u8 = 2;
+
+/// Rows written to a table at once
+const WRITE_BATCH: usize = 10_000;
+/// Maximum limit of `GET /proof-of-history`
+const PROOFS_PAGE: u32 = 100;
+
+/// Writes the dump of `GET /bootstrap` from one snapshot of the database, taken between blocks
+pub fn write_dump(server: &Server, out: &mut impl Write) -> anyhow::Result<()> {
+    let rocks = server.db.rocks();
+    let snapshot = {
+        let _writing = server.block_writes.lock();
+        rocks.snapshot()
+    };
+
+    for table in dump_tables() {
+        out.write_all(&[FRAME_TABLE])?;
+        write_chunk(out, table.as_bytes())?;
+        snapshot.for_each_row(&table.to_uppercase(), |key, value| {
+            out.write_all(&[FRAME_ROW])?;
+            write_chunk(out, key)?;
+            write_chunk(out, value)
+        })?;
+    }
+
+    out.write_all(&[FRAME_END])?;
+    out.flush()?;
+
+    Ok(())
+}
+
+fn write_chunk(out: &mut impl Write, bytes: &[u8]) -> anyhow::Result<()> {
+    out.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    out.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_chunk(input: &mut impl Read) -> anyhow::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    input.read_exact(&mut len)?;
+    let mut bytes = vec![0; u32::from_be_bytes(len) as usize];
+    input.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn raw_table(db: &DB, table: &str) -> RocksTable<Cow<'static, [u8]>, Cow<'static, [u8]>> {
+    db.rocks().table(table.to_uppercase())
+}
+
+/// Client of the public routes of the peer
+struct Peer {
+    client: reqwest::blocking::Client,
+    url: String,
+}
+
+impl Peer {
+    fn get(&self, path: &str, query: &[(&str, String)]) -> anyhow::Result<reqwest::blocking::Response> {
+        let response = self.client.get(format!("{}{path}", self.url)).query(query).send()?;
+        if !response.status().is_success() {
+            anyhow::bail!("Peer answered {path} with {}: {}", response.status(), response.text().unwrap_or_default());
+        }
+
+        Ok(response)
+    }
+
+    /// Proof of history the peer publishes for the block with the encoding version of its events,
+    /// `proofs` is the rest of the last fetched page
+    fn proof(&self, proofs: &mut HashMap<u32, (sha256::Hash, PohVersion)>, height: u32, last_height: u32) -> anyhow::Result<(sha256::Hash, PohVersion)> {
+        if proofs.is_empty() {
+            // Pages go down from the block before `offset`
+            let query = [("offset", (height + PROOFS_PAGE).min(last_height + 1).to_string()), ("limit", PROOFS_PAGE.to_string())];
+            let page: Vec<rest::types::ProofOfHistory> = serde_json::from_reader(self.get("/proof-of-history", &query)?)?;
+            for proof in page.into_iter().filter(|x| x.height >= height) {
+                proofs.insert(proof.height, (sha256::Hash::from_str(&proof.hash)?, PohVersion::from_db(Some(proof.version))));
+            }
+        }
+
+        proofs.remove(&height).anyhow_with(format!("Peer has no proof of history of block {height}"))
+    }
+}
+
+impl Indexer {
+    /// Fills an empty database from `GET /bootstrap` of the indexer at `BOOTSTRAP_PEER_URL` instead of indexing the blocks up to its last
+    /// block, the indexer then continues from the node. The copied rows are checked before the last block is written:
+    /// - the proof of history of every block is computed by this indexer from the copied events and has to equal the one the peer
+    ///   publishes at `GET /proof-of-history`
+    /// - balances, token metas and transferable amounts are recomputed from the history and have to equal the copied ones
+    ///
+    /// The ordinals tables (prevouts, partials and inscription offsets) are taken as they are. A database of an interrupted bootstrap is refused.
+    pub fn bootstrap(&self, url: &str) -> anyhow::Result<()> {
+        let db = &self.server.db;
+        if db.last_block.get(()).is_some() {
+            return Ok(());
+        }
+
+        // `history_origin_height` is set on the first start of any database
+        let interrupted = dump_tables()
+            .filter(|table| *table != "history_origin_height")
+            .any(|table| raw_table(db, table).iter().next().is_some());
+        if interrupted {
+            anyhow::bail!("{} has rows of an interrupted bootstrap, remove it to bootstrap again", *DB_PATH);
+        }
+
+        let peer = Peer {
+            // The dump is read for as long as it takes
+            client: reqwest::blocking::Client::builder().timeout(None).build()?,
+            url: url.trim_end_matches('/').to_string(),
+        };
+
+        info!("Bootstrapping from {}", peer.url);
+
+        let (last_height, dump_history_id) = self.import_dump(&peer)?;
+
+        // The next blocks are indexed from the node on top of the dump
+        let block_info = db.block_info.get(last_height).anyhow_with(format!("Dump has no info of block {last_height}"))?;
+        if self.server.client.get_block_hash(last_height as u64)? != block_info.hash.to_raw_hash() {
+            anyhow::bail!("Block {last_height} of the peer isn't in the chain of the node");
+        }
+
+        let progress = Progress::begin("Checking history", (last_height + 1).saturating_sub(*START_HEIGHT) as u64, 0);
+        let mut replay = Replay::default();
+        let mut proofs = HashMap::new();
+        let mut prev_proof = *DEFAULT_HASH;
+        let mut last_history_id = 0;
+        for height in *START_HEIGHT..=last_height {
+            if self.server.token.is_cancelled() {
+                anyhow::bail!("Bootstrap is cancelled");
+            }
+
+            let keys = db.block_events.get(height).unwrap_or_default();
+            let history = db
+                .address_token_to_history
+                .multi_get(keys.iter())
+                .into_iter()
+                .zip(keys)
+                .map(|(value, key)| {
+                    value
+                        .map(|value| (key, value))
+                        .ok_or_else(|| anyhow::anyhow!("Dump has no history {} of block {height}", key.id))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let addresses = self
+                .server
+                .load_addresses(history.iter().flat_map(|(key, value)| [Some(key.address), value.action.address().copied()]).flatten())?;
+            let (expected, poh_version) = peer.proof(&mut proofs, height, last_height)?;
+            let block_proof = poh::block_proof(prev_proof, &history, &addresses, poh_version)?;
+            if block_proof.proof != expected {
+                anyhow::bail!("Proof of history of block {height} is {}, the peer publishes {expected}", block_proof.proof);
+            }
+
+            replay.apply(db, &history)?;
+            last_history_id = history.last().map(|(key, _)| key.id).unwrap_or(last_history_id);
+
+            db.proof_of_history.set(height, block_proof.proof);
+            db.proof_of_history_events_hash.set(height, block_proof.events_hash);
+            db.proof_of_history_version.set(height, poh_version as u8);
+            prev_proof = block_proof.proof;
+            progress.inc(1);
+        }
+
+        if last_history_id != dump_history_id {
+            anyhow::bail!("Last history id of the dump is {dump_history_id}, the history ends at {last_history_id}");
+        }
+
+        replay.verify(db)?;
+
+        db.last_history_id.set((), last_history_id);
+        db.commit_block(last_height);
+        db.flush_all();
+
+        info!("Bootstrapped up to block {} with proof of history {}", last_height, prev_proof);
+
+        Ok(())
+    }
+
+    /// Writes the rows of the dump but `last_block` and `last_history_id`, which are returned
+    fn import_dump(&self, peer: &Peer) -> anyhow::Result<(u32, u64)> {
+        let db = &self.server.db;
+        let mut dump = BufReader::new(peer.get("/bootstrap", &[])?);
+
+        let mut last_block = None;
+        let mut last_history_id = None;
+        let mut table = String::new();
+        let mut rows = vec![];
+        let mut count = 0;
+        loop {
+            let mut frame = [0];
+            dump.read_exact(&mut frame)?;
+
+            if frame[0] == FRAME_ROW {
+                let key = read_chunk(&mut dump)?;
+                let value = read_chunk(&mut dump)?;
+                match table.as_str() {
+                    "" => anyhow::bail!("Dump has a row before the first table"),
+                    "last_block" => last_block = Some(<u32 as rocksdb_wrapper::Pebble>::from_bytes(Cow::Owned(value))?),
+                    "last_history_id" => last_history_id = Some(<u64 as rocksdb_wrapper::Pebble>::from_bytes(Cow::Owned(value))?),
+                    _ => rows.push((Cow::Owned(key), Cow::Owned(value))),
+                }
+
+                count += 1;
+                if rows.len() == WRITE_BATCH {
+                    raw_table(db, &table).extend(rows.drain(..));
+                }
+                continue;
+            }
+
+            if !table.is_empty() {
+                raw_table(db, &table).extend(rows.drain(..));
+                info!("Copied {} rows of {}", count, table);
+                count = 0;
+            }
+
+            match frame[0] {
+                FRAME_TABLE => {
+                    table = String::from_utf8(read_chunk(&mut dump)?)?;
+                    if !dump_tables().any(|x| x == table) {
+                        anyhow::bail!("Dump has unknown table {table}");
+                    }
+                }
+                FRAME_END => break,
+                frame => anyhow::bail!("Dump has unknown frame {frame}"),
+            }
+        }
+
+        Ok((last_block.anyhow_with("Peer has no indexed blocks")?, last_history_id.unwrap_or_default()))
+    }
+}
+
+/// Token state recomputed from the imported history, the same way `TokenCache::process_token_actions` changes it
+#[derive(Default)]
+struct Replay {
+    metas: HashMap<LowerCaseTokenTick, TokenMetaDB>,
+    balances: HashMap<AddressToken, TokenBalance>,
+}
+
+impl Replay {
+    fn apply(&mut self, db: &DB, history: &[(AddressTokenIdDB, HistoryValue)]) -> anyhow::Result<()> {
+        for (key, value) in history {
+            match value.action {
+                TokenHistoryDB::Deploy { max, lim, dec, txid, vout } => {
+                    let created = db.block_info.get(value.height).anyhow_with(format!("Dump has no info of block {}", value.height))?.created;
+                    let meta = TokenMetaDB {
+                        genesis: InscriptionId { txid, index: vout },
+                        proto: DeployProtoDB {
+                            tick: key.token,
+                            max,
+                            lim,
+                            dec,
+                            supply: Fixed128::ZERO,
+                            transfer_count: 0,
+                            mint_count: 0,
+                            height: value.height,
+                            created,
+                            deployer: key.address,
+                            transactions: 1,
+                        },
+                    };
+                    if self.metas.insert(key.token.into(), meta).is_some() {
+                        anyhow::bail!("Token {} is deployed twice in the history", key.token);
+                    }
+                }
+                TokenHistoryDB::Mint { amt, .. } => {
+                    let proto = self.proto(key)?;
+                    proto.supply += amt;
+                    proto.mint_count += 1;
+                    proto.transactions += 1;
+
+                    self.account(key).balance += amt;
+                }
+                TokenHistoryDB::DeployTransfer { amt, .. } => {
+                    let proto = self.proto(key)?;
+                    proto.transfer_count += 1;
+                    proto.transactions += 1;
+
+                    let account = self.account(key);
+                    account.balance -= amt;
+                    account.transferable_balance += amt;
+                    account.transfers_count += 1;
+                }
+                TokenHistoryDB::Send { amt, .. } => {
+                    self.proto(key)?.transactions += 1;
+
+                    let account = self.account(key);
+                    account.transferable_balance -= amt;
+                    account.transfers_count -= 1;
+                }
+                TokenHistoryDB::Receive { amt, .. } => {
+                    // Burned amounts have no balance
+                    if !key.address.is_op_return_hash() {
+                        self.account(key).balance += amt;
+                    }
+                }
+                TokenHistoryDB::SendReceive { amt, .. } => {
+                    self.proto(key)?.transactions += 1;
+
+                    let account = self.account(key);
+                    account.transferable_balance -= amt;
+                    account.transfers_count -= 1;
+                    account.balance += amt;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn proto(&mut self, key: &AddressTokenIdDB) -> anyhow::Result<&mut DeployProtoDB> {
+        self.metas
+            .get_mut(&key.token.into())
+            .map(|meta| &mut meta.proto)
+            .anyhow_with(format!("History of {} starts before its deploy", key.token))
+    }
+
+    fn account(&mut self, key: &AddressTokenIdDB) -> &mut TokenBalance {
+        self.balances.entry(AddressToken::from(*key)).or_default()
+    }
+
+    /// Compares the copied token metas, balances and transfers with the recomputed ones
+    fn verify(mut self, db: &DB) -> anyhow::Result<()> {
+        for (tick, meta) in db.token_to_meta.iter() {
+            if self.metas.remove(&tick).as_ref() != Some(&meta) {
+                anyhow::bail!("Token {} differs from its history", meta.proto.tick);
+            }
+        }
+        if let Some(meta) = self.metas.values().next() {
+            anyhow::bail!("Token {} of the history isn't in the dump", meta.proto.tick);
+        }
+
+        let mut transfers = HashMap::<AddressToken, (Fixed128, u64)>::new();
+        for (key, transfer) in db.address_location_to_transfer.iter() {
+            let (amt, count) = transfers
+                .entry(AddressToken {
+                    address: key.address,
+                    token: transfer.tick,
+                })
+                .or_default();
+            *amt += transfer.amt;
+            *count += 1;
+        }
+
+        // Accounts may stay with zero balances, so missing ones are zero
+        let mut mismatches = 0;
+        for (key, balance) in db.address_token_to_balance.iter() {
+            if self.balances.remove(&key).unwrap_or_default() != balance {
+                mismatches += 1;
+            }
+            if transfers.remove(&key).unwrap_or_default() != (balance.transferable_balance, balance.transfers_count) {
+                mismatches += 1;
+            }
+        }
+        mismatches += self.balances.values().filter(|balance| **balance != TokenBalance::default()).count();
+        mismatches += transfers.len();
+
+        if mismatches > 0 {
+            anyhow::bail!("{mismatches} balances or transfers of the dump differ from the history");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use nint_blk::proto::block::Block;
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator};
+
+use crate::inscriptions::indexer::PreparedBlock;
+
+use super::*;
+
+/// Transactions of a block and the ones it spends outputs of
+struct Footprint {
+    created: HashSet<Txid>,
+    spent: HashSet<Txid>,
+}
+
+impl Footprint {
+    fn new(block: &Block) -> Self {
+        Self {
+            created: block.txs.iter().map(|tx| Txid::from(tx.hash)).collect(),
+            spent: block
+                .txs
+                .iter()
+                .filter(|tx| !tx.value.is_coinbase())
+                .flat_map(|tx| tx.value.inputs.iter().map(|txin| txin.outpoint.txid))
+                .collect(),
+        }
+    }
+}
+
+impl InscriptionIndexer {
+    /// Indexes consecutive blocks which are too deep to be reorged, enabled by `CONCURRENT_BLOCKS`. <br/>
+    /// Runs of blocks which don't spend outputs of each other are prepared in parallel, since the state they read
+    /// (prevouts, partials, inscription offsets and transfers) is keyed by the spent outputs. Their token actions are then applied
+    /// in parallel for runs without shared ticks, and every block is finished and written in order, so the result is byte-identical
+    /// to indexing the blocks one by one. Blocks with holders snapshots or state digests end a run, since they read the whole state.
+    /// `on_written` is called after every written block, the rest is dropped if the indexer is stopped.
+    pub fn handle_concurrent(&self, blocks: Vec<(u32, Block)>, mut on_written: impl FnMut(u32)) -> anyhow::Result<()> {
+        let footprints = blocks.par_iter().map(|(_, block)| Footprint::new(block)).collect::<Vec<_>>();
+
+        let mut groups: Vec<Vec<(u32, Block)>> = vec![];
+        let mut created = HashSet::new();
+        for (block, footprint) in blocks.into_iter().zip(footprints) {
+            if groups.is_empty() || !footprint.spent.is_disjoint(&created) {
+                groups.push(vec![]);
+                created.clear();
+            }
+
+            created.extend(footprint.created);
+            groups.last_mut().unwrap().push(block);
+        }
+
+        for group in groups {
+            let prepared = group
+                .into_par_iter()
+                .map(|(height, block)| self.prepare(height, block))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let mut batch = vec![];
+            let mut ticks = HashSet::new();
+            for block in prepared {
+                let block_ticks = block.ticks();
+                if !block_ticks.is_disjoint(&ticks) {
+                    if !self.apply_batch(std::mem::take(&mut batch), &mut on_written)? {
+                        return Ok(());
+                    }
+                    ticks.clear();
+                }
+
+                let reads_whole_state = Self::reads_whole_state(block.height);
+                ticks.extend(block_ticks);
+                batch.push(block);
+
+                if reads_whole_state {
+                    if !self.apply_batch(std::mem::take(&mut batch), &mut on_written)? {
+                        return Ok(());
+                    }
+                    ticks.clear();
+                }
+            }
+
+            if !self.apply_batch(batch, &mut on_written)? {
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies token actions of blocks without shared ticks in parallel and writes the blocks in order, `false` if the indexer is stopped
+    fn apply_batch(&self, mut batch: Vec<PreparedBlock>, on_written: &mut impl FnMut(u32)) -> anyhow::Result<bool> {
+        batch.par_iter_mut().try_for_each(|block| self.apply_tokens(block))?;
+
+        for block in batch {
+            let height = block.height;
+            let to_write = self.finish(block)?;
+            self.write(height, to_write, false);
+            on_written(height);
+
+            if self.server.token.is_cancelled() {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Holders snapshots and state digests are taken from the state after the block, which later blocks of a batch would change
+    fn reads_whole_state(height: u32) -> bool {
+        height >= *START_HEIGHT && (height % *HOLDERS_SNAPSHOT_INTERVAL == 0 || (*STATE_DIGEST_INTERVAL).is_some_and(|interval| height % interval == 0))
+    }
+}
@@ -57,6 +57,9 @@ impl From<RawEnvelope> for ParsedEnvelope {
                 pointer,
                 rune,
                 unrecognized_even_field,
+                envelope_offset: envelope.offset,
+                pushnum: envelope.pushnum,
+                stutter: envelope.stutter,
             },
             input: envelope.input,
             offset: envelope.offset,
@@ -74,6 +77,11 @@ impl RawEnvelope {
 
         let mut stuttered = false;
         while let Some(instruction) = instructions.next().transpose()? {
+            // one envelope over `MAX_ENVELOPES_PER_INPUT` is enough to reject the input
+            if MAX_ENVELOPES_PER_INPUT.is_some_and(|max| envelopes.len() > max) {
+                break;
+            }
+
             if instruction == PushBytes((&[]).into()) {
                 let (stutter, envelope) = Self::from_instructions(&mut instructions, input, envelopes.len(), stuttered)?;
                 if let Some(envelope) = envelope {
@@ -0,0 +1,66 @@
+use crate::inscriptions::process_data::ProcessedData;
+
+use super::*;
+
+/// Locations and movements of all inscriptions in the block, collected when `INSCRIPTION_HISTORY` is enabled
+pub struct InscriptionMoves {
+    height: u32,
+    location_to_inscriptions: HashMap<Location, Vec<InscriptionId>>,
+    to_remove: Vec<(Location, Vec<InscriptionId>)>,
+    history: Vec<(InscriptionHistoryKey, InscriptionHistoryValue)>,
+}
+
+impl InscriptionMoves {
+    pub fn load(server: &Server, height: u32, outpoint_to_offsets: &[(OutPoint, HashSet<u64>)]) -> Self {
+        let keys = outpoint_to_offsets
+            .iter()
+            .flat_map(|(outpoint, offsets)| offsets.iter().map(|offset| Location { outpoint: *outpoint, offset: *offset }))
+            .collect_vec();
+
+        let location_to_inscriptions: HashMap<_, _> = server
+            .db
+            .location_to_inscriptions
+            .multi_get_kv(keys.iter(), false)
+            .into_iter()
+            .map(|(k, v)| (*k, v))
+            .collect();
+
+        Self {
+            height,
+            to_remove: location_to_inscriptions.iter().map(|(k, v)| (*k, v.clone())).collect(),
+            location_to_inscriptions,
+            history: vec![],
+        }
+    }
+
+    /// Takes inscriptions out of the spent location
+    pub fn take(&mut self, location: &Location) -> Vec<InscriptionId> {
+        self.location_to_inscriptions.remove(location).unwrap_or_default()
+    }
+
+    pub fn put(&mut self, ids: Vec<InscriptionId>, location: Location, owner: FullHash, kind: InscriptionMoveKind) {
+        if ids.is_empty() {
+            return;
+        }
+
+        for id in &ids {
+            let key = InscriptionHistoryKey {
+                id: *id,
+                height: self.height,
+                seq: self.history.len() as u32,
+            };
+
+            self.history.push((key, InscriptionHistoryValue { kind, location, owner }));
+        }
+
+        self.location_to_inscriptions.entry(location).or_default().extend(ids);
+    }
+
+    pub fn into_processed_data(self) -> ProcessedData {
+        ProcessedData::InscriptionHistory {
+            to_remove: self.to_remove,
+            to_write: self.location_to_inscriptions.into_iter().collect(),
+            history: self.history,
+        }
+    }
+}
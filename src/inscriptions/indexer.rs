@@ -1,9 +1,10 @@
 use bitcoin_hashes::sha256;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
 use super::*;
 
 pub struct InscriptionIndexer {
-    server: Arc<Server>,
+    pub(super) server: Arc<Server>,
     reorg_cache: Arc<parking_lot::Mutex<ReorgCache>>,
 }
 
@@ -14,87 +15,206 @@ pub struct DataToWrite {
     pub history: Vec<(AddressTokenIdDB, HistoryValue)>,
 }
 
+/// Block between [`InscriptionIndexer::prepare`] and [`InscriptionIndexer::finish`]
+pub struct PreparedBlock {
+    pub height: u32,
+    block_info: BlockInfo,
+    block_summary: BlockSummary,
+    to_write: DataToWrite,
+    /// Addresses of the outputs of the block
+    addresses: HashMap<FullHash, String>,
+    tokens: BlockTokens,
+}
+
+enum BlockTokens {
+    /// Below `START_HEIGHT`, only addresses and prevouts are written
+    Skipped,
+    /// Only the coinbase
+    Empty,
+    Parsed {
+        token_cache: TokenCache,
+        transfers_to_remove: HashSet<AddressLocation>,
+        /// Set by [`InscriptionIndexer::apply_tokens`]
+        actions: Vec<HistoryTokenAction>,
+    },
+}
+
+impl PreparedBlock {
+    /// Ticks which token state is read and written by the block
+    pub fn ticks(&self) -> HashSet<LowerCaseTokenTick> {
+        match &self.tokens {
+            BlockTokens::Parsed { token_cache, .. } => token_cache.ticks(),
+            _ => HashSet::new(),
+        }
+    }
+}
+
 impl InscriptionIndexer {
     pub fn new(server: Arc<Server>, reorg_cache: Arc<parking_lot::Mutex<ReorgCache>>) -> Self {
         Self { reorg_cache, server }
     }
 
     pub fn handle(&self, block_height: u32, block: nint_blk::proto::block::Block, handle_reorgs: bool) -> anyhow::Result<()> {
-        let mut to_write = DataToWrite::default();
+        if handle_reorgs {
+            debug!("Syncing block: {} ({})", block.header.hash, block_height);
+        }
 
-        self.handle_block(&mut to_write, block_height, block, handle_reorgs)?;
+        let to_write = self.handle_block(block_height, block)?;
+        self.write(block_height, to_write, handle_reorgs);
 
-        if handle_reorgs {
-            self.reorg_cache.lock().new_block(block_height);
+        Ok(())
+    }
+
+    /// Writes the data of the block, which must follow the last written one
+    pub(super) fn write(&self, block_height: u32, mut to_write: DataToWrite, handle_reorgs: bool) {
+        if block_height >= *START_HEIGHT && block_height % *HOLDERS_SNAPSHOT_INTERVAL == 0 {
+            let snapshot = self.holders_snapshot(block_height, &to_write.processed);
+            to_write.processed.push(snapshot);
         }
 
-        // write/remove data from block
-        for data in to_write.processed {
-            data.write(&self.server, handle_reorgs.then_some(self.reorg_cache.clone()));
+        // Before the block data, so events of a block which is indexed again after a crash are delivered at least once
+        self.server.outbox.push(&self.server.db, std::mem::take(&mut to_write.history));
+
+        // Data, journal and commit marker of the block are written in one batch, so a crash leaves either the whole block or none of it,
+        // see `Server::verify_commit_marker`. Data is written by its kind, which only reads its own tables before writing them.
+        {
+            let _writing = self.server.block_writes.lock();
+            self.server.db.rocks().atomic(|| {
+                if handle_reorgs {
+                    self.reorg_cache.lock().new_block(&self.server.db, block_height);
+                }
+
+                for data in to_write.processed {
+                    data.write(&self.server, handle_reorgs.then_some(self.reorg_cache.clone()));
+                }
+
+                if handle_reorgs {
+                    self.reorg_cache.lock().persist_last(&self.server.db);
+                }
+
+                self.server.db.commit_block(block_height);
+            });
         }
 
-        for event in to_write.block_events {
-            self.server.event_sender.send(event).ok();
+        // Read from the committed state, a digest lost by a crash right after the commit is only missing from `/state-digest`
+        if let Some(interval) = *STATE_DIGEST_INTERVAL {
+            if block_height >= *START_HEIGHT && block_height % interval == 0 {
+                let started = Instant::now();
+                let digest = poh::state_digest(&self.server.db);
+                self.server.db.state_digest.set(block_height, digest);
+                info!("State digest of block {} taken in {:?}", block_height, started.elapsed());
+            }
         }
 
-        if self.server.raw_event_sender.send(to_write.history).is_err() && !self.server.token.is_cancelled() {
-            panic!("Failed to send raw event");
+        for event in to_write.block_events {
+            self.server.event_sender.send(event).ok();
         }
+    }
 
-        Ok(())
+    /// Data of the block without writing it or sending its events, `self.server.db` must be at the state before the block
+    pub fn dry_run(&self, block_height: u32, block: nint_blk::proto::block::Block) -> anyhow::Result<DataToWrite> {
+        self.handle_block(block_height, block)
     }
 
-    fn handle_block(&self, to_write: &mut DataToWrite, block_height: u32, block: nint_blk::proto::block::Block, handle_reorgs: bool) -> anyhow::Result<()> {
-        let current_hash = block.header.hash;
+    /// Holders of the ticks with balance changes since the previous snapshot, of all ticks for the first one
+    fn holders_snapshot(&self, height: u32, processed: &[ProcessedData]) -> ProcessedData {
+        let db = &self.server.db;
+
+        let ticks = match db.holders_snapshot_ticks.range(..&height, true).next() {
+            Some((prev_height, _)) => {
+                let from = prev_height + 1;
+                let current = processed
+                    .iter()
+                    .filter_map(|data| match data {
+                        ProcessedData::History { balance_leaves, .. } => Some(balance_leaves),
+                        _ => None,
+                    })
+                    .flatten()
+                    .map(|leaf| leaf.token);
+
+                db.block_balance_leaves
+                    .range(&from..&height, false)
+                    .flat_map(|(_, leaves)| leaves.into_iter().map(|leaf| leaf.token))
+                    .chain(current)
+                    .unique()
+                    .collect_vec()
+            }
+            None => self.server.holders.ticks(),
+        };
 
-        let mut last_history_id = self.server.db.last_history_id.get(()).unwrap_or_default();
+        let holders = ticks
+            .iter()
+            .map(|tick| {
+                let holders = self.server.holders.get_holders(tick).map(|x| x.iter().cloned().collect_vec()).unwrap_or_default();
+                (TokenHeight { tick: *tick, height }, holders)
+            })
+            .collect_vec();
 
-        if handle_reorgs {
-            debug!("Syncing block: {} ({})", current_hash, block_height);
-        }
+        ProcessedData::HoldersSnapshot { height, ticks, holders }
+    }
+
+    fn handle_block(&self, block_height: u32, block: nint_blk::proto::block::Block) -> anyhow::Result<DataToWrite> {
+        let mut prepared = self.prepare(block_height, block)?;
+        self.apply_tokens(&mut prepared)?;
+        self.finish(prepared)
+    }
+
+    /// Parses the block up to its token actions. Only the state of the outputs spent by the block is read,
+    /// so blocks which don't spend outputs of each other can be prepared at once, see [`super::concurrent`].
+    pub(super) fn prepare(&self, block_height: u32, block: nint_blk::proto::block::Block) -> anyhow::Result<PreparedBlock> {
+        let mut to_write = DataToWrite::default();
 
         let block_info = BlockInfo {
             created: block.header.value.timestamp,
-            hash: current_hash.into(),
+            hash: block.header.hash.into(),
         };
 
-        let prev_block_height = block_height.checked_sub(1).unwrap_or_default();
-        let prev_block_proof = self.server.db.proof_of_history.get(prev_block_height).unwrap_or(*DEFAULT_HASH);
-
-        let outpoint_fullhash_to_address = block
-            .txs
-            .iter()
-            .flat_map(|x| &x.value.outputs)
-            .filter_map(|x| {
-                x.script.address.as_ref().map(|address| {
-                    let fullhash: FullHash = sha256::Hash::hash(&x.out.script_pubkey).into();
-                    (fullhash, address.to_owned())
-                })
-            })
-            .collect::<HashMap<_, _>>();
+        let block_summary = BlockSummary {
+            prev_hash: block.header.value.prev_hash.into(),
+            tx_count: block.txs.len() as u32,
+            size: block.size,
+        };
 
-        let prevouts = utils::process_prevouts(self.server.db.clone(), &block, &mut to_write.processed)?;
+        // Address strings are encoded in parallel when the block is parsed, only hashing scripts of every output is left for this pass,
+        // which runs while prevouts are loaded. The map doesn't depend on the order of the outputs, so the proof of history input stays the same.
+        let (outpoint_fullhash_to_address, prevouts) = rayon::join(
+            || {
+                block
+                    .txs
+                    .par_iter()
+                    .flat_map_iter(|x| &x.value.outputs)
+                    .filter_map(|x| {
+                        x.script.address.as_ref().map(|address| {
+                            let fullhash: FullHash = sha256::Hash::hash(&x.out.script_pubkey).into();
+                            (fullhash, address.to_owned())
+                        })
+                    })
+                    .collect::<HashMap<_, _>>()
+            },
+            || utils::process_prevouts(self.server.db.clone(), &block, &mut to_write.processed),
+        );
+        let prevouts = prevouts?;
 
         to_write.processed.push(ProcessedData::FullHash {
-            addresses: outpoint_fullhash_to_address.iter().map(|(fullhash, address)| (*fullhash, address.to_owned())).collect(),
+            addresses: outpoint_fullhash_to_address.par_iter().map(|(fullhash, address)| (*fullhash, address.to_owned())).collect(),
         });
 
+        let mut prepared = PreparedBlock {
+            height: block_height,
+            block_info,
+            block_summary,
+            to_write,
+            addresses: outpoint_fullhash_to_address,
+            tokens: BlockTokens::Skipped,
+        };
+
         if block_height < *START_HEIGHT {
-            return Ok(());
+            return Ok(prepared);
         }
 
         if block.txs.len() == 1 {
-            let new_proof = Server::generate_history_hash(prev_block_proof, &[], &Default::default())?;
-
-            to_write.processed.push(ProcessedData::Info {
-                block_number: block_height,
-                block_info,
-                block_proof: new_proof,
-            });
-
-            to_write.block_events.push(ServerEvent::NewBlock(block_height, new_proof, block.header.hash.into()));
-
-            return Ok(());
+            prepared.tokens = BlockTokens::Empty;
+            return Ok(prepared);
         }
 
         let mut token_cache = TokenCache::load(&prevouts, &self.server.db);
@@ -110,14 +230,105 @@ impl InscriptionIndexer {
             server: &self.server,
         };
 
-        parser.parse_block(block_height, block, &prevouts, &mut to_write.processed);
+        parser.parse_block(block_height, block, &prevouts, &mut prepared.to_write.processed);
+
+        if !token_cache.rejected_deploys.is_empty() {
+            prepared.to_write.processed.push(ProcessedData::RejectedDeploys {
+                height: block_height,
+                deploys: std::mem::take(&mut token_cache.rejected_deploys),
+            });
+        }
+
+        prepared.tokens = BlockTokens::Parsed {
+            token_cache,
+            transfers_to_remove,
+            actions: vec![],
+        };
+
+        Ok(prepared)
+    }
 
-        token_cache.load_tokens_data(&self.server.db)?;
+    /// Validates the token actions against the token state, which is only read for the ticks of the block
+    pub(super) fn apply_tokens(&self, prepared: &mut PreparedBlock) -> anyhow::Result<()> {
+        let BlockTokens::Parsed { token_cache, actions, .. } = &mut prepared.tokens else {
+            return Ok(());
+        };
+
+        token_cache.load_tokens_data(&self.server.db, &self.server.token_accounts)?;
+
+        *actions = token_cache.process_token_actions(&self.server.holders);
+
+        let mint_fees = HistoryTokenAction::mint_fees(actions, &token_cache.tx_fees);
+        if !mint_fees.is_empty() {
+            prepared.to_write.processed.push(ProcessedData::MintFees { fees: mint_fees });
+        }
+
+        let mint_activity = HistoryTokenAction::mint_activity(actions);
+        if !mint_activity.is_empty() {
+            prepared.to_write.processed.push(ProcessedData::MintActivity {
+                height: prepared.height,
+                activity: mint_activity,
+            });
+        }
+
+        let first_mints = HistoryTokenAction::first_mints(actions, prepared.height);
+        if !first_mints.is_empty() {
+            prepared.to_write.processed.push(ProcessedData::FirstMints { mints: first_mints });
+        }
+
+        Ok(())
+    }
+
+    /// Data chained to the previous block: history ids, proof of history and median time. Previous blocks must be written.
+    pub(super) fn finish(&self, prepared: PreparedBlock) -> anyhow::Result<DataToWrite> {
+        let PreparedBlock {
+            height: block_height,
+            block_info,
+            block_summary,
+            mut to_write,
+            addresses: outpoint_fullhash_to_address,
+            tokens,
+        } = prepared;
+
+        let (token_cache, transfers_to_remove, actions) = match tokens {
+            BlockTokens::Skipped => return Ok(to_write),
+            BlockTokens::Empty => (None, HashSet::new(), vec![]),
+            BlockTokens::Parsed {
+                token_cache,
+                transfers_to_remove,
+                actions,
+            } => (Some(token_cache), transfers_to_remove, actions),
+        };
+
+        let mut last_history_id = self.server.db.last_history_id.get(()).unwrap_or_default();
+
+        let prev_block_height = block_height.checked_sub(1).unwrap_or_default();
+        let prev_block_proof = self.server.db.proof_of_history.get(prev_block_height).unwrap_or(*DEFAULT_HASH);
+        let poh_version = PohVersion::at_height(block_height);
+
+        let median_time = self.server.db.median_time_past(block_height, block_info.created);
+
+        let Some(token_cache) = token_cache else {
+            let new_proof = poh::block_proof(prev_block_proof, &[], &Default::default(), poh_version)?;
+
+            to_write.processed.push(ProcessedData::Info {
+                block_number: block_height,
+                block_info,
+                median_time,
+                block_summary,
+                block_proof: new_proof,
+                poh_version,
+            });
+
+            to_write.block_events.push(ServerEvent::NewBlock(block_height, new_proof.proof, block_info.hash));
+
+            return Ok(to_write);
+        };
 
         let mut fullhash_to_load = HashSet::new();
+        let mut origins = vec![];
 
-        to_write.history = token_cache
-            .process_token_actions(&self.server.holders)
+        to_write.history = actions
             .into_iter()
             .flat_map(|action| {
                 last_history_id += 1;
@@ -165,6 +376,10 @@ impl InscriptionIndexer {
                     ));
                 }
 
+                if action.origin() != ActionOrigin::Normal {
+                    origins.extend(results.iter().map(|(key, _)| (key.id, action.origin())));
+                }
+
                 results
             })
             .collect();
@@ -180,15 +395,39 @@ impl InscriptionIndexer {
             .collect::<HashMap<_, _>>()
             .into();
 
-        let new_proof = Server::generate_history_hash(prev_block_proof, &to_write.history, &rest_addresses)?;
+        let new_proof = poh::block_proof(prev_block_proof, &to_write.history, &rest_addresses, poh_version)?;
+
+        let balance_leaves = to_write
+            .history
+            .iter()
+            .map(|(key, _)| AddressToken::from(*key))
+            .unique()
+            .sorted()
+            .map(|key| {
+                let balance = token_cache.token_accounts.get(&key).cloned().unwrap_or_default();
+                BalanceLeaf {
+                    address: key.address,
+                    token: key.token,
+                    balance: balance.balance,
+                    transferable_balance: balance.transferable_balance,
+                }
+            })
+            .collect_vec();
 
         to_write.processed.push(ProcessedData::History {
             block_number: block_height,
             last_history_id,
             history: to_write.history.clone(),
+            balance_leaves,
+            origins,
         });
 
+        to_write
+            .block_events
+            .extend(token_cache.completed.iter().map(|tick| ServerEvent::TokenCompleted(*tick, block_height)));
+
         to_write.processed.push(ProcessedData::Tokens {
+            completed: token_cache.completed.into_iter().map(|tick| (LowerCaseTokenTick::from(tick), block_height)).collect(),
             metas: token_cache.tokens.into_iter().map(|(k, v)| (k, TokenMetaDB::from(v))).collect(),
             balances: token_cache.token_accounts.into_iter().collect(),
             transfers_to_write: token_cache
@@ -199,15 +438,18 @@ impl InscriptionIndexer {
             transfers_to_remove: transfers_to_remove.into_iter().collect(),
         });
 
-        to_write.block_events.push(ServerEvent::NewBlock(block_height, new_proof, current_hash.into()));
+        to_write.block_events.push(ServerEvent::NewBlock(block_height, new_proof.proof, block_info.hash));
 
         to_write.processed.push(ProcessedData::Info {
             block_number: block_height,
             block_info,
+            median_time,
+            block_summary,
             block_proof: new_proof,
+            poh_version,
         });
 
-        Ok(())
+        Ok(to_write)
     }
 }
 
@@ -5,11 +5,23 @@ use super::*;
 #[derive(Clone)]
 pub enum LeakedInscription {
     Creation,
-    Move,
+    /// Holds ids of the moved inscriptions when `INSCRIPTION_HISTORY` is enabled
+    Move(Vec<InscriptionId>),
+}
+
+/// Input whose inscription ended up in the fees
+#[derive(Clone, Copy)]
+pub struct LeakedFrom {
+    pub txid: Txid,
+    pub input: u32,
+    /// Spent output and the offset of the inscription in it
+    pub location: Location,
+    /// Owner of the spent output
+    pub owner: FullHash,
 }
 
 pub struct LeakedInscriptions {
-    pub inscriptions: HashMap<u64, Vec<LeakedInscription>>,
+    pub inscriptions: HashMap<u64, Vec<(LeakedFrom, LeakedInscription)>>,
     pub total_amount: u64,
     pub coinbase_tx: Hashed<EvaluatedTx>,
     pub coinbase_reward: Option<u64>,
@@ -35,12 +47,20 @@ impl LeakedInscriptions {
 
         let diff = fee_result.fee - fee_result.fee_offset;
 
+        let outpoint = tx.value.inputs[input_idx].outpoint;
+        let from = LeakedFrom {
+            txid: tx.hash.into(),
+            input: input_idx as u32,
+            location: Location { outpoint, offset: input_offset },
+            owner: tx_outs[&outpoint].script_hash,
+        };
+
         self.inscriptions
             .entry(self.total_amount - diff)
             .and_modify(|x| {
-                x.push(inscription.clone());
+                x.push((from, inscription.clone()));
             })
-            .or_insert(vec![inscription]);
+            .or_insert(vec![(from, inscription)]);
     }
 
     pub fn add_tx_fee(&mut self, tx: &Hashed<EvaluatedTx>, txos: &HashMap<OutPoint, TxPrevout>) -> u64 {
@@ -57,20 +77,24 @@ impl LeakedInscriptions {
         self.coinbase_reward = Some(self.coinbase_tx.value.outputs.iter().map(|x| x.out.value).sum::<u64>() - self.total_amount);
     }
 
-    pub fn get_leaked_inscriptions(mut self) -> impl Iterator<Item = Location> {
+    pub fn get_leaked_inscriptions(mut self) -> impl Iterator<Item = (Location, LeakedFrom, LeakedInscription)> {
         self.update_reward();
 
         self.inscriptions
             .clone()
             .into_iter()
             .flat_map(|(offset, x)| x.into_iter().map(move |x| (offset, x)))
-            .filter_map(move |(offset, _)| {
-                self.find_inscription_vout(offset).map(|(vout, offset)| Location {
-                    offset,
-                    outpoint: OutPoint {
-                        txid: self.coinbase_tx.hash.into(),
-                        vout,
-                    },
+            .filter_map(move |(offset, (from, inscription))| {
+                self.find_inscription_vout(offset).map(|(vout, offset)| {
+                    let location = Location {
+                        offset,
+                        outpoint: OutPoint {
+                            txid: self.coinbase_tx.hash.into(),
+                            vout,
+                        },
+                    };
+
+                    (location, from, inscription)
                 })
             })
     }
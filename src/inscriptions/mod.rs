@@ -2,14 +2,21 @@ use super::*;
 
 pub const PROTOCOL_ID: &[u8; 3] = b"ord";
 
+mod bootstrap;
+mod concurrent;
 mod envelope;
+mod history;
 mod indexer;
 mod leaked;
 mod parser;
+mod preview;
 mod process_data;
+mod reorg_simulation;
+mod reprocess;
 mod searcher;
 pub mod structs;
 mod tag;
+mod trace;
 mod utils;
 
 use envelope::{ParsedEnvelope, RawEnvelope};
@@ -20,6 +27,9 @@ use process_data::ProcessedData;
 use structs::Inscription;
 use tag::Tag;
 
+pub use bootstrap::write_dump;
+pub use preview::preview_tx;
+pub use trace::trace_tx;
 pub use structs::Location;
 
 pub struct Indexer {
@@ -30,36 +40,72 @@ pub struct Indexer {
 impl Indexer {
     pub fn new(server: Arc<Server>) -> Self {
         Self {
-            reorg_cache: Arc::new(parking_lot::Mutex::new(ReorgCache::new())),
+            reorg_cache: Arc::new(parking_lot::Mutex::new(ReorgCache::load(&server.db))),
             server,
         }
     }
 
+    /// Memtables are flushed by [`crate::server::ShutdownCoordinator`] once every writer is stopped
     pub fn run(self) -> anyhow::Result<()> {
-        let res = self.index();
-
-        self.reorg_cache.lock().restore_all(&self.server).track().ok();
-        self.server.db.flush_all();
-
-        res
+        let rx = self.server.indexer.clone().parse_blocks();
+        self.run_from(rx)
     }
 
-    fn index(&self) -> anyhow::Result<()> {
-        let rx = self.server.indexer.clone().parse_blocks();
+    /// Indexes blocks fed by the embedding service instead of the ones parsed from the node. <br/>
+    /// The first block must follow the last indexed one, a block with `reorg_len` rolls back that many blocks before it's applied.
+    pub fn run_from(self, rx: nint_blk::BlockReceiver) -> anyhow::Result<()> {
+        self.index(rx)
+    }
 
+    fn index(&self, rx: nint_blk::BlockReceiver) -> anyhow::Result<()> {
         let indexer = InscriptionIndexer::new(self.server.clone(), self.reorg_cache.clone());
 
         let mut progress: Option<Progress> = Some(Progress::begin("Indexing", self.server.indexer.last_block.height, self.server.indexer.last_block.height));
 
-        let mut prev_height: Option<u64> = None;
+        let mut prev_height: Option<u64> = self.reorg_cache.lock().blocks.last_key_value().map(|x| *x.0 as u64);
+        // Received while a window of `CONCURRENT_BLOCKS` was filled, but has to be indexed on its own
+        let mut next: Option<BlockEvent> = None;
         while !self.server.token.is_cancelled() {
-            let data = match rx.try_recv() {
-                Ok(Some(data)) => data,
-                Ok(None) => {
-                    std::thread::sleep(Duration::from_millis(50));
-                    continue;
+            if let Some(depth) = self.server.reorg_simulations.pending() {
+                match self.simulate_reorg(&indexer, depth) {
+                    Ok(report) => self.server.reorg_simulations.finish(report),
+                    Err(e) => {
+                        self.server.reorg_simulations.finish(rest::types::ReorgSimulation::failed(depth, e.to_string()));
+                        return Err(e);
+                    }
+                }
+            }
+
+            // The live database is only read, so a failed reprocessing doesn't stop the indexer
+            if let Some((height, reply)) = self.server.reprocess_requests.take() {
+                let report = self.reprocess_block(height).unwrap_or_else(|e| rest::types::BlockReprocess::failed(height, e.to_string()));
+                reply.send(report).ok();
+            }
+
+            // Taken between blocks, so the checkpoint never has a partially written block
+            if self.server.backups.take() {
+                if let Err(e) = self.server.backups.create(&self.server.db) {
+                    error!("Failed to create a backup: {}", e);
                 }
-                Err(_) => break,
+            }
+
+            // Audited by the supply auditor, so indexing only pauses for the checkpoint
+            if self.server.supply_audits.pending() {
+                if let Err(e) = self.server.supply_audits.checkpoint(&self.server.db) {
+                    error!("Failed to take a supply audit checkpoint: {}", e);
+                }
+            }
+
+            let data = match next.take() {
+                Some(data) => data,
+                None => match rx.try_recv() {
+                    Ok(Some(data)) => data,
+                    Ok(None) => {
+                        std::thread::sleep(Duration::from_millis(50));
+                        continue;
+                    }
+                    Err(_) => break,
+                },
             };
             if let Some(progress) = progress.as_mut() {
                 progress.update_len(data.tip.saturating_sub(REORG_CACHE_MAX_LEN as u64));
@@ -67,7 +113,8 @@ impl Indexer {
 
             let BlockEvent { block, id, tip, reorg_len } = data;
 
-            let handle_reorgs = id.height > tip - REORG_CACHE_MAX_LEN as u64;
+            // Chains shorter than the reorg window, e.g. a fresh regtest, are within it from the first block
+            let handle_reorgs = id.height > tip.saturating_sub(REORG_CACHE_MAX_LEN as u64);
 
             if handle_reorgs {
                 progress.take();
@@ -76,7 +123,7 @@ impl Indexer {
             {
                 let mut cache = self.reorg_cache.lock();
                 if !cache.blocks.is_empty() && !handle_reorgs {
-                    cache.blocks.clear();
+                    cache.clear(&self.server.db);
                 }
             }
 
@@ -84,7 +131,11 @@ impl Indexer {
                 warn!("Reorg detected: {} blocks", reorg_len);
                 let restore_height = prev_height.unwrap_or_default().saturating_sub(reorg_len as u64);
 
-                self.reorg_cache.lock().restore(&self.server, restore_height as u32)?;
+                {
+                    let _writing = self.server.block_writes.lock();
+                    self.reorg_cache.lock().restore(&self.server.db, restore_height as u32, &self.server.reorg_in_progress)?;
+                }
+                self.server.token_accounts.clear();
                 self.server.event_sender.send(ServerEvent::Reorg(reorg_len as u32, id.height as u32)).ok();
             }
 
@@ -94,12 +145,46 @@ impl Indexer {
                 }
             }
 
-            indexer.handle(id.height as u32, block, handle_reorgs).track()?;
+            match (*CONCURRENT_BLOCKS).filter(|_| !handle_reorgs && reorg_len == 0) {
+                Some(window) => {
+                    let mut blocks = vec![(id.height as u32, block)];
+                    while blocks.len() < window {
+                        match rx.try_recv() {
+                            Ok(Some(data)) if data.reorg_len == 0 && data.id.height <= data.tip.saturating_sub(REORG_CACHE_MAX_LEN as u64) => {
+                                blocks.push((data.id.height as u32, data.block));
+                            }
+                            Ok(Some(data)) => {
+                                next = Some(data);
+                                break;
+                            }
+                            // Not received yet, or the channel is closed, which the next iteration finds out
+                            _ => break,
+                        }
+                    }
+
+                    indexer
+                        .handle_concurrent(blocks, |height| {
+                            self.server.write_throttle.throttle(self.server.db.rocks(), &self.server.token);
+
+                            prev_height = Some(height as u64);
+
+                            if let Some(progress) = progress.as_ref() {
+                                progress.inc(1);
+                            }
+                        })
+                        .track()?;
+                }
+                None => {
+                    indexer.handle(id.height as u32, block, handle_reorgs).track()?;
+
+                    self.server.write_throttle.throttle(self.server.db.rocks(), &self.server.token);
 
-            prev_height = Some(id.height);
+                    prev_height = Some(id.height);
 
-            if let Some(progress) = progress.as_ref() {
-                progress.inc(1);
+                    if let Some(progress) = progress.as_ref() {
+                        progress.inc(1);
+                    }
+                }
             }
 
             if self.server.token.is_cancelled() {
@@ -3,6 +3,7 @@ use bitcoin_hashes::sha256;
 use nint_blk::proto::{tx::EvaluatedTx, Hashed};
 
 use crate::inscriptions::{
+    history::InscriptionMoves,
     indexer::ParsedInscriptionResult,
     leaked::{LeakedInscription, LeakedInscriptions},
     process_data::ProcessedData,
@@ -15,6 +16,8 @@ use super::*;
 pub struct ParseInscription<'a> {
     tx: &'a Hashed<EvaluatedTx>,
     input_index: u32,
+    /// Inscriptions created by the previous inputs of the transaction
+    created_before: u32,
     inputs_cum: &'a [u64],
     partials: &'a Partials,
     prevouts: &'a HashMap<OutPoint, TxPrevout>,
@@ -39,8 +42,14 @@ impl Parser<'_> {
 
         let prev_offsets = inscription_outpoint_to_offsets.iter().map(|(k, v)| (*k, v.clone())).collect_vec();
 
+        let mut inscription_moves = INSCRIPTION_HISTORY.then(|| InscriptionMoves::load(self.server, height, &prev_offsets));
+
         let mut leaked: Option<LeakedInscriptions> = None;
 
+        let mut inscription_stats = InscriptionStatsDB::default();
+        let mut fee_stats = FeeStatsDB::default();
+        let mut inscription_curses = vec![];
+
         for tx in &block.txs {
             if tx.value.is_coinbase() {
                 leaked = Some(LeakedInscriptions::new(Hashed {
@@ -51,7 +60,12 @@ impl Parser<'_> {
                 continue;
             }
 
-            leaked.as_mut().unwrap().add_tx_fee(tx, prevouts);
+            let fee = {
+                let leaked = leaked.as_mut().unwrap();
+                let total_before = leaked.total_amount;
+                leaked.add_tx_fee(tx, prevouts) - total_before
+            };
+            let mut has_inscriptions = false;
 
             let mut inscription_index_in_tx = 0;
             let txid: Txid = tx.hash.into();
@@ -76,6 +90,16 @@ impl Parser<'_> {
 
                                 inscription_outpoint_to_offsets.entry(new_outpoint).or_default().insert(new_offset);
 
+                                if let Some(inscription_moves) = inscription_moves.as_mut() {
+                                    let ids = inscription_moves.take(&old_location);
+                                    let new_location = Location {
+                                        outpoint: new_outpoint,
+                                        offset: new_offset,
+                                    };
+                                    let owner = Self::output_owner(&tx.value.outputs[new_vout as usize].out.script_pubkey);
+                                    inscription_moves.put(ids, new_location, owner, InscriptionMoveKind::Transferred);
+                                }
+
                                 // handle move of token transfer
                                 if is_token_transfer_move {
                                     if ScriptBuf::from_bytes(tx.value.outputs[new_vout as usize].out.script_pubkey.clone()).is_op_return() {
@@ -92,9 +116,10 @@ impl Parser<'_> {
                                     // because of token protocol leaked token amount
                                     // comeback to owner
                                     let recipient = prevouts.get(&txin.outpoint).expect("Owner of token transfer must exist").script_hash;
-                                    self.token_cache.transferred(old_location, recipient, txid, 0);
+                                    self.token_cache.leaked_transfer(old_location, recipient, txid);
                                 }
-                                leaked.as_mut().unwrap().add(input_index, tx, inscription_offset, prevouts, LeakedInscription::Move);
+                                let ids = inscription_moves.as_mut().map(|x| x.take(&old_location)).unwrap_or_default();
+                                leaked.as_mut().unwrap().add(input_index, tx, inscription_offset, prevouts, LeakedInscription::Move(ids));
                             }
                         }
                     }
@@ -108,24 +133,13 @@ impl Parser<'_> {
                         parts: vec![],
                     });
 
-                    let part = if let Some(tapscript) = txin.witness.tapscript() {
-                        Part {
-                            is_tapscript: true,
-                            script_buffer: tapscript.to_bytes(),
-                        }
-                    } else {
-                        Part {
-                            is_tapscript: false,
-                            script_buffer: txin.script_sig.clone(),
-                        }
-                    };
-
-                    partials.parts.push(part);
+                    partials.parts.push(Part::from_input(txin, height));
 
                     let parsed_result = Self::parse_inscription(
                         ParseInscription {
                             tx,
                             input_index: input_index as u32,
+                            created_before: inscription_index_in_tx,
                             inputs_cum: &inputs_cum,
                             partials: &partials,
                             prevouts,
@@ -164,12 +178,23 @@ impl Parser<'_> {
                         }
                     };
 
-                    for inscription_template in inscription_templates {
+                    for mut inscription_template in inscription_templates {
                         let mut offset_occupied = !inscription_outpoint_to_offsets
                             .entry(inscription_template.location.outpoint)
                             .or_default()
                             .insert(inscription_template.location.offset); // return false if item already exist
 
+                        let mut curses = std::mem::take(&mut inscription_template.curses);
+                        if offset_occupied {
+                            curses.push(InscriptionCurse::Reinscription);
+                        }
+                        if !curses.is_empty() {
+                            if is_jubilee_height && inscription_template.origin == ActionOrigin::Normal {
+                                inscription_template.origin = ActionOrigin::Vindicated;
+                            }
+                            inscription_curses.push((inscription_template.genesis, curses));
+                        }
+
                         // This is only for BELLS
                         if *JUBILEE_HEIGHT == 133_000 {
                             offset_occupied = false;
@@ -180,18 +205,59 @@ impl Parser<'_> {
                             continue;
                         }
 
+                        inscription_stats.add(inscription_template.content_type.as_deref());
+                        has_inscriptions = true;
+
                         // handle token deploy|mint|transfer creation
                         self.token_cache.parse_token_action(&inscription_template, height, block.header.value.timestamp);
+
+                        if let Some(inscription_moves) = inscription_moves.as_mut() {
+                            inscription_moves.put(
+                                vec![inscription_template.genesis],
+                                inscription_template.location,
+                                inscription_template.owner,
+                                InscriptionMoveKind::Created,
+                            );
+                        }
                     }
                 }
             }
+
+            if has_inscriptions {
+                fee_stats.add(fee);
+                self.token_cache.tx_fees.insert(txid, fee);
+            }
         }
 
-        leaked.unwrap().get_leaked_inscriptions().for_each(|location| {
+        let mut leaked_inscriptions = vec![];
+        for (location, from, inscription) in leaked.unwrap().get_leaked_inscriptions() {
             inscription_outpoint_to_offsets.entry(location.outpoint).or_default().insert(location.offset);
-        });
+
+            let (kind, ids) = match inscription {
+                LeakedInscription::Creation => (LeakedKind::Creation, vec![]),
+                LeakedInscription::Move(ids) => (LeakedKind::Move, ids),
+            };
+
+            if let (Some(inscription_moves), LeakedKind::Move) = (inscription_moves.as_mut(), kind) {
+                let owner = Self::output_owner(&block.txs[0].value.outputs[location.outpoint.vout as usize].out.script_pubkey);
+                inscription_moves.put(ids.clone(), location, owner, InscriptionMoveKind::Leaked);
+            }
+
+            leaked_inscriptions.push(LeakedInscriptionDB {
+                kind,
+                txid: from.txid,
+                input: from.input,
+                from: from.location,
+                owner: from.owner,
+                location,
+                inscriptions: ids,
+            });
+        }
+        // Collected from a map, sorted so the stored order doesn't depend on it
+        leaked_inscriptions.sort_by_key(|x| (x.location, x.from));
 
         data_to_write.push(ProcessedData::InscriptionPartials {
+            height,
             to_remove: partials_to_remove,
             to_write: outpoint_to_partials.into_iter().collect(),
         });
@@ -200,6 +266,37 @@ impl Parser<'_> {
             to_remove: prev_offsets,
             to_write: inscription_outpoint_to_offsets.into_iter().collect(),
         });
+
+        data_to_write.push(ProcessedData::InscriptionStats {
+            block_number: height,
+            stats: inscription_stats,
+        });
+
+        data_to_write.push(ProcessedData::FeeStats {
+            block_number: height,
+            stats: fee_stats,
+        });
+
+        data_to_write.push(ProcessedData::InscriptionCurses { curses: inscription_curses });
+
+        if !leaked_inscriptions.is_empty() {
+            data_to_write.push(ProcessedData::LeakedInscriptions {
+                height,
+                leaked: leaked_inscriptions,
+            });
+        }
+
+        if let Some(inscription_moves) = inscription_moves {
+            data_to_write.push(inscription_moves.into_processed_data());
+        }
+    }
+
+    pub(super) fn output_owner(script_pubkey: &[u8]) -> FullHash {
+        if ScriptBuf::from_bytes(script_pubkey.to_vec()).is_op_return() {
+            *OP_RETURN_HASH
+        } else {
+            sha256::Hash::hash(script_pubkey).into()
+        }
     }
 
     fn load_partials(server: &Server, outpoints: Vec<OutPoint>) -> HashMap<OutPoint, Partials> {
@@ -223,10 +320,20 @@ impl Parser<'_> {
     }
 
     fn parse_inscription(payload: ParseInscription, leaked: &mut LeakedInscriptions) -> ParsedInscriptionResult {
-        let parsed = Inscription::from_parts(&payload.partials.parts, payload.input_index);
+        let mut parsed = Inscription::from_parts(&payload.partials.parts, payload.input_index);
+
+        let txid: Txid = payload.tx.hash.into();
+        // continued multi-part inscriptions were created by an earlier transaction
+        if payload.partials.genesis_txid == txid {
+            parsed = parsed.limit_per_tx(payload.created_before);
+        }
 
         match parsed {
             ParsedInscription::None => ParsedInscriptionResult::None,
+            ParsedInscription::Rejected(limit) => {
+                warn!("Rejected inscriptions of input {} of {}: {}", payload.input_index, txid, limit);
+                ParsedInscriptionResult::None
+            }
             ParsedInscription::Partial => ParsedInscriptionResult::Partials,
             ParsedInscription::Single(inscription) => Self::convert_to_template(&payload, inscription, leaked)
                 .map(ParsedInscriptionResult::Single)
@@ -253,6 +360,7 @@ impl Parser<'_> {
         let pointer = inscription.pointer();
 
         let mut inscription_template = InscriptionTemplate {
+            curses: inscription.curses(payload.input_index),
             content: inscription.into_body(),
             content_type,
             genesis,
@@ -266,6 +374,7 @@ impl Parser<'_> {
             owner: FullHash::ZERO,
             value: 0,
             leaked: false,
+            origin: ActionOrigin::Normal,
         };
 
         let Ok((mut vout, mut offset)) = InscriptionSearcher::get_output_index_by_input(payload.inputs_cum.get(payload.input_index as usize).copied(), &payload.tx.value.outputs)
@@ -274,9 +383,10 @@ impl Parser<'_> {
             return None;
         };
 
-        if let Ok((new_vout, new_offset)) = InscriptionSearcher::get_output_index_by_input(pointer, &payload.tx.value.outputs) {
+        if let Some((new_vout, new_offset)) = InscriptionSearcher::pointer_location(pointer, COIN.pointer_policy, &payload.tx.value.outputs) {
             vout = new_vout;
             offset = new_offset;
+            inscription_template.origin = ActionOrigin::Pointer;
         }
 
         let location: Location = Location {
@@ -289,12 +399,7 @@ impl Parser<'_> {
 
         let tx_out = &payload.tx.value.outputs[vout as usize];
 
-        if ScriptBuf::from_bytes(tx_out.out.script_pubkey.clone()).is_op_return() {
-            inscription_template.owner = *OP_RETURN_HASH;
-        } else {
-            inscription_template.owner = sha256::Hash::hash(&tx_out.out.script_pubkey).into();
-        }
-
+        inscription_template.owner = Self::output_owner(&tx_out.out.script_pubkey);
         inscription_template.location = location;
         inscription_template.value = tx_out.out.value;
 
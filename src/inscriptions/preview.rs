@@ -0,0 +1,134 @@
+use nint_blk::proto::{tx::EvaluatedTx, Hashed};
+
+use crate::inscriptions::{
+    searcher::InscriptionSearcher,
+    structs::{ParsedInscription, Part},
+};
+
+use super::*;
+
+/// Token actions the unconfirmed transaction would trigger if it was confirmed in the next block. <br/>
+/// Other unconfirmed transactions are ignored, `None` if some of the inputs aren't known unspent outputs.
+pub fn preview_tx(server: &Server, tx: &Hashed<EvaluatedTx>) -> Option<Vec<HistoryTokenAction>> {
+    let db = &server.db;
+    let height = db.last_block.get(())? + 1;
+    let created = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as u32;
+    let is_jubilee_height = height as usize >= *JUBILEE_HEIGHT;
+    let txid: Txid = tx.hash.into();
+    let outputs = &tx.value.outputs;
+
+    let prevouts: HashMap<OutPoint, TxPrevout> = db
+        .prevouts
+        .multi_get_kv(tx.value.inputs.iter().map(|x| &x.outpoint), false)
+        .into_iter()
+        .map(|(k, v)| (*k, v))
+        .collect();
+
+    if tx.value.inputs.iter().any(|x| !prevouts.contains_key(&x.outpoint))
+        || prevouts.values().map(|x| x.value).sum::<u64>() < outputs.iter().map(|x| x.out.value).sum::<u64>()
+    {
+        return None;
+    }
+
+    let inputs_cum = InscriptionSearcher::calc_offsets(tx, &prevouts)?;
+    let mut token_cache = TokenCache::load(&prevouts, db);
+    let mut inscription_index_in_tx = 0;
+
+    for (input_index, txin) in tx.value.inputs.iter().enumerate() {
+        let moved = token_cache.valid_transfers.keys().filter(|x| x.outpoint == txin.outpoint).copied().collect_vec();
+
+        for location in moved {
+            let offset = inputs_cum.get(input_index).map(|x| *x + location.offset);
+            match InscriptionSearcher::get_output_index_by_input(offset, outputs) {
+                Ok((vout, _)) => {
+                    let owner = Parser::output_owner(&outputs[vout as usize].out.script_pubkey);
+                    if owner.is_op_return_hash() {
+                        token_cache.burned_transfer(location, txid, vout);
+                    } else {
+                        token_cache.transferred(location, owner, txid, vout);
+                    }
+                }
+                // leaked transfers come back to the owner
+                Err(_) => token_cache.leaked_transfer(location, prevouts[&txin.outpoint].script_hash, txid),
+            }
+        }
+
+        if !is_jubilee_height && input_index != 0 {
+            continue;
+        }
+
+        let mut partials = db.outpoint_to_partials.get(txin.outpoint).unwrap_or(Partials {
+            genesis_txid: txid,
+            inscription_index: 0,
+            parts: vec![],
+        });
+
+        partials.parts.push(Part::from_input(txin, height));
+
+        let mut parsed = Inscription::from_parts(&partials.parts, input_index as u32);
+        if partials.genesis_txid == txid {
+            parsed = parsed.limit_per_tx(inscription_index_in_tx);
+        }
+
+        let inscriptions = match parsed {
+            ParsedInscription::Single(inscription) => vec![inscription],
+            ParsedInscription::Many(inscriptions) => inscriptions,
+            ParsedInscription::None | ParsedInscription::Partial | ParsedInscription::Rejected(_) => continue,
+        };
+
+        for inscription in inscriptions {
+            let mut genesis = InscriptionId {
+                txid: partials.genesis_txid,
+                index: 0,
+            };
+            if partials.genesis_txid == txid {
+                genesis.index = inscription_index_in_tx;
+                inscription_index_in_tx += 1;
+            }
+
+            let content_type = inscription.content_type().map(|x| x.to_owned());
+            let pointer = inscription.pointer();
+
+            // leaked inscriptions can't be token actions
+            let Ok((mut vout, mut offset)) = InscriptionSearcher::get_output_index_by_input(inputs_cum.get(input_index).copied(), outputs) else {
+                continue;
+            };
+
+            let mut origin = ActionOrigin::Normal;
+            if let Some((new_vout, new_offset)) = InscriptionSearcher::pointer_location(pointer, COIN.pointer_policy, outputs) {
+                vout = new_vout;
+                offset = new_offset;
+                origin = ActionOrigin::Pointer;
+            }
+
+            let curses = inscription.curses(input_index as u32);
+            if is_jubilee_height && !curses.is_empty() && origin == ActionOrigin::Normal {
+                origin = ActionOrigin::Vindicated;
+            }
+
+            let tx_out = &outputs[vout as usize];
+
+            let inscription_template = InscriptionTemplate {
+                genesis,
+                location: Location {
+                    outpoint: OutPoint { txid, vout },
+                    offset,
+                },
+                content_type,
+                owner: Parser::output_owner(&tx_out.out.script_pubkey),
+                value: tx_out.out.value,
+                leaked: false,
+                curses,
+                content: inscription.into_body(),
+                origin,
+            };
+
+            token_cache.parse_token_action(&inscription_template, height, created);
+        }
+    }
+
+    token_cache.load_tokens_data(db, &server.token_accounts).ok()?;
+
+    // Holders of the preview mustn't affect the served ones
+    Some(token_cache.process_token_actions(&Holders::default()))
+}
@@ -6,7 +6,10 @@ pub enum ProcessedData {
     Info {
         block_number: u32,
         block_info: BlockInfo,
-        block_proof: sha256::Hash,
+        median_time: u32,
+        block_summary: BlockSummary,
+        block_proof: poh::BlockProof,
+        poh_version: PohVersion,
     },
     Prevouts {
         to_write: HashMap<OutPoint, TxPrevout>,
@@ -19,14 +22,20 @@ pub enum ProcessedData {
         block_number: u32,
         last_history_id: u64,
         history: Vec<(AddressTokenIdDB, HistoryValue)>,
+        balance_leaves: Vec<BalanceLeaf>,
+        /// Origins of the events which aren't `ActionOrigin::Normal`
+        origins: Vec<(u64, ActionOrigin)>,
     },
     Tokens {
+        /// Tokens fully minted in the block with the block height
+        completed: Vec<(LowerCaseTokenTick, u32)>,
         metas: Vec<(LowerCaseTokenTick, TokenMetaDB)>,
         balances: Vec<(AddressToken, TokenBalance)>,
         transfers_to_write: Vec<(AddressLocation, TransferProtoDB)>,
         transfers_to_remove: Vec<AddressLocation>,
     },
     InscriptionPartials {
+        height: u32,
         to_remove: Vec<(OutPoint, Partials)>,
         to_write: Vec<(OutPoint, Partials)>,
     },
@@ -34,6 +43,47 @@ pub enum ProcessedData {
         to_remove: Vec<(OutPoint, HashSet<u64>)>,
         to_write: Vec<(OutPoint, HashSet<u64>)>,
     },
+    InscriptionStats {
+        block_number: u32,
+        stats: InscriptionStatsDB,
+    },
+    FeeStats {
+        block_number: u32,
+        stats: FeeStatsDB,
+    },
+    /// Fees of the valid mints in the block by token
+    MintFees {
+        fees: Vec<(LowerCaseTokenTick, u64)>,
+    },
+    InscriptionHistory {
+        to_remove: Vec<(Location, Vec<InscriptionId>)>,
+        to_write: Vec<(Location, Vec<InscriptionId>)>,
+        history: Vec<(InscriptionHistoryKey, InscriptionHistoryValue)>,
+    },
+    HoldersSnapshot {
+        height: u32,
+        ticks: Vec<OriginalTokenTick>,
+        holders: Vec<(TokenHeight, Vec<SortedByBalance>)>,
+    },
+    MintActivity {
+        height: u32,
+        activity: Vec<(OriginalTokenTick, MintActivityDB)>,
+    },
+    /// First mints of the block, kept only for tokens without an earlier one
+    FirstMints {
+        mints: Vec<(LowerCaseTokenTick, FirstMintDB)>,
+    },
+    RejectedDeploys {
+        height: u32,
+        deploys: Vec<RejectedDeployDB>,
+    },
+    InscriptionCurses {
+        curses: Vec<(InscriptionId, Vec<InscriptionCurse>)>,
+    },
+    LeakedInscriptions {
+        height: u32,
+        leaked: Vec<LeakedInscriptionDB>,
+    },
 }
 
 impl ProcessedData {
@@ -44,11 +94,19 @@ impl ProcessedData {
             ProcessedData::Info {
                 block_number,
                 block_info,
+                median_time,
+                block_summary,
                 block_proof,
+                poh_version,
             } => {
-                server.db.last_block.set((), block_number);
+                server.db.block_hash_to_height.set(block_info.hash, block_number);
+                server.db.add_block_time(block_number, block_info.created);
                 server.db.block_info.set(block_number, block_info);
-                server.db.proof_of_history.set(block_number, block_proof);
+                server.db.block_median_time.set(block_number, median_time);
+                server.db.block_summary.set(block_number, block_summary);
+                server.db.proof_of_history.set(block_number, block_proof.proof);
+                server.db.proof_of_history_events_hash.set(block_number, block_proof.events_hash);
+                server.db.proof_of_history_version.set(block_number, poh_version as u8);
             }
             ProcessedData::Prevouts { to_write, to_remove } => {
                 if let Some(reorg_cache) = reorg_cache.as_mut() {
@@ -74,6 +132,8 @@ impl ProcessedData {
                 block_number,
                 last_history_id,
                 history,
+                balance_leaves,
+                origins,
             } => {
                 let block_events: Vec<_> = history
                     .iter()
@@ -99,7 +159,24 @@ impl ProcessedData {
                     })
                     .collect_vec();
 
+                let address_tokens = history.iter().map(|(key, _)| AddressToken::from(*key)).unique().collect_vec();
+                let stats_before = server
+                    .db
+                    .address_token_history_stats
+                    .multi_get_kv(address_tokens.iter(), false)
+                    .into_iter()
+                    .map(|(k, v)| (*k, v))
+                    .collect::<HashMap<_, _>>();
+
+                let mut stats = stats_before.clone();
+                for (key, value) in &history {
+                    stats.entry(AddressToken::from(*key)).or_insert_with(|| HistoryStats::new(value.height)).push(value.height);
+                }
+
                 if let Some(reorg_cache) = reorg_cache.as_mut() {
+                    let new_stats = address_tokens.iter().filter(|x| !stats_before.contains_key(x)).copied().collect_vec();
+                    reorg_cache.push_token_entry(TokenHistoryEntry::HistoryStatsBefore(stats_before.into_iter().collect()));
+                    reorg_cache.push_token_entry(TokenHistoryEntry::HistoryStatsToRemove(new_stats));
                     reorg_cache.push_token_entry(TokenHistoryEntry::RemoveHistory {
                         height: block_number,
                         last_history_id: server.db.last_history_id.get(()).unwrap_or_default(),
@@ -109,13 +186,43 @@ impl ProcessedData {
                     });
                 }
 
+                // Number of transfers moved by transactions which moved more than one
+                let batches = history
+                    .iter()
+                    .filter(|(_, value)| matches!(value.action, TokenHistoryDB::Send { .. } | TokenHistoryDB::SendReceive { .. }))
+                    .counts_by(|(_, value)| value.action.outpoint().txid)
+                    .into_iter()
+                    .filter(|(_, count)| *count > 1)
+                    .map(|(txid, count)| (txid, count as u32))
+                    .collect_vec();
+
+                server.db.txid_to_batch.extend(batches);
                 server.db.token_id_to_event.extend(token_id_to_event);
                 server.db.block_events.set(block_number, block_events);
                 server.db.last_history_id.set((), last_history_id);
                 server.db.outpoint_to_event.extend(outpoint_to_event);
                 server.db.address_token_to_history.extend(history);
+                server.db.address_token_history_stats.extend(stats);
+                server.db.history_origin.extend(origins);
+
+                if !balance_leaves.is_empty() {
+                    let (balance_root, _) = poh::balance_tree(&balance_leaves, None);
+                    server.db.balance_root.set(block_number, balance_root);
+                    server.db.address_token_balance_height.extend(balance_leaves.iter().map(|leaf| {
+                        (
+                            AddressTokenHeight {
+                                address: leaf.address,
+                                token: leaf.token,
+                                height: block_number,
+                            },
+                            (),
+                        )
+                    }));
+                    server.db.block_balance_leaves.set(block_number, balance_leaves);
+                }
             }
             ProcessedData::Tokens {
+                completed,
                 metas,
                 balances,
                 transfers_to_write,
@@ -170,17 +277,25 @@ impl ProcessedData {
                     }
                 }
 
+                // Keys of already deployed tokens are rewritten as is
+                server.db.token_skeleton.extend(metas.iter().map(|(_, meta)| (TokenSkeletonKey::new(meta.proto.tick), ())));
+                server.db.deployer_tokens.extend(metas.iter().map(|(_, meta)| (meta.proto.deployer_token(), ())));
+                server.db.token_completion_height.extend(completed);
                 server.db.token_to_meta.extend(metas);
+                server.token_accounts.update(&balances);
+                server.db.update_held_tokens(&balances);
                 server.db.address_token_to_balance.extend(balances);
                 server.db.address_location_to_transfer.remove_batch(transfers_to_remove);
                 server.db.address_location_to_transfer.extend(transfers_to_write);
             }
-            ProcessedData::InscriptionPartials { to_remove, to_write } => {
+            ProcessedData::InscriptionPartials { height, to_remove, to_write } => {
                 if let Some(reorg_cache) = reorg_cache.as_mut() {
                     reorg_cache.push_ordinals_entry(OrdinalsEntry::RestorePartial(to_remove.clone()));
                     reorg_cache.push_ordinals_entry(OrdinalsEntry::RemovePartials(to_write.iter().map(|x| x.0).collect_vec()));
                 }
 
+                server.db.partials_height.remove_batch(to_remove.iter().map(|x| x.0));
+                server.db.partials_height.extend(to_write.iter().map(|x| (x.0, height)));
                 server.db.outpoint_to_partials.remove_batch(to_remove.iter().map(|x| x.0));
                 server.db.outpoint_to_partials.extend(to_write);
             }
@@ -193,6 +308,114 @@ impl ProcessedData {
                 server.db.outpoint_to_inscription_offsets.remove_batch(to_remove.iter().map(|x| x.0));
                 server.db.outpoint_to_inscription_offsets.extend(to_write);
             }
+            ProcessedData::InscriptionStats { block_number, stats } => {
+                if let Some(reorg_cache) = reorg_cache.as_mut() {
+                    reorg_cache.push_ordinals_entry(OrdinalsEntry::RemoveInscriptionStats(block_number));
+                }
+
+                server.db.block_inscription_stats.set(block_number, stats);
+            }
+            ProcessedData::FeeStats { block_number, stats } => {
+                if let Some(reorg_cache) = reorg_cache.as_mut() {
+                    reorg_cache.push_ordinals_entry(OrdinalsEntry::RemoveFeeStats(block_number));
+                }
+
+                server.db.block_fee_stats.set(block_number, stats);
+            }
+            ProcessedData::MintFees { fees } => {
+                let fees_before = server
+                    .db
+                    .token_mint_fees
+                    .multi_get_kv(fees.iter().map(|x| &x.0), false)
+                    .into_iter()
+                    .map(|(k, v)| (k.clone(), v))
+                    .collect::<HashMap<_, _>>();
+
+                if let Some(reorg_cache) = reorg_cache.as_mut() {
+                    let new_ticks = fees.iter().map(|x| x.0.clone()).filter(|x| !fees_before.contains_key(x)).collect_vec();
+                    reorg_cache.push_token_entry(TokenHistoryEntry::MintFeesBefore(fees_before.clone().into_iter().collect()));
+                    reorg_cache.push_token_entry(TokenHistoryEntry::MintFeesToRemove(new_ticks));
+                }
+
+                server
+                    .db
+                    .token_mint_fees
+                    .extend(fees.into_iter().map(|(tick, fee)| (tick.clone(), fees_before.get(&tick).copied().unwrap_or_default() + fee)));
+            }
+            ProcessedData::InscriptionHistory { to_remove, to_write, history } => {
+                // The last move of the inscription in the block, history is in the order of the moves
+                let locations = history.iter().map(|(key, value)| (key.id, value.location)).collect::<HashMap<_, _>>();
+
+                if let Some(reorg_cache) = reorg_cache.as_mut() {
+                    reorg_cache.push_ordinals_entry(OrdinalsEntry::RestoreInscriptionLocations(to_remove.clone()));
+                    reorg_cache.push_ordinals_entry(OrdinalsEntry::RemoveInscriptionLocations(to_write.iter().map(|x| x.0).collect_vec()));
+                    reorg_cache.push_ordinals_entry(OrdinalsEntry::RemoveInscriptionHistory(history.iter().map(|x| x.0).collect_vec()));
+
+                    let ids = locations.keys().copied().collect_vec();
+                    let (mut moved, mut created) = (vec![], vec![]);
+                    for (id, location) in ids.iter().zip(server.db.inscription_location.multi_get(ids.iter())) {
+                        match location {
+                            Some(location) => moved.push((*id, location)),
+                            None => created.push(*id),
+                        }
+                    }
+                    reorg_cache.push_ordinals_entry(OrdinalsEntry::RestoreInscriptionIdLocations(moved));
+                    reorg_cache.push_ordinals_entry(OrdinalsEntry::RemoveInscriptionIdLocations(created));
+                }
+
+                server.db.location_to_inscriptions.remove_batch(to_remove.iter().map(|x| x.0));
+                server.db.location_to_inscriptions.extend(to_write);
+                server.db.inscription_history.extend(history);
+                server.db.inscription_location.extend(locations);
+            }
+            ProcessedData::HoldersSnapshot { height, ticks, holders } => {
+                server.db.holders_snapshot.extend(holders);
+                server.db.holders_snapshot_ticks.set(height, ticks);
+            }
+            ProcessedData::MintActivity { height, activity } => {
+                if let Some(reorg_cache) = reorg_cache.as_mut() {
+                    reorg_cache.push_token_entry(TokenHistoryEntry::RemoveMintActivity {
+                        height,
+                        ticks: activity.iter().map(|x| x.0).collect(),
+                    });
+                }
+
+                server.db.token_mint_activity.extend(activity.into_iter().map(|(tick, activity)| (TokenHeight { tick, height }, activity)));
+            }
+            ProcessedData::FirstMints { mints } => {
+                let minted_before = server.db.token_first_mint.multi_get(mints.iter().map(|x| &x.0));
+                let mints = mints.into_iter().zip(minted_before).filter(|(_, before)| before.is_none()).map(|(mint, _)| mint).collect_vec();
+
+                if let Some(reorg_cache) = reorg_cache.as_mut() {
+                    reorg_cache.push_token_entry(TokenHistoryEntry::RemoveFirstMints(mints.iter().map(|x| x.0.clone()).collect()));
+                }
+
+                server.db.token_first_mint.extend(mints);
+            }
+            ProcessedData::RejectedDeploys { height, deploys } => {
+                if let Some(reorg_cache) = reorg_cache.as_mut() {
+                    reorg_cache.push_token_entry(TokenHistoryEntry::RemoveRejectedDeploys(height));
+                }
+
+                server.db.rejected_deploys.set(height, deploys);
+            }
+            ProcessedData::InscriptionCurses { curses } => {
+                if let Some(reorg_cache) = reorg_cache.as_mut() {
+                    reorg_cache.push_ordinals_entry(OrdinalsEntry::RemoveInscriptionCurses(curses.iter().map(|x| x.0).collect_vec()));
+                }
+
+                server.db.inscription_curses.extend(curses);
+            }
+            ProcessedData::LeakedInscriptions { height, leaked } => {
+                let owners = leaked.iter().map(|x| x.owner).unique().collect_vec();
+
+                if let Some(reorg_cache) = reorg_cache.as_mut() {
+                    reorg_cache.push_ordinals_entry(OrdinalsEntry::RemoveLeakedInscriptions { height, owners: owners.clone() });
+                }
+
+                server.db.address_leaked.extend(owners.into_iter().map(|address| (AddressHeight { address, height }, ())));
+                server.db.block_leaked.set(height, leaked);
+            }
         }
     }
 }
@@ -0,0 +1,143 @@
+use super::*;
+
+/// State of a rolled back block before the simulation, compared after it's indexed again
+struct BlockState {
+    height: u32,
+    hash: BlockHash,
+    proof: Option<sha256::Hash>,
+    balance_root: Option<sha256::Hash>,
+}
+
+impl Indexer {
+    /// Runs a depth-N rollback through the reorg cache as if the last blocks were replaced, then indexes the same blocks again. <br/>
+    /// Checks that the rollback restores the state before the rolled back blocks and that indexing them again
+    /// gives the same proofs and balances. An error after the rollback is fatal, since the indexer can't continue from a partial replay.
+    pub(super) fn simulate_reorg(&self, indexer: &InscriptionIndexer, depth: u32) -> anyhow::Result<rest::types::ReorgSimulation> {
+        let db = &self.server.db;
+
+        let Some(tip) = db.last_block.get(()) else {
+            return Ok(rest::types::ReorgSimulation::failed(depth, "Nothing is indexed yet".to_string()));
+        };
+
+        let cached = self.reorg_cache.lock().blocks.len() as u32;
+        if depth == 0 || depth > cached {
+            return Ok(rest::types::ReorgSimulation::failed(
+                depth,
+                format!("Depth must be between 1 and {cached}, the number of blocks in the reorg cache"),
+            ));
+        }
+
+        if self.reorg_cache.lock().blocks.last_key_value().map(|x| *x.0) != Some(tip) {
+            return Ok(rest::types::ReorgSimulation::failed(depth, format!("Reorg cache doesn't end at the last block {tip}")));
+        }
+
+        let restore_height = tip - depth;
+
+        let blocks = (restore_height + 1..=tip)
+            .map(|height| {
+                let hash = db.block_info.get(height).ok_or_else(|| anyhow::anyhow!("Block {height} isn't indexed"))?.hash;
+                Ok(BlockState {
+                    height,
+                    hash,
+                    proof: db.proof_of_history.get(height),
+                    balance_root: db.balance_root.get(height),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>();
+
+        let blocks = match blocks {
+            Ok(blocks) => blocks,
+            Err(e) => return Ok(rest::types::ReorgSimulation::failed(depth, e.to_string())),
+        };
+
+        let events = blocks.iter().flat_map(|block| db.block_events.get(block.height).unwrap_or_default()).collect_vec();
+        let accounts = events.iter().map(|key| AddressToken::from(*key)).unique().collect_vec();
+        let balances_before = db.load_token_accounts(accounts.clone());
+        let last_history_id = db.last_history_id.get(()).unwrap_or_default();
+        let expected_history_id = events.iter().map(|key| key.id).min().map(|id| id - 1).unwrap_or(last_history_id);
+
+        let mut report = rest::types::ReorgSimulation {
+            depth,
+            restore_height: Some(restore_height),
+            tip: Some(tip),
+            rollback_ms: 0,
+            replay_ms: 0,
+            ok: false,
+            error: None,
+            checks: vec![],
+        };
+
+        warn!("Simulating reorg of {} blocks, rolling back to {}", depth, restore_height);
+
+        let started = Instant::now();
+        self.reorg_cache.lock().restore(db, restore_height, &self.server.reorg_in_progress)?;
+        self.server.token_accounts.clear();
+        self.server.event_sender.send(ServerEvent::Reorg(depth, restore_height + 1)).ok();
+        report.rollback_ms = started.elapsed().as_millis() as u64;
+
+        let mut check = |name: &str, ok: bool, details: String| {
+            report.checks.push(rest::types::ReorgSimulationCheck {
+                name: name.to_string(),
+                ok,
+                details: (!ok).then_some(details),
+            });
+        };
+
+        let last_block = db.last_block.get(());
+        check("rollback_last_block", last_block == Some(restore_height), format!("expected {restore_height}, got {last_block:?}"));
+
+        let marker = db.last_committed_block_marker.get(());
+        check(
+            "rollback_commit_marker",
+            marker == Some(restore_height),
+            format!("expected {restore_height}, got {marker:?}"),
+        );
+
+        let reorg_tip = self.reorg_cache.lock().blocks.last_key_value().map(|x| *x.0);
+        check("rollback_reorg_cache", reorg_tip == Some(restore_height), format!("expected {restore_height}, got {reorg_tip:?}"));
+
+        let history_id = db.last_history_id.get(()).unwrap_or_default();
+        check("rollback_last_history_id", history_id == expected_history_id, format!("expected {expected_history_id}, got {history_id}"));
+
+        let left_events = blocks.iter().filter(|block| db.block_events.get(block.height).is_some()).map(|block| block.height).collect_vec();
+        check("rollback_block_events", left_events.is_empty(), format!("events left at heights {left_events:?}"));
+
+        let left_history = db.address_token_to_history.multi_get_kv(events.iter(), false).len();
+        check("rollback_history", left_history == 0, format!("{left_history} events left"));
+
+        let started = Instant::now();
+        for block in &blocks {
+            let data = self.server.client.try_get_block(&block.hash.to_raw_hash())?;
+            indexer.handle(block.height, data, true)?;
+        }
+        report.replay_ms = started.elapsed().as_millis() as u64;
+
+        let last_block = db.last_block.get(());
+        check("replay_last_block", last_block == Some(tip), format!("expected {tip}, got {last_block:?}"));
+
+        let marker = db.last_committed_block_marker.get(());
+        check("replay_commit_marker", marker == Some(tip), format!("expected {tip}, got {marker:?}"));
+
+        let history_id = db.last_history_id.get(()).unwrap_or_default();
+        check("replay_last_history_id", history_id == last_history_id, format!("expected {last_history_id}, got {history_id}"));
+
+        let proofs = blocks.iter().filter(|block| db.proof_of_history.get(block.height) != block.proof).map(|block| block.height).collect_vec();
+        check("replay_proof_of_history", proofs.is_empty(), format!("proofs differ at heights {proofs:?}"));
+
+        let roots = blocks.iter().filter(|block| db.balance_root.get(block.height) != block.balance_root).map(|block| block.height).collect_vec();
+        check("replay_balance_root", roots.is_empty(), format!("balance roots differ at heights {roots:?}"));
+
+        let balances_after = db.load_token_accounts(accounts);
+        let balances = balances_before.iter().filter(|(key, balance)| balances_after.get(key) != Some(balance)).count();
+        check("replay_balances", balances == 0, format!("{balances} of {} balances differ", balances_before.len()));
+
+        report.ok = report.checks.iter().all(|x| x.ok);
+        if report.ok {
+            info!("Reorg simulation of {} blocks passed", depth);
+        } else {
+            error!("Reorg simulation of {} blocks failed: {:?}", depth, report.checks.iter().filter(|x| !x.ok).map(|x| &x.name).collect_vec());
+        }
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,190 @@
+use super::*;
+
+/// Removes the sandbox directory when reprocessing ends, including early returns
+struct SandboxDir(String);
+
+impl Drop for SandboxDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove reprocess sandbox {}: {}", self.0, e);
+            }
+        }
+    }
+}
+
+impl Indexer {
+    /// Indexes the block again over a checkpoint of the database which is rolled back to the previous block through the reorg cache,
+    /// then compares the result with the stored block. <br/>
+    /// Nothing is written to the live database, so only blocks still in the reorg cache can be reprocessed: the state before older ones isn't kept.
+    pub(super) fn reprocess_block(&self, height: u32) -> anyhow::Result<rest::types::BlockReprocess> {
+        let db = &self.server.db;
+
+        let Some(tip) = db.last_block.get(()) else {
+            return Ok(rest::types::BlockReprocess::failed(height, "Nothing is indexed yet".to_string()));
+        };
+
+        let (first, last) = {
+            let cache = self.reorg_cache.lock();
+            (cache.blocks.first_key_value().map(|x| *x.0), cache.blocks.last_key_value().map(|x| *x.0))
+        };
+
+        let Some(first) = first.map(|first| first.max(1)) else {
+            return Ok(rest::types::BlockReprocess::failed(height, "Reorg cache is empty, the indexer isn't at the chain tip".to_string()));
+        };
+
+        if last != Some(tip) {
+            return Ok(rest::types::BlockReprocess::failed(height, format!("Reorg cache doesn't end at the last block {tip}")));
+        }
+
+        if height < first || height > tip {
+            return Ok(rest::types::BlockReprocess::failed(
+                height,
+                format!("Height must be between {first} and {tip}, the blocks in the reorg cache"),
+            ));
+        }
+
+        let hash = db.block_info.get(height).ok_or_else(|| anyhow::anyhow!("Block {height} isn't indexed"))?.hash;
+        let block = self.server.client.try_get_block(&hash.to_raw_hash())?;
+
+        let path = format!("{}-reprocess", *DB_PATH);
+        let _sandbox_dir = SandboxDir(path.clone());
+        if std::path::Path::new(&path).exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+
+        info!("Reprocessing block {} in a sandbox at {}", height, path);
+
+        let started = Instant::now();
+        db.rocks().checkpoint(&path)?;
+
+        let sandbox_db = Arc::new(DB::open(&path));
+        let mut reorg_cache = ReorgCache::load(&sandbox_db);
+        reorg_cache.restore(&sandbox_db, height - 1, &ReorgInProgress::default())?;
+
+        // Holders are loaded from the restored balances
+        let sandbox = Arc::new(self.server.sandbox(sandbox_db));
+        let restore_ms = started.elapsed().as_millis() as u64;
+
+        let started = Instant::now();
+        let data = InscriptionIndexer::new(sandbox, Arc::new(parking_lot::Mutex::new(reorg_cache))).dry_run(height, block)?;
+        let process_ms = started.elapsed().as_millis() as u64;
+
+        let checks = self.compare_block(height, &data.processed);
+        let ok = checks.iter().all(|x| x.ok);
+        if ok {
+            info!("Block {} reprocessed without divergence", height);
+        } else {
+            error!("Reprocessed block {} diverges: {:?}", height, checks.iter().filter(|x| !x.ok).map(|x| &x.name).collect_vec());
+        }
+
+        Ok(rest::types::BlockReprocess {
+            height,
+            hash: Some(hash.to_string()),
+            restore_ms,
+            process_ms,
+            ok,
+            error: None,
+            checks,
+        })
+    }
+
+    /// Recomputed data of the block against the stored one, data which later blocks overwrite (balances, deploys) is covered by the balance leaves
+    fn compare_block(&self, height: u32, processed: &[ProcessedData]) -> Vec<rest::types::ReorgSimulationCheck> {
+        let db = &self.server.db;
+
+        let mut checks = vec![];
+        let mut check = |name: &str, ok: bool, details: String| {
+            checks.push(rest::types::ReorgSimulationCheck {
+                name: name.to_string(),
+                ok,
+                details: (!ok).then_some(details),
+            });
+        };
+
+        let (history, balance_leaves) = processed
+            .iter()
+            .find_map(|data| match data {
+                ProcessedData::History { history, balance_leaves, .. } => Some((history.as_slice(), balance_leaves.as_slice())),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        for data in processed {
+            match data {
+                ProcessedData::Info {
+                    median_time,
+                    block_summary,
+                    block_proof,
+                    ..
+                } => {
+                    let stored = db.block_median_time.get(height);
+                    check("median_time", stored == Some(*median_time), format!("recomputed {median_time}, stored {stored:?}"));
+
+                    // Blocks indexed before summaries and events hashes were stored have none
+                    if let Some(stored) = db.block_summary.get(height) {
+                        check("block_summary", stored == *block_summary, format!("recomputed {block_summary:?}, stored {stored:?}"));
+                    }
+
+                    if let Some(stored) = db.proof_of_history_events_hash.get(height) {
+                        let events_hash = block_proof.events_hash;
+                        check("events_hash", stored == events_hash, format!("recomputed {events_hash}, stored {stored}"));
+                    }
+
+                    let stored = db.proof_of_history.get(height);
+                    let proof = block_proof.proof;
+                    check("proof_of_history", stored == Some(proof), format!("recomputed {proof}, stored {stored:?}"));
+                }
+                ProcessedData::Tokens { completed, .. } => {
+                    let stored = db.token_completion_height.multi_get(completed.iter().map(|x| &x.0));
+                    let differ = completed
+                        .iter()
+                        .zip(stored)
+                        .filter(|((_, completed_at), stored)| *stored != Some(*completed_at))
+                        .map(|((tick, _), _)| String::from_utf8_lossy(tick).into_owned())
+                        .collect_vec();
+                    check("completed_tokens", differ.is_empty(), format!("completion heights of {differ:?} differ"));
+                }
+                ProcessedData::InscriptionStats { stats, .. } => {
+                    let stored = db.block_inscription_stats.get(height);
+                    check("inscription_stats", stored == Some(*stats), format!("recomputed {stats:?}, stored {stored:?}"));
+                }
+                ProcessedData::FeeStats { stats, .. } => {
+                    let stored = db.block_fee_stats.get(height);
+                    check("fee_stats", stored == Some(*stats), format!("recomputed {stats:?}, stored {stored:?}"));
+                }
+                _ => {}
+            }
+        }
+
+        let stored_keys = db.block_events.get(height).unwrap_or_default();
+        let stored = db
+            .address_token_to_history
+            .multi_get_kv(stored_keys.iter(), false)
+            .into_iter()
+            .map(|(k, v)| (*k, v))
+            .collect::<HashMap<_, _>>();
+        let recomputed = history.iter().map(|(k, _)| *k).collect::<HashSet<_>>();
+        let differ = history.iter().filter(|(k, v)| stored.get(k) != Some(v)).map(|(k, _)| k.id).sorted().collect_vec();
+        let not_recomputed = stored_keys.iter().filter(|k| !recomputed.contains(*k)).map(|k| k.id).sorted().collect_vec();
+        check(
+            "history",
+            differ.is_empty() && not_recomputed.is_empty(),
+            format!(
+                "{} recomputed and {} stored events, recomputed ids {differ:?} are missing or differ, stored ids {not_recomputed:?} weren't recomputed",
+                history.len(),
+                stored_keys.len()
+            ),
+        );
+
+        let stored = db.block_balance_leaves.get(height).unwrap_or_default();
+        let differ = balance_leaves.iter().filter(|leaf| !stored.contains(leaf)).count();
+        check(
+            "balance_leaves",
+            differ == 0 && stored.len() == balance_leaves.len(),
+            format!("{} recomputed and {} stored balances, {differ} recomputed ones differ", balance_leaves.len(), stored.len()),
+        );
+
+        checks
+    }
+}
@@ -1,6 +1,9 @@
-use nint_blk::proto::{
-    tx::{EvaluatedTx, EvaluatedTxOut},
-    Hashed,
+use nint_blk::{
+    proto::{
+        tx::{EvaluatedTx, EvaluatedTxOut},
+        Hashed,
+    },
+    PointerPolicy,
 };
 
 use super::*;
@@ -51,4 +54,113 @@ impl InscriptionSearcher {
 
         Err(anyhow::anyhow!("leaked: offset exhausted"))
     }
+
+    /// Output and offset the pointer moves the inscription to, `None` if the coin ignores pointers or it points into the fee. <br/>
+    /// Inscriptions whose first sat is spent on the fee are leaked before the pointer is looked at.
+    pub fn pointer_location(pointer: Option<u64>, policy: PointerPolicy, tx_outs: &[EvaluatedTxOut]) -> Option<(u32, u64)> {
+        match policy {
+            PointerPolicy::IgnorePointer => None,
+            PointerPolicy::OrdPointer => Self::get_output_index_by_input(pointer, tx_outs).ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nint_blk::{
+        proto::{tx::TxOutput, varuint::VarUint},
+        CoinType,
+    };
+
+    use super::*;
+
+    fn outputs(values: &[u64]) -> Vec<EvaluatedTxOut> {
+        values
+            .iter()
+            .map(|value| {
+                let out = TxOutput {
+                    value: *value,
+                    script_len: VarUint::from(0u8),
+                    script_pubkey: vec![],
+                };
+                EvaluatedTxOut::eval_script(out, CoinType::default())
+            })
+            .collect()
+    }
+
+    fn inscription(pointer: Vec<u8>) -> Inscription {
+        Inscription {
+            body: Some(b"{}".to_vec()),
+            content_type: Some(b"text/plain".to_vec()),
+            content_encoding: None,
+            delegate: None,
+            duplicate_field: false,
+            incomplete_field: false,
+            metadata: None,
+            metaprotocol: None,
+            parents: vec![],
+            pointer: Some(pointer),
+            rune: None,
+            unrecognized_even_field: false,
+            envelope_offset: 0,
+            pushnum: false,
+            stutter: false,
+        }
+    }
+
+    #[test]
+    fn pointer_within_outputs() {
+        let outputs = outputs(&[1_000, 2_000]);
+        let pointer = inscription(1_500u64.to_le_bytes().to_vec()).pointer();
+
+        assert_eq!(InscriptionSearcher::pointer_location(pointer, PointerPolicy::OrdPointer, &outputs), Some((1, 500)));
+        assert_eq!(InscriptionSearcher::pointer_location(pointer, PointerPolicy::IgnorePointer, &outputs), None);
+    }
+
+    #[test]
+    fn pointer_beyond_outputs() {
+        let outputs = outputs(&[1_000, 2_000]);
+
+        let pointer = inscription(1_000_000u64.to_le_bytes().to_vec()).pointer();
+        assert_eq!(InscriptionSearcher::pointer_location(pointer, PointerPolicy::OrdPointer, &outputs), None);
+
+        // Values wider than u64 aren't pointers at all
+        let pointer = inscription([1_500u64.to_le_bytes().as_slice(), &[1]].concat()).pointer();
+        assert_eq!(pointer, None);
+        assert_eq!(InscriptionSearcher::pointer_location(pointer, PointerPolicy::OrdPointer, &outputs), None);
+
+        // Trailing zeros are allowed
+        let pointer = inscription([1_500u64.to_le_bytes().as_slice(), &[0, 0]].concat()).pointer();
+        assert_eq!(InscriptionSearcher::pointer_location(pointer, PointerPolicy::OrdPointer, &outputs), Some((1, 500)));
+    }
+
+    #[test]
+    fn pointer_into_fee() {
+        let outputs = outputs(&[1_000, 2_000]);
+
+        // The first sat after the outputs is spent on the fee
+        let pointer = inscription(3_000u64.to_le_bytes().to_vec()).pointer();
+        assert_eq!(InscriptionSearcher::pointer_location(pointer, PointerPolicy::OrdPointer, &outputs), None);
+
+        let pointer = inscription(2_999u64.to_le_bytes().to_vec()).pointer();
+        assert_eq!(InscriptionSearcher::pointer_location(pointer, PointerPolicy::OrdPointer, &outputs), Some((1, 1_999)));
+
+        // An inscription whose first sat is spent on the fee is leaked, the pointer doesn't move it back into the outputs
+        assert!(InscriptionSearcher::get_output_index_by_input(Some(3_000), &outputs).is_err());
+    }
+
+    #[test]
+    fn pointer_on_cursed_inscription() {
+        let outputs = outputs(&[1_000, 2_000]);
+        let mut inscription = inscription(1_500u64.to_le_bytes().to_vec());
+        inscription.duplicate_field = true;
+
+        // Curses don't depend on the policy, the pointer is still applied to cursed inscriptions
+        assert_eq!(
+            inscription.curses(1),
+            vec![InscriptionCurse::NotInFirstInput, InscriptionCurse::DuplicateField, InscriptionCurse::Pointer]
+        );
+        assert_eq!(InscriptionSearcher::pointer_location(inscription.pointer(), PointerPolicy::OrdPointer, &outputs), Some((1, 500)));
+        assert_eq!(InscriptionSearcher::pointer_location(inscription.pointer(), PointerPolicy::IgnorePointer, &outputs), None);
+    }
 }
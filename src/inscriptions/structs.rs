@@ -16,6 +16,10 @@ pub struct Inscription {
     pub pointer: Option<Vec<u8>>,
     pub rune: Option<Vec<u8>>,
     pub unrecognized_even_field: bool,
+    /// Envelope flags, always unset for the legacy format
+    pub envelope_offset: u32,
+    pub pushnum: bool,
+    pub stutter: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -24,14 +28,76 @@ pub enum ParsedInscription {
     Partial,
     Single(Inscription),
     Many(Vec<Inscription>),
+    /// Over one of the parser limits, nothing of the input is indexed
+    Rejected(LimitExceeded),
+}
+
+/// Hard limit of the parser hit by a pathological transaction
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LimitExceeded {
+    /// `MAX_PARTIAL_PARTS`
+    PartialParts,
+    /// `MAX_INSCRIPTION_BYTES`
+    InscriptionBytes,
+    /// `MAX_INSCRIPTIONS_PER_TX`
+    InscriptionsPerTx,
+    /// `MAX_ENVELOPES_PER_INPUT`
+    EnvelopesPerInput,
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (name, limit) = match self {
+            LimitExceeded::PartialParts => ("MAX_PARTIAL_PARTS", *MAX_PARTIAL_PARTS),
+            LimitExceeded::InscriptionBytes => ("MAX_INSCRIPTION_BYTES", *MAX_INSCRIPTION_BYTES),
+            LimitExceeded::InscriptionsPerTx => ("MAX_INSCRIPTIONS_PER_TX", *MAX_INSCRIPTIONS_PER_TX),
+            LimitExceeded::EnvelopesPerInput => ("MAX_ENVELOPES_PER_INPUT", *MAX_ENVELOPES_PER_INPUT),
+        };
+        write!(f, "over {name} ({})", limit.unwrap_or_default())
+    }
+}
+
+impl ParsedInscription {
+    /// Inscriptions the input creates, the start of a multi-part inscription is one
+    fn created(&self) -> usize {
+        match self {
+            ParsedInscription::None | ParsedInscription::Rejected(_) => 0,
+            ParsedInscription::Partial | ParsedInscription::Single(_) => 1,
+            ParsedInscription::Many(inscriptions) => inscriptions.len(),
+        }
+    }
+
+    /// Rejects the input if its inscriptions take the transaction over `MAX_INSCRIPTIONS_PER_TX`,
+    /// `created_before` is the number of inscriptions created by the previous inputs
+    pub fn limit_per_tx(self, created_before: u32) -> Self {
+        match *MAX_INSCRIPTIONS_PER_TX {
+            Some(max) if created_before as usize + self.created() > max => ParsedInscription::Rejected(LimitExceeded::InscriptionsPerTx),
+            _ => self,
+        }
+    }
+}
+
+fn over_limit(limit: Option<usize>, value: usize) -> bool {
+    limit.is_some_and(|max| value > max)
 }
 
 impl Inscription {
     pub fn from_parts(partials: &[Part], vin: u32) -> ParsedInscription {
+        if over_limit(*MAX_PARTIAL_PARTS, partials.len()) {
+            return ParsedInscription::Rejected(LimitExceeded::PartialParts);
+        }
+
         if partials.len() == 1 && partials[0].is_tapscript {
             let script = Script::from_bytes(&partials[0].script_buffer);
             if let Result::Ok(v) = RawEnvelope::from_tapscript(script, vin as usize) {
-                let data = v.into_iter().map(ParsedEnvelope::from).map(|x| x.payload).collect();
+                if over_limit(*MAX_ENVELOPES_PER_INPUT, v.len()) {
+                    return ParsedInscription::Rejected(LimitExceeded::EnvelopesPerInput);
+                }
+
+                let data: Vec<_> = v.into_iter().map(ParsedEnvelope::from).map(|x| x.payload).collect();
+                if data.iter().any(|x| over_limit(*MAX_INSCRIPTION_BYTES, x.body.as_ref().map_or(0, Vec::len))) {
+                    return ParsedInscription::Rejected(LimitExceeded::InscriptionBytes);
+                }
 
                 return ParsedInscription::Many(data);
             }
@@ -45,6 +111,24 @@ impl Inscription {
         InscriptionParser::parse(sig_scripts)
     }
 
+    /// Curses known from the inscription alone, reinscriptions are found by the location
+    pub fn curses(&self, input_index: u32) -> Vec<InscriptionCurse> {
+        [
+            (input_index != 0, InscriptionCurse::NotInFirstInput),
+            (self.duplicate_field, InscriptionCurse::DuplicateField),
+            (self.incomplete_field, InscriptionCurse::IncompleteField),
+            (self.unrecognized_even_field, InscriptionCurse::UnrecognizedEvenField),
+            (self.envelope_offset != 0, InscriptionCurse::NotAtOffsetZero),
+            (self.pushnum, InscriptionCurse::Pushnum),
+            (self.stutter, InscriptionCurse::Stutter),
+            (self.pointer.is_some(), InscriptionCurse::Pointer),
+        ]
+        .into_iter()
+        .filter(|(cursed, _)| *cursed)
+        .map(|(_, curse)| curse)
+        .collect()
+    }
+
     pub fn into_body(self) -> Option<Vec<u8>> {
         self.body
     }
@@ -141,6 +225,9 @@ impl InscriptionParser {
                         pointer: None,
                         rune: None,
                         unrecognized_even_field: false,
+                        envelope_offset: 0,
+                        pushnum: false,
+                        stutter: false,
                     };
 
                     return ParsedInscription::Single(inscription);
@@ -160,6 +247,9 @@ impl InscriptionParser {
                 }
 
                 body.append(&mut push_datas[1].clone());
+                if over_limit(*MAX_INSCRIPTION_BYTES, body.len()) {
+                    return ParsedInscription::Rejected(LimitExceeded::InscriptionBytes);
+                }
 
                 push_datas = &push_datas[2..];
                 npieces -= 1;
@@ -328,3 +418,53 @@ pub struct Part {
     pub is_tapscript: bool,
     pub script_buffer: Vec<u8>,
 }
+
+impl Part {
+    /// First byte of the annex, the last witness item if present (BIP-341)
+    const TAPROOT_ANNEX_PREFIX: u8 = 0x50;
+
+    /// Script of the input which may reveal inscriptions: the leaf script of a script-path spend (the annex is skipped),
+    /// otherwise the script sig of legacy multi-part inscriptions. <br/>
+    /// From `WITNESS_SCAN_HEIGHT` a leaf script without envelopes (e.g. a multisig leaf) falls back to the first
+    /// witness stack item holding one, so inscriptions pushed as script-path arguments are indexed too.
+    pub fn from_input(txin: &nint_blk::proto::tx::TxInput, height: u32) -> Self {
+        let Some(tapscript) = txin.witness.tapscript() else {
+            return Self {
+                is_tapscript: false,
+                script_buffer: txin.script_sig.clone(),
+            };
+        };
+
+        let scan_stack = WITNESS_SCAN_HEIGHT.is_some_and(|x| height >= x);
+        let script_buffer = if scan_stack && !Self::has_envelope(tapscript.as_bytes()) {
+            Self::stack_items(&txin.witness)
+                .into_iter()
+                .find(|x| Self::has_envelope(x))
+                .map(|x| x.to_vec())
+                .unwrap_or_else(|| tapscript.to_bytes())
+        } else {
+            tapscript.to_bytes()
+        };
+
+        Self {
+            is_tapscript: true,
+            script_buffer,
+        }
+    }
+
+    /// Arguments of a script-path spend, the witness without the annex, leaf script and control block
+    fn stack_items(witness: &bellscoin::Witness) -> Vec<&[u8]> {
+        let mut items = witness.iter().collect_vec();
+
+        if items.len() >= 2 && items.last().and_then(|x| x.first()) == Some(&Self::TAPROOT_ANNEX_PREFIX) {
+            items.pop();
+        }
+
+        items.truncate(items.len().saturating_sub(2));
+        items
+    }
+
+    fn has_envelope(script: &[u8]) -> bool {
+        RawEnvelope::from_tapscript(Script::from_bytes(script), 0).is_ok_and(|x| !x.is_empty())
+    }
+}
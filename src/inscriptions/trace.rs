@@ -0,0 +1,255 @@
+use nint_blk::proto::{block::Block, tx::EvaluatedTx, Hashed};
+
+use crate::inscriptions::{
+    searcher::InscriptionSearcher,
+    structs::{ParsedInscription, Part},
+};
+
+use super::*;
+
+/// Replays inscription parsing of a confirmed transaction in memory, the same way [`Parser::parse_block`] does. <br/>
+/// Nothing is written, prevouts from earlier blocks are requested from the node, which requires `txindex` on it.
+/// Moves of inscriptions already in the inputs and partials started in earlier transactions aren't traced,
+/// since the state before the block isn't kept.
+pub fn trace_tx(server: &Server, height: u32, block: &Block, txid: Txid) -> anyhow::Result<rest::types::TxTrace> {
+    let is_jubilee_height = height as usize >= *JUBILEE_HEIGHT;
+
+    let tx = block
+        .txs
+        .iter()
+        .find(|tx| Txid::from(tx.hash) == txid)
+        .ok_or_else(|| anyhow::anyhow!("Transaction {txid} isn't in block {height}"))?;
+
+    if tx.value.is_coinbase() {
+        anyhow::bail!("Coinbase transactions don't create inscriptions");
+    }
+
+    let prevouts = load_prevouts(server, block, tx)?;
+    let inputs_cum = InscriptionSearcher::calc_offsets(tx, &prevouts).ok_or_else(|| anyhow::anyhow!("Inputs are worth less than outputs"))?;
+    let outputs = &tx.value.outputs;
+
+    let mut inputs = vec![];
+    let mut occupied = HashSet::new();
+    let mut inscription_index_in_tx = 0;
+
+    for (input_index, txin) in tx.value.inputs.iter().enumerate() {
+        let mut input = rest::types::InputTrace {
+            input_index: input_index as u32,
+            outpoint: txin.outpoint.to_string(),
+            envelope: "skipped".to_string(),
+            inscriptions: vec![],
+        };
+
+        if !is_jubilee_height && input_index != 0 {
+            inputs.push(input);
+            continue;
+        }
+
+        let part = Part::from_input(txin, height);
+
+        let parsed = Inscription::from_parts(std::slice::from_ref(&part), input_index as u32).limit_per_tx(inscription_index_in_tx);
+
+        let inscriptions = match parsed {
+            ParsedInscription::None => {
+                input.envelope = "none".to_string();
+                inputs.push(input);
+                continue;
+            }
+            ParsedInscription::Partial => {
+                input.envelope = "partial".to_string();
+                inscription_index_in_tx += 1;
+                inputs.push(input);
+                continue;
+            }
+            ParsedInscription::Rejected(limit) => {
+                input.envelope = format!("rejected: {limit}");
+                inputs.push(input);
+                continue;
+            }
+            ParsedInscription::Single(inscription) => vec![inscription],
+            ParsedInscription::Many(inscriptions) => inscriptions,
+        };
+
+        input.envelope = "inscriptions".to_string();
+
+        for inscription in inscriptions {
+            let id = InscriptionId {
+                txid,
+                index: inscription_index_in_tx,
+            };
+            inscription_index_in_tx += 1;
+
+            let curses = inscription.curses(input_index as u32);
+            let content_type = inscription.content_type().map(|x| x.to_owned());
+            let pointer = inscription.pointer();
+            let body = inscription.into_body();
+
+            let mut trace = rest::types::InscriptionTrace {
+                id: id.to_string(),
+                content_type: content_type.clone(),
+                body_size: body.as_ref().map(|x| x.len()).unwrap_or_default(),
+                curses,
+                pointer,
+                pointer_applied: false,
+                leaked: false,
+                location: None,
+                owner: None,
+                reinscription: false,
+                skipped: false,
+                token: rest::types::TokenTrace {
+                    parsed: false,
+                    op: None,
+                    tick: None,
+                    error: None,
+                },
+            };
+
+            let Ok((mut vout, mut offset)) = InscriptionSearcher::get_output_index_by_input(inputs_cum.get(input_index).copied(), outputs) else {
+                trace.leaked = true;
+                trace.token.error = Some("leaked".to_string());
+                input.inscriptions.push(trace);
+                continue;
+            };
+
+            if let Some((new_vout, new_offset)) = InscriptionSearcher::pointer_location(pointer, COIN.pointer_policy, outputs) {
+                vout = new_vout;
+                offset = new_offset;
+                trace.pointer_applied = true;
+            }
+
+            let owner = Parser::output_owner(&outputs[vout as usize].out.script_pubkey);
+            trace.location = Some(Location { outpoint: OutPoint { txid, vout }, offset }.to_string());
+            trace.owner = Some(fullhash_to_address_str(&owner, server.db.fullhash_to_address.get(owner)));
+
+            // Same rule as in `Parser::parse_block`
+            trace.reinscription = !occupied.insert((vout, offset));
+            if trace.reinscription {
+                trace.curses.push(InscriptionCurse::Reinscription);
+            }
+            trace.skipped = trace.reinscription && !is_jubilee_height && *JUBILEE_HEIGHT != 133_000;
+
+            trace.token = if trace.skipped {
+                rest::types::TokenTrace {
+                    parsed: false,
+                    op: None,
+                    tick: None,
+                    error: Some("skipped_reinscription".to_string()),
+                }
+            } else if owner.is_op_return_hash() {
+                rest::types::TokenTrace {
+                    parsed: false,
+                    op: None,
+                    tick: None,
+                    error: Some("op_return".to_string()),
+                }
+            } else {
+                trace_token(content_type.as_deref(), body.as_deref())
+            };
+
+            input.inscriptions.push(trace);
+        }
+
+        inputs.push(input);
+    }
+
+    Ok(rest::types::TxTrace {
+        txid: txid.to_string(),
+        height,
+        jubilee: is_jubilee_height,
+        inputs,
+        events: tx_events(server, txid)?,
+    })
+}
+
+fn trace_token(content_type: Option<&str>, body: Option<&[u8]>) -> rest::types::TokenTrace {
+    let mut trace = rest::types::TokenTrace {
+        parsed: false,
+        op: None,
+        tick: None,
+        error: None,
+    };
+
+    let (Some(content_type), Some(body)) = (content_type, body) else {
+        trace.error = Some("no_content".to_string());
+        return trace;
+    };
+
+    match TokenCache::try_parse(content_type, body) {
+        Ok(brc4) => {
+            let (op, value) = brc4.op_value();
+            trace.op = Some(op.to_string());
+
+            match value {
+                Ok(value) => {
+                    trace.parsed = true;
+                    trace.tick = Some(value.tick().to_string());
+                }
+                Err(e) => trace.error = Some(e.to_string()),
+            }
+        }
+        Err(Brc4ParseErr::Unknown(msg)) => trace.error = Some(msg),
+        Err(e) => trace.error = Some(format!("{e:?}")),
+    }
+
+    trace
+}
+
+/// Outputs spent by the transaction, taken from the block or requested from the node
+fn load_prevouts(server: &Server, block: &Block, tx: &Hashed<EvaluatedTx>) -> anyhow::Result<HashMap<OutPoint, TxPrevout>> {
+    let block_txs: HashMap<Txid, &Hashed<EvaluatedTx>> = block.txs.iter().map(|x| (x.hash.into(), x)).collect();
+    let mut prev_txs = HashMap::<Txid, Hashed<EvaluatedTx>>::new();
+    let mut prevouts = HashMap::new();
+
+    for txin in &tx.value.inputs {
+        let outpoint = txin.outpoint;
+
+        let prev_tx = match block_txs.get(&outpoint.txid) {
+            Some(prev_tx) => *prev_tx,
+            None => {
+                if !prev_txs.contains_key(&outpoint.txid) {
+                    let prev_tx = server
+                        .client
+                        .try_get_raw_transaction(&outpoint.txid.to_raw_hash())
+                        .map_err(|e| anyhow::anyhow!("Node can't return transaction {} (txindex is required): {e}", outpoint.txid))?;
+                    prev_txs.insert(outpoint.txid, prev_tx);
+                }
+
+                &prev_txs[&outpoint.txid]
+            }
+        };
+
+        let out = &prev_tx.value.outputs.get(outpoint.vout as usize).ok_or_else(|| anyhow::anyhow!("Spent output doesn't exist"))?.out;
+
+        prevouts.insert(
+            outpoint,
+            TxPrevout {
+                script_hash: sha256::Hash::hash(&out.script_pubkey).into(),
+                value: out.value,
+            },
+        );
+    }
+
+    Ok(prevouts)
+}
+
+fn tx_events(server: &Server, txid: Txid) -> anyhow::Result<Vec<rest::types::History>> {
+    let keys = server
+        .db
+        .outpoint_to_event
+        .range(&OutPoint { txid, vout: 0 }..&OutPoint { txid, vout: u32::MAX }, false)
+        .map(|(_, v)| v)
+        .collect_vec();
+
+    let rows = server
+        .db
+        .address_token_to_history
+        .multi_get_kv(keys.iter(), false)
+        .into_iter()
+        .map(|(k, v)| (*k, v))
+        .collect_vec();
+
+    let mut events = rest::types::History::new_page(rows, server)?;
+    events.sort_unstable_by_key(|x| x.address_token.id);
+
+    Ok(events)
+}
@@ -3,6 +3,9 @@ use nint_blk::proto::block::Block;
 
 use super::{process_data::ProcessedData, *};
 
+/// Blocks with fewer inputs read their prevouts in a single `multi_get`
+const PREVOUTS_MIN_CHUNK: usize = 1_000;
+
 pub fn process_prevouts(db: Arc<DB>, block: &Block, data_to_write: &mut Vec<ProcessedData>) -> anyhow::Result<HashMap<OutPoint, TxPrevout>> {
     let prevouts = block
         .txs
@@ -37,7 +40,7 @@ pub fn process_prevouts(db: Arc<DB>, block: &Block, data_to_write: &mut Vec<Proc
     let mut result = HashMap::new();
 
     if !txids_keys.is_empty() {
-        let from_db = db.prevouts.multi_get(txids_keys.iter());
+        let from_db = db.prevouts.multi_get_chunked(&txids_keys, PREVOUTS_MIN_CHUNK, rayon::current_num_threads());
 
         for (key, maybe_val) in txids_keys.iter().zip(from_db) {
             match maybe_val {
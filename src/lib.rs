@@ -0,0 +1,215 @@
+//! Block processing and token state of the indexer. <br/>
+//! The REST server and the node binary are behind the default `server` feature, so the engine can be embedded without them.
+
+// Without the server most of the query helpers have no caller
+#![cfg_attr(not(feature = "server"), allow(dead_code, unused_imports))]
+
+extern crate serde;
+#[macro_use]
+extern crate tracing;
+
+use {
+    bellscoin::{
+        hashes::{sha256, Hash},
+        opcodes, script, BlockHash, Network, OutPoint, TxOut, Txid,
+    },
+    blockchain::Blockchain,
+    config::Config,
+    db::*,
+    dutils::{
+        error::{ApiError, ContextWrapper},
+        wait_token::WaitToken,
+    },
+    inscriptions::Location,
+    poh::PohVersion,
+    itertools::Itertools,
+    num_traits::Zero,
+    reorg::{ReorgCache, ReorgInProgress, REORG_CACHE_MAX_LEN},
+    rocksdb_wrapper::{RocksDB, RocksTable, UsingConsensus, UsingSerde},
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    serde_with::{serde_as, DisplayFromStr},
+    std::{
+        borrow::Cow,
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+        fmt::{Display, Formatter},
+        future::IntoFuture,
+        iter::Peekable,
+        ops::{Deref, RangeInclusive},
+        str::FromStr,
+        sync::{atomic::AtomicU64, Arc},
+        time::{Duration, Instant},
+    },
+    tokens::*,
+    tracing::info,
+    tracing_indicatif::span_ext::IndicatifSpanExt,
+    utils::*,
+};
+
+mod config;
+mod diff;
+mod inscriptions;
+mod poh;
+mod reorg;
+pub mod rest;
+pub mod tokens;
+#[macro_use]
+mod utils;
+mod blockchain;
+pub mod db;
+mod preflight;
+mod server;
+#[cfg(feature = "server")]
+mod golden;
+#[cfg(feature = "server")]
+mod node;
+#[cfg(feature = "parquet-export")]
+mod parquet_export;
+
+pub use diff::run_diff;
+pub use inscriptions::Indexer;
+#[cfg(feature = "server")]
+pub use node::run_node;
+pub use server::{Server, ServerEvent};
+
+pub type Fixed128 = nintypes::utils::fixed::Fixed128<18>;
+const OP_RETURN_ADDRESS: &str = "BURNED";
+const NON_STANDARD_ADDRESS: &str = "non-standard";
+
+define_static! {
+    OP_RETURN_HASH: FullHash = OP_RETURN_ADDRESS.compute_script_hash();
+    // detected under the configured path, see `preflight::resolve_blk_dir`
+    BLK_DIR: Option<String> = load_opt_env!("BLK_DIR").map(|x| preflight::resolve_blk_dir(std::path::Path::new(&x), *COIN).to_string_lossy().into_owned());
+    // check merkle root and proof of work of blocks read from blk files
+    VALIDATE_BLOCKS: bool = load_opt_env!("VALIDATE_BLOCKS").map(|x| x == "true" || x == "1").unwrap_or(false);
+    // blocks read ahead from blk files while the previous ones are parsed, disabled if zero
+    BLK_PREFETCH_BLOCKS: usize = load_opt_env!("BLK_PREFETCH_BLOCKS").map(|x| x.parse().unwrap()).unwrap_or(32);
+    URL: String = load_env!("RPC_URL");
+    USER: Option<String> = load_opt_env!("RPC_USER");
+    PASS: Option<String> = load_opt_env!("RPC_PASS");
+    // `.cookie` of the node or its datadir, used instead of RPC_USER and RPC_PASS
+    RPC_COOKIE_PATH: Option<String> = load_opt_env!("RPC_COOKIE_PATH");
+    RPC_AUTH: nint_blk::Auth = match (RPC_COOKIE_PATH.as_ref(), USER.as_ref(), PASS.as_ref()) {
+        (Some(path), _, _) => nint_blk::Auth::cookie(path),
+        (None, Some(user), Some(pass)) => nint_blk::Auth::UserPass(user.clone(), pass.clone()),
+        _ => panic!("Either RPC_COOKIE_PATH or RPC_USER and RPC_PASS must be set"),
+    };
+    BLOCKCHAIN: Blockchain = Blockchain::from_str(&load_env!("BLOCKCHAIN")).unwrap();
+    INDEX_DIR: Option<String> = load_opt_env!("INDEX_DIR").map(|x| preflight::resolve_index_dir(std::path::Path::new(&x)).to_string_lossy().into_owned());
+    NETWORK: Network = load_opt_env!("NETWORK")
+        .map(|x| Network::from_str(&x).unwrap())
+        .unwrap_or(Network::Bellscoin);
+    // JSON file with a coin which isn't built in (e.g. a fork), its tokens follow the rules of BLOCKCHAIN
+    COIN_CONFIG: Option<String> = load_opt_env!("COIN_CONFIG");
+    // built-in coin indexed instead of the one of BLOCKCHAIN/NETWORK (e.g. bellscoin-testnet), for setups which aren't supported out of the box
+    COIN_OVERRIDE: Option<String> = load_opt_env!("COIN_OVERRIDE");
+    // validated by the preflight checks, so an unsupported combination never falls back to another chain
+    COIN: nint_blk::CoinType = match (COIN_CONFIG.as_ref(), COIN_OVERRIDE.as_ref()) {
+        (Some(path), _) => nint_blk::CoinType::register(blockchain::load_custom_coin(std::path::Path::new(path)).unwrap()).unwrap(),
+        (None, Some(name)) => nint_blk::CoinType::from_str(name).unwrap(),
+        (None, None) => nint_blk::CoinType::from_str(BLOCKCHAIN.coin_name(*NETWORK).expect("Unsupported BLOCKCHAIN and NETWORK")).unwrap(),
+    };
+    // multiple input inscription scan activation
+    JUBILEE_HEIGHT: usize = COIN.jubilee_height.map(|x| x as usize).unwrap_or(match (*NETWORK, *BLOCKCHAIN) {
+        (Network::Bellscoin, Blockchain::Bellscoin) => 133_000,
+        (_, Blockchain::Dogecoin) => usize::MAX,
+        _ => 0,
+    });
+    // first height where inscriptions are also searched in the witness stack of script-path spends, not only in the leaf script, disabled if not set
+    WITNESS_SCAN_HEIGHT: Option<u32> = load_opt_env!("WITNESS_SCAN_HEIGHT").map(|x| x.parse().unwrap());
+    // hard limits of the inscription parser, transactions over them are rejected and logged, unlimited if not set
+    MAX_PARTIAL_PARTS: Option<usize> = load_opt_env!("MAX_PARTIAL_PARTS").map(|x| x.parse::<usize>().unwrap().max(1));
+    MAX_INSCRIPTION_BYTES: Option<usize> = load_opt_env!("MAX_INSCRIPTION_BYTES").map(|x| x.parse().unwrap());
+    MAX_INSCRIPTIONS_PER_TX: Option<usize> = load_opt_env!("MAX_INSCRIPTIONS_PER_TX").map(|x| x.parse::<usize>().unwrap().max(1));
+    MAX_ENVELOPES_PER_INPUT: Option<usize> = load_opt_env!("MAX_ENVELOPES_PER_INPUT").map(|x| x.parse::<usize>().unwrap().max(1));
+    // first token block height
+    START_HEIGHT: u32 = COIN.first_inscription_height.map(|x| x as u32).unwrap_or(match (*NETWORK, *BLOCKCHAIN) {
+        (Network::Bellscoin, Blockchain::Bellscoin) => 26_371,
+        (Network::Bellscoin, Blockchain::Dogecoin) => 4_609_001,
+        (Network::Testnet, Blockchain::Dogecoin) => 4_260_001,
+        (Network::Bellscoin, Blockchain::Litecoin) => 2_424_429,
+        (Network::Testnet, Blockchain::Litecoin) => 2_669_127,
+        _ => 0,
+    });
+    SERVER_URL: String =
+        load_opt_env!("SERVER_BIND_URL").unwrap_or("0.0.0.0:8000".to_string());
+    // public base URL of the API, used in the OpenAPI `servers` section
+    API_PUBLIC_URL: Option<String> = load_opt_env!("API_PUBLIC_URL");
+    // bearer token of the admin API, which is disabled if not set
+    ADMIN_TOKEN: Option<String> = load_opt_env!("ADMIN_TOKEN");
+    DEFAULT_HASH: sha256::Hash = sha256::Hash::hash("null".as_bytes());
+    DB_PATH: String = load_opt_env!("DB_PATH").unwrap_or("rocksdb".to_string());
+    // second RocksDB instance for the rarely read COLD_TABLES, e.g. on a slower disk, see `RocksDB::open_tiered`
+    COLD_DB_PATH: Option<String> = load_opt_env!("COLD_DB_PATH");
+    // tables kept in COLD_DB_PATH, validated by the preflight checks
+    COLD_TABLES: Vec<String> = load_opt_env!("COLD_TABLES")
+        .map(|x| x.split(',').map(|x| x.trim().to_string()).filter(|x| !x.is_empty()).collect())
+        .unwrap_or(vec!["address_token_to_history".to_string(), "block_events".to_string()]);
+    // threads of the RocksDB pool flushing memtables, shared by all instances of the process
+    ROCKSDB_FLUSH_THREADS: Option<u32> = load_opt_env!("ROCKSDB_FLUSH_THREADS").map(|x| x.parse::<u32>().unwrap().max(1));
+    // threads of the RocksDB pool compacting SST files, shared by all instances of the process
+    ROCKSDB_COMPACTION_THREADS: Option<u32> = load_opt_env!("ROCKSDB_COMPACTION_THREADS").map(|x| x.parse::<u32>().unwrap().max(1));
+    // run RocksDB compactions at lower CPU and IO priority than block processing
+    ROCKSDB_LOW_PRIORITY_COMPACTION: bool = load_opt_env!("ROCKSDB_LOW_PRIORITY_COMPACTION").map(|x| x == "true" || x == "1").unwrap_or(false);
+    // flag transfer outpoints which are already spent by unconfirmed transactions
+    MEMPOOL_CHECK: bool = load_opt_env!("MEMPOOL_CHECK").map(|x| x == "true" || x == "1").unwrap_or(false);
+    // serve GET /bootstrap, a dump of the database which fills the empty database of another indexer
+    BOOTSTRAP_SERVE: bool = load_opt_env!("BOOTSTRAP_SERVE").map(|x| x == "true" || x == "1").unwrap_or(false);
+    // indexer serving GET /bootstrap, an empty database is filled from it instead of indexing the blocks up to its last block
+    BOOTSTRAP_PEER_URL: Option<String> = load_opt_env!("BOOTSTRAP_PEER_URL");
+    // track movements of all inscriptions, not only token transfers
+    INSCRIPTION_HISTORY: bool = load_opt_env!("INSCRIPTION_HISTORY").map(|x| x == "true" || x == "1").unwrap_or(false);
+    // roll up per-address history older than this number of blocks into checkpoints, disabled if not set
+    HISTORY_COMPACTION_DEPTH: Option<u32> = load_opt_env!("HISTORY_COMPACTION_DEPTH")
+        .map(|x| x.parse::<u32>().unwrap().max(REORG_CACHE_MAX_LEN as u32));
+    // number of blocks summarized by one history checkpoint
+    HISTORY_CHECKPOINT_INTERVAL: u32 = load_opt_env!("HISTORY_CHECKPOINT_INTERVAL")
+        .map(|x| x.parse::<u32>().unwrap().max(1))
+        .unwrap_or(10_000);
+    // first block which proof of history uses the canonical binary encoding (v2)
+    POH_V2_HEIGHT: Option<u32> = load_opt_env!("POH_V2_HEIGHT").map(|x| x.parse().unwrap());
+    // write rate limit of the indexer while REST queries are being served, unlimited if not set
+    MAX_WRITE_MBPS: Option<u64> = load_opt_env!("MAX_WRITE_MBPS").map(|x| x.parse::<u64>().unwrap().max(1));
+    // remove partials of multi-part inscriptions spent without continuation more than this number of blocks ago, disabled if not set
+    PARTIALS_GC_DEPTH: Option<u32> = load_opt_env!("PARTIALS_GC_DEPTH")
+        .map(|x| x.parse::<u32>().unwrap().max(REORG_CACHE_MAX_LEN as u32));
+    // number of blocks between snapshots of token holders used by `/holders?height=`
+    HOLDERS_SNAPSHOT_INTERVAL: u32 = load_opt_env!("HOLDERS_SNAPSHOT_INTERVAL")
+        .map(|x| x.parse::<u32>().unwrap().max(1))
+        .unwrap_or(10_000);
+    // number of blocks between digests of the full token state served by `/state-digest/{height}`, disabled if not set
+    STATE_DIGEST_INTERVAL: Option<u32> = load_opt_env!("STATE_DIGEST_INTERVAL").map(|x| x.parse::<u32>().unwrap().max(1));
+    // memory budget of recently active token balances kept in memory, every balance is read from the database if not set
+    TOKEN_CACHE_MB: Option<usize> = load_opt_env!("TOKEN_CACHE_MB").map(|x| x.parse().unwrap());
+    // HTTP endpoint receiving committed blocks with their token events as NDJSON, replication is disabled if not set
+    REPLICATION_URL: Option<String> = load_opt_env!("REPLICATION_URL");
+    // hot standby of another indexer, read-only and checked against the primary's replication stream until `/admin/promote`
+    STANDBY: bool = load_opt_env!("STANDBY").map(|x| x == "true" || x == "1").unwrap_or(false);
+    // allows `/admin/simulate-reorg`, which rolls back and replays the last blocks, for staging and regtest only
+    SIMULATE_REORG: bool = load_opt_env!("SIMULATE_REORG").map(|x| x == "true" || x == "1").unwrap_or(false);
+    // directory receiving RocksDB checkpoints of the database, backups are disabled if not set
+    BACKUP_DIR: Option<String> = load_opt_env!("BACKUP_DIR");
+    // hours between automatic backups, only `POST /admin/backups` creates them if not set
+    BACKUP_INTERVAL_HOURS: Option<u64> = load_opt_env!("BACKUP_INTERVAL_HOURS").map(|x| x.parse::<u64>().unwrap().max(1));
+    // number of newest backups kept after each automatic backup, all are kept if not set
+    BACKUP_KEEP: Option<usize> = load_opt_env!("BACKUP_KEEP").map(|x| x.parse::<usize>().unwrap().max(1));
+    // S3-compatible bucket URL (path-style, optionally with a key prefix) receiving every backup, uploads are disabled if not set
+    BACKUP_S3_URL: Option<String> = load_opt_env!("BACKUP_S3_URL");
+    BACKUP_S3_REGION: String = load_opt_env!("BACKUP_S3_REGION").unwrap_or("us-east-1".to_string());
+    BACKUP_S3_ACCESS_KEY: Option<String> = load_opt_env!("BACKUP_S3_ACCESS_KEY");
+    BACKUP_S3_SECRET_KEY: Option<String> = load_opt_env!("BACKUP_S3_SECRET_KEY");
+    // hours between automatic supply audits, only `POST /admin/supply-audit` starts them if not set
+    SUPPLY_AUDIT_INTERVAL_HOURS: Option<u64> = load_opt_env!("SUPPLY_AUDIT_INTERVAL_HOURS").map(|x| x.parse::<u64>().unwrap().max(1));
+    // seconds between spot audits of random past blocks against the node, disabled if not set
+    SPOT_AUDIT_INTERVAL_SECS: Option<u64> = load_opt_env!("SPOT_AUDIT_INTERVAL_SECS").map(|x| x.parse::<u64>().unwrap().max(1));
+    // log REST requests slower than this number of milliseconds with their query, disabled if not set
+    SLOW_QUERY_MS: Option<u64> = load_opt_env!("SLOW_QUERY_MS").map(|x| x.parse().unwrap());
+    // JSON file with the ticks which can't be deployed, validated by the preflight checks
+    TICK_POLICY_FILE: Option<String> = load_opt_env!("TICK_POLICY_FILE");
+    TICK_POLICY: TickPolicy = TICK_POLICY_FILE.as_ref().map(|x| TickPolicy::load(std::path::Path::new(x)).unwrap()).unwrap_or_default();
+    // experimental: number of blocks far from the tip prepared in parallel, see `inscriptions::concurrent`, disabled if not set
+    CONCURRENT_BLOCKS: Option<usize> = load_opt_env!("CONCURRENT_BLOCKS").map(|x| x.parse::<usize>().unwrap().max(2));
+    // seconds caches may serve REST responses without revalidating their ETag, zero revalidates on every request
+    HTTP_CACHE_MAX_AGE_SECS: u64 = load_opt_env!("HTTP_CACHE_MAX_AGE_SECS").map(|x| x.parse().unwrap()).unwrap_or(0);
+}
+
+const PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,124 @@
+use super::*;
+
+use crate::{
+    rest::run_rest,
+    server::{
+        threads::{AccessStatsFlusher, BackupManager, EventSender, HistoryCompactor, PartialsCollector, ReplicationSink, SpotAuditor, StandbyVerifier, SupplyAuditor, WebhookSender},
+        ShutdownCoordinator,
+    },
+};
+
+/// Runs the indexer with the REST server and the background jobs until SIGTERM or SIGINT
+pub fn run_node() {
+    dotenv::dotenv().ok();
+    utils::init_logger();
+
+    // Reads only the database, so it doesn't need the node or the environment checks
+    if std::env::args().nth(1).as_deref() == Some("export-parquet") {
+        #[cfg(feature = "parquet-export")]
+        std::process::exit(parquet_export::export_parquet());
+        #[cfg(not(feature = "parquet-export"))]
+        {
+            error!("export-parquet needs the parquet-export feature");
+            std::process::exit(preflight::exit_code::CONFIG);
+        }
+    }
+
+    if let Err(exit_code) = preflight::run() {
+        std::process::exit(exit_code);
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("scan-blk") {
+        std::process::exit(preflight::scan_blk_files());
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("golden") {
+        std::process::exit(golden::run_golden());
+    }
+
+    let config = Config::new();
+    info!("Config loaded:\n{:#?}", config.redacted());
+
+    if let Some(url) = BOOTSTRAP_PEER_URL.as_deref() {
+        let (_, server) = Server::new(&DB_PATH).unwrap();
+        Indexer::new(Arc::new(server)).bootstrap(url).unwrap();
+    }
+
+    let (event_tx, server) = Server::new(&DB_PATH).unwrap();
+
+    let server = Arc::new(server);
+
+    shutdown_handler(server.token.clone());
+
+    let mut shutdown = ShutdownCoordinator::new(server.clone());
+
+    let rest_server = server.clone();
+    let rest = std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_multi_thread().thread_name("rest").enable_all().build().unwrap();
+        runtime.block_on(run_rest(rest_server))
+    });
+    shutdown.intake("rest", rest);
+
+    let event_sender = EventSender {
+        event_tx,
+        server: server.clone(),
+    };
+
+    let indexer_server = server.clone();
+    let indexer = std::thread::spawn(move || {
+        let result = Indexer::new(indexer_server.clone()).run();
+        indexer_server.token.cancel();
+        result
+    });
+
+    // The indexer goes first, so the last block is committed before the background jobs stop
+    shutdown.writer("indexer", indexer);
+    shutdown.writer("event sender", std::thread::spawn(move || event_sender.run()));
+
+    let webhook_sender = WebhookSender { server: server.clone() };
+    shutdown.writer("webhook sender", std::thread::spawn(move || webhook_sender.run()));
+
+    let access_stats_flusher = AccessStatsFlusher { server: server.clone() };
+    shutdown.writer("access stats flusher", std::thread::spawn(move || access_stats_flusher.run()));
+
+    let history_compactor = HistoryCompactor { server: server.clone() };
+    shutdown.writer("history compactor", std::thread::spawn(move || history_compactor.run()));
+
+    let partials_collector = PartialsCollector { server: server.clone() };
+    shutdown.writer("partials collector", std::thread::spawn(move || partials_collector.run()));
+
+    let replication_sink = ReplicationSink { server: server.clone() };
+    shutdown.writer("replication sink", std::thread::spawn(move || replication_sink.run()));
+
+    let backup_manager = BackupManager { server: server.clone() };
+    shutdown.writer("backup manager", std::thread::spawn(move || backup_manager.run()));
+
+    let supply_auditor = SupplyAuditor { server: server.clone() };
+    shutdown.writer("supply auditor", std::thread::spawn(move || supply_auditor.run()));
+
+    let spot_auditor = SpotAuditor { server: server.clone() };
+    shutdown.writer("spot auditor", std::thread::spawn(move || spot_auditor.run()));
+
+    let standby_verifier = StandbyVerifier { server: server.clone() };
+    shutdown.writer("standby verifier", std::thread::spawn(move || standby_verifier.run()));
+
+    drop(server);
+
+    for result in shutdown.run() {
+        result.track().ok();
+    }
+}
+
+fn shutdown_handler(token: dutils::wait_token::WaitToken) {
+    let _: std::thread::JoinHandle<Result<(), std::io::Error>> = std::thread::spawn(move || {
+        let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT]).inspect_err(|_| {
+            token.cancel();
+        })?;
+
+        for _ in &mut signals {
+            token.cancel();
+        }
+
+        Ok(())
+    });
+}
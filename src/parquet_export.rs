@@ -0,0 +1,216 @@
+use std::path::Path;
+
+use arrow_array::{
+    builder::{StringBuilder, UInt32Builder, UInt64Builder, UInt8Builder},
+    ArrayRef, RecordBatch,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, ZstdLevel},
+    file::properties::WriterProperties,
+};
+
+use super::*;
+use crate::preflight::exit_code;
+
+/// Heights per file if the command doesn't set it
+const DEFAULT_BLOCKS_PER_FILE: u32 = 100_000;
+/// Blocks whose events are loaded and written as one row group
+const BLOCKS_PER_BATCH: usize = 1_000;
+
+/// Maintenance command which writes the token history with resolved addresses and block timestamps into Parquet files, one per
+/// range of `blocks per file` heights, for DuckDB or Spark. <br/>
+/// Files of complete ranges are kept, so the next run only writes new ranges and rewrites the last one.
+/// Opens `DB_PATH`, so it runs with the node stopped or against a backup. Returns exit code.
+pub fn export_parquet() -> i32 {
+    let mut args = std::env::args().skip(2);
+    let Some(dir) = args.next() else {
+        error!("Usage: bel_20_node export-parquet <dir> [blocks per file]");
+        return exit_code::CONFIG;
+    };
+    let blocks_per_file = match args.next().map(|x| x.parse::<u32>()) {
+        None => DEFAULT_BLOCKS_PER_FILE,
+        Some(Ok(x)) if x > 0 => x,
+        Some(_) => {
+            error!("Blocks per file must be a positive number");
+            return exit_code::CONFIG;
+        }
+    };
+
+    if !Path::new(&*DB_PATH).exists() {
+        error!("No database at {}", *DB_PATH);
+        return exit_code::DB_PATH;
+    }
+
+    match export(&DB::open(&DB_PATH), Path::new(&dir), blocks_per_file) {
+        Ok(files) => {
+            info!("Written {files} files to {dir}");
+            0
+        }
+        Err(e) => {
+            error!("Failed to export token history: {e}");
+            exit_code::EXPORT
+        }
+    }
+}
+
+/// Number of written files
+fn export(db: &DB, dir: &Path, blocks_per_file: u32) -> anyhow::Result<usize> {
+    let (Some((first_block, _)), Some(last_block)) = (db.block_events.iter().next(), db.last_block.get(())) else {
+        info!("No token history in {}", *DB_PATH);
+        return Ok(0);
+    };
+
+    std::fs::create_dir_all(dir)?;
+
+    let mut written = 0;
+    let mut from = first_block / blocks_per_file * blocks_per_file;
+    while from <= last_block {
+        let to = from.saturating_add(blocks_per_file - 1).min(last_block);
+        let prefix = format!("events-{from:010}-");
+        let path = dir.join(format!("{prefix}{to:010}.parquet"));
+
+        // The file of the last range is named by the last indexed block and written on every run, so it follows new blocks and reorgs
+        if to == last_block || !path.exists() {
+            let rows = write_file(db, &path, from, to)?;
+            info!("Written {rows} events of blocks {from}..={to} to {}", path.display());
+            written += 1;
+
+            for entry in std::fs::read_dir(dir)? {
+                let entry = entry?.path();
+                let name = entry.file_name().and_then(|x| x.to_str()).unwrap_or_default();
+                if entry != path && name.starts_with(&prefix) && name.ends_with(".parquet") {
+                    std::fs::remove_file(&entry)?;
+                }
+            }
+        }
+
+        from = match to.checked_add(1) {
+            Some(x) => x,
+            None => break,
+        };
+    }
+
+    Ok(written)
+}
+
+/// Written to a temporary file first, so readers of the directory never see a partial one. Returns number of rows.
+fn write_file(db: &DB, path: &Path, from: u32, to: u32) -> anyhow::Result<usize> {
+    let tmp = path.with_extension("parquet.tmp");
+    let props = WriterProperties::builder().set_compression(Compression::ZSTD(ZstdLevel::default())).build();
+    let mut writer = ArrowWriter::try_new(std::fs::File::create(&tmp)?, schema(), Some(props))?;
+
+    let mut rows = 0;
+    for blocks in &db.block_events.range(&from..=&to, false).chunks(BLOCKS_PER_BATCH) {
+        let batch = load_batch(db, blocks.collect_vec())?;
+        rows += batch.num_rows();
+        writer.write(&batch)?;
+    }
+    writer.close()?;
+
+    std::fs::rename(&tmp, path)?;
+    Ok(rows)
+}
+
+fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::UInt64, false),
+        Field::new("height", DataType::UInt32, false),
+        Field::new("created", DataType::UInt32, false),
+        Field::new("tick", DataType::Utf8, false),
+        Field::new("address", DataType::Utf8, false),
+        Field::new("type", DataType::Utf8, false),
+        Field::new("amount", DataType::Utf8, true),
+        Field::new("max", DataType::Utf8, true),
+        Field::new("lim", DataType::Utf8, true),
+        Field::new("dec", DataType::UInt8, true),
+        Field::new("counterparty", DataType::Utf8, true),
+        Field::new("txid", DataType::Utf8, false),
+        Field::new("vout", DataType::UInt32, false),
+    ]))
+}
+
+/// Events of the blocks in id order
+fn load_batch(db: &DB, blocks: Vec<(u32, Vec<AddressTokenIdDB>)>) -> anyhow::Result<RecordBatch> {
+    let keys = blocks.iter().flat_map(|(_, keys)| keys.iter().copied()).collect_vec();
+    let history = db.address_token_to_history.multi_get_kv(keys.iter(), true);
+
+    let created = db
+        .block_info
+        .multi_get_kv(blocks.iter().map(|(height, _)| height), false)
+        .into_iter()
+        .map(|(height, info)| (*height, info.created))
+        .collect::<HashMap<_, _>>();
+
+    let hashes = history
+        .iter()
+        .flat_map(|(key, value)| [Some(key.address), value.action.address().copied()])
+        .flatten()
+        .collect::<HashSet<_>>();
+    let addresses = db
+        .fullhash_to_address
+        .multi_get_kv(hashes.iter(), false)
+        .into_iter()
+        .map(|(hash, address)| (*hash, address))
+        .collect::<HashMap<_, _>>();
+    let address = |hash: &FullHash| fullhash_to_address_str(hash, addresses.get(hash).cloned());
+
+    let mut id = UInt64Builder::new();
+    let mut height = UInt32Builder::new();
+    let mut block_created = UInt32Builder::new();
+    let mut tick = StringBuilder::new();
+    let mut owner = StringBuilder::new();
+    let mut kind = StringBuilder::new();
+    let mut amount = StringBuilder::new();
+    let mut max = StringBuilder::new();
+    let mut lim = StringBuilder::new();
+    let mut dec = UInt8Builder::new();
+    let mut counterparty = StringBuilder::new();
+    let mut txid = StringBuilder::new();
+    let mut vout = UInt32Builder::new();
+
+    for (key, value) in history {
+        let (event_type, amt, deploy) = match &value.action {
+            TokenHistoryDB::Deploy { max, lim, dec, .. } => ("Deploy", None, Some((max, lim, *dec))),
+            TokenHistoryDB::Mint { amt, .. } => ("Mint", Some(amt), None),
+            TokenHistoryDB::DeployTransfer { amt, .. } => ("DeployTransfer", Some(amt), None),
+            TokenHistoryDB::Send { amt, .. } => ("Send", Some(amt), None),
+            TokenHistoryDB::Receive { amt, .. } => ("Receive", Some(amt), None),
+            TokenHistoryDB::SendReceive { amt, .. } => ("SendReceive", Some(amt), None),
+        };
+        let outpoint = value.action.outpoint();
+
+        id.append_value(key.id);
+        height.append_value(value.height);
+        block_created.append_value(created.get(&value.height).copied().unwrap_or_default());
+        tick.append_value(key.token.to_string());
+        owner.append_value(address(&key.address));
+        kind.append_value(event_type);
+        amount.append_option(amt.map(|x| x.to_string()));
+        max.append_option(deploy.map(|(max, _, _)| max.to_string()));
+        lim.append_option(deploy.map(|(_, lim, _)| lim.to_string()));
+        dec.append_option(deploy.map(|(_, _, dec)| dec));
+        counterparty.append_option(value.action.address().map(address));
+        txid.append_value(outpoint.txid.to_string());
+        vout.append_value(outpoint.vout);
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(id.finish()),
+        Arc::new(height.finish()),
+        Arc::new(block_created.finish()),
+        Arc::new(tick.finish()),
+        Arc::new(owner.finish()),
+        Arc::new(kind.finish()),
+        Arc::new(amount.finish()),
+        Arc::new(max.finish()),
+        Arc::new(lim.finish()),
+        Arc::new(dec.finish()),
+        Arc::new(counterparty.finish()),
+        Arc::new(txid.finish()),
+        Arc::new(vout.finish()),
+    ];
+
+    Ok(RecordBatch::try_new(schema(), columns)?)
+}
@@ -0,0 +1,281 @@
+use bellscoin::hashes::HashEngine;
+
+use super::*;
+
+/// Encoding of the block events which is hashed into the proof of history
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PohVersion {
+    /// Concatenated JSON of `rest::types::History`, depends on serde field order
+    V1 = 1,
+    /// Canonical binary encoding, see [`encode_v2`]
+    V2 = 2,
+}
+
+impl PohVersion {
+    pub fn at_height(height: u32) -> Self {
+        if POH_V2_HEIGHT.is_some_and(|v2_height| height >= v2_height) {
+            Self::V2
+        } else {
+            Self::V1
+        }
+    }
+
+    /// Blocks indexed before the version was persisted are V1
+    pub fn from_db(value: Option<u8>) -> Self {
+        match value {
+            Some(2) => Self::V2,
+            _ => Self::V1,
+        }
+    }
+}
+
+/// Proof of history of the block with the hash of its events, which links it to the proof of the previous block
+#[derive(Clone, Copy)]
+pub struct BlockProof {
+    pub proof: sha256::Hash,
+    pub events_hash: sha256::Hash,
+}
+
+pub fn block_proof(prev_history_hash: sha256::Hash, history: &[(AddressTokenIdDB, HistoryValue)], addresses: &AddressesFullHash, version: PohVersion) -> anyhow::Result<BlockProof> {
+    let current_hash = if history.is_empty() {
+        *DEFAULT_HASH
+    } else {
+        let buffer = match version {
+            PohVersion::V1 => encode_v1(history, addresses)?,
+            PohVersion::V2 => encode_v2(history, addresses),
+        };
+
+        sha256::Hash::hash(&buffer)
+    };
+
+    Ok(BlockProof {
+        proof: link(prev_history_hash, current_hash),
+        events_hash: current_hash,
+    })
+}
+
+/// Proof of the block from the proof of the previous one and the hash of its events, see [`SPEC_BLOCK_PROOF`]
+pub fn link(prev_history_hash: sha256::Hash, events_hash: sha256::Hash) -> sha256::Hash {
+    let mut buffer = prev_history_hash.as_byte_array().to_vec();
+    buffer.extend_from_slice(events_hash.as_byte_array());
+
+    sha256::Hash::hash(&buffer)
+}
+
+pub fn encode_v1(history: &[(AddressTokenIdDB, HistoryValue)], addresses: &AddressesFullHash) -> anyhow::Result<Vec<u8>> {
+    let mut buffer = Vec::<u8>::new();
+
+    for (address_token, action) in history {
+        let rest = rest::types::History {
+            height: action.height,
+            action: rest::types::TokenAction::from_with_addresses(action.action.clone(), addresses),
+            address_token: rest::types::AddressTokenId {
+                address: addresses.get(&address_token.address),
+                id: address_token.id,
+                tick: address_token.token.into(),
+            },
+            // Skipped when `None`, so the hashed JSON stays as it was before origins existed
+            origin: None,
+        };
+        buffer.extend(serde_json::to_vec(&rest)?);
+    }
+
+    Ok(buffer)
+}
+
+/// Version byte followed by the events in the order of their ids, see [`SPEC_V2_EVENT`]
+pub fn encode_v2(history: &[(AddressTokenIdDB, HistoryValue)], addresses: &AddressesFullHash) -> Vec<u8> {
+    let mut buffer = vec![PohVersion::V2 as u8];
+
+    for (address_token, value) in history.iter().sorted_by_key(|(k, _)| k.id) {
+        buffer.extend(address_token.id.to_be_bytes());
+        buffer.extend(value.height.to_be_bytes());
+        buffer.extend(address_token.token.0);
+        write_str(&mut buffer, &addresses.get(&address_token.address));
+
+        match &value.action {
+            TokenHistoryDB::Deploy { max, lim, dec, txid, vout } => {
+                buffer.push(0);
+                write_str(&mut buffer, &max.to_string());
+                write_str(&mut buffer, &lim.to_string());
+                buffer.push(*dec);
+                write_outpoint(&mut buffer, txid, *vout);
+            }
+            TokenHistoryDB::Mint { amt, txid, vout } => {
+                buffer.push(1);
+                write_str(&mut buffer, &amt.to_string());
+                write_outpoint(&mut buffer, txid, *vout);
+            }
+            TokenHistoryDB::DeployTransfer { amt, txid, vout } => {
+                buffer.push(2);
+                write_str(&mut buffer, &amt.to_string());
+                write_outpoint(&mut buffer, txid, *vout);
+            }
+            TokenHistoryDB::Send { amt, recipient, txid, vout } => {
+                buffer.push(3);
+                write_str(&mut buffer, &amt.to_string());
+                write_str(&mut buffer, &addresses.get(recipient));
+                write_outpoint(&mut buffer, txid, *vout);
+            }
+            TokenHistoryDB::Receive { amt, sender, txid, vout } => {
+                buffer.push(4);
+                write_str(&mut buffer, &amt.to_string());
+                write_str(&mut buffer, &addresses.get(sender));
+                write_outpoint(&mut buffer, txid, *vout);
+            }
+            TokenHistoryDB::SendReceive { amt, txid, vout } => {
+                buffer.push(5);
+                write_str(&mut buffer, &amt.to_string());
+                write_outpoint(&mut buffer, txid, *vout);
+            }
+        }
+    }
+
+    buffer
+}
+
+/// Root of the block balance tree and the path of the leaf at `index`, see [`SPEC_BALANCE_TREE`]. <br/>
+/// The path is bottom-up, each item is a sibling hash and whether the sibling is on the left.
+pub fn balance_tree(leaves: &[BalanceLeaf], index: Option<usize>) -> (sha256::Hash, Vec<(sha256::Hash, bool)>) {
+    let mut level = leaves.iter().map(balance_leaf_hash).collect_vec();
+    let mut index = index;
+    let mut path = vec![];
+
+    if level.is_empty() {
+        return (*DEFAULT_HASH, path);
+    }
+
+    while level.len() > 1 {
+        if let Some(i) = index {
+            if let Some(sibling) = level.get(i ^ 1) {
+                path.push((*sibling, i % 2 == 1));
+            }
+            index = Some(i / 2);
+        }
+
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => balance_node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    (level[0], path)
+}
+
+/// Digest of every balance and token at the current state, see [`SPEC_STATE_DIGEST`]. Reads the whole state, so it's taken every `STATE_DIGEST_INTERVAL` blocks
+pub fn state_digest(db: &DB) -> StateDigestDB {
+    let mut engine = sha256::Hash::engine();
+    let mut accounts = 0;
+    let mut tokens = 0;
+
+    for (key, balance) in db.address_token_to_balance.iter() {
+        if balance == TokenBalance::default() {
+            continue;
+        }
+
+        let mut buffer = vec![0];
+        buffer.extend(key.address);
+        buffer.extend(key.token.0);
+        write_str(&mut buffer, &balance.balance.to_string());
+        write_str(&mut buffer, &balance.transferable_balance.to_string());
+        buffer.extend(balance.transfers_count.to_be_bytes());
+
+        engine.input(&buffer);
+        accounts += 1;
+    }
+
+    for (_, meta) in db.token_to_meta.iter() {
+        let proto = &meta.proto;
+
+        let mut buffer = vec![1];
+        buffer.extend(proto.tick.0);
+        write_outpoint(&mut buffer, &meta.genesis.txid, meta.genesis.index);
+        write_str(&mut buffer, &proto.max.to_string());
+        write_str(&mut buffer, &proto.lim.to_string());
+        buffer.push(proto.dec);
+        write_str(&mut buffer, &proto.supply.to_string());
+        buffer.extend(proto.mint_count.to_be_bytes());
+        buffer.extend(proto.transfer_count.to_be_bytes());
+        buffer.extend(proto.transactions.to_be_bytes());
+        buffer.extend(proto.height.to_be_bytes());
+        buffer.extend(proto.deployer);
+
+        engine.input(&buffer);
+        tokens += 1;
+    }
+
+    StateDigestDB {
+        digest: sha256::Hash::from_engine(engine).to_byte_array(),
+        accounts,
+        tokens,
+    }
+}
+
+pub fn balance_leaf_hash(leaf: &BalanceLeaf) -> sha256::Hash {
+    let mut buffer = vec![0];
+    buffer.extend(leaf.address);
+    buffer.extend(leaf.token.0);
+    write_str(&mut buffer, &leaf.balance.to_string());
+    write_str(&mut buffer, &leaf.transferable_balance.to_string());
+
+    sha256::Hash::hash(&buffer)
+}
+
+fn balance_node_hash(left: &sha256::Hash, right: &sha256::Hash) -> sha256::Hash {
+    let mut buffer = vec![1];
+    buffer.extend_from_slice(left.as_byte_array());
+    buffer.extend_from_slice(right.as_byte_array());
+
+    sha256::Hash::hash(&buffer)
+}
+
+fn write_str(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend((value.len() as u32).to_be_bytes());
+    buffer.extend(value.as_bytes());
+}
+
+/// Txid in the byte order of its hex representation
+fn write_outpoint(buffer: &mut Vec<u8>, txid: &Txid, vout: u32) {
+    let mut txid = txid.to_byte_array();
+    txid.reverse();
+    buffer.extend(txid);
+    buffer.extend(vout.to_be_bytes());
+}
+
+pub const SPEC_BLOCK_PROOF: &str = "proof = sha256(prev_proof || events_hash), where prev_proof is the proof of the previous block (sha256(\"null\") before the first one) \
+    and events_hash is sha256(\"null\") for blocks without events, sha256(encoding) otherwise";
+
+pub const SPEC_V1: &str = "Concatenation of the JSON objects of the block events as returned by `/events/{height}` (without whitespace, fields in the order of the response)";
+
+pub const SPEC_V2_EVENT: &[(&str, &str)] = &[
+    ("version", "u8, 0x02, once at the beginning of the encoding"),
+    ("id", "u64 big-endian, events are ordered by id"),
+    ("height", "u32 big-endian"),
+    ("tick", "4 bytes of the tick in the original case"),
+    ("address", "string"),
+    ("type", "u8: 0 Deploy, 1 Mint, 2 DeployTransfer, 3 Send, 4 Receive, 5 SendReceive"),
+    ("max, lim", "string each, Deploy only"),
+    ("dec", "u8, Deploy only"),
+    ("amt", "string, all types except Deploy"),
+    ("recipient", "string, Send only"),
+    ("sender", "string, Receive only"),
+    ("txid", "32 bytes in the byte order of its hex representation"),
+    ("vout", "u32 big-endian"),
+];
+
+pub const SPEC_V2_STRING: &str = "u32 big-endian length followed by UTF-8 bytes, amounts are decimal strings as returned by the API";
+
+pub const SPEC_BALANCE_TREE: &str = "Binary Merkle tree over the balances of the (address, tick) pairs with events in the block, \
+    sorted by script hash and tick. leaf = sha256(0x00 || script_hash || tick || balance || transferable_balance), \
+    where script_hash is sha256 of the script_pubkey (32 bytes, not reversed), tick is 4 bytes in the original case \
+    and balances are strings. node = sha256(0x01 || left || right), the last node of an odd level is moved up unchanged";
+
+pub const SPEC_STATE_DIGEST: &str = "sha256 over the concatenation of the records of all non-zero balances in the order of script hash and tick, \
+    followed by the records of all tokens in the order of the lowercase tick. balance = 0x00 || script_hash || tick || balance || transferable_balance || transfers_count, \
+    token = 0x01 || tick || genesis txid || genesis index || max || lim || dec || supply || mint_count || transfer_count || transactions || height || deployer script_hash. \
+    Integers are big-endian (u64 counts, u32 transactions, index and height, u8 dec), other fields are encoded as in the balance tree and v2";
@@ -0,0 +1,344 @@
+use super::*;
+
+use std::path::{Path, PathBuf};
+
+/// Process exit codes of failed preflight checks
+pub mod exit_code {
+    pub const CONFIG: i32 = 2;
+    pub const RPC: i32 = 3;
+    pub const BLK_DIR: i32 = 4;
+    pub const INDEX_DIR: i32 = 5;
+    pub const DB_PATH: i32 = 6;
+    /// Some blocks of the chain index can't be read from blk files, see [`super::scan_blk_files`]
+    pub const BLK_SCAN: i32 = 7;
+    /// Token history can't be written, see `export-parquet`
+    pub const EXPORT: i32 = 8;
+    /// Compared databases or snapshots differ, see `bel20-diff`, or responses differ from the golden checksums, see `golden`
+    pub const DIFFERS: i32 = 1;
+    /// Snapshot of `bel20-diff` can't be read or written
+    pub const SNAPSHOT: i32 = 9;
+    /// Blocks of the golden file can't be replayed or its queries can't be run, see `golden`
+    pub const REPLAY: i32 = 10;
+}
+
+struct Check {
+    name: &'static str,
+    exit_code: i32,
+    result: Result<String, String>,
+}
+
+/// Validates the environment before any lazy static is touched, so misconfiguration is reported at once instead of panicking later. <br/>
+/// Returns exit code of the first failed check.
+pub fn run() -> Result<(), i32> {
+    let mut checks = vec![];
+
+    let config = check_env();
+    let config_ok = config.is_ok();
+    checks.push(Check {
+        name: "environment",
+        exit_code: exit_code::CONFIG,
+        result: config.map(|_| "required variables are set".to_string()),
+    });
+
+    let rpc = config_ok.then(check_rpc);
+    let rpc_ok = rpc.as_ref().is_some_and(|x| x.is_ok());
+    if let Some(result) = rpc {
+        checks.push(Check {
+            name: "rpc",
+            exit_code: exit_code::RPC,
+            result,
+        });
+    }
+
+    if let Some(blk_dir) = load_opt_env!("BLK_DIR") {
+        // BLOCKCHAIN and NETWORK are valid once the environment check passed
+        let coin = config_ok.then(|| *COIN);
+        checks.push(Check {
+            name: "blk dir",
+            exit_code: exit_code::BLK_DIR,
+            result: check_blk_dir(Path::new(&blk_dir), coin, rpc_ok),
+        });
+    }
+
+    if let Some(index_dir) = load_opt_env!("INDEX_DIR") {
+        checks.push(Check {
+            name: "index dir",
+            exit_code: exit_code::INDEX_DIR,
+            result: check_index_dir(Path::new(&index_dir)),
+        });
+    }
+
+    let db_path = load_opt_env!("DB_PATH").unwrap_or("rocksdb".to_string());
+    checks.push(Check {
+        name: "db path",
+        exit_code: exit_code::DB_PATH,
+        result: check_db_path(Path::new(&db_path)),
+    });
+
+    if let Some(cold_db_path) = load_opt_env!("COLD_DB_PATH") {
+        checks.push(Check {
+            name: "cold db path",
+            exit_code: exit_code::DB_PATH,
+            result: check_cold_tables(load_opt_env!("COLD_TABLES")).and_then(|_| check_db_path(Path::new(&cold_db_path))),
+        });
+    }
+
+    let report = checks
+        .iter()
+        .map(|check| match &check.result {
+            Ok(message) => format!("  [ok]   {}: {}", check.name, message),
+            Err(message) => format!("  [fail] {}: {} (exit code {})", check.name, message, check.exit_code),
+        })
+        .join("\n");
+
+    match checks.iter().find(|check| check.result.is_err()) {
+        Some(failed) => {
+            error!("Preflight checks failed:\n{report}");
+            Err(failed.exit_code)
+        }
+        None => {
+            info!("Preflight checks passed:\n{report}");
+            Ok(())
+        }
+    }
+}
+
+/// Maintenance command which decodes the block of every chain index entry from blk files and compares its hash, so broken
+/// files are reported with their blk index and offset before indexing fails on them. <br/>
+/// Returns exit code, zero if every block is readable.
+pub fn scan_blk_files() -> i32 {
+    let (Some(blk_dir), Some(index_dir)) = (BLK_DIR.as_ref(), INDEX_DIR.as_ref()) else {
+        error!("BLK_DIR and INDEX_DIR must be set to scan blk files");
+        return exit_code::CONFIG;
+    };
+
+    info!("Scanning the chain index in {index_dir} against blk files in {blk_dir}");
+
+    let report = match nint_blk::scan_blk_files(blk_dir, index_dir, *COIN) {
+        Ok(report) => report,
+        Err(e) => {
+            error!("Failed to load the chain index or blk files: {e}");
+            return exit_code::BLK_DIR;
+        }
+    };
+
+    for issue in &report.issues {
+        error!("{issue}");
+    }
+
+    if report.issues.is_empty() {
+        info!("All {} blocks of the chain index are readable", report.blocks);
+        0
+    } else {
+        error!("{} of {} blocks of the chain index can't be read from blk files", report.issues.len(), report.blocks);
+        exit_code::BLK_SCAN
+    }
+}
+
+fn check_env() -> Result<(), String> {
+    // Credentials aren't needed with cookie authentication
+    let credentials = if std::env::var("RPC_COOKIE_PATH").is_ok() { vec![] } else { vec!["RPC_USER", "RPC_PASS"] };
+    let missing = ["RPC_URL", "BLOCKCHAIN"]
+        .into_iter()
+        .chain(credentials)
+        .filter(|var| std::env::var(var).is_err())
+        .collect_vec();
+
+    if !missing.is_empty() {
+        return Err(format!("missing environment variables: {}", missing.join(", ")));
+    }
+
+    if let Some(path) = load_opt_env!("TICK_POLICY_FILE") {
+        TickPolicy::load(Path::new(&path)).map_err(|e| format!("TICK_POLICY_FILE={path}: {e}"))?;
+    }
+
+    let blockchain_str = load_env!("BLOCKCHAIN");
+    let blockchain = Blockchain::from_str(&blockchain_str).map_err(|e| format!("BLOCKCHAIN={blockchain_str}: {e}"))?;
+
+    let network_str = load_opt_env!("NETWORK");
+    let network = match &network_str {
+        Some(network) => Network::from_str(network).map_err(|e| format!("NETWORK={network}: {e}"))?,
+        None => Network::Bellscoin,
+    };
+
+    // Registered once `COIN` is initialized, only the definition is checked here
+    if let Some(path) = load_opt_env!("COIN_CONFIG") {
+        blockchain::load_custom_coin(Path::new(&path)).map_err(|e| format!("COIN_CONFIG={path}: {e}"))?;
+        return Ok(());
+    }
+
+    if let Some(name) = load_opt_env!("COIN_OVERRIDE") {
+        nint_blk::CoinType::from_str(&name).map_err(|_| format!("COIN_OVERRIDE={name}: unknown coin, expected one of {}", nint_blk::CoinType::NAMES.join(", ")))?;
+        warn!("COIN_OVERRIDE={name} is indexed instead of BLOCKCHAIN={blockchain_str}");
+        return Ok(());
+    }
+
+    if blockchain.coin_name(network).is_none() {
+        return Err(format!(
+            "BLOCKCHAIN={blockchain_str} NETWORK={} isn't supported, supported combinations: {}. Set COIN_OVERRIDE or COIN_CONFIG for other setups",
+            network_str.as_deref().unwrap_or("mainnet"),
+            blockchain::supported_coins()
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_rpc() -> Result<String, String> {
+    let url = load_env!("RPC_URL");
+
+    // Only called with a valid config, so blocks are decoded as the configured coin
+    let client = nint_blk::Client::new(&url, RPC_AUTH.clone(), *COIN, WaitToken::default()).map_err(|e| format!("{url}: {e}"))?;
+    let best_hash = client.ping().map_err(|e| format!("{url}: {e}, check RPC_URL and credentials"))?;
+    let block_format = client.probe_block_format().map_err(|e| format!("{url}: can't decode blocks returned by getblock: {e}"))?;
+
+    Ok(format!("{url} is reachable, best block {best_hash}, getblock format {block_format:?}"))
+}
+
+/// Directories with blk files are looked up in the given one, its `blocks` folder and `<network>/blocks` folders of a node datadir
+fn blk_dir_candidates(path: &Path) -> Vec<PathBuf> {
+    let mut candidates = vec![path.to_path_buf(), path.join("blocks")];
+
+    if let Ok(entries) = std::fs::read_dir(path) {
+        candidates.extend(entries.filter_map(|entry| entry.ok()).map(|entry| entry.path().join("blocks")).sorted());
+    }
+
+    candidates
+}
+
+/// First directory with blk files under `BLK_DIR`, preferring the one whose magic matches the coin. <br/>
+/// Falls back to the configured path, so the error is reported for it.
+pub fn resolve_blk_dir(path: &Path, coin: nint_blk::CoinType) -> PathBuf {
+    let found = blk_dir_candidates(path).into_iter().filter(|x| count_blk_files(x).is_ok_and(|count| count > 0)).collect_vec();
+
+    found
+        .iter()
+        .find(|x| coin.magic.is_some() && nint_blk::probe_blk_dir(x).is_ok_and(|probe| Some(probe.magic) == coin.magic))
+        .or(found.first())
+        .cloned()
+        .unwrap_or(path.to_path_buf())
+}
+
+/// Block index is looked up in the given directory, its `index` and `blocks/index` folders
+pub fn resolve_index_dir(path: &Path) -> PathBuf {
+    [path.to_path_buf(), path.join("index"), path.join("blocks").join("index")]
+        .into_iter()
+        .find(|x| x.join("CURRENT").is_file())
+        .unwrap_or(path.to_path_buf())
+}
+
+fn count_blk_files(path: &Path) -> Result<usize, String> {
+    let entries = std::fs::read_dir(path).map_err(|e| format!("{} isn't readable: {e}", path.display()))?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with("blk") && name.ends_with(".dat")
+        })
+        .count())
+}
+
+fn check_blk_dir(configured: &Path, coin: Option<nint_blk::CoinType>, rpc_ok: bool) -> Result<String, String> {
+    let path = match coin {
+        Some(coin) => resolve_blk_dir(configured, coin),
+        None => configured.to_path_buf(),
+    };
+
+    let blk_files = count_blk_files(&path)?;
+    if blk_files == 0 {
+        return Err(format!("no blk*.dat files found in {} or its blocks folders", configured.display()));
+    }
+
+    let mut message = format!("{blk_files} blk files found in {}", path.display());
+    if path != configured {
+        message += &format!(" (detected under {})", configured.display());
+    }
+
+    let Some(coin) = coin else {
+        return Ok(message);
+    };
+
+    let probe = nint_blk::probe_blk_dir(&path).map_err(|e| format!("can't read the first block of {}: {e}", path.display()))?;
+    let expected = format!("BLOCKCHAIN={:?} NETWORK={:?} expects {}", *BLOCKCHAIN, *NETWORK, coin.name);
+    let owners = nint_blk::CoinType::names_by_magic(probe.magic);
+    let magic = probe.magic.iter().map(|x| format!("{x:02x}")).join("");
+
+    match coin.magic {
+        Some(expected_magic) if expected_magic != probe.magic => {
+            let found = if owners.is_empty() { "an unknown coin".to_string() } else { owners.join(" / ") };
+            return Err(format!("{} starts with magic {magic} of {found}, but {expected}", probe.path.display()));
+        }
+        Some(_) => message += &format!(", magic {magic} matches {}", coin.name),
+        None if !owners.is_empty() => {
+            return Err(format!("{} starts with magic {magic} of {}, but {expected}", probe.path.display(), owners.join(" / ")));
+        }
+        None => {}
+    }
+
+    // Genesis block is only available if the node isn't pruned
+    if probe.index == 0 && rpc_ok {
+        let client = nint_blk::Client::new(&URL, RPC_AUTH.clone(), coin, WaitToken::default()).map_err(|e| e.to_string())?;
+        let genesis = client.get_genesis_hash().map_err(|e| format!("can't get the genesis block from the node: {e}"))?;
+
+        if genesis != probe.first_block {
+            return Err(format!(
+                "first block of {} is {}, but the genesis block of the node is {genesis}: blk files belong to another chain than the node, {expected}",
+                probe.path.display(),
+                probe.first_block
+            ));
+        }
+
+        message += ", genesis block matches the node";
+    }
+
+    Ok(message)
+}
+
+fn check_index_dir(configured: &Path) -> Result<String, String> {
+    let path = resolve_index_dir(configured);
+
+    if !path.is_dir() {
+        return Err(format!("{} isn't a directory", path.display()));
+    }
+
+    // Every LevelDB database has a CURRENT file pointing at the active manifest
+    if !path.join("CURRENT").is_file() {
+        return Err(format!("{} doesn't look like a LevelDB block index (no CURRENT file in it or its index folders)", path.display()));
+    }
+
+    if path != configured {
+        return Ok(format!("LevelDB index found at {} (detected under {})", path.display(), configured.display()));
+    }
+
+    Ok(format!("LevelDB index found at {}", path.display()))
+}
+
+fn check_cold_tables(value: Option<String>) -> Result<(), String> {
+    let tables = value.iter().flat_map(|x| x.split(',')).map(str::trim).filter(|x| !x.is_empty()).collect_vec();
+
+    let unknown = tables.iter().filter(|x| !DB::TABLES.contains(x)).collect_vec();
+    if !unknown.is_empty() {
+        return Err(format!("COLD_TABLES has unknown tables: {}", unknown.iter().join(", ")));
+    }
+
+    // The rest is written in the batch of the block, see `DB::COLD_TABLES_ALLOWED`
+    let hot = tables.iter().filter(|x| !DB::COLD_TABLES_ALLOWED.contains(x)).collect_vec();
+    if !hot.is_empty() {
+        let allowed = DB::COLD_TABLES_ALLOWED.join(", ");
+        return Err(format!("COLD_TABLES may only contain {allowed}, not {}", hot.iter().join(", ")));
+    }
+
+    Ok(())
+}
+
+fn check_db_path(path: &Path) -> Result<String, String> {
+    std::fs::create_dir_all(path).map_err(|e| format!("can't create {}: {e}", path.display()))?;
+
+    let probe = path.join(".preflight");
+    std::fs::write(&probe, b"").map_err(|e| format!("{} isn't writable: {e}", path.display()))?;
+    std::fs::remove_file(&probe).ok();
+
+    Ok(format!("{} is writable", path.display()))
+}
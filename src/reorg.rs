@@ -1,7 +1,73 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
 use super::*;
 
 pub const REORG_CACHE_MAX_LEN: usize = 30;
 
+/// Heights covered by one advisory lock of [`ReorgInProgress`]
+const HEIGHT_LOCK_SPAN: u32 = 16;
+/// Locks of [`ReorgInProgress`], heights are assigned to them round robin by spans
+const HEIGHT_LOCK_SHARDS: usize = 8;
+
+/// Shared between the indexer and REST, so queries can't observe partially rolled back state. <br/>
+/// Heights are guarded by sharded locks: the rollback takes the locks of the rolled back heights, queries take the locks
+/// of the heights they read, so the rollback never waits for queries of other heights.
+#[derive(Default)]
+pub struct ReorgInProgress {
+    /// First rolled back height plus one, zero if there is no rollback
+    height: AtomicU32,
+    shards: [tokio::sync::RwLock<()>; HEIGHT_LOCK_SHARDS],
+}
+
+impl ReorgInProgress {
+    /// First height which is being rolled back
+    pub fn height(&self) -> Option<u32> {
+        self.height.load(Ordering::Acquire).checked_sub(1)
+    }
+
+    /// Returns `None` while a rollback of some of the heights is running. <br/>
+    /// A rollback of the heights waits for all returned guards to be dropped before touching the state.
+    pub async fn read(&self, heights: RangeInclusive<u32>) -> Option<Vec<tokio::sync::RwLockReadGuard<'_, ()>>> {
+        if self.height().is_some_and(|height| height <= *heights.end()) {
+            return None;
+        }
+
+        let mut guards = vec![];
+        for shard in Self::shards(heights) {
+            guards.push(self.shards[shard].read().await);
+        }
+
+        Some(guards)
+    }
+
+    fn start(&self, heights: RangeInclusive<u32>) -> ReorgInProgressGuard<'_> {
+        self.height.store(heights.start() + 1, Ordering::Release);
+
+        ReorgInProgressGuard {
+            state: self,
+            _locks: Self::shards(heights).into_iter().map(|shard| self.shards[shard].blocking_write()).collect(),
+        }
+    }
+
+    /// Shards of the heights in ascending order, which is the locking order of both readers and the rollback
+    fn shards(heights: RangeInclusive<u32>) -> BTreeSet<usize> {
+        let spans = (heights.start() / HEIGHT_LOCK_SPAN)..=(heights.end() / HEIGHT_LOCK_SPAN);
+        spans.take(HEIGHT_LOCK_SHARDS).map(|span| span as usize % HEIGHT_LOCK_SHARDS).collect()
+    }
+}
+
+struct ReorgInProgressGuard<'a> {
+    state: &'a ReorgInProgress,
+    _locks: Vec<tokio::sync::RwLockWriteGuard<'a, ()>>,
+}
+
+impl Drop for ReorgInProgressGuard<'_> {
+    fn drop(&mut self) {
+        self.state.height.store(0, Ordering::Release);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub enum TokenHistoryEntry {
     BalancesBefore(Vec<(AddressToken, TokenBalance)>),
     BalancesToRemove(Vec<AddressToken>),
@@ -16,32 +82,50 @@ pub enum TokenHistoryEntry {
         height: u32,
         token_id_to_event: Vec<TokenId>,
     },
+    HistoryStatsBefore(Vec<(AddressToken, HistoryStats)>),
+    HistoryStatsToRemove(Vec<AddressToken>),
+    MintFeesBefore(Vec<(LowerCaseTokenTick, u64)>),
+    MintFeesToRemove(Vec<LowerCaseTokenTick>),
+    RemoveMintActivity {
+        height: u32,
+        ticks: Vec<OriginalTokenTick>,
+    },
+    RemoveFirstMints(Vec<LowerCaseTokenTick>),
+    RemoveRejectedDeploys(u32),
 }
 
 trait ProceedReorg: Sized {
-    fn proceed(self, server: &Server) -> anyhow::Result<()>;
+    fn proceed(self, db: &DB);
 }
 
 impl ProceedReorg for TokenHistoryEntry {
-    fn proceed(self, server: &Server) -> anyhow::Result<()> {
+    fn proceed(self, db: &DB) {
         match self {
             TokenHistoryEntry::DeploysToRemove(to_remove) => {
-                server.db.token_to_meta.remove_batch(to_remove);
+                let metas = db.token_to_meta.multi_get_kv(to_remove.iter(), false).into_iter().map(|(_, meta)| meta).collect_vec();
+                db.token_skeleton.remove_batch(metas.iter().map(|meta| TokenSkeletonKey::new(meta.proto.tick)));
+                db.deployer_tokens.remove_batch(metas.iter().map(|meta| meta.proto.deployer_token()));
+                db.token_completion_height.remove_batch(to_remove.iter());
+                db.token_to_meta.remove_batch(to_remove);
             }
             TokenHistoryEntry::DeploysToRestore(items) => {
-                server.db.token_to_meta.extend(items);
+                // Tokens completed in the removed block are in progress again
+                db.token_completion_height.remove_batch(items.iter().filter(|(_, meta)| !meta.is_completed()).map(|(tick, _)| tick));
+                db.token_to_meta.extend(items);
             }
             TokenHistoryEntry::BalancesBefore(items) => {
-                server.db.address_token_to_balance.extend(items);
+                db.update_held_tokens(&items);
+                db.address_token_to_balance.extend(items);
             }
             TokenHistoryEntry::BalancesToRemove(address_tokens) => {
-                server.db.address_token_to_balance.remove_batch(address_tokens);
+                db.address_held_tokens.remove_batch(address_tokens.iter());
+                db.address_token_to_balance.remove_batch(address_tokens);
             }
             TokenHistoryEntry::RestoreTransfers(items) => {
-                server.db.address_location_to_transfer.extend(items);
+                db.address_location_to_transfer.extend(items);
             }
             TokenHistoryEntry::RemoveTransfers(address_locations) => {
-                server.db.address_location_to_transfer.remove_batch(address_locations);
+                db.address_location_to_transfer.remove_batch(address_locations);
             }
             TokenHistoryEntry::RemoveHistory {
                 to_remove,
@@ -50,51 +134,109 @@ impl ProceedReorg for TokenHistoryEntry {
                 height,
                 token_id_to_event,
             } => {
-                server.db.last_history_id.set((), last_history_id);
-                server.db.block_events.remove(height);
-                server.db.address_token_to_history.remove_batch(to_remove);
-                server.db.outpoint_to_event.remove_batch(outpoint_to_event);
-                server.db.token_id_to_event.remove_batch(token_id_to_event);
+                db.last_history_id.set((), last_history_id);
+                db.block_events.remove(height);
+                db.history_origin.remove_batch(to_remove.iter().map(|x| x.id));
+                db.address_token_to_history.remove_batch(to_remove);
+                db.txid_to_batch.remove_batch(outpoint_to_event.iter().map(|x| x.txid));
+                db.outpoint_to_event.remove_batch(outpoint_to_event);
+                db.token_id_to_event.remove_batch(token_id_to_event);
+            }
+            TokenHistoryEntry::HistoryStatsBefore(items) => {
+                db.address_token_history_stats.extend(items);
+            }
+            TokenHistoryEntry::HistoryStatsToRemove(address_tokens) => {
+                db.address_token_history_stats.remove_batch(address_tokens);
+            }
+            TokenHistoryEntry::MintFeesBefore(items) => {
+                db.token_mint_fees.extend(items);
+            }
+            TokenHistoryEntry::MintFeesToRemove(ticks) => {
+                db.token_mint_fees.remove_batch(ticks);
+            }
+            TokenHistoryEntry::RemoveMintActivity { height, ticks } => {
+                db.token_mint_activity.remove_batch(ticks.into_iter().map(|tick| TokenHeight { tick, height }));
+            }
+            TokenHistoryEntry::RemoveFirstMints(ticks) => {
+                db.token_first_mint.remove_batch(ticks);
+            }
+            TokenHistoryEntry::RemoveRejectedDeploys(height) => {
+                db.rejected_deploys.remove(height);
             }
         }
-
-        Ok(())
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub enum OrdinalsEntry {
     RestoreOffsets(Vec<(OutPoint, HashSet<u64>)>),
     RemoveOffsets(Vec<OutPoint>),
     RestorePrevouts(Vec<(OutPoint, TxPrevout)>),
     RestorePartial(Vec<(OutPoint, Partials)>),
     RemovePartials(Vec<OutPoint>),
+    RemoveInscriptionStats(u32),
+    RestoreInscriptionLocations(Vec<(Location, Vec<InscriptionId>)>),
+    RemoveInscriptionLocations(Vec<Location>),
+    RemoveInscriptionHistory(Vec<InscriptionHistoryKey>),
+    RemoveFeeStats(u32),
+    RemoveInscriptionCurses(Vec<InscriptionId>),
+    RemoveLeakedInscriptions { height: u32, owners: Vec<FullHash> },
+    RestoreInscriptionIdLocations(Vec<(InscriptionId, Location)>),
+    RemoveInscriptionIdLocations(Vec<InscriptionId>),
 }
 
 impl ProceedReorg for OrdinalsEntry {
-    fn proceed(self, server: &Server) -> anyhow::Result<()> {
+    fn proceed(self, db: &DB) {
         match self {
             OrdinalsEntry::RestoreOffsets(items) => {
-                server.db.outpoint_to_inscription_offsets.extend(items);
+                db.outpoint_to_inscription_offsets.extend(items);
             }
             OrdinalsEntry::RemoveOffsets(outpoints) => {
-                server.db.outpoint_to_inscription_offsets.remove_batch(outpoints);
+                db.outpoint_to_inscription_offsets.remove_batch(outpoints);
             }
             OrdinalsEntry::RestorePrevouts(items) => {
-                server.db.prevouts.extend(items);
+                db.prevouts.extend(items);
             }
             OrdinalsEntry::RestorePartial(items) => {
-                server.db.outpoint_to_partials.extend(items);
+                db.outpoint_to_partials.extend(items);
             }
             OrdinalsEntry::RemovePartials(outpoints) => {
-                server.db.outpoint_to_partials.remove_batch(outpoints);
+                db.partials_height.remove_batch(outpoints.iter());
+                db.outpoint_to_partials.remove_batch(outpoints);
+            }
+            OrdinalsEntry::RemoveInscriptionStats(height) => {
+                db.block_inscription_stats.remove(height);
+            }
+            OrdinalsEntry::RestoreInscriptionLocations(items) => {
+                db.location_to_inscriptions.extend(items);
+            }
+            OrdinalsEntry::RemoveInscriptionLocations(locations) => {
+                db.location_to_inscriptions.remove_batch(locations);
+            }
+            OrdinalsEntry::RemoveInscriptionHistory(keys) => {
+                db.inscription_history.remove_batch(keys);
+            }
+            OrdinalsEntry::RestoreInscriptionIdLocations(items) => {
+                db.inscription_location.extend(items);
+            }
+            OrdinalsEntry::RemoveInscriptionIdLocations(ids) => {
+                db.inscription_location.remove_batch(ids);
+            }
+            OrdinalsEntry::RemoveFeeStats(height) => {
+                db.block_fee_stats.remove(height);
+            }
+            OrdinalsEntry::RemoveInscriptionCurses(ids) => {
+                db.inscription_curses.remove_batch(ids);
+            }
+            OrdinalsEntry::RemoveLeakedInscriptions { height, owners } => {
+                db.address_leaked.remove_batch(owners.into_iter().map(|address| AddressHeight { address, height }));
+                db.block_leaked.remove(height);
             }
         }
-
-        Ok(())
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct ReorgHistoryBlock {
     token_history: Vec<TokenHistoryEntry>,
     ordinals_history: Vec<OrdinalsEntry>,
@@ -106,6 +248,8 @@ impl ReorgHistoryBlock {
     }
 }
 
+/// Undo journal of the last blocks. <br/>
+/// Every block is mirrored into the `reorg_journal` CF, so rollback capability survives restarts.
 pub struct ReorgCache {
     pub blocks: BTreeMap<u32, ReorgHistoryBlock>,
     len: usize,
@@ -119,13 +263,47 @@ impl ReorgCache {
         }
     }
 
-    pub fn new_block(&mut self, block_height: u32) {
+    /// Restores the journal persisted by the previous run
+    pub fn load(db: &DB) -> Self {
+        let mut cache = Self::new();
+        cache.blocks.extend(db.reorg_journal.iter());
+
+        // Journal of a block is written in one batch with its data, so it ends at the last block unless the blocks were indexed without it
+        let last_block = db.last_block.get(());
+        if !cache.blocks.is_empty() && cache.blocks.last_key_value().map(|x| *x.0) != last_block {
+            warn!("Reorg journal doesn't end at the last block {:?}, dropping it", last_block);
+            cache.clear(db);
+        }
+
+        while cache.blocks.len() > cache.len {
+            let (height, _) = cache.blocks.pop_first().unwrap();
+            db.reorg_journal.remove(height);
+        }
+
+        cache
+    }
+
+    pub fn new_block(&mut self, db: &DB, block_height: u32) {
         if self.blocks.len() == self.len {
-            self.blocks.pop_first();
+            if let Some((height, _)) = self.blocks.pop_first() {
+                db.reorg_journal.remove(height);
+            }
         }
         self.blocks.insert(block_height, ReorgHistoryBlock::new());
     }
 
+    /// Must be called once all entries of the last block are pushed
+    pub fn persist_last(&self, db: &DB) {
+        if let Some((height, block)) = self.blocks.last_key_value() {
+            db.reorg_journal.set(height, block);
+        }
+    }
+
+    pub fn clear(&mut self, db: &DB) {
+        db.reorg_journal.remove_batch(self.blocks.keys());
+        self.blocks.clear();
+    }
+
     pub fn push_ordinals_entry(&mut self, data: OrdinalsEntry) {
         self.blocks.last_entry().unwrap().get_mut().ordinals_history.push(data);
     }
@@ -134,29 +312,57 @@ impl ReorgCache {
         self.blocks.last_entry().unwrap().get_mut().token_history.push(data);
     }
 
-    pub fn restore(&mut self, server: &Server, block_height: u32) -> anyhow::Result<()> {
+    pub fn restore(&mut self, db: &DB, block_height: u32, reorg_in_progress: &ReorgInProgress) -> anyhow::Result<()> {
+        if !self.blocks.last_key_value().is_some_and(|(height, _)| block_height < *height) {
+            return Ok(());
+        }
+
+        let tip = *self.blocks.last_key_value().anyhow()?.0;
+        let _guard = reorg_in_progress.start(block_height + 1..=tip);
+
         while !self.blocks.is_empty() && block_height < *self.blocks.last_key_value().unwrap().0 {
             let (height, data) = self.blocks.pop_last().anyhow()?;
 
-            server.db.last_block.set((), height - 1);
-            server.db.block_info.remove(height);
-
-            for entry in data.token_history.into_iter().rev() {
-                entry.proceed(server)?;
-            }
-            for entry in data.ordinals_history.into_iter().rev() {
-                entry.proceed(server)?;
-            }
+            // Undone in one batch like it was written, so a crash leaves the block either whole or removed
+            db.rocks().atomic(|| Self::restore_block(db, height, data));
         }
 
         Ok(())
     }
 
-    pub fn restore_all(&mut self, server: &Server) -> anyhow::Result<()> {
-        let from = self.blocks.first_key_value().map(|x| *x.0);
-        let to = self.blocks.last_key_value().map(|x| *x.0);
+    fn restore_block(db: &DB, height: u32, data: ReorgHistoryBlock) {
+        db.commit_block(height - 1);
+        if let Some(block_info) = db.block_info.get(height) {
+            db.block_hash_to_height.remove(block_info.hash);
+            db.remove_block_time(height, block_info.created);
+        }
+        db.block_info.remove(height);
+        db.block_median_time.remove(height);
+        db.block_summary.remove(height);
+        db.proof_of_history_version.remove(height);
+        db.proof_of_history_events_hash.remove(height);
+        db.state_digest.remove(height);
+        if let Some(balance_leaves) = db.block_balance_leaves.get(height) {
+            db.address_token_balance_height.remove_batch(balance_leaves.into_iter().map(|leaf| AddressTokenHeight {
+                address: leaf.address,
+                token: leaf.token,
+                height,
+            }));
+            db.block_balance_leaves.remove(height);
+            db.balance_root.remove(height);
+        }
+        if let Some(ticks) = db.holders_snapshot_ticks.get(height) {
+            db.holders_snapshot.remove_batch(ticks.into_iter().map(|tick| TokenHeight { tick, height }));
+            db.holders_snapshot_ticks.remove(height);
+        }
+
+        for entry in data.token_history.into_iter().rev() {
+            entry.proceed(db);
+        }
+        for entry in data.ordinals_history.into_iter().rev() {
+            entry.proceed(db);
+        }
 
-        warn!("Restoring savepoints from {:?} to {:?}", from, to);
-        self.restore(server, 0)
+        db.reorg_journal.remove(height);
     }
 }
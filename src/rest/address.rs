@@ -21,9 +21,10 @@ pub async fn address_tokens_tick(
         .bad_request_from_error()?
         .into();
 
+    // Only tokens with a nonzero balance are indexed, so emptied balances aren't paged through
     let data = state
         .db
-        .address_token_to_balance
+        .address_held_tokens
         .range(
             &AddressToken {
                 address: scripthash,
@@ -42,7 +43,8 @@ pub async fn address_tokens_tick(
                 .map(|x| k.token.to_string().to_lowercase().starts_with(&x))
                 .unwrap_or(true)
         })
-        .skip(params.offset.is_some() as usize)
+        // The offset tick may have been emptied since the previous page
+        .filter(|(k, _)| Some(k.token) != token)
         .take(params.limit)
         .map(|(key, _)| key.token.to_string())
         .collect_vec();
@@ -51,7 +53,7 @@ pub async fn address_tokens_tick(
 }
 
 pub fn address_tokens_tick_docs(op: TransformOperation) -> TransformOperation {
-    op.description("A list of token ticks for the address").tag("address")
+    op.description("A list of token ticks with a nonzero balance for the address").tag("address")
 }
 
 pub async fn address_token_balance(
@@ -60,8 +62,6 @@ pub async fn address_token_balance(
     Path((script_str, tick)): Path<(String, OriginalTokenTickRest)>,
     Query(params): Query<types::AddressTokenBalanceArgs>,
 ) -> ApiResult<impl IntoApiResponse> {
-    params.validate().bad_request_from_error()?;
-
     let script_type = url.path().split('/').nth(1).internal(INTERNAL)?;
     let scripthash: FullHash = state
         .indexer
@@ -69,12 +69,26 @@ pub async fn address_token_balance(
         .bad_request_from_error()?
         .into();
 
+    Ok(Json(blocking(move || token_balance(&state, scripthash, tick, params)).await?))
+}
+
+/// Shared by `GET /address/{address}/{tick}/balance` and the `get_balance` RPC method
+pub(super) fn token_balance(
+    state: &Server,
+    scripthash: FullHash,
+    tick: OriginalTokenTickRest,
+    params: types::AddressTokenBalanceArgs,
+) -> ApiResult<types::TokenBalance> {
+    params.validate().bad_request_from_error()?;
+
     let token: LowerCaseTokenTick = tick.into();
 
     let deploy_proto = state.db.token_to_meta.get(&token).not_found("Token not found")?;
 
     let tick = deploy_proto.proto.tick;
 
+    state.access_stats.hit(tick);
+
     let balance = state.db.address_token_to_balance.get(AddressToken { address: scripthash, token: tick }).unwrap_or_default();
 
     let (from, to) = AddressLocation::search(scripthash, params.offset.map(|x| x.into())).into_inner();
@@ -84,27 +98,185 @@ pub async fn address_token_balance(
         .address_location_to_transfer
         .range(&from..&to, false)
         .filter(|(_, v)| v.tick == tick)
-        .map(|(k, v)| TokenTransfer {
-            amount: v.amt,
-            outpoint: k.location.outpoint.into(),
-        })
         .skip(params.offset.is_some() as usize)
         .take(params.limit)
-        .collect_vec();
+        .map(|(k, v)| {
+            anyhow::Ok(TokenTransfer {
+                amount: v.amt,
+                spent_in_mempool: state.spent_in_mempool(&k.location.outpoint)?,
+                outpoint: k.location.outpoint.into(),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .internal("Failed to check mempool")?;
 
-    let data = types::TokenBalance {
+    Ok(types::TokenBalance {
         transfers,
         tick: tick.into(),
         balance: balance.balance,
         transferable_balance: balance.transferable_balance,
         transfers_count: balance.transfers_count,
+    })
+}
+
+pub fn address_token_balance_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Detailed info about the token balance for the address (with transfers").tag("address")
+}
+
+pub async fn address_token_balance_proof(
+    url: Uri,
+    State(state): State<Arc<Server>>,
+    Path((script_str, tick)): Path<(String, OriginalTokenTickRest)>,
+    Query(params): Query<types::BalanceProofArgs>,
+) -> ApiResult<impl IntoApiResponse> {
+    let script_type = url.path().split('/').nth(1).internal(INTERNAL)?;
+    let scripthash: FullHash = state
+        .indexer
+        .to_scripthash(&script_str, script_type.parse().bad_request("Invalid script type")?)
+        .bad_request_from_error()?
+        .into();
+
+    let token: LowerCaseTokenTick = tick.into();
+    let tick = state.db.token_to_meta.get(&token).not_found("Token not found")?.proto.tick;
+
+    let height = match params.height {
+        Some(height) => height,
+        None => state.db.last_block.get(()).internal("Failed to get last height")?,
+    };
+
+    let from = AddressTokenHeight {
+        address: scripthash,
+        token: tick,
+        height: 0,
+    };
+    let to = AddressTokenHeight { height, ..from };
+
+    let (key, _) = state
+        .db
+        .address_token_balance_height
+        .range(&from..=&to, true)
+        .next()
+        .not_found("No balance changes with a balance tree at or below the height")?;
+
+    let leaves = state.db.block_balance_leaves.get(key.height).internal("Failed to get balance leaves")?;
+    let index = leaves
+        .binary_search_by_key(&(scripthash, tick), |leaf| (leaf.address, leaf.token))
+        .ok()
+        .internal("Failed to find balance leaf")?;
+
+    let (balance_root, path) = poh::balance_tree(&leaves, Some(index));
+    let leaf = &leaves[index];
+
+    Ok(Json(types::BalanceProof {
+        height: key.height,
+        tick: tick.into(),
+        script_hash: sha256::Hash::from_byte_array(*leaf.address).to_string(),
+        balance: leaf.balance,
+        transferable_balance: leaf.transferable_balance,
+        index,
+        leaf: poh::balance_leaf_hash(leaf).to_string(),
+        path: path
+            .into_iter()
+            .map(|(hash, left)| types::BalanceProofStep { hash: hash.to_string(), left })
+            .collect(),
+        balance_root: balance_root.to_string(),
+    }))
+}
+
+pub fn address_token_balance_proof_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Merkle proof of the token balance against the balance root of the block of its last change, see `/proof-of-history/spec`")
+        .tag("address")
+}
+
+pub async fn address_token_balance_history(
+    url: Uri,
+    State(state): State<Arc<Server>>,
+    Path((script_str, tick)): Path<(String, OriginalTokenTickRest)>,
+    Query(params): Query<types::BalanceHistoryArgs>,
+) -> ApiResult<impl IntoApiResponse> {
+    params.validate().bad_request_from_error()?;
+
+    let script_type = url.path().split('/').nth(1).internal(INTERNAL)?;
+    let scripthash: FullHash = state
+        .indexer
+        .to_scripthash(&script_str, script_type.parse().bad_request("Invalid script type")?)
+        .bad_request_from_error()?
+        .into();
+
+    let token: LowerCaseTokenTick = tick.into();
+    let tick = state.db.token_to_meta.get(&token).not_found("Token not found")?.proto.tick;
+
+    state.access_stats.hit(tick);
+
+    let from = match params.after_height {
+        Some(height) => height.checked_add(1),
+        None => Some(0),
     };
+    let changes = from
+        .map(|from| state.db.balance_changes(AddressToken { address: scripthash, token: tick }, from, params.limit))
+        .unwrap_or_default();
+
+    let times = state.db.block_times(changes.iter().map(|(height, _)| *height));
+
+    let data = changes
+        .into_iter()
+        .map(|(height, balance)| types::BalanceChange {
+            height,
+            created: times.get(&height).map(|x| x.median_time).unwrap_or_default(),
+            block_time: times.get(&height).map(|x| x.block_time).unwrap_or_default(),
+            balance,
+        })
+        .collect_vec();
 
     Ok(Json(data))
 }
 
-pub fn address_token_balance_docs(op: TransformOperation) -> TransformOperation {
-    op.description("Detailed info about the token balance for the address (with transfers").tag("address")
+pub fn address_token_balance_history_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Total token balance of the address after each block where it changed, oldest first")
+        .tag("address")
+}
+
+pub async fn address_leaked(
+    url: Uri,
+    State(state): State<Arc<Server>>,
+    Path(script_str): Path<String>,
+    Query(params): Query<types::LeakedArgs>,
+) -> ApiResult<impl IntoApiResponse> {
+    params.validate().bad_request_from_error()?;
+
+    let script_type = url.path().split('/').nth(1).internal(INTERNAL)?;
+    let scripthash: FullHash = state
+        .indexer
+        .to_scripthash(&script_str, script_type.parse().bad_request("Invalid script type")?)
+        .bad_request_from_error()?
+        .into();
+
+    let from = AddressHeight { address: scripthash, height: 0 };
+    let to = AddressHeight {
+        address: scripthash,
+        height: params.offset.unwrap_or(u32::MAX),
+    };
+
+    let mut leaked = vec![];
+    for (key, _) in state.db.address_leaked.range(&from..&to, true) {
+        if leaked.len() >= params.limit {
+            break;
+        }
+
+        let block = state.db.block_leaked.get(key.height).unwrap_or_default();
+        leaked.extend(block.into_iter().filter(|x| x.owner == scripthash).map(|x| (key.height, x)));
+    }
+
+    let addresses = state.load_addresses([scripthash]).internal("Failed to load addresses")?;
+
+    let data = leaked.into_iter().map(|(height, x)| types::LeakedInscription::new(height, x, &addresses)).collect_vec();
+
+    Ok(Json(data))
+}
+
+pub fn address_leaked_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Inscriptions of the address created or moved into the fees, newest block first")
+        .tag("address")
 }
 
 pub async fn address_tokens(
@@ -166,3 +338,69 @@ pub async fn address_tokens(
 pub fn address_tokens_docs(op: TransformOperation) -> TransformOperation {
     op.description("A list of tokens for the address (without transfers)").tag("address")
 }
+
+pub async fn address_deltas(
+    url: Uri,
+    State(state): State<Arc<Server>>,
+    Path(script_str): Path<String>,
+    Query(params): Query<types::AddressDeltasArgs>,
+) -> ApiResult<impl IntoApiResponse> {
+    params.validate().bad_request_from_error()?;
+
+    let last_height = state.db.last_block.get(()).internal("Failed to get last height")?;
+    let heights = params.heights(last_height).bad_request_from_error()?;
+
+    let script_type = url.path().split('/').nth(1).internal(INTERNAL)?;
+    let scripthash: FullHash = state
+        .indexer
+        .to_scripthash(&script_str, script_type.parse().bad_request("Invalid script type")?)
+        .bad_request_from_error()?
+        .into();
+
+    let tokens = state.db.address_token_to_balance.prefix::<FullHash>(&scripthash, false).map(|(k, _)| k.token).collect_vec();
+
+    // (received, sent) by height and tick
+    let mut blocks = BTreeMap::<u32, BTreeMap<OriginalTokenTick, (Fixed128, Fixed128)>>::new();
+
+    for token in tokens {
+        let key = AddressToken { address: scripthash, token };
+
+        // Event ids grow with height, so the newest events come first
+        let rows = state
+            .db
+            .address_token_to_history
+            .prefix::<AddressToken>(&key, true)
+            .skip_while(|(_, v)| v.height > *heights.end())
+            .take_while(|(_, v)| v.height >= *heights.start());
+
+        for (_, v) in rows {
+            let (received, sent) = match v.action {
+                TokenHistoryDB::Mint { amt, .. } | TokenHistoryDB::Receive { amt, .. } => (amt, Fixed128::default()),
+                TokenHistoryDB::Send { amt, .. } => (Fixed128::default(), amt),
+                TokenHistoryDB::Deploy { .. } | TokenHistoryDB::DeployTransfer { .. } | TokenHistoryDB::SendReceive { .. } => continue,
+            };
+
+            let entry = blocks.entry(v.height).or_default().entry(token).or_default();
+            entry.0 += received;
+            entry.1 += sent;
+        }
+    }
+
+    let times = state.db.block_times(blocks.keys().copied());
+
+    let data = blocks
+        .into_iter()
+        .map(|(height, deltas)| types::BlockDeltas {
+            height,
+            created: times.get(&height).map(|x| x.median_time).unwrap_or_default(),
+            block_time: times.get(&height).map(|x| x.block_time).unwrap_or_default(),
+            deltas: deltas.into_iter().map(|(tick, (received, sent))| types::TokenDelta::new(tick, received, sent)).collect(),
+        })
+        .collect_vec();
+
+    Ok(Json(data))
+}
+
+pub fn address_deltas_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Net token balance changes of the address per block").tag("address")
+}
@@ -0,0 +1,500 @@
+use axum::{extract::FromRequestParts, http::request::Parts, http::StatusCode};
+
+use super::*;
+
+const MAX_TOKEN_EXTRAS_LEN: usize = 16 * 1024;
+const MIN_WEBHOOK_SECRET_LEN: usize = 16;
+/// Admin bodies are small, larger requests are rejected before they reach the handler
+const MAX_AUDIT_BODY_LEN: usize = 64 * 1024;
+/// Operators share the admin token, so the audit log names the one declared by this header
+const OPERATOR_HEADER: &str = "X-Admin-Operator";
+const MAX_OPERATOR_LEN: usize = 64;
+/// Body fields which are never written to the audit log
+const REDACTED_FIELDS: &[&str] = &["secret"];
+
+/// Ids of the audit log are allocated by one request at a time
+static AUDIT_LOCK: parking_lot::Mutex<()> = parking_lot::const_mutex(());
+
+/// Requires `Authorization: Bearer <ADMIN_TOKEN>`, admin routes are disabled if `ADMIN_TOKEN` isn't set
+pub struct AdminAuth;
+
+impl<S: Send + Sync> FromRequestParts<S> for AdminAuth {
+    type Rejection = Response<String>;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let Some(admin_token) = ADMIN_TOKEN.as_deref() else {
+            return Err(error_response(StatusCode::NOT_FOUND, "Admin API is disabled"));
+        };
+
+        let provided = parts
+            .headers
+            .get(axum::http::header::AUTHORIZATION)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.strip_prefix("Bearer "));
+
+        match provided {
+            Some(provided) if constant_time_eq(provided.as_bytes(), admin_token.as_bytes()) => Ok(AdminAuth),
+            _ => Err(error_response(StatusCode::UNAUTHORIZED, "Invalid admin token")),
+        }
+    }
+}
+
+impl aide::OperationInput for AdminAuth {}
+
+fn error_response(status: StatusCode, message: &str) -> Response<String> {
+    let mut response = Response::new(message.to_string());
+    *response.status_mut() = status;
+    response
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Admin writes are rejected while the indexer is a standby, so its state stays the same as the primary's until it's promoted.
+/// Added inside [`audit_admin`], so rejected attempts are audited too.
+pub async fn standby_guard(State(server): State<Arc<Server>>, request: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+    let path = request.extensions().get::<axum::extract::MatchedPath>().map(|x| x.as_str().trim_start_matches("/v1").to_string());
+    let write = request.method() != axum::http::Method::GET && path.is_some_and(|path| path.starts_with("/admin/") && path != "/admin/promote");
+    if write && server.standby.is_active() {
+        return error_response(StatusCode::SERVICE_UNAVAILABLE, "Indexer is a standby, promote it with POST /admin/promote first").into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Records every admin request except reads in `admin_audit`, rejected ones included
+pub async fn audit_admin(State(server): State<Arc<Server>>, request: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+    let path = request.extensions().get::<axum::extract::MatchedPath>().map(|x| x.as_str().trim_start_matches("/v1").to_string());
+    if request.method() == axum::http::Method::GET || !path.is_some_and(|path| path.starts_with("/admin/")) {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = axum::body::to_bytes(body, MAX_AUDIT_BODY_LEN).await;
+
+    let mut entry = AdminAuditDB {
+        time: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
+        operator: parts
+            .headers
+            .get(OPERATOR_HEADER)
+            .and_then(|x| x.to_str().ok())
+            .map(|x| x.chars().take(MAX_OPERATOR_LEN).collect()),
+        method: parts.method.to_string(),
+        path: parts.uri.path().to_string(),
+        query: parts.uri.query().map(|x| x.to_string()),
+        body: match &body {
+            Ok(body) if body.is_empty() => None,
+            Ok(body) => Some(redact_body(body)),
+            Err(_) => Some(format!("<more than {MAX_AUDIT_BODY_LEN} bytes>")),
+        },
+        status: 0,
+    };
+
+    let response = match body {
+        Ok(body) => next.run(axum::extract::Request::from_parts(parts, axum::body::Body::from(body))).await,
+        Err(_) => error_response(StatusCode::PAYLOAD_TOO_LARGE, "Admin request body is too large").into_response(),
+    };
+    entry.status = response.status().as_u16();
+
+    {
+        let _lock = AUDIT_LOCK.lock();
+        let id = server.db.admin_audit.range(.., true).next().map(|(id, _)| id + 1).unwrap_or_default();
+        server.db.admin_audit.set(id, entry);
+    }
+
+    response
+}
+
+fn redact_body(body: &[u8]) -> String {
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Object(mut fields)) => {
+            for field in REDACTED_FIELDS {
+                if let Some(value) = fields.get_mut(*field) {
+                    *value = "<redacted>".into();
+                }
+            }
+            serde_json::Value::Object(fields).to_string()
+        }
+        _ => String::from_utf8_lossy(body).into_owned(),
+    }
+}
+
+pub async fn audit_log(_: AdminAuth, State(server): State<Arc<Server>>, Query(args): Query<types::AdminAuditArgs>) -> ApiResult<impl IntoApiResponse> {
+    args.validate().bad_request_from_error()?;
+
+    let entries = match args.offset {
+        Some(offset) => server.db.admin_audit.range(..&offset, true),
+        None => server.db.admin_audit.range(.., true),
+    };
+
+    let result = entries
+        .take(args.limit)
+        .map(|(id, entry)| types::AdminAuditEntry {
+            id,
+            time: entry.time,
+            operator: entry.operator,
+            method: entry.method,
+            path: entry.path,
+            query: entry.query,
+            body: entry.body,
+            status: entry.status,
+        })
+        .collect_vec();
+
+    Ok(Json(result))
+}
+
+pub fn audit_log_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Admin requests which change the indexer, newest first. Operators are named by the `X-Admin-Operator` header")
+        .tag("admin")
+}
+
+pub async fn rejected_deploys(
+    _: AdminAuth,
+    State(server): State<Arc<Server>>,
+    Query(args): Query<types::RejectedDeploysArgs>,
+) -> ApiResult<impl IntoApiResponse> {
+    args.validate().bad_request_from_error()?;
+
+    let blocks = match args.offset {
+        Some(offset) => server.db.rejected_deploys.range(..&offset, true),
+        None => server.db.rejected_deploys.range(.., true),
+    };
+
+    let mut result = vec![];
+    for (height, deploys) in blocks {
+        if result.len() >= args.limit {
+            break;
+        }
+
+        result.extend(deploys.into_iter().map(|deploy| types::RejectedDeploy {
+            height,
+            created: deploy.created,
+            tick: deploy.tick.into(),
+            genesis: deploy.genesis.into(),
+            deployer: fullhash_to_address_str(&deploy.deployer, server.db.fullhash_to_address.get(deploy.deployer)),
+            reason: deploy.reason,
+        }));
+    }
+
+    Ok(Json(result))
+}
+
+pub fn rejected_deploys_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Deploys blocked by `TICK_POLICY_FILE`, newest first").tag("admin")
+}
+
+pub async fn request_supply_audit(_: AdminAuth, State(server): State<Arc<Server>>) -> ApiResult<impl IntoApiResponse> {
+    // The indexer takes the checkpoint between blocks
+    if !server.supply_audits.request() {
+        return Err(error_response(StatusCode::CONFLICT, "Another supply audit is pending or running"));
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub fn request_supply_audit_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Queues a supply audit, which recomputes supply, mint count and transfer count of every token from its history. GET lists reports")
+        .tag("admin")
+}
+
+pub async fn supply_audits(_: AdminAuth, State(server): State<Arc<Server>>, Query(args): Query<types::SupplyAuditArgs>) -> ApiResult<impl IntoApiResponse> {
+    args.validate().bad_request_from_error()?;
+
+    let reports = match args.offset {
+        Some(offset) => server.db.supply_audit.range(..&offset, true),
+        None => server.db.supply_audit.range(.., true),
+    };
+
+    let result = reports
+        .take(args.limit)
+        .map(|(id, report)| types::SupplyAudit {
+            id,
+            height: report.height,
+            created: report.created,
+            duration_ms: report.duration_ms,
+            tokens: report.tokens,
+            discrepancies: report
+                .discrepancies
+                .into_iter()
+                .map(|x| types::SupplyDiscrepancy {
+                    tick: x.tick.into(),
+                    stored: x.stored.into(),
+                    computed: x.computed.into(),
+                })
+                .collect(),
+            error: report.error,
+        })
+        .collect_vec();
+
+    Ok(Json(result))
+}
+
+pub fn supply_audits_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Reports of supply audits, newest first").tag("admin")
+}
+
+pub async fn set_token_extras(
+    _: AdminAuth,
+    State(server): State<Arc<Server>>,
+    Path(tick): Path<OriginalTokenTickRest>,
+    Json(extras): Json<serde_json::Value>,
+) -> ApiResult<impl IntoApiResponse> {
+    let lower_case_token_tick: LowerCaseTokenTick = tick.into();
+    server.db.token_to_meta.get(&lower_case_token_tick).not_found(format!("Tick {tick} not found"))?;
+
+    let extras = serde_json::to_string(&extras).internal("Failed to serialize extras")?;
+    if extras.len() > MAX_TOKEN_EXTRAS_LEN {
+        return Err(error_response(StatusCode::BAD_REQUEST, &format!("Extras must not exceed {MAX_TOKEN_EXTRAS_LEN} bytes")));
+    }
+
+    server.db.token_extras.set(lower_case_token_tick, extras);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn set_token_extras_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Attaches off-chain metadata (logo, website, etc.) to the token, returned as `extras` by `/token`")
+        .tag("admin")
+}
+
+pub async fn remove_token_extras(_: AdminAuth, State(server): State<Arc<Server>>, Path(tick): Path<OriginalTokenTickRest>) -> ApiResult<impl IntoApiResponse> {
+    let lower_case_token_tick: LowerCaseTokenTick = tick.into();
+    server.db.token_extras.remove(lower_case_token_tick);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn remove_token_extras_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Removes off-chain metadata of the token").tag("admin")
+}
+
+pub async fn partials_stats(_: AdminAuth, State(server): State<Arc<Server>>) -> ApiResult<impl IntoApiResponse> {
+    let mut stats = types::PartialsStats::default();
+
+    for chunk in &server.db.outpoint_to_partials.iter().chunks(10_000) {
+        let chunk = chunk.collect_vec();
+        let prevouts = server.db.prevouts.multi_get(chunk.iter().map(|(outpoint, _)| outpoint));
+        let heights = server.db.partials_height.multi_get(chunk.iter().map(|(outpoint, _)| outpoint));
+
+        for (((_, partials), prevout), height) in chunk.iter().zip(prevouts).zip(heights) {
+            stats.count += 1;
+            stats.total_bytes += <Partials as rocksdb_wrapper::Pebble>::get_bytes(partials).len() as u64;
+            stats.spent += prevout.is_none() as u64;
+            stats.oldest_height = match (stats.oldest_height, height) {
+                (Some(oldest), Some(height)) => Some(oldest.min(height)),
+                (oldest, height) => oldest.or(height),
+            };
+            stats.unknown_height += height.is_none() as u64;
+        }
+    }
+
+    Ok(Json(stats))
+}
+
+pub fn partials_stats_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Stored partials of multi-part inscriptions, spent ones are removed by `PARTIALS_GC_DEPTH` collection")
+        .tag("admin")
+}
+
+pub async fn simulate_reorg(_: AdminAuth, State(server): State<Arc<Server>>, Query(args): Query<types::SimulateReorgArgs>) -> ApiResult<impl IntoApiResponse> {
+    if !*SIMULATE_REORG {
+        return Err(error_response(StatusCode::NOT_FOUND, "Reorg simulation is disabled"));
+    }
+
+    if args.depth == 0 || args.depth as usize > REORG_CACHE_MAX_LEN {
+        return Err(error_response(StatusCode::BAD_REQUEST, &format!("Depth must be between 1 and {REORG_CACHE_MAX_LEN}")));
+    }
+
+    // The indexer runs it between blocks, requests hold the reorg guard so they can't wait for the rollback
+    if !server.reorg_simulations.request(args.depth) {
+        return Err(error_response(StatusCode::CONFLICT, "Another reorg simulation is pending"));
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub fn simulate_reorg_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Queues a rollback of the last blocks through the reorg path and indexing them again, GET returns the report. Requires `SIMULATE_REORG`")
+        .tag("admin")
+}
+
+pub async fn reorg_simulation(_: AdminAuth, State(server): State<Arc<Server>>) -> ApiResult<impl IntoApiResponse> {
+    if !*SIMULATE_REORG {
+        return Err(error_response(StatusCode::NOT_FOUND, "Reorg simulation is disabled"));
+    }
+
+    let report = server.reorg_simulations.last().not_found("No reorg simulation has finished yet")?;
+
+    Ok(Json(report))
+}
+
+pub fn reorg_simulation_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Report of the last finished reorg simulation").tag("admin")
+}
+
+pub async fn reprocess_block(_: AdminAuth, State(server): State<Arc<Server>>, Path(height): Path<u32>) -> ApiResult<impl IntoApiResponse> {
+    // The indexer runs it between blocks, the route isn't guarded so a rollback never waits for the response
+    let report = server.reprocess_requests.request(height).ok_or_else(|| error_response(StatusCode::CONFLICT, "Another block is being reprocessed"))?;
+    let report = report.await.ok().internal("Indexer stopped before reprocessing the block")?;
+
+    Ok(Json(report))
+}
+
+pub fn reprocess_block_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Indexes a block of the reorg cache again in a sandbox and compares the result with the stored block, nothing is written")
+        .tag("admin")
+}
+
+pub async fn create_webhook(_: AdminAuth, State(server): State<Arc<Server>>, Json(args): Json<types::WebhookArgs>) -> ApiResult<impl IntoApiResponse> {
+    if !args.url.starts_with("http://") && !args.url.starts_with("https://") {
+        return Err(error_response(StatusCode::BAD_REQUEST, "Webhook url must start with http:// or https://"));
+    }
+
+    if args.secret.len() < MIN_WEBHOOK_SECRET_LEN {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("Webhook secret must be at least {MIN_WEBHOOK_SECRET_LEN} characters"),
+        ));
+    }
+
+    let id = server.db.webhooks.range(.., true).next().map(|(id, _)| id + 1).unwrap_or_default();
+
+    server.db.webhooks.set(
+        id,
+        WebhookDB {
+            url: args.url,
+            tick: args.tick.into(),
+            event_types: args.event_types.into_iter().collect(),
+            secret: args.secret,
+        },
+    );
+
+    Ok(Json(types::WebhookCreated { id }))
+}
+
+pub fn create_webhook_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Registers a webhook for events of one token, requests are signed with the secret, see README").tag("admin")
+}
+
+pub async fn webhooks(_: AdminAuth, State(server): State<Arc<Server>>) -> ApiResult<impl IntoApiResponse> {
+    let webhooks = server.db.webhooks.iter().collect_vec();
+    let statuses = server.db.webhook_status.multi_get(webhooks.iter().map(|(id, _)| id));
+
+    let result = webhooks
+        .into_iter()
+        .zip(statuses)
+        .map(|((id, webhook), status)| {
+            let status = status.unwrap_or_default();
+
+            types::Webhook {
+                id,
+                url: webhook.url,
+                tick: webhook.tick.into(),
+                event_types: webhook.event_types,
+                delivered: status.delivered,
+                failed: status.failed,
+                last_attempt: status.last_attempt,
+                last_success: status.last_success,
+                last_error: status.last_error,
+                last_height: status.last_height,
+            }
+        })
+        .collect_vec();
+
+    Ok(Json(result))
+}
+
+pub fn webhooks_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Registered webhooks with their delivery status, secrets aren't returned").tag("admin")
+}
+
+pub async fn remove_webhook(_: AdminAuth, State(server): State<Arc<Server>>, Path(id): Path<u64>) -> ApiResult<impl IntoApiResponse> {
+    server.db.webhooks.get(id).not_found(format!("Webhook {id} not found"))?;
+
+    server.db.webhooks.remove(id);
+    server.db.webhook_status.remove(id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn remove_webhook_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Removes the webhook, a request which is being delivered isn't interrupted").tag("admin")
+}
+
+fn backups_enabled() -> ApiResult<()> {
+    if !Backups::enabled() {
+        return Err(error_response(StatusCode::NOT_FOUND, "Backups are disabled"));
+    }
+
+    Ok(())
+}
+
+pub async fn create_backup(_: AdminAuth, State(server): State<Arc<Server>>) -> ApiResult<impl IntoApiResponse> {
+    backups_enabled()?;
+
+    // The indexer takes the checkpoint between blocks
+    if !server.backups.request() {
+        return Err(error_response(StatusCode::CONFLICT, "Another backup is pending"));
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub fn create_backup_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Queues a RocksDB checkpoint of the database into `BACKUP_DIR`, created by the indexer before the next block. GET lists backups")
+        .tag("admin")
+}
+
+pub async fn backups(_: AdminAuth, State(server): State<Arc<Server>>) -> ApiResult<impl IntoApiResponse> {
+    backups_enabled()?;
+
+    Ok(Json(server.backups.list().ok().internal("Failed to list backups")?))
+}
+
+pub fn backups_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Backups in `BACKUP_DIR`, newest first").tag("admin")
+}
+
+pub async fn remove_backup(_: AdminAuth, State(server): State<Arc<Server>>, Path(name): Path<String>) -> ApiResult<impl IntoApiResponse> {
+    backups_enabled()?;
+
+    let removed = server.backups.remove(&name).ok().internal("Failed to remove the backup")?;
+    if !removed {
+        return Err(error_response(StatusCode::NOT_FOUND, &format!("Backup {name} not found")));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn remove_backup_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Removes the local backup, uploaded copies are kept").tag("admin")
+}
+
+pub async fn prune_backups(_: AdminAuth, State(server): State<Arc<Server>>, Query(args): Query<types::PruneBackupsArgs>) -> ApiResult<impl IntoApiResponse> {
+    backups_enabled()?;
+
+    let removed = server.backups.prune(args.keep).ok().internal("Failed to prune backups")?;
+
+    Ok(Json(types::PrunedBackups { removed }))
+}
+
+pub fn prune_backups_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Removes all local backups but the newest `keep`, including the ones which aren't uploaded yet").tag("admin")
+}
+
+pub async fn promote(_: AdminAuth, State(server): State<Arc<Server>>) -> ApiResult<impl IntoApiResponse> {
+    if !server.standby.promote() {
+        return Err(error_response(StatusCode::CONFLICT, "Indexer isn't a standby"));
+    }
+
+    warn!("Standby is promoted to primary at block {:?}", server.db.last_block.get(()));
+
+    Ok(Json(types::StandbyStatus::new(&server)))
+}
+
+pub fn promote_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Promotes the standby to primary: admin writes are accepted and webhooks and replication start. Unset `STANDBY` before the next restart")
+        .tag("admin")
+}
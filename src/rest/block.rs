@@ -0,0 +1,48 @@
+use super::*;
+
+pub async fn block(State(server): State<Arc<Server>>, Path(block): Path<String>) -> ApiResult<impl IntoApiResponse> {
+    let height = match block.parse::<u32>() {
+        Ok(height) => height,
+        Err(_) => {
+            let hash = BlockHash::from_str(&block).bad_request("Expected block height or hash")?;
+            server.db.block_hash_to_height.get(hash).not_found(format!("Block {block} not found"))?
+        }
+    };
+
+    let info = server.db.block_info.get(height).not_found(format!("Block {height} is not indexed"))?;
+    let summary = server.db.block_summary.get(height);
+    let events = server.db.block_events.get(height).map(|x| x.len()).unwrap_or_default();
+
+    Ok(Json(types::BlockDetails {
+        height,
+        hash: info.hash.to_string(),
+        prev_hash: summary.map(|x| x.prev_hash.to_string()),
+        created: info.created,
+        median_time: server.db.block_median_time.get(height),
+        tx_count: summary.map(|x| x.tx_count),
+        size: summary.map(|x| x.size),
+        events,
+    }))
+}
+
+pub fn block_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Summary of the block by height or hash, with the number of token events in it")
+        .tag("stats")
+}
+
+pub async fn block_leaked(State(server): State<Arc<Server>>, Path(height): Path<u32>) -> ApiResult<impl IntoApiResponse> {
+    let last_height = server.db.last_block.get(()).internal("Failed to get last height")?;
+    (height <= last_height).then_some(()).not_found(format!("Block {height} is not indexed yet"))?;
+
+    let leaked = server.db.block_leaked.get(height).unwrap_or_default();
+    let addresses = server.load_addresses(leaked.iter().map(|x| x.owner)).internal("Failed to load addresses")?;
+
+    let data = leaked.into_iter().map(|x| types::LeakedInscription::new(height, x, &addresses)).collect_vec();
+
+    Ok(Json(data))
+}
+
+pub fn block_leaked_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Inscriptions created or moved into the fees in the block, which the miner received in the coinbase")
+        .tag("stats")
+}
@@ -0,0 +1,37 @@
+use std::io::Write;
+
+use super::*;
+
+/// Chunks of the response body
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// Sends what's written as chunks of the response body, fails once the client is gone
+struct BodyWriter(mpsc::Sender<std::io::Result<Vec<u8>>>);
+
+impl Write for BodyWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.blocking_send(Ok(buf.to_vec())).map_err(|_| std::io::ErrorKind::BrokenPipe)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Dump of the database which fills the empty database of another indexer, see `BOOTSTRAP_PEER_URL`. Served only with `BOOTSTRAP_SERVE`
+pub async fn dump(State(server): State<Arc<Server>>) -> ApiResult<impl IntoResponse> {
+    BOOTSTRAP_SERVE.then_some(()).not_found("Bootstrap isn't served by this indexer")?;
+
+    let (tx, rx) = mpsc::channel(4);
+
+    tokio::task::spawn_blocking(move || {
+        let mut out = std::io::BufWriter::with_capacity(CHUNK_SIZE, BodyWriter(tx.clone()));
+        if let Err(e) = inscriptions::write_dump(&server, &mut out) {
+            warn!("Bootstrap dump failed: {}", e);
+            tx.blocking_send(Err(std::io::Error::other(e.to_string()))).ok();
+        }
+    });
+
+    Ok(axum::body::Body::from_stream(ReceiverStream::new(rx)))
+}
@@ -0,0 +1,27 @@
+use super::*;
+
+pub async fn tx_trace(State(server): State<Arc<Server>>, Path(txid): Path<rest::Txid>, Query(args): Query<types::TxTraceArgs>) -> ApiResult<impl IntoApiResponse> {
+    let height = match args.height {
+        Some(height) => height,
+        None => server
+            .db
+            .outpoint_to_event
+            .range(&bellscoin::OutPoint { txid: *txid, vout: 0 }..&bellscoin::OutPoint { txid: *txid, vout: u32::MAX }, false)
+            .next()
+            .and_then(|(_, key)| server.db.address_token_to_history.get(key))
+            .map(|x| x.height)
+            .not_found("Transaction has no token events, pass the height of its block")?,
+    };
+
+    let block_info = server.db.block_info.get(height).not_found(format!("Block {height} isn't indexed"))?;
+    let block = server.client.try_get_block(&block_info.hash.to_raw_hash()).internal("Node can't return the block")?;
+
+    let trace = crate::inscriptions::trace_tx(&server, height, &block, *txid).bad_request_from_error()?;
+
+    Ok(Json(trace))
+}
+
+pub fn tx_trace_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Replays inscription parsing of a confirmed transaction and explains how its inscriptions were treated, nothing is written")
+        .tag("debug")
+}
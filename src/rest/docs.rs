@@ -2,7 +2,7 @@ use super::*;
 
 pub fn docs_routes(state: Arc<Server>) -> ApiRouter {
     let router: ApiRouter = ApiRouter::new()
-        .route("/", get(Scalar::new("/docs/private/api.json").with_title("OpenAPI").axum_handler()))
+        .route("/", get(Scalar::new("/v1/docs/private/api.json").with_title("OpenAPI").axum_handler()))
         .route("/private/api.json", get(serve_docs))
         .with_state(state);
 
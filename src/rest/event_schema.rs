@@ -0,0 +1,229 @@
+use axum::http::{HeaderMap, HeaderValue};
+
+use super::*;
+
+/// Request header selecting the schema, echoed in the response
+const SCHEMA_HEADER: &str = "X-Event-Schema";
+
+/// Version of the JSON payloads sent by `POST /events` and `/address/{address}/events/stream`.
+/// V1 stays the default until clients had time to move to v2, then v2 becomes the default and v1 is removed after another release.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum EventSchema {
+    /// Token events have no `event_type`, deprecated
+    #[default]
+    V1,
+    /// Every event has `schema` and `event_type`, token events are `token_event`
+    V2,
+}
+
+#[derive(Deserialize)]
+pub struct EventSchemaArgs {
+    /// `v1` or `v2`, takes precedence over the `X-Event-Schema` header
+    schema: Option<String>,
+}
+
+/// Event to send, serialized once the schema of the stream is known
+pub enum StreamEvent {
+    History(types::History),
+    NewBlock { height: u32, proof: sha256::Hash, blockhash: BlockHash },
+    Reorg { blocks_count: u32, new_height: u32 },
+    TokenCompleted { tick: OriginalTokenTickRest, height: u32 },
+}
+
+#[derive(Serialize)]
+struct V2Event<'a> {
+    schema: &'static str,
+    #[serde(flatten)]
+    payload: V2Payload<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+enum V2Payload<'a> {
+    TokenEvent(&'a types::History),
+    NewBlock { height: u32, proof: sha256::Hash, blockhash: BlockHash },
+    Reorg { blocks_count: u32, new_height: u32 },
+    TokenCompleted { tick: OriginalTokenTickRest, height: u32 },
+}
+
+impl EventSchema {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "v1" => Some(Self::V1),
+            "v2" => Some(Self::V2),
+            _ => None,
+        }
+    }
+
+    /// `?schema=` first, then the `X-Event-Schema` header, v1 if neither is set
+    pub fn negotiate(args: EventSchemaArgs, headers: &HeaderMap) -> ApiResult<Self> {
+        let requested = match args.schema {
+            Some(schema) => Some(schema),
+            None => match headers.get(SCHEMA_HEADER) {
+                Some(value) => Some(value.to_str().ok().bad_request("Invalid X-Event-Schema header")?.to_string()),
+                None => None,
+            },
+        };
+
+        match requested {
+            Some(schema) => Self::parse(&schema).bad_request("Unknown event schema, expected v1 or v2"),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Schema of the stream, v1 streams are also marked as deprecated
+    pub fn response_headers(self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(SCHEMA_HEADER, HeaderValue::from_static(self.as_str()));
+        if self == Self::V1 {
+            headers.insert("Deprecation", HeaderValue::from_static("true"));
+        }
+
+        headers
+    }
+
+    pub fn serialize(self, event: &StreamEvent) -> serde_json::Result<String> {
+        match self {
+            Self::V1 => Self::serialize_v1(event),
+            Self::V2 => Self::serialize_v2(event),
+        }
+    }
+
+    /// Output of the indexer before schemas were introduced, must not change
+    fn serialize_v1(event: &StreamEvent) -> serde_json::Result<String> {
+        match event {
+            StreamEvent::History(history) => serde_json::to_string(history),
+            StreamEvent::NewBlock { height, proof, blockhash } => serde_json::to_string(&types::NewBlock {
+                event_type: "new_block".to_string(),
+                height: *height,
+                proof: *proof,
+                blockhash: *blockhash,
+            }),
+            StreamEvent::Reorg { blocks_count, new_height } => serde_json::to_string(&types::Reorg {
+                event_type: "reorg".to_string(),
+                blocks_count: *blocks_count,
+                new_height: *new_height,
+            }),
+            StreamEvent::TokenCompleted { tick, height } => serde_json::to_string(&types::TokenCompleted {
+                event_type: "token_completed".to_string(),
+                tick: *tick,
+                height: *height,
+            }),
+        }
+    }
+
+    fn serialize_v2(event: &StreamEvent) -> serde_json::Result<String> {
+        let payload = match event {
+            StreamEvent::History(history) => V2Payload::TokenEvent(history),
+            StreamEvent::NewBlock { height, proof, blockhash } => V2Payload::NewBlock {
+                height: *height,
+                proof: *proof,
+                blockhash: *blockhash,
+            },
+            StreamEvent::Reorg { blocks_count, new_height } => V2Payload::Reorg {
+                blocks_count: *blocks_count,
+                new_height: *new_height,
+            },
+            StreamEvent::TokenCompleted { tick, height } => V2Payload::TokenCompleted { tick: *tick, height: *height },
+        };
+
+        serde_json::to_string(&V2Event {
+            schema: Self::V2.as_str(),
+            payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick() -> OriginalTokenTickRest {
+        serde_json::from_str("\"abcd\"").unwrap()
+    }
+
+    fn mint() -> StreamEvent {
+        StreamEvent::History(types::History {
+            address_token: types::AddressTokenId {
+                id: 1,
+                address: "addr".to_string(),
+                tick: tick(),
+            },
+            height: 10,
+            action: types::TokenAction::Mint {
+                amt: Fixed128::from(1000),
+                txid: Txid::all_zeros(),
+                vout: 0,
+            },
+            origin: None,
+        })
+    }
+
+    fn zeros() -> String {
+        "0".repeat(64)
+    }
+
+    #[test]
+    fn v1_is_unchanged() {
+        // Amounts are formatted by Fixed128 itself, everything around them is pinned
+        let amt = serde_json::to_string(&Fixed128::from(1000)).unwrap();
+        let zeros = zeros();
+
+        assert_eq!(
+            EventSchema::V1.serialize(&mint()).unwrap(),
+            format!(r#"{{"id":1,"address":"addr","tick":"abcd","height":10,"type":"Mint","amt":{amt},"txid":"{zeros}","vout":0}}"#)
+        );
+
+        let new_block = StreamEvent::NewBlock {
+            height: 10,
+            proof: sha256::Hash::all_zeros(),
+            blockhash: BlockHash::all_zeros(),
+        };
+        assert_eq!(
+            EventSchema::V1.serialize(&new_block).unwrap(),
+            format!(r#"{{"event_type":"new_block","height":10,"proof":"{zeros}","blockhash":"{zeros}"}}"#)
+        );
+
+        let reorg = StreamEvent::Reorg { blocks_count: 2, new_height: 9 };
+        assert_eq!(EventSchema::V1.serialize(&reorg).unwrap(), r#"{"event_type":"reorg","blocks_count":2,"new_height":9}"#);
+
+        let completed = StreamEvent::TokenCompleted { tick: tick(), height: 10 };
+        assert_eq!(EventSchema::V1.serialize(&completed).unwrap(), r#"{"event_type":"token_completed","tick":"abcd","height":10}"#);
+    }
+
+    #[test]
+    fn v2_tags_every_event() {
+        let amt = serde_json::to_string(&Fixed128::from(1000)).unwrap();
+        let zeros = zeros();
+
+        assert_eq!(
+            EventSchema::V2.serialize(&mint()).unwrap(),
+            format!(r#"{{"schema":"v2","event_type":"token_event","id":1,"address":"addr","tick":"abcd","height":10,"type":"Mint","amt":{amt},"txid":"{zeros}","vout":0}}"#)
+        );
+
+        let reorg = StreamEvent::Reorg { blocks_count: 2, new_height: 9 };
+        assert_eq!(EventSchema::V2.serialize(&reorg).unwrap(), r#"{"schema":"v2","event_type":"reorg","blocks_count":2,"new_height":9}"#);
+    }
+
+    #[test]
+    fn negotiation() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(EventSchema::negotiate(EventSchemaArgs { schema: None }, &headers).unwrap(), EventSchema::V1);
+
+        headers.insert(SCHEMA_HEADER, HeaderValue::from_static("V2"));
+        assert_eq!(EventSchema::negotiate(EventSchemaArgs { schema: None }, &headers).unwrap(), EventSchema::V2);
+
+        let args = EventSchemaArgs { schema: Some("v1".to_string()) };
+        assert_eq!(EventSchema::negotiate(args, &headers).unwrap(), EventSchema::V1);
+
+        let args = EventSchemaArgs { schema: Some("v3".to_string()) };
+        assert!(EventSchema::negotiate(args, &headers).is_err());
+    }
+}
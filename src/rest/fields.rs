@@ -0,0 +1,82 @@
+use axum::http::StatusCode;
+
+use super::*;
+
+/// `?fields=` of endpoints with large payloads, see [`Projected`]
+#[derive(Deserialize, Default, schemars::JsonSchema)]
+pub struct FieldsArgs {
+    /// Comma-separated fields to return, e.g. `total,history.id,history.amt`.
+    /// Nested fields are separated by dots and apply to every item of arrays, all fields are returned if not set
+    #[serde(default)]
+    pub fields: Option<String>,
+}
+
+/// Response serialized with only the fields selected by [`FieldsArgs`]
+pub struct Projected<T>(pub T, pub FieldsArgs);
+
+impl<T: Serialize> IntoResponse for Projected<T> {
+    fn into_response(self) -> axum::response::Response {
+        let Some(tree) = self.1.fields.as_deref().map(FieldTree::parse).filter(|x| !x.0.is_empty()) else {
+            return Json(self.0).into_response();
+        };
+
+        match serde_json::to_value(&self.0) {
+            Ok(mut value) => {
+                tree.apply(&mut value);
+                Json(value).into_response()
+            }
+            Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, INTERNAL).into_response(),
+        }
+    }
+}
+
+impl<T: schemars::JsonSchema> aide::OperationOutput for Projected<T> {
+    type Inner = T;
+
+    fn operation_response(ctx: &mut aide::generate::GenContext, operation: &mut aide::openapi::Operation) -> Option<aide::openapi::Response> {
+        <Json<T> as aide::OperationOutput>::operation_response(ctx, operation)
+    }
+
+    fn inferred_responses(
+        ctx: &mut aide::generate::GenContext,
+        operation: &mut aide::openapi::Operation,
+    ) -> Vec<(Option<aide::openapi::StatusCode>, aide::openapi::Response)> {
+        <Json<T> as aide::OperationOutput>::inferred_responses(ctx, operation)
+    }
+}
+
+/// Selected fields by name, a field without nested ones is returned as is
+#[derive(Default)]
+struct FieldTree(BTreeMap<String, FieldTree>);
+
+impl FieldTree {
+    fn parse(fields: &str) -> Self {
+        let mut root = Self::default();
+
+        for path in fields.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            let mut node = &mut root;
+            for name in path.split('.') {
+                node = node.0.entry(name.to_string()).or_default();
+            }
+        }
+
+        root
+    }
+
+    fn apply(&self, value: &mut serde_json::Value) {
+        if self.0.is_empty() {
+            return;
+        }
+
+        match value {
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|x| self.apply(x)),
+            serde_json::Value::Object(map) => {
+                map.retain(|k, _| self.0.contains_key(k));
+                for (k, v) in map.iter_mut() {
+                    self.0[k].apply(v);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,13 +1,41 @@
-use nint_blk::ScriptType;
+use axum::http::HeaderMap;
+use super::event_schema::{EventSchema, EventSchemaArgs, StreamEvent};
 
 use super::*;
 
-pub async fn subscribe(State(server): State<Arc<Server>>, Json(payload): Json<types::SubscribeArgs>) -> ApiResult<impl IntoResponse> {
-    let (tx, rx) = mpsc::channel::<Result<Event, std::convert::Infallible>>(200_000);
+pub async fn subscribe(
+    State(server): State<Arc<Server>>,
+    Query(schema): Query<EventSchemaArgs>,
+    headers: HeaderMap,
+    Json(payload): Json<types::SubscribeArgs>,
+) -> ApiResult<impl IntoResponse> {
+    let schema = EventSchema::negotiate(schema, &headers)?;
+    payload.check_min_amounts().bad_request_from_error()?;
+
+    Ok((schema.response_headers(), stream_events(server, EventFilter::new(payload), schema)))
+}
+
+/// Token events of the address only, without the subscribe protocol of `/events`. Reorgs are still sent since they invalidate the received events.
+pub async fn address_events_stream(
+    State(server): State<Arc<Server>>,
+    Path(address): Path<String>,
+    Query(schema): Query<EventSchemaArgs>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    let schema = EventSchema::negotiate(schema, &headers)?;
+    server.indexer.to_scripthash(&address, nint_blk::ScriptType::Address).bad_request_from_error()?;
+
+    let filter = EventFilter {
+        addresses: HashSet::from([address]),
+        token_events_only: true,
+        ..Default::default()
+    };
 
-    let addresses = payload.addresses.unwrap_or_default();
+    Ok((schema.response_headers(), stream_events(server, filter, schema)))
+}
 
-    let tokens = payload.tokens.unwrap_or_default().into_iter().map(LowerCaseTokenTick::from).collect::<HashSet<_>>();
+fn stream_events(server: Arc<Server>, filter: EventFilter, schema: EventSchema) -> Sse<ReceiverStream<Result<Event, std::convert::Infallible>>> {
+    let (tx, rx) = mpsc::channel::<Result<Event, std::convert::Infallible>>(200_000);
 
     {
         let mut rx = server.event_sender.subscribe();
@@ -16,59 +44,40 @@ pub async fn subscribe(State(server): State<Arc<Server>>, Json(payload): Json<ty
             while !server.token.is_cancelled() {
                 match rx.try_recv() {
                     Ok(event) => {
-                        match event {
+                        let event = match event {
                             ServerEvent::NewHistory(address_token, action) => {
-                                if !addresses.is_empty() && !addresses.contains(&address_token.address) {
+                                if !filter.matches(&address_token, &action.action) {
                                     continue;
                                 }
 
-                                if !tokens.is_empty() && !tokens.contains(&address_token.token.into()) {
+                                StreamEvent::History(types::History {
+                                    address_token: address_token.into(),
+                                    height: action.height,
+                                    action: action.into(),
+                                    origin: None,
+                                })
+                            }
+                            ServerEvent::Reorg(blocks_count, new_height) => StreamEvent::Reorg { blocks_count, new_height },
+                            ServerEvent::TokenCompleted(tick, height) => {
+                                if filter.token_events_only || (!filter.tokens.is_empty() && !filter.tokens.contains(&LowerCaseTokenTick::from(tick))) {
                                     continue;
                                 }
 
-                                let data = Event::default().data(
-                                    serde_json::to_string(&types::History {
-                                        address_token: address_token.into(),
-                                        height: action.height,
-                                        action: action.into(),
-                                    })
-                                    .unwrap(),
-                                );
-
-                                if tx.send(Ok(data)).await.is_err() {
-                                    break;
-                                };
-                            }
-                            ServerEvent::Reorg(blocks_count, new_height) => {
-                                let data = Event::default().data(
-                                    serde_json::to_string(&types::Reorg {
-                                        event_type: "reorg".to_string(),
-                                        blocks_count,
-                                        new_height,
-                                    })
-                                    .unwrap(),
-                                );
-
-                                if tx.send(Ok(data)).await.is_err() {
-                                    break;
-                                };
+                                StreamEvent::TokenCompleted { tick: tick.into(), height }
                             }
                             ServerEvent::NewBlock(height, poh, blockhash) => {
-                                let data = Event::default().data(
-                                    serde_json::to_string(&types::NewBlock {
-                                        event_type: "new_block".to_string(),
-                                        height,
-                                        proof: poh,
-                                        blockhash,
-                                    })
-                                    .unwrap(),
-                                );
-
-                                if tx.send(Ok(data)).await.is_err() {
-                                    break;
-                                };
+                                if filter.token_events_only {
+                                    continue;
+                                }
+
+                                StreamEvent::NewBlock { height, proof: poh, blockhash }
                             }
                         };
+
+                        let data = Event::default().data(schema.serialize(&event).unwrap());
+                        if tx.send(Ok(data)).await.is_err() {
+                            break;
+                        };
                     }
                     Err(tokio::sync::broadcast::error::TryRecvError::Lagged(count)) => {
                         error!("Lagged {} events. Disconnecting...", count);
@@ -85,18 +94,112 @@ pub async fn subscribe(State(server): State<Arc<Server>>, Json(payload): Json<ty
         });
     }
 
-    let stream = ReceiverStream::new(rx);
-    Ok(Sse::new(stream))
+    Sse::new(ReceiverStream::new(rx))
+}
+
+/// Subscription filters, checked before the event is serialized
+#[derive(Default)]
+struct EventFilter {
+    addresses: HashSet<String>,
+    tokens: HashSet<LowerCaseTokenTick>,
+    event_types: HashSet<types::TokenEventType>,
+    min_amount: Option<Fixed128>,
+    /// Thresholds of the (address, token) pairs, then of the tokens, then of the addresses, the first one found is applied
+    address_token_min_amounts: HashMap<String, HashMap<LowerCaseTokenTick, Fixed128>>,
+    token_min_amounts: HashMap<LowerCaseTokenTick, Fixed128>,
+    address_min_amounts: HashMap<String, Fixed128>,
+    mint_tokens: HashSet<LowerCaseTokenTick>,
+    /// Skip new block and token completion events
+    token_events_only: bool,
+}
+
+impl EventFilter {
+    fn new(args: types::SubscribeArgs) -> Self {
+        let mut filter = Self {
+            addresses: args.addresses.unwrap_or_default(),
+            tokens: args.tokens.unwrap_or_default().into_iter().map(LowerCaseTokenTick::from).collect(),
+            event_types: args.event_types.unwrap_or_default(),
+            min_amount: args.min_amount,
+            mint_tokens: args.mint_tokens.unwrap_or_default().into_iter().map(LowerCaseTokenTick::from).collect(),
+            ..Default::default()
+        };
+
+        for threshold in args.min_amounts.unwrap_or_default() {
+            match (threshold.address, threshold.tick.map(LowerCaseTokenTick::from)) {
+                (Some(address), Some(tick)) => {
+                    filter.address_token_min_amounts.entry(address).or_default().insert(tick, threshold.min_amount);
+                }
+                (None, Some(tick)) => {
+                    filter.token_min_amounts.insert(tick, threshold.min_amount);
+                }
+                (Some(address), None) => {
+                    filter.address_min_amounts.insert(address, threshold.min_amount);
+                }
+                (None, None) => {}
+            }
+        }
+
+        filter
+    }
+
+    fn min_amount(&self, address: &str, token: &LowerCaseTokenTick) -> Option<Fixed128> {
+        self.address_token_min_amounts
+            .get(address)
+            .and_then(|x| x.get(token))
+            .or_else(|| self.token_min_amounts.get(token))
+            .or_else(|| self.address_min_amounts.get(address))
+            .copied()
+            .or(self.min_amount)
+    }
+
+    fn matches(&self, address_token: &server::AddressTokenIdEvent, action: &server::TokenHistoryEvent) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&address_token.address) {
+            return false;
+        }
+
+        let token = LowerCaseTokenTick::from(address_token.token);
+        if !self.tokens.is_empty() && !self.tokens.contains(&token) {
+            return false;
+        }
+
+        if !self.event_types.is_empty() && !self.event_types.contains(&action.into()) {
+            return false;
+        }
+
+        if let (Some(min_amount), Some(amount)) = (self.min_amount(&address_token.address, &token), action.amount()) {
+            if amount < min_amount {
+                return false;
+            }
+        }
+
+        if !self.mint_tokens.is_empty() && matches!(action, server::TokenHistoryEvent::Mint { .. }) && !self.mint_tokens.contains(&token) {
+            return false;
+        }
+
+        true
+    }
 }
 
 pub async fn address_token_history(
+    url: Uri,
     State(server): State<Arc<Server>>,
     Path(script_str): Path<String>,
     Query(query): Query<types::AddressTokenHistoryArgs>,
+    Query(fields): Query<FieldsArgs>,
 ) -> ApiResult<impl IntoApiResponse> {
-    query.validate().bad_request_from_error()?;
+    let script_type = url.path().split('/').nth(1).internal(INTERNAL)?;
+    let scripthash: FullHash = server
+        .indexer
+        .to_scripthash(&script_str, script_type.parse().bad_request("Invalid script type")?)
+        .bad_request_from_error()?
+        .into();
+
+    Ok(Projected(address_history_page(&server, scripthash, query)?, fields))
+}
 
-    let scripthash: FullHash = server.indexer.to_scripthash(&script_str, ScriptType::Address).bad_request_from_error()?.into();
+/// Shared by `GET /address/{address}/history` and the `get_history` RPC method
+pub(super) fn address_history_page(server: &Server, scripthash: FullHash, query: types::AddressTokenHistoryArgs) -> ApiResult<types::AddressHistoryPage> {
+    query.validate().bad_request_from_error()?;
 
     let token: LowerCaseTokenTick = query.tick.into();
 
@@ -104,47 +207,83 @@ pub async fn address_token_history(
 
     let token = deploy_proto.proto.tick;
 
+    server.access_stats.hit(token);
+
+    let stats = server.db.address_token_history_stats.get(AddressToken { address: scripthash, token });
+    let mut page = types::AddressHistoryPage {
+        total: stats.map(|x| x.count).unwrap_or_default(),
+        first_height: stats.map(|x| x.first_height),
+        last_height: stats.map(|x| x.last_height),
+        history: vec![],
+    };
+
+    let ids = server.db.history_ids_by_time(query.from_ts, query.to_ts);
+    let to_id = query.offset.unwrap_or(u64::MAX).min(ids.end);
+    if ids.start >= to_id {
+        return Ok(page);
+    }
+
     let from = AddressTokenIdDB {
         address: scripthash,
-        id: 0,
+        id: ids.start,
         token,
     };
 
     let to = AddressTokenIdDB {
         address: scripthash,
-        id: query.offset.unwrap_or(u64::MAX),
+        id: to_id,
         token,
     };
 
-    let res = server
-        .db
-        .address_token_to_history
-        .range(&from..&to, true)
-        .take(query.limit)
-        .map(|(k, v)| types::AddressHistory::new(v.height, v.action, k, &server))
-        .collect::<anyhow::Result<Vec<_>>>()
-        .internal("Failed to load addresses")?;
-
-    Ok(Json(res))
+    // Events below the compacted ones are returned as their checkpoints, except for the deploy, which is the first event of the deployer
+    let compacted = AddressTokenIdDB {
+        id: server.db.first_uncompacted_id().clamp(from.id, to.id),
+        ..from
+    };
+    let mut rows = server.db.address_token_to_history.range(&compacted..&to, true).take(query.limit).collect_vec();
+    rows.extend(
+        server
+            .db
+            .address_token_to_history
+            .range(&from..&compacted, false)
+            .next()
+            .filter(|(_, value)| matches!(value.action, TokenHistoryDB::Deploy { .. })),
+    );
+    let checkpoints = server.db.address_token_to_checkpoint.range(&from..&compacted, true).take(query.limit).collect_vec();
+
+    let mut res = types::AddressHistory::new_page(rows, server).internal("Failed to load addresses")?;
+    res.extend(types::AddressHistory::new_checkpoints_page(checkpoints, server).internal("Failed to load addresses")?);
+
+    // Checkpoints share the id space with the events they replace
+    res.sort_unstable_by_key(|x| std::cmp::Reverse(x.history.address_token.id));
+    res.truncate(query.limit);
+    page.history = res;
+
+    Ok(page)
 }
 
 pub fn address_token_history_docs(op: TransformOperation) -> TransformOperation {
-    op.description("A list of token history for the address").tag("address")
+    op.description("A page of token history for the address with the total number of its events").tag("address")
 }
 
-pub async fn events_by_height(State(server): State<Arc<Server>>, Path(height): Path<u32>) -> ApiResult<impl IntoApiResponse> {
+pub async fn events_by_height(
+    State(server): State<Arc<Server>>,
+    Path(height): Path<u32>,
+    Query(fields): Query<FieldsArgs>,
+) -> ApiResult<impl IntoApiResponse> {
     let keys = server.db.block_events.get(height).unwrap_or_default();
 
-    let res = server
+    let rows = server
         .db
         .address_token_to_history
         .multi_get_kv(keys.iter(), true)
         .into_iter()
-        .map(|(k, v)| types::History::new(v.height, v.action, *k, &server))
-        .collect::<anyhow::Result<Vec<_>>>()
-        .internal("Failed to load addresses")?;
+        .map(|(k, v)| (*k, v))
+        .collect_vec();
 
-    Ok(Json(res))
+    let res = types::History::new_page(rows, &server).internal("Failed to load addresses")?;
+
+    Ok(Projected(res, fields))
 }
 
 pub fn events_by_height_docs(op: TransformOperation) -> TransformOperation {
@@ -154,14 +293,38 @@ pub fn events_by_height_docs(op: TransformOperation) -> TransformOperation {
 pub async fn proof_of_history(State(server): State<Arc<Server>>, Query(query): Query<types::ProofHistoryArgs>) -> ApiResult<impl IntoApiResponse> {
     query.validate().bad_request_from_error()?;
 
-    let res = server
-        .db
-        .proof_of_history
-        .range(..&query.offset.unwrap_or(u32::MAX), true)
-        .map(|(height, hash)| types::ProofOfHistory { hash: hash.to_string(), height })
-        .take(query.limit)
+    let rows = server.db.proof_of_history.range(..&query.offset.unwrap_or(u32::MAX), true).take(query.limit).collect_vec();
+    let versions = server.db.proof_of_history_version.multi_get(rows.iter().map(|(height, _)| height));
+    let balance_roots = server.db.balance_root.multi_get(rows.iter().map(|(height, _)| height));
+
+    let mut res = rows
+        .iter()
+        .zip(versions)
+        .zip(balance_roots)
+        .map(|(((height, hash), version), balance_root)| types::ProofOfHistory {
+            hash: hash.to_string(),
+            height: *height,
+            version: PohVersion::from_db(version) as u8,
+            balance_root: balance_root.map(|x| x.to_string()),
+            prev_proof: None,
+            history_hash: None,
+        })
         .collect_vec();
 
+    if query.links {
+        let prev_heights = rows.iter().map(|(height, _)| height.checked_sub(1)).collect_vec();
+        let prev_proofs = server.db.proof_of_history.multi_get(prev_heights.iter().flatten());
+        let mut prev_proofs = prev_proofs.into_iter();
+        let events_hashes = server.db.proof_of_history_events_hash.multi_get(rows.iter().map(|(height, _)| height));
+
+        for ((proof, prev_height), events_hash) in res.iter_mut().zip(prev_heights).zip(events_hashes) {
+            // The first block with a proof is linked to sha256("null"), the same way it was indexed
+            let prev_proof = prev_height.and_then(|_| prev_proofs.next().flatten()).unwrap_or(*DEFAULT_HASH);
+            proof.prev_proof = Some(prev_proof.to_string());
+            proof.history_hash = events_hash.map(|x| x.to_string());
+        }
+    }
+
     Ok(Json(res))
 }
 
@@ -169,6 +332,60 @@ pub fn proof_of_history_docs(op: TransformOperation) -> TransformOperation {
     op.description("Proof of history of the blocks").tag("status")
 }
 
+pub async fn state_digest(State(server): State<Arc<Server>>, Path(height): Path<u32>) -> ApiResult<impl IntoApiResponse> {
+    STATE_DIGEST_INTERVAL.is_some().then_some(()).not_found("State digests are not enabled on this indexer")?;
+
+    let digest = server
+        .db
+        .state_digest
+        .get(height)
+        .not_found(format!("No state digest at height {height}, digests are taken every {} blocks", STATE_DIGEST_INTERVAL.unwrap_or_default()))?;
+
+    Ok(Json(types::StateDigest {
+        height,
+        digest: sha256::Hash::from_byte_array(digest.digest).to_string(),
+        accounts: digest.accounts,
+        tokens: digest.tokens,
+    }))
+}
+
+pub fn state_digest_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Digest of all balances and tokens at the end of the block, taken every `STATE_DIGEST_INTERVAL` blocks, to compare indexers")
+        .tag("status")
+}
+
+pub async fn proof_of_history_spec() -> ApiResult<impl IntoApiResponse> {
+    Ok(Json(types::ProofOfHistorySpec {
+        v2_height: *POH_V2_HEIGHT,
+        block_proof: poh::SPEC_BLOCK_PROOF.to_string(),
+        versions: vec![
+            types::PohVersionSpec {
+                version: PohVersion::V1 as u8,
+                encoding: poh::SPEC_V1.to_string(),
+                fields: vec![],
+            },
+            types::PohVersionSpec {
+                version: PohVersion::V2 as u8,
+                encoding: poh::SPEC_V2_STRING.to_string(),
+                fields: poh::SPEC_V2_EVENT
+                    .iter()
+                    .map(|(name, encoding)| types::PohFieldSpec {
+                        name: name.to_string(),
+                        encoding: encoding.to_string(),
+                    })
+                    .collect(),
+            },
+        ],
+        balance_tree: poh::SPEC_BALANCE_TREE.to_string(),
+        state_digest: poh::SPEC_STATE_DIGEST.to_string(),
+    }))
+}
+
+pub fn proof_of_history_spec_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Encodings of the block events hashed into the proof of history, to verify proofs independently")
+        .tag("status")
+}
+
 pub async fn txid_events(State(server): State<Arc<Server>>, Path(txid): Path<rest::Txid>) -> ApiResult<impl IntoApiResponse> {
     let keys = server
         .db
@@ -177,14 +394,15 @@ pub async fn txid_events(State(server): State<Arc<Server>>, Path(txid): Path<res
         .map(|(_, v)| v)
         .collect_vec();
 
-    let mut events = server
+    let rows = server
         .db
         .address_token_to_history
         .multi_get_kv(keys.iter(), false)
         .into_iter()
-        .map(|(k, v)| types::History::new(v.height, v.action, *k, &server))
-        .collect::<anyhow::Result<Vec<_>>>()
-        .internal("Failed to load addresses")?;
+        .map(|(k, v)| (*k, v))
+        .collect_vec();
+
+    let mut events = types::History::new_page(rows, &server).internal("Failed to load addresses")?;
 
     events.sort_unstable_by_key(|x| x.address_token.id);
 
@@ -194,3 +412,58 @@ pub async fn txid_events(State(server): State<Arc<Server>>, Path(txid): Path<res
 pub fn txid_events_docs(op: TransformOperation) -> TransformOperation {
     op.description("A list of events by txid").tag("event")
 }
+
+pub async fn txid_batch_summary(State(server): State<Arc<Server>>, Path(txid): Path<rest::Txid>) -> ApiResult<impl IntoApiResponse> {
+    let keys = server
+        .db
+        .outpoint_to_event
+        .range(&bellscoin::OutPoint { txid: *txid, vout: 0 }..&bellscoin::OutPoint { txid: *txid, vout: u32::MAX }, false)
+        .map(|(_, v)| v)
+        .collect_vec();
+
+    // Send and Receive of the same transfer share the outpoint, so every transfer is counted once
+    let transfers = server
+        .db
+        .address_token_to_history
+        .multi_get_kv(keys.iter(), false)
+        .into_iter()
+        .filter_map(|(k, v)| match v.action {
+            TokenHistoryDB::Send { amt, recipient, .. } => Some((k.token, k.address, recipient, amt)),
+            TokenHistoryDB::Receive { amt, sender, .. } => Some((k.token, sender, k.address, amt)),
+            TokenHistoryDB::SendReceive { amt, .. } => Some((k.token, k.address, k.address, amt)),
+            _ => None,
+        })
+        .collect_vec();
+
+    (!transfers.is_empty()).then_some(()).not_found("No transfers found in the transaction")?;
+
+    let addresses = server
+        .load_addresses(transfers.iter().flat_map(|(_, sender, recipient, _)| [*sender, *recipient]))
+        .internal("Failed to load addresses")?;
+
+    let mut recipients = BTreeMap::<(OriginalTokenTick, FullHash, FullHash), (Fixed128, usize)>::new();
+    for (tick, sender, recipient, amt) in &transfers {
+        let entry = recipients.entry((*tick, *sender, *recipient)).or_default();
+        entry.0 += *amt;
+        entry.1 += 1;
+    }
+
+    Ok(Json(types::BatchSummary {
+        txid: txid.to_string(),
+        transfers: transfers.len(),
+        recipients: recipients
+            .into_iter()
+            .map(|((tick, sender, recipient), (amount, transfers))| types::BatchRecipient {
+                tick: tick.into(),
+                sender: addresses.get(&sender),
+                recipient: addresses.get(&recipient),
+                amount,
+                transfers,
+            })
+            .collect(),
+    }))
+}
+
+pub fn txid_batch_summary_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Transfers moved by the transaction aggregated per recipient").tag("event")
+}
@@ -1,39 +1,80 @@
 use super::*;
 
-pub async fn holders(State(server): State<Arc<Server>>, Query(query): Query<types::HoldersArgs>) -> ApiResult<impl IntoApiResponse> {
+pub async fn holders(State(server): State<Arc<Server>>, Query(query): Query<types::HoldersArgs>, Query(fields): Query<FieldsArgs>) -> ApiResult<impl IntoApiResponse> {
+    Ok(Projected(holders_page(&server, query)?, fields))
+}
+
+/// Shared by `GET /holders` and the `get_holders` RPC method
+pub(super) fn holders_page(server: &Server, query: types::HoldersArgs) -> ApiResult<types::Holders> {
     query.validate().bad_request_from_error()?;
 
     let tick: LowerCaseTokenTick = query.tick.into();
     let proto = server.db.token_to_meta.get(&tick).map(|x| x.proto).not_found("Tick not found")?;
 
-    let result = if let Some(data) = server.holders.get_holders(&proto.tick) {
-        let count = data.len();
-        let pages = count.div_ceil(query.page_size);
+    server.access_stats.hit(proto.tick);
+
+    let last_block = server.db.last_block.get(()).unwrap_or_default();
+    let (data, supply) = match query.height.filter(|height| *height < last_block) {
+        Some(height) => {
+            let oldest = server.db.holders_snapshot_ticks.range(.., false).next().map(|(height, _)| height);
+            let data = server
+                .db
+                .holders_at(proto.tick, height)
+                .not_found(match oldest {
+                    Some(oldest) => format!("No holders snapshot at or below height {height}, the oldest one is at {oldest}"),
+                    None => "No holders snapshots yet".to_string(),
+                })?;
+            let supply = data.iter().fold(Fixed128::ZERO, |acc, x| acc + x.0);
+
+            (Some(Arc::new(data)), supply)
+        }
+        None => (server.holders.get_holders(&proto.tick), proto.supply),
+    };
+
+    let holders = if let Some(data) = data.filter(|data| !data.is_empty()) {
+        let filtered = query.min_balance.is_some() || query.address.is_some();
+        let skip = (query.page - 1) * query.page_size;
+        let mut matched = 0;
         let mut holders = Vec::with_capacity(query.page_size);
-        let max_percent = data.last().map(|x| x.0 / proto.supply * Fixed128::from(100)).unwrap_or_default();
+        let max_percent = data.last().map(|x| x.0 / supply * Fixed128::from(100)).unwrap_or_default();
+
+        // Ranks are among all holders, so a filtered holder keeps its place
+        for (rank, SortedByBalance(balance, hash)) in data.iter().rev().enumerate() {
+            // Holders are in descending order of balance
+            if query.min_balance.is_some_and(|min_balance| *balance < min_balance) {
+                break;
+            }
+            if holders.len() == query.page_size && !filtered {
+                break;
+            }
 
-        let keys = data
-            .iter()
-            .rev()
-            .enumerate()
-            .skip((query.page - 1) * query.page_size)
-            .take(query.page_size)
-            .map(|(rank, x)| (rank + 1, x.0, x.1));
+            // Addresses are resolved only for the returned holders unless they're searched
+            let mut address = None;
+            if let Some(search) = &query.address {
+                let resolved = fullhash_to_address_str(hash, server.db.fullhash_to_address.get(hash));
+                if !resolved.starts_with(search.as_str()) {
+                    continue;
+                }
+                address = Some(resolved);
+            }
 
-        for (rank, balance, hash) in keys {
-            let address = fullhash_to_address_str(&hash, server.db.fullhash_to_address.get(hash));
-            let percent = balance / proto.supply * Fixed128::from(100);
+            matched += 1;
+            if matched <= skip || holders.len() == query.page_size {
+                continue;
+            }
 
+            let percent = *balance / supply * Fixed128::from(100);
             holders.push(types::Holder {
-                rank,
-                address,
+                rank: rank + 1,
+                address: address.unwrap_or_else(|| fullhash_to_address_str(hash, server.db.fullhash_to_address.get(hash))),
                 balance: balance.to_string(),
                 percent: percent.to_string(),
             })
         }
 
+        let count = if filtered { matched } else { data.len() };
         types::Holders {
-            pages,
+            pages: count.div_ceil(query.page_size),
             count,
             max_percent: max_percent.to_string(),
             holders,
@@ -42,7 +83,7 @@ pub async fn holders(State(server): State<Arc<Server>>, Query(query): Query<type
         types::Holders::default()
     };
 
-    Ok(Json(result))
+    Ok(holders)
 }
 
 pub fn holders_docs(op: TransformOperation) -> TransformOperation {
@@ -53,7 +94,30 @@ pub async fn holders_stats(State(server): State<Arc<Server>>, Query(query): Quer
     let tick: LowerCaseTokenTick = query.tick.into();
     let proto = server.db.token_to_meta.get(&tick).map(|x| x.proto).not_found("Tick not found")?;
 
-    let result = if let Some(data) = server.holders.get_holders(&proto.tick) {
+    server.access_stats.hit(proto.tick);
+
+    let data = server.holders.get_holders(&proto.tick);
+    let shares = holders_shares(data.as_deref(), proto.supply);
+
+    if !query.distribution {
+        return Ok(Json(types::HoldersStats::Shares(shares)));
+    }
+
+    let distribution = match data.filter(|data| !data.is_empty() && !proto.supply.is_zero()) {
+        Some(data) => holders_distribution(&data, proto.supply, shares),
+        None => types::HoldersDistribution { shares, ..Default::default() },
+    };
+
+    Ok(Json(types::HoldersStats::Distribution(distribution)))
+}
+
+pub fn holders_stats_docs(op: TransformOperation) -> TransformOperation {
+    op.description("A stats of holders for specific token, with `distribution=true` also concentration, gini coefficient and a balance histogram")
+        .tag("token")
+}
+
+fn holders_shares(data: Option<&BTreeSet<SortedByBalance>>, supply: Fixed128) -> Vec<Fixed128> {
+    if let Some(data) = data {
         let mut result = Vec::with_capacity(5);
 
         let mut iter = data.iter().rev().map(|x| x.0);
@@ -68,21 +132,78 @@ pub async fn holders_stats(State(server): State<Arc<Server>>, Query(query): Quer
 
             total_value += value;
 
-            let percent = value / proto.supply * Fixed128::from(100);
+            let percent = value / supply * Fixed128::from(100);
             result.push(percent);
         }
 
-        let left = (proto.supply - total_value) / proto.supply * Fixed128::from(100);
+        let left = (supply - total_value) / supply * Fixed128::from(100);
         result.push(left);
 
         result
     } else {
         vec![]
-    };
-
-    Ok(Json(result))
+    }
 }
 
-pub fn holders_stats_docs(op: TransformOperation) -> TransformOperation {
-    op.description("A stats of holders for specific token").tag("token")
+/// `data` is sorted by balance in ascending order and isn't empty
+fn holders_distribution(data: &BTreeSet<SortedByBalance>, supply: Fixed128, shares: Vec<Fixed128>) -> types::HoldersDistribution {
+    let hundred = Fixed128::from(100);
+    let count = data.len();
+    let top = |n: usize| data.iter().rev().take(n).fold(Fixed128::ZERO, |acc, x| acc + x.0) / supply * hundred;
+
+    // G = 2 * sum(i / n * x_i / total) - (n + 1) / n, where i is the 1-based rank in ascending order
+    let total = data.iter().fold(Fixed128::ZERO, |acc, x| acc + x.0);
+    let n = Fixed128::from(count as u64);
+    let weighted = data
+        .iter()
+        .enumerate()
+        .fold(Fixed128::ZERO, |acc, (i, x)| acc + Fixed128::from(i as u64 + 1) / n * (x.0 / total));
+    let offset = Fixed128::from(count as u64 + 1) / n;
+    let doubled = weighted + weighted;
+    let gini = if doubled > offset { doubled - offset } else { Fixed128::ZERO };
+
+    // Bounds 0, 1, 10, 100, ... up to the first one above the largest balance
+    let max_balance = data.last().map(|x| x.0).unwrap_or_default();
+    let mut bounds = vec![Fixed128::ZERO];
+    let mut power = Some(1u64);
+    while let Some(bound) = power {
+        bounds.push(Fixed128::from(bound));
+        if Fixed128::from(bound) > max_balance {
+            break;
+        }
+        power = bound.checked_mul(10);
+    }
+
+    let mut histogram = vec![];
+    let mut iter = data.iter().peekable();
+    for (i, min) in bounds.iter().enumerate() {
+        let max = bounds.get(i + 1);
+        let mut bucket_count = 0;
+        let mut value = Fixed128::ZERO;
+
+        while let Some(x) = iter.next_if(|x| max.is_none_or(|max| x.0 < *max)) {
+            bucket_count += 1;
+            value += x.0;
+        }
+
+        if max.is_none() && bucket_count == 0 {
+            break;
+        }
+
+        histogram.push(types::BalanceBucket {
+            min: min.to_string(),
+            max: max.map(|x| x.to_string()),
+            count: bucket_count,
+            percent: (value / supply * hundred).to_string(),
+        });
+    }
+
+    types::HoldersDistribution {
+        count,
+        shares,
+        top_10_percent: top(10).to_string(),
+        top_100_percent: top(100).to_string(),
+        gini: gini.to_string(),
+        histogram,
+    }
 }
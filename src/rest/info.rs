@@ -1,27 +1,53 @@
+use std::ops::Bound;
+
+use bellscoin::hashes::hex::FromHex;
+
 use super::*;
 
-pub async fn all_addresses(State(server): State<Arc<Server>>) -> ApiResult<impl IntoResponse> {
-    let (tx, rx) = tokio::sync::mpsc::channel(1000);
-    tokio::spawn(async move {
-        let mut last_address: Option<FullHash> = None;
-        for fullhash in server.db.address_token_to_balance.iter().map(|x| x.0.address) {
-            if last_address.is_some_and(|x| x == fullhash) {
-                continue;
-            }
-
-            let Some(address_str) = server.db.fullhash_to_address.get(fullhash) else {
-                continue;
-            };
-
-            if tx.send(address_str).await.is_err() {
-                break;
-            }
-
-            last_address = Some(fullhash);
-        }
-    });
-    let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
-    Ok(axum_streams::StreamBodyAs::json_array(stream))
+const TOTAL_ESTIMATE_HEADER: &str = "x-total-estimate";
+const NEXT_AFTER_HEADER: &str = "x-next-after";
+
+/// Page of the script hash to address mapping as NDJSON, not documented since it isn't JSON. <br/>
+/// The cursor of the next page and the estimated number of addresses are returned in headers, so rows can be streamed.
+pub async fn addresses_export(State(server): State<Arc<Server>>, Query(args): Query<types::AddressesExportArgs>) -> ApiResult<impl IntoResponse> {
+    args.validate().bad_request_from_error()?;
+
+    let after = match args.after.as_deref() {
+        Some(hash) => Some(scripthash_from_hex(hash).bad_request("Invalid after, expected a script hash")?),
+        None => None,
+    };
+
+    let bounds = (after.as_ref().map_or(Bound::Unbounded, Bound::Excluded), Bound::Unbounded);
+    let rows = server
+        .db
+        .fullhash_to_address
+        .range(bounds, false)
+        .take(args.limit)
+        .map(|(fullhash, address)| types::ExportedAddress {
+            scripthash: scripthash_to_hex(&fullhash),
+            address,
+        })
+        .collect_vec();
+
+    let mut headers = axum::http::HeaderMap::new();
+    headers.insert(axum::http::header::CONTENT_TYPE, axum::http::HeaderValue::from_static("application/x-ndjson"));
+    headers.insert(TOTAL_ESTIMATE_HEADER, server.db.fullhash_to_address.estimate_len().into());
+    if let Some(last) = rows.last().filter(|_| rows.len() == args.limit) {
+        headers.insert(NEXT_AFTER_HEADER, axum::http::HeaderValue::from_str(&last.scripthash).ok().internal(INTERNAL)?);
+    }
+
+    Ok((headers, axum_streams::StreamBodyAs::json_nl(tokio_stream::iter(rows))))
+}
+
+/// Electrum-style script hash, sha256 of the script_pubkey in reversed byte order
+fn scripthash_to_hex(fullhash: &FullHash) -> String {
+    fullhash.iter().rev().map(|x| format!("{x:02x}")).collect()
+}
+
+fn scripthash_from_hex(hash: &str) -> Option<FullHash> {
+    let mut bytes = Vec::<u8>::from_hex(hash).ok()?;
+    bytes.reverse();
+    FullHash::try_from(bytes).ok()
 }
 
 pub async fn status(State(server): State<Arc<Server>>) -> ApiResult<impl IntoApiResponse> {
@@ -37,6 +63,13 @@ pub async fn status(State(server): State<Arc<Server>>) -> ApiResult<impl IntoApi
         blockhash: last_block_hash.to_string(),
         version: PKG_VERSION.to_string(),
         uptime_secs: server.start_time.elapsed().as_secs(),
+        token_cache: server.token_accounts.stats(),
+        parser_limits: types::ParserLimits {
+            max_partial_parts: *MAX_PARTIAL_PARTS,
+            max_inscription_bytes: *MAX_INSCRIPTION_BYTES,
+            max_inscriptions_per_tx: *MAX_INSCRIPTIONS_PER_TX,
+            max_envelopes_per_input: *MAX_ENVELOPES_PER_INPUT,
+        },
     };
 
     Ok(Json(data))
@@ -45,3 +78,11 @@ pub async fn status(State(server): State<Arc<Server>>) -> ApiResult<impl IntoApi
 pub fn status_docs(op: TransformOperation) -> TransformOperation {
     op.description("Status of the indexer").tag("status")
 }
+
+/// Prometheus text format, not documented since it isn't JSON
+pub async fn metrics(State(server): State<Arc<Server>>) -> impl IntoResponse {
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        server.request_metrics.render() + &server.spot_audits.render(),
+    )
+}
@@ -0,0 +1,110 @@
+use super::*;
+
+const MAX_LOCATION_IDS: usize = 1000;
+
+pub async fn inscription_history(
+    State(server): State<Arc<Server>>,
+    Path(id): Path<String>,
+    Query(args): Query<types::InscriptionHistoryArgs>,
+) -> ApiResult<impl IntoApiResponse> {
+    args.validate().bad_request_from_error()?;
+
+    INSCRIPTION_HISTORY.then_some(()).not_found("Inscription history is not enabled on this indexer")?;
+
+    let id = InscriptionId::from_str(&id).bad_request("Invalid inscription id")?;
+
+    let (from, to) = InscriptionHistoryKey::search(id).into_inner();
+
+    let rows = server
+        .db
+        .inscription_history
+        .range(&from..=&to, false)
+        .skip(args.offset)
+        .take(args.limit)
+        .collect_vec();
+
+    (!rows.is_empty() || args.offset > 0).then_some(()).not_found("Inscription not found")?;
+
+    let addresses = server.load_addresses(rows.iter().map(|(_, v)| v.owner)).internal("Failed to load addresses")?;
+
+    let data = rows
+        .into_iter()
+        .map(|(k, v)| types::InscriptionMove {
+            kind: v.kind.into(),
+            height: k.height,
+            location: location_str(v.location),
+            address: addresses.get(&v.owner),
+        })
+        .collect_vec();
+
+    Ok(Json(data))
+}
+
+pub fn inscription_history_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Movements of the inscription from its creation, oldest first. Available only if `INSCRIPTION_HISTORY` is enabled")
+        .tag("inscription")
+}
+
+pub async fn inscription_location(State(server): State<Arc<Server>>, Path(id): Path<String>) -> ApiResult<impl IntoApiResponse> {
+    INSCRIPTION_HISTORY.then_some(()).not_found("Inscription history is not enabled on this indexer")?;
+
+    let id = InscriptionId::from_str(&id).bad_request("Invalid inscription id")?;
+    let location = server.db.inscription_location.get(id).not_found("Inscription not found")?;
+
+    Ok(Json(types::InscriptionLocation {
+        id: id.to_string(),
+        location: Some(location_str(location)),
+    }))
+}
+
+pub fn inscription_location_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Current location of the inscription. Available only if `INSCRIPTION_HISTORY` is enabled")
+        .tag("inscription")
+}
+
+pub async fn inscription_locations(State(server): State<Arc<Server>>, Json(ids): Json<Vec<String>>) -> ApiResult<impl IntoApiResponse> {
+    INSCRIPTION_HISTORY.then_some(()).not_found("Inscription history is not enabled on this indexer")?;
+
+    (ids.len() <= MAX_LOCATION_IDS)
+        .then_some(())
+        .bad_request(format!("At most {MAX_LOCATION_IDS} ids are allowed"))?;
+
+    let ids = ids
+        .iter()
+        .map(|id| InscriptionId::from_str(id).bad_request(format!("Invalid inscription id {id}")))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let data = ids
+        .iter()
+        .zip(server.db.inscription_location.multi_get(ids.iter()))
+        .map(|(id, location)| types::InscriptionLocation {
+            id: id.to_string(),
+            location: location.map(location_str),
+        })
+        .collect_vec();
+
+    Ok(Json(data))
+}
+
+pub fn inscription_locations_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Batch operation to get the current location of each inscription from the provided list, in the same order. Available only if `INSCRIPTION_HISTORY` is enabled")
+        .tag("inscription")
+}
+
+fn location_str(location: Location) -> String {
+    format!("{}:{}:{}", location.outpoint.txid, location.outpoint.vout, location.offset)
+}
+
+pub async fn inscription_curses(State(server): State<Arc<Server>>, Path(id): Path<String>) -> ApiResult<impl IntoApiResponse> {
+    let id = InscriptionId::from_str(&id).bad_request("Invalid inscription id")?;
+
+    Ok(Json(types::InscriptionCurses {
+        id: id.to_string(),
+        curses: server.db.inscription_curses.get(id).unwrap_or_default(),
+    }))
+}
+
+pub fn inscription_curses_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Reasons ord would curse the inscription, empty if it isn't cursed or was created before curses were recorded")
+        .tag("inscription")
+}
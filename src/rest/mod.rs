@@ -1,44 +1,134 @@
-use crate::rest::docs::docs_routes;
-
 use super::*;
 
-use aide::{
+use validator::Validate;
+
+#[cfg(feature = "server")]
+use {
+    crate::rest::docs::docs_routes,
+    aide::{
+        axum::{
+            routing::{delete_with, get, get_with, post_with, put_with},
+            ApiRouter, IntoApiResponse,
+        },
+        openapi::{OpenApi, Tag},
+        scalar::Scalar,
+        transform::{TransformOpenApi, TransformOperation},
+    },
     axum::{
-        routing::{get, get_with, post_with},
-        ApiRouter, IntoApiResponse,
+        extract::{Path, Query, State},
+        http::Response,
+        http::Uri,
+        response::{sse::Event, IntoResponse, Sse},
+        Extension, Json,
     },
-    openapi::{OpenApi, Tag},
-    scalar::Scalar,
-    transform::{TransformOpenApi, TransformOperation},
-};
-use axum::{
-    extract::{Path, Query, State},
-    http::Response,
-    http::Uri,
-    response::{sse::Event, IntoResponse, Sse},
-    Extension, Json,
+    fields::{FieldsArgs, Projected},
+    nintypes::common::inscriptions::Outpoint,
+    tokio::sync::mpsc,
+    tokio_stream::wrappers::ReceiverStream,
+    tower_http::compression::CompressionLayer,
 };
-use nintypes::common::inscriptions::Outpoint;
-use tokio::sync::mpsc;
-use tokio_stream::wrappers::ReceiverStream;
-use tower_http::compression::CompressionLayer;
-use validator::Validate;
 
+pub mod types;
+mod utils;
+mod wrappers;
+
+pub use wrappers::{OutPoint, Txid};
+
+// Handlers of the REST server
+#[cfg(feature = "server")]
 mod address;
+#[cfg(feature = "server")]
+mod admin;
+#[cfg(feature = "server")]
+mod block;
+#[cfg(feature = "server")]
+mod bootstrap;
+#[cfg(feature = "server")]
+mod debug;
+#[cfg(feature = "server")]
 mod docs;
+#[cfg(feature = "server")]
+mod event_schema;
+#[cfg(feature = "server")]
+mod fields;
+#[cfg(feature = "server")]
 mod history;
+#[cfg(feature = "server")]
 mod holders;
+#[cfg(feature = "server")]
 mod info;
+#[cfg(feature = "server")]
+mod inscriptions;
+#[cfg(feature = "server")]
+mod outpoint;
+#[cfg(feature = "server")]
+mod rpc;
+#[cfg(feature = "server")]
+mod standby;
+#[cfg(feature = "server")]
+mod stats;
+#[cfg(feature = "server")]
 mod tokens;
-pub mod types;
-mod utils;
-mod wrappers;
-
-pub use wrappers::{OutPoint, Txid};
+#[cfg(feature = "server")]
+mod tx;
+#[cfg(feature = "server")]
+mod validate;
 
+#[cfg(feature = "server")]
 type ApiResult<T> = core::result::Result<T, Response<String>>;
+/// Full scans, which may observe partially rolled back state, and requests waiting for the indexer, see [`reorg_guard`]
+#[cfg(feature = "server")]
+const UNGUARDED_ROUTES: &[&str] = &[
+    "/addresses/export",
+    "/all-tickers",
+    "/admin/partials-stats",
+    "/admin/reprocess/{height}",
+    "/metrics",
+    "/standby/replication",
+];
+/// Read routes which answer from more than the indexed blocks (mempool, uptime, query counters, admin state), see [`http_cache`]
+#[cfg(feature = "server")]
+const UNCACHED_ROUTES: &[&str] = &[
+    "/status",
+    "/metrics",
+    "/standby",
+    // counters of the queries in the hours up to the current one
+    "/tokens/trending",
+    // extras are set by the admin API
+    "/token",
+    "/estimate/transfer",
+    "/address/{address}/{tick}/balance",
+    "/scripthash/{hash}/{tick}/balance",
+    "/token/proof/{address}/{outpoint}",
+    "/address/{address}/events/stream",
+];
+/// Queries of a single block by height, which can't be rolled back if it's below the reorg cache
+#[cfg(feature = "server")]
+const HEIGHT_ROUTES: &[&str] = &[
+    "/events/{height}",
+    "/block/{height}",
+    "/block/{height}/inscription-stats",
+    "/block/{height}/leaked",
+    "/state-digest/{height}",
+];
+#[cfg(feature = "server")]
 const INTERNAL: &str = "Internal server error";
+#[cfg(feature = "server")]
+const REORG_RETRY_AFTER_SECS: u64 = 2;
+/// Query parameters which values are never written to the slow query log
+#[cfg(feature = "server")]
+const REDACTED_QUERY_PARAMS: &[&str] = &["secret", "token", "access_token", "api_key", "key", "password", "signature"];
+/// Longer values of query parameters are truncated in the slow query log
+#[cfg(feature = "server")]
+const MAX_LOGGED_PARAM_LEN: usize = 128;
+
+/// Runs handler code which sends requests to the node (mempool checks) on a blocking thread, so it doesn't stall the runtime
+#[cfg(feature = "server")]
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> ApiResult<T> + Send + 'static) -> ApiResult<T> {
+    tokio::task::spawn_blocking(f).await.internal(INTERNAL)?
+}
 
+#[cfg(feature = "server")]
 pub async fn run_rest(server: Arc<Server>) -> anyhow::Result<()> {
     let token = server.token.clone();
 
@@ -52,42 +142,156 @@ pub async fn run_rest(server: Arc<Server>) -> anyhow::Result<()> {
 
     let listener = tokio::net::TcpListener::bind(&*SERVER_URL).await.unwrap();
 
+    let v1 = ApiRouter::new()
+        // Address
+        .api_route("/address/{address}", get_with(address::address_tokens, address::address_tokens_docs))
+        .api_route("/address/{address}/tokens", get_with(address::address_tokens, address::address_tokens_docs))
+        .api_route("/address/{address}/history", get_with(history::address_token_history, history::address_token_history_docs))
+        .api_route("/address/{address}/tokens-tick", get_with(address::address_tokens_tick, address::address_tokens_tick_docs))
+        .api_route("/address/{address}/deltas", get_with(address::address_deltas, address::address_deltas_docs))
+        .api_route("/address/{address}/leaked", get_with(address::address_leaked, address::address_leaked_docs))
+        .api_route(
+            "/address/{address}/{tick}/balance",
+            get_with(address::address_token_balance, address::address_token_balance_docs),
+        )
+        .api_route(
+            "/address/{address}/{tick}/balance-history",
+            get_with(address::address_token_balance_history, address::address_token_balance_history_docs),
+        )
+        .api_route(
+            "/address/{address}/{tick}/balance-proof",
+            get_with(address::address_token_balance_proof, address::address_token_balance_proof_docs),
+        )
+        .api_route("/outpoint/{outpoint}", get_with(outpoint::outpoint, outpoint::outpoint_docs))
+        // Script hash (Electrum-style, sha256 of the script_pubkey in reversed byte order)
+        .api_route("/scripthash/{hash}/tokens", get_with(address::address_tokens, address::address_tokens_docs))
+        .api_route("/scripthash/{hash}/history", get_with(history::address_token_history, history::address_token_history_docs))
+        .api_route("/scripthash/{hash}/tokens-tick", get_with(address::address_tokens_tick, address::address_tokens_tick_docs))
+        .api_route("/scripthash/{hash}/deltas", get_with(address::address_deltas, address::address_deltas_docs))
+        .api_route("/scripthash/{hash}/leaked", get_with(address::address_leaked, address::address_leaked_docs))
+        .api_route(
+            "/scripthash/{hash}/{tick}/balance",
+            get_with(address::address_token_balance, address::address_token_balance_docs),
+        )
+        .api_route(
+            "/scripthash/{hash}/{tick}/balance-history",
+            get_with(address::address_token_balance_history, address::address_token_balance_history_docs),
+        )
+        .api_route(
+            "/scripthash/{hash}/{tick}/balance-proof",
+            get_with(address::address_token_balance_proof, address::address_token_balance_proof_docs),
+        )
+        // Token
+        .api_route("/tokens", get_with(tokens::tokens, tokens::tokens_docs))
+        .api_route("/tokens/trending", get_with(tokens::trending_tokens, tokens::trending_tokens_docs))
+        .api_route("/token", get_with(tokens::token, tokens::token_docs))
+        .api_route("/deployer/{address}", get_with(tokens::deployer_tokens, tokens::deployer_tokens_docs))
+        .api_route("/token-supplies", post_with(tokens::token_supplies, tokens::token_supplies_docs))
+        .api_route(
+            "/token/proof/{address}/{outpoint}",
+            get_with(tokens::token_transfer_proof, tokens::token_transfer_proof_docs),
+        )
+        .api_route("/holders", get_with(holders::holders, holders::holders_docs))
+        .api_route("/holders-stats", get_with(holders::holders_stats, holders::holders_stats_docs))
+        // Events
+        .api_route("/events/{height}", get_with(history::events_by_height, history::events_by_height_docs))
+        .api_route("/txid/{txid}", get_with(history::txid_events, history::txid_events_docs))
+        .api_route("/txid/{txid}/batch-summary", get_with(history::txid_batch_summary, history::txid_batch_summary_docs))
+        .api_route("/token-events/{tick}", get_with(tokens::token_events, tokens::token_events_docs))
+        // Transactions
+        .api_route("/tx/broadcast", post_with(tx::broadcast, tx::broadcast_docs))
+        .api_route("/estimate/transfer", get_with(tx::estimate_transfer, tx::estimate_transfer_docs))
+        .api_route("/validate/inscription", post_with(validate::validate_inscription, validate::validate_inscription_docs))
+        // Status
+        .api_route("/status", get_with(info::status, info::status_docs))
+        .api_route("/proof-of-history", get_with(history::proof_of_history, history::proof_of_history_docs))
+        .api_route("/proof-of-history/spec", get_with(history::proof_of_history_spec, history::proof_of_history_spec_docs))
+        .api_route("/state-digest/{height}", get_with(history::state_digest, history::state_digest_docs))
+        .api_route("/standby", get_with(standby::standby, standby::standby_docs))
+        // Stats
+        .api_route("/block/{height}", get_with(block::block, block::block_docs))
+        .api_route("/block/{height}/leaked", get_with(block::block_leaked, block::block_leaked_docs))
+        .api_route(
+            "/block/{height}/inscription-stats",
+            get_with(stats::block_inscription_stats, stats::block_inscription_stats_docs),
+        )
+        .api_route("/stats/inscriptions", get_with(stats::inscription_stats, stats::inscription_stats_docs))
+        .api_route("/stats/fees", get_with(stats::fee_stats, stats::fee_stats_docs))
+        .api_route("/token/{tick}/stats", get_with(stats::token_stats, stats::token_stats_docs))
+        .api_route("/token/{tick}/mint-activity", get_with(stats::mint_activity, stats::mint_activity_docs))
+        .api_route("/token/{tick}/overview", get_with(tokens::token_overview, tokens::token_overview_docs))
+        // Inscription
+        .api_route(
+            "/inscription/{id}/history",
+            get_with(inscriptions::inscription_history, inscriptions::inscription_history_docs),
+        )
+        .api_route(
+            "/inscription/{id}/curses",
+            get_with(inscriptions::inscription_curses, inscriptions::inscription_curses_docs),
+        )
+        .api_route(
+            "/inscription/{id}/location",
+            get_with(inscriptions::inscription_location, inscriptions::inscription_location_docs),
+        )
+        .api_route(
+            "/inscriptions/locations",
+            post_with(inscriptions::inscription_locations, inscriptions::inscription_locations_docs),
+        )
+        // Admin
+        .api_route(
+            "/admin/token/{tick}/extras",
+            put_with(admin::set_token_extras, admin::set_token_extras_docs).delete_with(admin::remove_token_extras, admin::remove_token_extras_docs),
+        )
+        .api_route("/admin/partials-stats", get_with(admin::partials_stats, admin::partials_stats_docs))
+        .api_route(
+            "/admin/webhooks",
+            get_with(admin::webhooks, admin::webhooks_docs).post_with(admin::create_webhook, admin::create_webhook_docs),
+        )
+        .api_route("/admin/webhooks/{id}", delete_with(admin::remove_webhook, admin::remove_webhook_docs))
+        .api_route(
+            "/admin/simulate-reorg",
+            get_with(admin::reorg_simulation, admin::reorg_simulation_docs).post_with(admin::simulate_reorg, admin::simulate_reorg_docs),
+        )
+        .api_route("/admin/reprocess/{height}", post_with(admin::reprocess_block, admin::reprocess_block_docs))
+        .api_route("/admin/audit-log", get_with(admin::audit_log, admin::audit_log_docs))
+        .api_route("/admin/rejected-deploys", get_with(admin::rejected_deploys, admin::rejected_deploys_docs))
+        .api_route(
+            "/admin/supply-audit",
+            get_with(admin::supply_audits, admin::supply_audits_docs).post_with(admin::request_supply_audit, admin::request_supply_audit_docs),
+        )
+        .api_route(
+            "/admin/backups",
+            get_with(admin::backups, admin::backups_docs).post_with(admin::create_backup, admin::create_backup_docs),
+        )
+        .api_route("/admin/backups/prune", post_with(admin::prune_backups, admin::prune_backups_docs))
+        .api_route("/admin/backups/{name}", delete_with(admin::remove_backup, admin::remove_backup_docs))
+        .api_route("/admin/promote", post_with(admin::promote, admin::promote_docs))
+        // Debug
+        .api_route("/debug/tx/{txid}/trace", get_with(debug::tx_trace, debug::tx_trace_docs))
+        .nest_api_service("/docs", docs_routes(server.clone()))
+        .finish_api_with(&mut api, api_docs)
+        // Not documented
+        .route("/addresses/export", axum::routing::get(info::addresses_export))
+        .route("/all-tickers", axum::routing::get(tokens::all_tickers))
+        .route("/events", axum::routing::post(history::subscribe))
+        .route("/rpc", axum::routing::post(rpc::rpc))
+        .route("/address/{address}/events/stream", axum::routing::get(history::address_events_stream))
+        .route("/metrics", axum::routing::get(info::metrics))
+        .route("/standby/replication", axum::routing::post(standby::standby_replication))
+        .route("/bootstrap", axum::routing::get(bootstrap::dump));
+
     let rest = axum::serve(
         listener,
-        ApiRouter::new()
-            // Address
-            .api_route("/address/{address}", get_with(address::address_tokens, address::address_tokens_docs))
-            .api_route("/address/{address}/tokens", get_with(address::address_tokens, address::address_tokens_docs))
-            .api_route("/address/{address}/history", get_with(history::address_token_history, history::address_token_history_docs))
-            .api_route("/address/{address}/tokens-tick", get_with(address::address_tokens_tick, address::address_tokens_tick_docs))
-            .api_route(
-                "/address/{address}/{tick}/balance",
-                get_with(address::address_token_balance, address::address_token_balance_docs),
-            )
-            // Token
-            .api_route("/tokens", get_with(tokens::tokens, tokens::tokens_docs))
-            .api_route("/token", get_with(tokens::token, tokens::token_docs))
-            .api_route("/token-supplies", post_with(tokens::token_supplies, tokens::token_supplies_docs))
-            .api_route(
-                "/token/proof/{address}/{outpoint}",
-                get_with(tokens::token_transfer_proof, tokens::token_transfer_proof_docs),
-            )
-            .api_route("/holders", get_with(holders::holders, holders::holders_docs))
-            .api_route("/holders-stats", get_with(holders::holders_stats, holders::holders_stats_docs))
-            // Events
-            .api_route("/events/{height}", get_with(history::events_by_height, history::events_by_height_docs))
-            .api_route("/txid/{txid}", get_with(history::txid_events, history::txid_events_docs))
-            .api_route("/token-events/{tick}", get_with(tokens::token_events, tokens::token_events_docs))
-            // Status
-            .api_route("/status", get_with(info::status, info::status_docs))
-            .api_route("/proof-of-history", get_with(history::proof_of_history, history::proof_of_history_docs))
-            // Debug
-            .nest_api_service("/docs", docs_routes(server.clone()))
-            .finish_api_with(&mut api, api_docs)
-            // Not documented
-            .route("/all-addresses", axum::routing::get(info::all_addresses))
-            .route("/all-tickers", axum::routing::get(tokens::all_tickers))
-            .route("/events", axum::routing::post(history::subscribe))
+        axum::Router::new()
+            .nest("/v1", v1.clone())
+            // Legacy unversioned routes
+            .merge(v1.layer(axum::middleware::from_fn(legacy_alias)))
+            .layer(axum::middleware::from_fn_with_state(server.clone(), http_cache))
+            .layer(axum::middleware::from_fn_with_state(server.clone(), reorg_guard))
+            .layer(axum::middleware::from_fn_with_state(server.clone(), admin::standby_guard))
+            .layer(axum::middleware::from_fn_with_state(server.clone(), admin::audit_admin))
+            .layer(axum::middleware::from_fn_with_state(server.clone(), track_query))
+            .layer(axum::middleware::from_fn_with_state(server.clone(), request_metrics))
             .layer(Extension(Arc::new(api)))
             .layer(CompressionLayer::new())
             .with_state(server),
@@ -112,8 +316,156 @@ pub async fn run_rest(server: Arc<Server>) -> anyhow::Result<()> {
     }
 }
 
+/// Queries of the heights which may be rolled back are rejected while the reorg rollback is running, because they could observe
+/// partially restored state. Full scans aren't guarded, so the rollback never waits for them.
+#[cfg(feature = "server")]
+async fn reorg_guard(State(server): State<Arc<Server>>, request: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+    let path = request.extensions().get::<axum::extract::MatchedPath>().map(|x| x.as_str().trim_start_matches("/v1").to_string());
+    if path.as_deref().is_some_and(|path| UNGUARDED_ROUTES.contains(&path)) {
+        return next.run(request).await;
+    }
+
+    // Blocks from the next one are included, since they may be indexed and rolled back during the query
+    let last_height = server.db.last_block.get(()).unwrap_or_default();
+    let mut heights = (last_height + 1).saturating_sub(REORG_CACHE_MAX_LEN as u32)..=last_height + 1;
+
+    let height = path
+        .filter(|path| HEIGHT_ROUTES.contains(&path.as_str()))
+        .and_then(|_| request.uri().path().split('/').find_map(|x| x.parse::<u32>().ok()));
+    match height {
+        Some(height) if height < *heights.start() => return next.run(request).await,
+        Some(height) => heights = height..=height,
+        None => {}
+    }
+
+    let Some(_guard) = server.reorg_in_progress.read(heights).await else {
+        let height = server.reorg_in_progress.height().unwrap_or_default();
+        return (
+            axum::http::StatusCode::SERVICE_UNAVAILABLE,
+            [(axum::http::header::RETRY_AFTER, REORG_RETRY_AFTER_SECS.to_string())],
+            format!("Reorg is in progress, blocks from {height} are being rolled back"),
+        )
+            .into_response();
+    };
+
+    next.run(request).await
+}
+
+/// `GET` responses of the read routes carry `ETag: "<height>-<hash>"`, where the hash covers the request URI and the last block
+/// hash, so the tag changes with every block and with reorgs at the same height. `If-None-Match` with the current tag is answered
+/// with 304 before the query runs. Added after [`reorg_guard`], so no tag is given out while blocks are rolled back.
+#[cfg(feature = "server")]
+async fn http_cache(State(server): State<Arc<Server>>, request: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+    let route = request.extensions().get::<axum::extract::MatchedPath>().map(|x| x.as_str().trim_start_matches("/v1").to_string());
+    let cached = request.method() == axum::http::Method::GET
+        && route.is_some_and(|route| !route.starts_with("/admin/") && !UNCACHED_ROUTES.contains(&route.as_str()));
+
+    // Read before the query, so the response is never older than its tag
+    let last_block = server.db.last_block.get(()).and_then(|height| server.db.block_info.get(height).map(|info| (height, info.hash)));
+    let Some((height, block_hash)) = last_block.filter(|_| cached) else {
+        return next.run(request).await;
+    };
+
+    let hash = sha256::Hash::hash(format!("{block_hash}{}", request.uri()).as_bytes()).to_string();
+    let etag = format!("\"{height}-{}\"", &hash[..16]);
+    let headers = [
+        (axum::http::header::ETAG, etag.clone()),
+        (axum::http::header::CACHE_CONTROL, format!("public, max-age={}, must-revalidate", *HTTP_CACHE_MAX_AGE_SECS)),
+    ];
+
+    // Compressing proxies may weaken the tag, which still matches by the weak comparison
+    let not_modified = request
+        .headers()
+        .get(axum::http::header::IF_NONE_MATCH)
+        .and_then(|x| x.to_str().ok())
+        .is_some_and(|tags| tags.split(',').map(str::trim).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag));
+    if not_modified {
+        return (axum::http::StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+
+    let response = next.run(request).await;
+    if response.status() != axum::http::StatusCode::OK {
+        return response;
+    }
+
+    (headers, response).into_response()
+}
+
+/// Live traffic slows down indexing writes, see `MAX_WRITE_MBPS`
+#[cfg(feature = "server")]
+async fn track_query(State(server): State<Arc<Server>>, request: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+    server.write_throttle.on_query();
+    next.run(request).await
+}
+
+/// Latency of every request by route for `/metrics`, requests slower than `SLOW_QUERY_MS` are also logged. <br/>
+/// Added last, so the time spent waiting in [`reorg_guard`] is included.
+#[cfg(feature = "server")]
+async fn request_metrics(State(server): State<Arc<Server>>, request: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+    let route = request
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map(|x| x.as_str().trim_start_matches("/v1").to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = request.method().clone();
+    let uri = SLOW_QUERY_MS.is_some().then(|| request.uri().clone());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let latency = started.elapsed();
+
+    let slow = SLOW_QUERY_MS.is_some_and(|ms| latency >= Duration::from_millis(ms));
+    server.request_metrics.record(method.as_str(), &route, latency, slow);
+
+    if let Some(uri) = uri.filter(|_| slow) {
+        let query = uri.query().map(sanitize_query).unwrap_or_default();
+        warn!("Slow query {} {} ({}) took {:?} with status {}, query: {}", method, uri.path(), route, latency, response.status(), query);
+    }
+
+    response
+}
+
+/// Redacts values of [`REDACTED_QUERY_PARAMS`] and truncates long values
+#[cfg(feature = "server")]
+fn sanitize_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            if REDACTED_QUERY_PARAMS.contains(&key.to_ascii_lowercase().as_str()) {
+                format!("{key}=<redacted>")
+            } else if value.len() > MAX_LOGGED_PARAM_LEN {
+                format!("{key}={}...", value.chars().take(MAX_LOGGED_PARAM_LEN).collect::<String>())
+            } else {
+                pair.to_string()
+            }
+        })
+        .join("&")
+}
+
+/// Legacy unversioned routes are kept as deprecated aliases of `/v1`
+#[cfg(feature = "server")]
+async fn legacy_alias(request: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+    let successor = format!("</v1{}>; rel=\"successor-version\"", request.uri().path());
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert("Deprecation", axum::http::HeaderValue::from_static("true"));
+    if let Ok(successor) = axum::http::HeaderValue::from_str(&successor) {
+        headers.insert(axum::http::header::LINK, successor);
+    }
+
+    response
+}
+
+#[cfg(feature = "server")]
 fn api_docs(api: TransformOpenApi) -> TransformOpenApi {
     api.title("BRC-20 Indexer API")
+        .server(aide::openapi::Server {
+            url: format!("{}/v1", API_PUBLIC_URL.as_deref().unwrap_or_default().trim_end_matches('/')),
+            description: Some("Current API version".into()),
+            ..Default::default()
+        })
         .tag(Tag {
             name: "address".into(),
             description: Some("Address Management".into()),
@@ -134,4 +486,29 @@ fn api_docs(api: TransformOpenApi) -> TransformOpenApi {
             description: Some("Status Management".into()),
             ..Default::default()
         })
+        .tag(Tag {
+            name: "stats".into(),
+            description: Some("Chain Statistics".into()),
+            ..Default::default()
+        })
+        .tag(Tag {
+            name: "inscription".into(),
+            description: Some("Inscription Provenance".into()),
+            ..Default::default()
+        })
+        .tag(Tag {
+            name: "transaction".into(),
+            description: Some("Transaction Relay".into()),
+            ..Default::default()
+        })
+        .tag(Tag {
+            name: "debug".into(),
+            description: Some("Indexing Diagnostics".into()),
+            ..Default::default()
+        })
+        .tag(Tag {
+            name: "admin".into(),
+            description: Some("Operator API, requires `Authorization: Bearer <ADMIN_TOKEN>`".into()),
+            ..Default::default()
+        })
 }
@@ -0,0 +1,37 @@
+use super::*;
+
+pub async fn outpoint(State(server): State<Arc<Server>>, Path(outpoint): Path<Outpoint>) -> ApiResult<impl IntoApiResponse> {
+    let outpoint: bellscoin::OutPoint = outpoint.into();
+
+    let prevout = server.db.prevouts.get(outpoint).not_found("Outpoint not found or already spent")?;
+
+    let inscription_offsets = server.db.outpoint_to_inscription_offsets.get(outpoint).unwrap_or_default().into_iter().sorted().collect_vec();
+
+    let (from, to) = AddressLocation::search_with_offset(prevout.script_hash, outpoint).into_inner();
+
+    let transfers = server
+        .db
+        .address_location_to_transfer
+        .range(&from..=&to, false)
+        .map(|(k, v)| types::OutPointTransfer {
+            offset: k.location.offset,
+            tick: v.tick.into(),
+            amt: v.amt,
+            height: v.height,
+        })
+        .collect_vec();
+
+    let data = types::OutPointInfo {
+        value: prevout.value,
+        script_hash: sha256::Hash::from_byte_array(*prevout.script_hash).to_string(),
+        address: fullhash_to_address_str(&prevout.script_hash, server.db.fullhash_to_address.get(prevout.script_hash)),
+        inscription_offsets,
+        transfers,
+    };
+
+    Ok(Json(data))
+}
+
+pub fn outpoint_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Stored value and owner of the unspent outpoint with inscriptions and active transfers on it").tag("address")
+}
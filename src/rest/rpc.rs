@@ -0,0 +1,186 @@
+use axum::http::StatusCode;
+use nint_blk::ScriptType;
+use serde::de::DeserializeOwned;
+
+use super::*;
+
+/// Requests of a batch are answered one by one, so its size is limited like the page sizes
+const MAX_BATCH_LEN: usize = 50;
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// Queried token or holders snapshot doesn't exist, from the range reserved for server errors
+const NOT_FOUND: i64 = -32004;
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: serde_json::Value,
+    /// Notifications have no id and get no response
+    id: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+    id: serde_json::Value,
+}
+
+impl RpcResponse {
+    fn new(id: serde_json::Value, result: Result<serde_json::Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        Self {
+            jsonrpc: "2.0",
+            result,
+            error,
+            id,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Display) -> Self {
+        Self { code, message: message.to_string() }
+    }
+}
+
+/// Errors of the REST handlers keep their message, the status is mapped to the error code
+impl From<Response<String>> for RpcError {
+    fn from(response: Response<String>) -> Self {
+        let code = match response.status() {
+            StatusCode::BAD_REQUEST => INVALID_PARAMS,
+            StatusCode::NOT_FOUND => NOT_FOUND,
+            _ => INTERNAL_ERROR,
+        };
+
+        Self::new(code, response.into_body())
+    }
+}
+
+/// Either `address` or `scripthash`, like the `/address/...` and `/scripthash/...` routes
+#[derive(Deserialize)]
+struct OwnerParams {
+    address: Option<String>,
+    scripthash: Option<String>,
+}
+
+impl OwnerParams {
+    fn scripthash(&self, server: &Server) -> ApiResult<FullHash> {
+        let (script_str, script_type) = match (&self.address, &self.scripthash) {
+            (Some(address), None) => Some((address, ScriptType::Address)),
+            (None, Some(hash)) => Some((hash, ScriptType::ScriptHash)),
+            _ => None,
+        }
+        .bad_request("Either address or scripthash is required")?;
+
+        Ok(server.indexer.to_scripthash(script_str, script_type).bad_request_from_error()?.into())
+    }
+}
+
+#[derive(Deserialize)]
+struct BalanceParams {
+    #[serde(flatten)]
+    owner: OwnerParams,
+    tick: OriginalTokenTickRest,
+    #[serde(flatten)]
+    args: types::AddressTokenBalanceArgs,
+}
+
+#[derive(Deserialize)]
+struct HistoryParams {
+    #[serde(flatten)]
+    owner: OwnerParams,
+    #[serde(flatten)]
+    args: types::AddressTokenHistoryArgs,
+}
+
+/// JSON-RPC 2.0 endpoint, a single request or a batch of them
+pub async fn rpc(State(server): State<Arc<Server>>, body: axum::body::Bytes) -> axum::response::Response {
+    let Ok(body) = serde_json::from_slice::<serde_json::Value>(&body) else {
+        return Json(RpcResponse::new(serde_json::Value::Null, Err(RpcError::new(PARSE_ERROR, "Parse error")))).into_response();
+    };
+
+    // `get_balance` checks transfers in the mempool of the node
+    tokio::task::spawn_blocking(move || respond(&server, body))
+        .await
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+fn respond(server: &Server, body: serde_json::Value) -> axum::response::Response {
+    match body {
+        serde_json::Value::Array(requests) => {
+            if requests.is_empty() || requests.len() > MAX_BATCH_LEN {
+                let error = RpcError::new(INVALID_REQUEST, format!("Batch must contain from 1 to {MAX_BATCH_LEN} requests"));
+                return Json(RpcResponse::new(serde_json::Value::Null, Err(error))).into_response();
+            }
+
+            let responses = requests.into_iter().filter_map(|request| call(server, request)).collect_vec();
+            if responses.is_empty() {
+                return StatusCode::NO_CONTENT.into_response();
+            }
+
+            Json(responses).into_response()
+        }
+        request => match call(server, request) {
+            Some(response) => Json(response).into_response(),
+            None => StatusCode::NO_CONTENT.into_response(),
+        },
+    }
+}
+
+/// Queries have no side effects, so notifications aren't executed at all
+fn call(server: &Server, request: serde_json::Value) -> Option<RpcResponse> {
+    let request = match serde_json::from_value::<RpcRequest>(request) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        _ => return Some(RpcResponse::new(serde_json::Value::Null, Err(RpcError::new(INVALID_REQUEST, "Invalid request")))),
+    };
+
+    let id = request.id?;
+    Some(RpcResponse::new(id, dispatch(server, &request.method, request.params)))
+}
+
+fn dispatch(server: &Server, method: &str, params: serde_json::Value) -> Result<serde_json::Value, RpcError> {
+    match method {
+        "get_balance" => {
+            let params: BalanceParams = parse_params(params)?;
+            let scripthash = params.owner.scripthash(server)?;
+            to_value(address::token_balance(server, scripthash, params.tick, params.args)?)
+        }
+        "get_token" => to_value(tokens::token_info(server, parse_params(params)?)?),
+        "get_history" => {
+            let params: HistoryParams = parse_params(params)?;
+            let scripthash = params.owner.scripthash(server)?;
+            to_value(history::address_history_page(server, scripthash, params.args)?)
+        }
+        "get_holders" => to_value(holders::holders_page(server, parse_params(params)?)?),
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Method {method} not found"))),
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: serde_json::Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e))
+}
+
+fn to_value(value: impl Serialize) -> Result<serde_json::Value, RpcError> {
+    serde_json::to_value(value).map_err(|_| RpcError::new(INTERNAL_ERROR, INTERNAL))
+}
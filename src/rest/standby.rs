@@ -0,0 +1,30 @@
+use super::*;
+
+/// Replicated blocks are small, the limit only guards against a misconfigured sender
+const MAX_REPLICATION_BODY_LEN: usize = 256 * 1024 * 1024;
+
+pub async fn standby(State(server): State<Arc<Server>>) -> ApiResult<impl IntoApiResponse> {
+    Ok(Json(types::StandbyStatus::new(&server)))
+}
+
+pub fn standby_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Hot-standby state and the proof of history parity with the primary, see `STANDBY`").tag("status")
+}
+
+/// Sink of the primary's `REPLICATION_URL`, not documented since it takes NDJSON. <br/>
+/// It's answered with 409 once the standby is promoted, so the primary keeps retrying instead of losing the blocks.
+pub async fn standby_replication(State(server): State<Arc<Server>>, body: axum::body::Body) -> axum::response::Response {
+    if !server.standby.is_active() {
+        return (axum::http::StatusCode::CONFLICT, "Indexer isn't a standby").into_response();
+    }
+
+    let body = match axum::body::to_bytes(body, MAX_REPLICATION_BODY_LEN).await {
+        Ok(body) => body,
+        Err(e) => return (axum::http::StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    match server.standby.ingest(&server.db, &body) {
+        Ok(_) => axum::http::StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (axum::http::StatusCode::BAD_REQUEST, format!("Invalid replication batch: {e}")).into_response(),
+    }
+}
@@ -0,0 +1,133 @@
+use super::*;
+
+pub async fn block_inscription_stats(State(server): State<Arc<Server>>, Path(height): Path<u32>) -> ApiResult<impl IntoApiResponse> {
+    let last_height = server.db.last_block.get(()).internal("Failed to get last height")?;
+
+    let stats = (height <= last_height)
+        .then(|| server.db.block_inscription_stats.get(height).unwrap_or_default())
+        .not_found(format!("Block {height} is not indexed yet"))?;
+
+    Ok(Json(types::InscriptionStats::from(stats)))
+}
+
+pub fn block_inscription_stats_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Number of inscriptions created in the block grouped by content type").tag("stats")
+}
+
+pub async fn inscription_stats(State(server): State<Arc<Server>>, Query(args): Query<types::StatsRangeArgs>) -> ApiResult<impl IntoApiResponse> {
+    args.validate().bad_request_from_error()?;
+
+    let stats = server
+        .db
+        .block_inscription_stats
+        .range(&args.from..=&args.to, false)
+        .fold(InscriptionStatsDB::default(), |mut acc, (_, stats)| {
+            acc.merge(&stats);
+            acc
+        });
+
+    Ok(Json(types::InscriptionStats::from(stats)))
+}
+
+pub fn inscription_stats_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Number of inscriptions created in the range of blocks grouped by content type").tag("stats")
+}
+
+pub async fn fee_stats(State(server): State<Arc<Server>>, Query(args): Query<types::StatsRangeArgs>) -> ApiResult<impl IntoApiResponse> {
+    args.validate().bad_request_from_error()?;
+
+    let stats = server.db.block_fee_stats.range(&args.from..=&args.to, false).fold(FeeStatsDB::default(), |mut acc, (_, stats)| {
+        acc.merge(&stats);
+        acc
+    });
+
+    Ok(Json(types::FeeStats::from(stats)))
+}
+
+pub fn fee_stats_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Fees paid by the transactions which created inscriptions in the range of blocks").tag("stats")
+}
+
+pub async fn token_stats(State(server): State<Arc<Server>>, Path(tick): Path<OriginalTokenTickRest>) -> ApiResult<impl IntoApiResponse> {
+    let lower_case_token_tick: LowerCaseTokenTick = tick.into();
+    let meta = server.db.token_to_meta.get(&lower_case_token_tick).not_found(format!("Tick {tick} not found"))?;
+
+    Ok(Json(types::TokenStats {
+        tick: meta.proto.tick.into(),
+        mint_count: meta.proto.mint_count,
+        mint_fees: server.db.token_mint_fees.get(&lower_case_token_tick).unwrap_or_default(),
+    }))
+}
+
+pub fn token_stats_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Mint statistics of the token, including fees paid by its mints").tag("stats")
+}
+
+pub async fn mint_activity(
+    State(server): State<Arc<Server>>,
+    Path(tick): Path<OriginalTokenTickRest>,
+    Query(range): Query<types::StatsRangeArgs>,
+    Query(args): Query<types::MintActivityArgs>,
+) -> ApiResult<impl IntoApiResponse> {
+    range.validate().bad_request_from_error()?;
+    args.validate().bad_request_from_error()?;
+
+    let lower_case_token_tick: LowerCaseTokenTick = tick.into();
+    let meta = server.db.token_to_meta.get(&lower_case_token_tick).not_found(format!("Tick {tick} not found"))?;
+    let tick = meta.proto.tick;
+
+    let from = TokenHeight { tick, height: range.from };
+    let to = TokenHeight { tick, height: range.to };
+
+    let mut mints = 0;
+    let mut amount = Fixed128::default();
+    let mut minters = HashMap::<FullHash, (u64, Fixed128)>::new();
+    let mut blocks = vec![];
+
+    for (key, activity) in server.db.token_mint_activity.range(&from..=&to, false) {
+        mints += activity.mints as u64;
+        amount += activity.amount;
+
+        for (address, address_mints, address_amount) in &activity.minters {
+            let minter = minters.entry(*address).or_default();
+            minter.0 += *address_mints as u64;
+            minter.1 += *address_amount;
+        }
+
+        blocks.push(types::MintActivityBlock {
+            height: key.height,
+            mints: activity.mints,
+            amount: activity.amount,
+            minters: activity.minters.len() as u32,
+        });
+    }
+
+    let minters_count = minters.len() as u64;
+    let top = minters
+        .into_iter()
+        .sorted_unstable_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.0.cmp(&b.0)))
+        .take(args.top)
+        .collect_vec();
+
+    let addresses = server.load_addresses(top.iter().map(|x| x.0)).internal("Failed to load addresses")?;
+
+    Ok(Json(types::MintActivity {
+        tick: tick.into(),
+        mints,
+        amount,
+        minters: minters_count,
+        blocks,
+        top_minters: top
+            .into_iter()
+            .map(|(address, (mints, amount))| types::Minter {
+                address: addresses.get(&address),
+                mints,
+                amount,
+            })
+            .collect(),
+    }))
+}
+
+pub fn mint_activity_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Valid mints of the token per block in the range of blocks and the addresses which received the most of them").tag("stats")
+}
@@ -4,9 +4,16 @@ use nint_blk::ScriptType;
 
 use super::*;
 
-pub async fn tokens(State(server): State<Arc<Server>>, Query(args): Query<types::TokensArgs>) -> ApiResult<impl IntoApiResponse> {
+/// Top holders in `/token/{tick}/overview`
+const OVERVIEW_HOLDERS: usize = 10;
+/// Last events in `/token/{tick}/overview`
+const OVERVIEW_EVENTS: usize = 20;
+
+pub async fn tokens(State(server): State<Arc<Server>>, Query(args): Query<types::TokensArgs>, Query(fields): Query<FieldsArgs>) -> ApiResult<impl IntoApiResponse> {
     args.validate().bad_request_from_error()?;
 
+    let search_skeleton = args.search.as_ref().filter(|_| args.include_lookalikes).map(|x| tick_skeleton(x.as_bytes()));
+
     let iter = server
         .db
         .token_to_meta
@@ -16,9 +23,16 @@ pub async fn tokens(State(server): State<Arc<Server>>, Query(args): Query<types:
             types::TokenFilterBy::Completed => x.1.is_completed(),
             types::TokenFilterBy::InProgress => !x.1.is_completed(),
         })
-        .filter(|x| args.search.as_ref().map(|tick| x.0.starts_with(tick)).unwrap_or(true));
+        .filter(|x| {
+            args.search.as_ref().map(|tick| x.0.starts_with(tick)).unwrap_or(true)
+                || search_skeleton.as_ref().is_some_and(|skeleton| tick_skeleton(&x.1.proto.tick.0).starts_with(skeleton))
+        });
 
     let stats = server.holders.stats();
+    let completion: HashMap<_, _> = match args.sort_by {
+        types::TokenSortBy::CompletionAsc | types::TokenSortBy::CompletionDesc => server.db.token_completion_height.iter().collect(),
+        _ => HashMap::new(),
+    };
     let all = match args.sort_by {
         types::TokenSortBy::DeployTimeAsc => iter.sorted_by_key(|(_, v)| v.proto.created).collect_vec(),
         types::TokenSortBy::DeployTimeDesc => iter.sorted_by_key(|(_, v)| v.proto.created).rev().collect_vec(),
@@ -26,6 +40,8 @@ pub async fn tokens(State(server): State<Arc<Server>>, Query(args): Query<types:
         types::TokenSortBy::HoldersDesc => iter.sorted_by_key(|(_, v)| stats.get(&v.proto.tick)).rev().collect_vec(),
         types::TokenSortBy::TransactionsAsc => iter.sorted_by_key(|(_, v)| v.proto.transactions).collect_vec(),
         types::TokenSortBy::TransactionsDesc => iter.sorted_by_key(|(_, v)| v.proto.transactions).rev().collect_vec(),
+        types::TokenSortBy::CompletionAsc => iter.sorted_by_key(|(k, _)| completion.get(k)).collect_vec(),
+        types::TokenSortBy::CompletionDesc => iter.sorted_by_key(|(k, _)| completion.get(k)).rev().collect_vec(),
     };
 
     let count = all.len();
@@ -34,25 +50,33 @@ pub async fn tokens(State(server): State<Arc<Server>>, Query(args): Query<types:
         .iter()
         .skip((args.page - 1) * args.page_size)
         .take(args.page_size)
-        .map(|(_, v)| types::Token {
-            height: v.proto.height,
-            created: v.proto.created,
-            mint_percent: v.proto.mint_percent().to_string(),
-            tick: v.proto.tick.into(),
-            genesis: v.genesis.into(),
-            deployer: fullhash_to_address_str(&v.proto.deployer, server.db.fullhash_to_address.get(v.proto.deployer)),
-            transactions: v.proto.transactions,
-            mint_count: v.proto.mint_count,
-            holders: server.holders.holders_by_tick(&v.proto.tick).unwrap_or(0) as u32,
-            supply: v.proto.supply,
-            completed: v.proto.is_completed(),
-            max: v.proto.max,
-            lim: v.proto.lim,
-            dec: v.proto.dec,
+        .map(|(k, v)| {
+            let (completed_height, first_mint, minted_out_in_blocks) = mint_progress(&server, k);
+            types::Token {
+                height: v.proto.height,
+                created: v.proto.created,
+                mint_percent: v.proto.mint_percent().to_string(),
+                tick: v.proto.tick.into(),
+                genesis: v.genesis.into(),
+                deployer: fullhash_to_address_str(&v.proto.deployer, server.db.fullhash_to_address.get(v.proto.deployer)),
+                transactions: v.proto.transactions,
+                mint_count: v.proto.mint_count,
+                holders: server.holders.holders_by_tick(&v.proto.tick).unwrap_or(0) as u32,
+                supply: v.proto.supply,
+                completed: v.proto.is_completed(),
+                completed_height,
+                first_mint,
+                minted_out_in_blocks,
+                max: v.proto.max,
+                lim: v.proto.lim,
+                dec: v.proto.dec,
+                extras: None,
+                lookalikes: None,
+            }
         })
         .collect_vec();
 
-    Ok(Json(types::TokensResult { count, pages, tokens }))
+    Ok(Projected(types::TokensResult { count, pages, tokens }, fields))
 }
 
 pub fn tokens_docs(op: TransformOperation) -> TransformOperation {
@@ -60,6 +84,11 @@ pub fn tokens_docs(op: TransformOperation) -> TransformOperation {
 }
 
 pub async fn token(State(server): State<Arc<Server>>, Query(args): Query<types::TokenArgs>) -> ApiResult<impl IntoApiResponse> {
+    Ok(Json(token_info(&server, args)?))
+}
+
+/// Shared by `GET /token` and the `get_token` RPC method
+pub(super) fn token_info(server: &Server, args: types::TokenArgs) -> ApiResult<types::Token> {
     args.validate().bad_request_from_error()?;
 
     let lower_case_token_tick: LowerCaseTokenTick = args.tick.into();
@@ -67,31 +96,172 @@ pub async fn token(State(server): State<Arc<Server>>, Query(args): Query<types::
         .db
         .token_to_meta
         .get(lower_case_token_tick.clone())
-        .map(|v| types::Token {
-            height: v.proto.height,
-            created: v.proto.created,
-            deployer: fullhash_to_address_str(&v.proto.deployer, server.db.fullhash_to_address.get(v.proto.deployer)),
-            transactions: v.proto.transactions,
-            mint_count: v.proto.mint_count,
-            holders: server.holders.holders_by_tick(&v.proto.tick).unwrap_or(0) as u32,
-            tick: v.proto.tick.into(),
-            genesis: v.genesis.into(),
-            supply: v.proto.supply,
-            mint_percent: v.proto.mint_percent().to_string(),
-            completed: v.proto.is_completed(),
-            max: v.proto.max,
-            lim: v.proto.lim,
-            dec: v.proto.dec,
+        .map(|v| {
+            let (completed_height, first_mint, minted_out_in_blocks) = mint_progress(server, &lower_case_token_tick);
+            types::Token {
+                height: v.proto.height,
+                created: v.proto.created,
+                deployer: fullhash_to_address_str(&v.proto.deployer, server.db.fullhash_to_address.get(v.proto.deployer)),
+                transactions: v.proto.transactions,
+                mint_count: v.proto.mint_count,
+                holders: server.holders.holders_by_tick(&v.proto.tick).unwrap_or(0) as u32,
+                tick: v.proto.tick.into(),
+                genesis: v.genesis.into(),
+                supply: v.proto.supply,
+                mint_percent: v.proto.mint_percent().to_string(),
+                completed: v.proto.is_completed(),
+                completed_height,
+                first_mint,
+                minted_out_in_blocks,
+                max: v.proto.max,
+                lim: v.proto.lim,
+                dec: v.proto.dec,
+                extras: server.db.token_extras.get(&lower_case_token_tick).and_then(|x| serde_json::from_str(&x).ok()),
+                lookalikes: Some(server.db.token_lookalikes(v.proto.tick).into_iter().map(Into::into).collect()),
+            }
         })
         .not_found(format!("Tick {} not found", args.tick))?;
 
-    Ok(Json(token))
+    server.access_stats.hit(token.tick.into());
+
+    Ok(token)
 }
 
 pub fn token_docs(op: TransformOperation) -> TransformOperation {
     op.description("Detailed information about a token").tag("token")
 }
 
+pub async fn token_overview(State(server): State<Arc<Server>>, Path(tick): Path<OriginalTokenTickRest>) -> ApiResult<impl IntoApiResponse> {
+    let token = token_info(&server, types::TokenArgs { tick })?;
+    let meta = server.db.token_to_meta.get(LowerCaseTokenTick::from(tick)).not_found(format!("Tick {tick} not found"))?;
+
+    let holders = holders::holders_page(
+        &server,
+        types::HoldersArgs {
+            page_size: OVERVIEW_HOLDERS,
+            page: 1,
+            tick,
+            height: None,
+            min_balance: None,
+            address: None,
+        },
+    )?;
+
+    let from = TokenId { token: meta.proto.tick, id: 0 };
+    let to = TokenId {
+        token: meta.proto.tick,
+        id: u64::MAX,
+    };
+
+    let keys = server.db.token_id_to_event.range(&from..=&to, true).take(OVERVIEW_EVENTS).map(|x| x.1).collect_vec();
+    let rows = server
+        .db
+        .address_token_to_history
+        .multi_get_kv(keys.iter(), false)
+        .into_iter()
+        .map(|(k, v)| (*k, v))
+        .collect_vec();
+
+    let events = types::AddressHistory::new_page(rows, &server).internal("Couldn't found block for history entry")?;
+
+    Ok(Json(types::TokenOverview {
+        token,
+        stats: types::TokenStats {
+            tick: meta.proto.tick.into(),
+            mint_count: meta.proto.mint_count,
+            mint_fees: server.db.token_mint_fees.get(LowerCaseTokenTick::from(tick)).unwrap_or_default(),
+        },
+        holders,
+        events,
+    }))
+}
+
+pub fn token_overview_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Token info, stats, top holders and last events for a token page in one call").tag("token")
+}
+
+pub async fn deployer_tokens(State(server): State<Arc<Server>>, Path(address): Path<String>) -> ApiResult<impl IntoApiResponse> {
+    let deployer: FullHash = server.indexer.to_scripthash(&address, ScriptType::Address).bad_request_from_error()?.into();
+
+    let keys = server.db.deployer_tokens(deployer).into_iter().map(LowerCaseTokenTick::from).collect_vec();
+    let tokens = server
+        .db
+        .token_to_meta
+        .multi_get(keys.iter())
+        .into_iter()
+        .flatten()
+        .sorted_by_key(|v| v.proto.created)
+        .map(|v| {
+            let (completed_height, first_mint, minted_out_in_blocks) = mint_progress(&server, &v.proto.tick.into());
+            types::Token {
+                height: v.proto.height,
+                created: v.proto.created,
+                mint_percent: v.proto.mint_percent().to_string(),
+                tick: v.proto.tick.into(),
+                genesis: v.genesis.into(),
+                deployer: address.clone(),
+                transactions: v.proto.transactions,
+                mint_count: v.proto.mint_count,
+                holders: server.holders.holders_by_tick(&v.proto.tick).unwrap_or(0) as u32,
+                supply: v.proto.supply,
+                completed: v.proto.is_completed(),
+                completed_height,
+                first_mint,
+                minted_out_in_blocks,
+                max: v.proto.max,
+                lim: v.proto.lim,
+                dec: v.proto.dec,
+                extras: None,
+                lookalikes: None,
+            }
+        })
+        .collect_vec();
+
+    Ok(Json(types::DeployerTokens {
+        count: tokens.len(),
+        completed: tokens.iter().filter(|x| x.completed).count(),
+        holders: tokens.iter().map(|x| x.holders as u64).sum(),
+        deployer: address,
+        tokens,
+    }))
+}
+
+pub fn deployer_tokens_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Tokens deployed by the address with their supply and holders").tag("token")
+}
+
+/// Completion height, first mint and the number of blocks between them
+fn mint_progress(server: &Server, tick: &LowerCaseTokenTick) -> (Option<u32>, Option<types::FirstMint>, Option<u32>) {
+    let completed_height = server.db.token_completion_height.get(tick);
+    let first_mint = server.db.token_first_mint.get(tick);
+    let minted_out_in_blocks = completed_height.zip(first_mint.as_ref()).map(|(completed, first)| completed.saturating_sub(first.height));
+
+    let first_mint = first_mint.map(|x| types::FirstMint {
+        height: x.height,
+        txid: x.txid.into(),
+        address: fullhash_to_address_str(&x.minter, server.db.fullhash_to_address.get(x.minter)),
+    });
+
+    (completed_height, first_mint, minted_out_in_blocks)
+}
+
+pub async fn trending_tokens(State(server): State<Arc<Server>>, Query(args): Query<types::TrendingTokensArgs>) -> ApiResult<impl IntoApiResponse> {
+    args.validate().bad_request_from_error()?;
+
+    let data = server
+        .access_stats
+        .trending(&server.db, args.window.hours(), args.limit)
+        .into_iter()
+        .map(|(tick, queries)| types::TrendingToken { tick: tick.into(), queries })
+        .collect_vec();
+
+    Ok(Json(data))
+}
+
+pub fn trending_tokens_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Most queried tokens for the time window").tag("token")
+}
+
 pub async fn token_supplies(State(server): State<Arc<Server>>, Json(ticks): Json<Vec<OriginalTokenTickRest>>) -> ApiResult<impl IntoApiResponse> {
     let keys = ticks.into_iter().map(LowerCaseTokenTick::from).collect_vec();
     let res = server
@@ -115,24 +285,54 @@ pub async fn token_transfer_proof(State(state): State<Arc<Server>>, Path((addres
 
     let (from, to) = AddressLocation::search_with_offset(scripthash.into(), outpoint.into()).into_inner();
 
+    Ok(Json(blocking(move || transfer_proofs(&state, from, to)).await?))
+}
+
+/// Transfers in `from..to` as of the best block of the node, polled until the indexer has it
+fn transfer_proofs(state: &Server, from: AddressLocation, to: AddressLocation) -> ApiResult<Vec<types::TokenTransferProof>> {
     let start = Instant::now();
 
     while start.elapsed() < Duration::from_secs(5) {
+        // A rollback removes transfers created in the rolled back blocks and restores the ones spent there
+        if let Some(height) = state.reorg_in_progress.height() {
+            let res = axum::response::Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header(axum::http::header::RETRY_AFTER, REORG_RETRY_AFTER_SECS)
+                .body(format!("Reorg is in progress, blocks from {height} are being rolled back"))
+                .internal("Failed to build body for the response")?;
+
+            return Err(res);
+        }
+
         let best_block_hash = state.client.get_best_block_hash().internal("Failed to connect to node")?;
         let last_block = state.db.last_block.get(()).internal("Failed to get last block")?;
-        let last_block_hash: sha256d::Hash = state.db.block_info.get(last_block).internal("Failed to get last block info")?.hash.into();
+        let last_block_info = state.db.block_info.get(last_block).internal("Failed to get last block info")?;
+        let last_block_hash: sha256d::Hash = last_block_info.hash.into();
 
         if best_block_hash == last_block_hash {
             let data: Vec<_> = state
                 .db
                 .address_location_to_transfer
                 .range(&from..&to, false)
-                .map(|(_, TransferProtoDB { tick, amt, height })| anyhow::Ok(types::TokenTransferProof { amt, tick: tick.into(), height }))
+                .map(|(k, TransferProtoDB { tick, amt, height })| {
+                    anyhow::Ok(types::TokenTransferProof {
+                        amt,
+                        tick: tick.into(),
+                        height,
+                        tip_height: last_block,
+                        valid_as_of: last_block_info.hash.to_string(),
+                        spent_in_mempool: state.spent_in_mempool(&k.location.outpoint)?,
+                    })
+                })
                 .try_collect()
                 .track_with("")
                 .internal(INTERNAL)?;
 
-            return Ok(Json(data));
+            // Transfers are read again if a block was indexed or rolled back while they were read
+            let tip = state.db.last_block.get(()).and_then(|height| state.db.block_info.get(height)).map(|info| info.hash);
+            if state.reorg_in_progress.height().is_none() && tip == Some(last_block_info.hash) {
+                return Ok(data);
+            }
         }
     }
 
@@ -145,14 +345,20 @@ pub async fn token_transfer_proof(State(state): State<Arc<Server>>, Path((addres
 }
 
 pub fn token_transfer_proof_docs(op: TransformOperation) -> TransformOperation {
-    op.description("Verifies a transfer by address and outpoint").tag("token")
+    op.description("Verifies a transfer by address and outpoint as of the returned tip, `503` is returned while a reorg is rolled back").tag("token")
 }
 
 pub async fn token_events(
     State(server): State<Arc<Server>>,
     Path(token): Path<OriginalTokenTickRest>,
     Query(args): Query<types::TokenEventsArgs>,
+    Query(fields): Query<FieldsArgs>,
 ) -> ApiResult<impl IntoApiResponse> {
+    let lower_case_token_tick: LowerCaseTokenTick = token.into();
+    if server.db.token_to_meta.get(lower_case_token_tick).is_some_and(|x| x.proto.tick == OriginalTokenTick::from(token)) {
+        server.access_stats.hit(token.into());
+    }
+
     if let Some(outpoint_str) = args.search {
         let txid = Txid::from_str(&outpoint_str[..64.min(outpoint_str.len())]).bad_request_from_error()?;
 
@@ -172,33 +378,39 @@ pub async fn token_events(
             vout: vout.unwrap_or(u32::MAX),
         };
 
-        let v = server
+        let keys = server.db.outpoint_to_event.range(&from..=&to, false).take(args.limit).map(|x| x.1).collect_vec();
+        let rows = server
             .db
-            .outpoint_to_event
-            .range(&from..=&to, false)
-            .take(args.limit)
-            .flat_map(|(_, x)| server.db.address_token_to_history.get(x).map(|v| (x, v)))
-            .map(|(k, v)| types::AddressHistory::new(v.height, v.action, k, &server))
-            .collect::<Result<Vec<_>, _>>()
-            .internal("Couldn't found block for history entry")?;
-
-        Ok(Json(v))
+            .address_token_to_history
+            .multi_get_kv(keys.iter(), false)
+            .into_iter()
+            .map(|(k, v)| (*k, v))
+            .collect_vec();
+
+        let v = types::AddressHistory::new_page(rows, &server).internal("Couldn't found block for history entry")?;
+
+        Ok(Projected(v, fields))
     } else {
-        let from = TokenId { id: 0, token: token.into() };
+        let ids = server.db.history_ids_by_time(args.from_ts, args.to_ts);
+        let offset = args.offset.unwrap_or(u64::MAX).min(ids.end);
+        if ids.start >= offset {
+            return Ok(Projected(Vec::<types::AddressHistory>::new(), fields));
+        }
 
-        let offset = args.offset.unwrap_or(u64::MAX);
+        let from = TokenId { id: ids.start, token: token.into() };
         let to = TokenId { id: offset, token: token.into() };
 
         let keys = server.db.token_id_to_event.range(&from..&to, true).take(args.limit).map(|x| x.1).collect_vec();
-        let history = server
+        let rows = server
             .db
             .address_token_to_history
             .multi_get_kv(keys.iter(), false)
             .into_iter()
-            .map(|(k, v)| types::AddressHistory::new(v.height, v.action, *k, &server))
-            .collect::<Result<Vec<_>, _>>()
-            .internal("Couldn't found block for history entry")?;
-        Ok(Json(history))
+            .map(|(k, v)| (*k, v))
+            .collect_vec();
+
+        let history = types::AddressHistory::new_page(rows, &server).internal("Couldn't found block for history entry")?;
+        Ok(Projected(history, fields))
     }
 }
 
@@ -0,0 +1,199 @@
+use bellscoin::hashes::hex::FromHex;
+use nint_blk::ScriptType;
+
+use super::*;
+
+// Virtual sizes of the parts of transactions with taproot key-path inputs and outputs, the overhead includes the segwit marker
+const TAPROOT_TX_OVERHEAD: u64 = 11;
+const TAPROOT_INPUT: u64 = 58;
+const TAPROOT_OUTPUT: u64 = 43;
+// Sizes of the parts of transactions with P2PKH inputs and outputs, inscriptions are committed to a P2SH output
+const LEGACY_TX_OVERHEAD: u64 = 10;
+const P2PKH_INPUT: u64 = 148;
+const P2PKH_OUTPUT: u64 = 34;
+const P2SH_OUTPUT: u64 = 32;
+/// Input without its script: outpoint, sequence and script length
+const BARE_INPUT: u64 = 41;
+/// Largest push of inscription data
+const MAX_PUSH: usize = 520;
+/// Satoshis per vbyte in a fee rate of one coin per kvB
+const SATS_PER_VBYTE: f64 = 100_000.0;
+
+pub async fn broadcast(State(server): State<Arc<Server>>, Json(args): Json<types::BroadcastArgs>) -> ApiResult<impl IntoApiResponse> {
+    let tx_bytes = Vec::<u8>::from_hex(args.hex.trim()).bad_request("Invalid transaction hex")?;
+    let tx = server.client.parse_tx(&tx_bytes).bad_request("Invalid transaction")?;
+
+    let preview = args.preview.then(|| crate::inscriptions::preview_tx(&server, &tx)).flatten().map(|actions| {
+        let keys = actions.iter().flat_map(|action| [Some(action.recipient()), action.sender()]).flatten().collect::<HashSet<_>>();
+
+        let addresses: AddressesFullHash = server
+            .db
+            .fullhash_to_address
+            .multi_get_kv(keys.iter(), false)
+            .into_iter()
+            .map(|(k, v)| (*k, v))
+            .chain(tx.value.outputs.iter().filter_map(|x| {
+                x.script
+                    .address
+                    .as_ref()
+                    .map(|address| (FullHash::from(sha256::Hash::hash(&x.out.script_pubkey)), address.to_owned()))
+            }))
+            .collect::<HashMap<_, _>>()
+            .into();
+
+        actions
+            .into_iter()
+            .map(|action| types::ActionPreview {
+                tick: action.tick().into(),
+                address: addresses.get(&action.sender().unwrap_or(action.recipient())),
+                action: types::TokenAction::from_with_addresses(TokenHistoryDB::from_token_history(action), &addresses),
+            })
+            .collect()
+    });
+
+    let txid = server
+        .client
+        .send_raw_transaction(args.hex.trim())
+        .map_err(|e| anyhow::anyhow!("Transaction rejected: {e}"))
+        .bad_request_from_error()?;
+
+    Ok(Json(types::BroadcastResult {
+        txid: txid.to_string(),
+        preview,
+    }))
+}
+
+pub fn broadcast_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Relays a raw transaction to the node, optionally with a preview of the token actions it would trigger if confirmed")
+        .tag("transaction")
+}
+
+pub async fn estimate_transfer(State(server): State<Arc<Server>>, Query(args): Query<types::EstimateTransferArgs>) -> ApiResult<impl IntoApiResponse> {
+    args.validate().bad_request_from_error()?;
+    (args.amt > Fixed128::ZERO).then_some(()).bad_request("Amount must be positive")?;
+
+    let scripthash: FullHash = server.indexer.to_scripthash(&args.address, ScriptType::Address).bad_request_from_error()?.into();
+    let meta = server.db.token_to_meta.get(LowerCaseTokenTick::from(args.tick)).not_found(format!("Tick {} not found", args.tick))?;
+    let tick = meta.proto.tick;
+
+    let balance = server.db.address_token_to_balance.get(AddressToken { address: scripthash, token: tick }).unwrap_or_default();
+
+    // A transfer inscription moves its whole amount, so only one with exactly the amount can be reused
+    let (from, to) = AddressLocation::search(scripthash, None).into_inner();
+    let amt = args.amt;
+    let transfer = blocking({
+        let server = server.clone();
+        move || {
+            server
+                .db
+                .address_location_to_transfer
+                .range(&from..&to, false)
+                .filter(|(_, v)| v.tick == tick && v.amt == amt)
+                .map(|(k, v)| {
+                    anyhow::Ok(TokenTransfer {
+                        amount: v.amt,
+                        spent_in_mempool: server.spent_in_mempool(&k.location.outpoint)?,
+                        outpoint: k.location.outpoint.into(),
+                    })
+                })
+                .find(|x| !matches!(x, Ok(TokenTransfer { spent_in_mempool: Some(true), .. })))
+                .transpose()
+                .internal("Failed to check mempool")
+        }
+    })
+    .await?;
+
+    (transfer.is_some() || balance.balance >= args.amt)
+        .then_some(())
+        .bad_request(format!("Available balance {} is less than {}", balance.balance, args.amt))?;
+
+    // Estimates are optional on the node, sizes are returned without fees then
+    let fee_rate = server.client.estimate_smart_fee(args.blocks).ok().and_then(|x| x.feerate).map(|x| x * SATS_PER_VBYTE);
+    let estimate = |vsize: u64| types::TxEstimate {
+        vsize,
+        fee: fee_rate.map(|rate| (vsize as f64 * rate).ceil() as u64),
+    };
+
+    let inscribe = transfer.is_none().then(|| {
+        let protocol = COIN.protocol.unwrap_or(BLOCKCHAIN.protocol());
+        let body = format!(r#"{{"p":"{}","op":"transfer","tick":"{}","amt":"{}"}}"#, protocol, tick, args.amt);
+        let (commit, reveal) = inscribe_vsizes("text/plain;charset=utf-8", body.as_bytes());
+
+        types::InscribeEstimate {
+            commit: estimate(commit),
+            reveal: estimate(reveal),
+        }
+    });
+
+    let send = estimate(send_vsize());
+    let total_fee = inscribe
+        .iter()
+        .flat_map(|x| [x.commit.fee, x.reveal.fee])
+        .chain([send.fee])
+        .sum::<Option<u64>>();
+
+    Ok(Json(types::TransferEstimate {
+        tick: tick.into(),
+        amt: args.amt,
+        transfer,
+        inscribe,
+        send,
+        fee_rate,
+        total_fee,
+    }))
+}
+
+pub fn estimate_transfer_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Sizes and fees of the transactions which send an amount of a token from the address, with the node fee estimate")
+        .tag("transaction")
+}
+
+fn push_len(len: usize) -> u64 {
+    len as u64
+        + match len {
+            0..=75 => 1,
+            76..=255 => 2,
+            _ => 3,
+        }
+}
+
+/// Envelope with the inscription: `ord` tag, content type tag and content type, body separator, body pushes and the end
+fn envelope_len(content_type: &str, body: &[u8]) -> u64 {
+    let body_len = body.chunks(MAX_PUSH).map(|x| push_len(x.len())).sum::<u64>();
+    2 + push_len(3) + 2 + push_len(content_type.len()) + 1 + body_len + 1
+}
+
+/// Virtual sizes of the commit with a funding input and change, and of the reveal with the inscription output
+fn inscribe_vsizes(content_type: &str, body: &[u8]) -> (u64, u64) {
+    let envelope = envelope_len(content_type, body);
+
+    if BLOCKCHAIN.has_taproot() {
+        let commit = TAPROOT_TX_OVERHEAD + TAPROOT_INPUT + 2 * TAPROOT_OUTPUT;
+
+        // Signature, leaf script with the key and the envelope, control block
+        let script = 34 + envelope;
+        let witness = 1 + push_len(64) + push_len(script as usize) + push_len(33);
+        let reveal = TAPROOT_TX_OVERHEAD + BARE_INPUT + TAPROOT_OUTPUT + witness.div_ceil(4);
+
+        (commit, reveal)
+    } else {
+        let commit = LEGACY_TX_OVERHEAD + P2PKH_INPUT + P2SH_OUTPUT + P2PKH_OUTPUT;
+
+        // Envelope, signature and the redeem script checking it
+        let script_sig = envelope + push_len(72) + push_len(37);
+        // Script length above 252 bytes takes 3 bytes instead of the one in `BARE_INPUT`
+        let script_sig_len = if script_sig > 252 { 2 } else { 0 };
+        let reveal = LEGACY_TX_OVERHEAD + BARE_INPUT + script_sig_len + script_sig + P2PKH_OUTPUT;
+
+        (commit, reveal)
+    }
+}
+
+/// Virtual size of the transaction spending the transfer inscription and a funding input to the recipient and change
+fn send_vsize() -> u64 {
+    if BLOCKCHAIN.has_taproot() {
+        TAPROOT_TX_OVERHEAD + 2 * TAPROOT_INPUT + 2 * TAPROOT_OUTPUT
+    } else {
+        LEGACY_TX_OVERHEAD + 2 * P2PKH_INPUT + 2 * P2PKH_OUTPUT
+    }
+}
@@ -21,6 +21,10 @@ pub struct TokenEventsArgs {
     pub limit: usize,
     /// Search by txid or outpoint
     pub search: Option<String>,
+    /// Events of blocks created at or after this unix timestamp, rounded down to an hour
+    pub from_ts: Option<u32>,
+    /// Events of blocks created at or before this unix timestamp, rounded up to an hour
+    pub to_ts: Option<u32>,
 }
 
 /// Address token history query arguments
@@ -33,6 +37,10 @@ pub struct AddressTokenHistoryArgs {
     #[validate(range(min = 1, max = 100))]
     pub limit: usize,
     pub tick: OriginalTokenTickRest,
+    /// Events of blocks created at or after this unix timestamp, rounded down to an hour
+    pub from_ts: Option<u32>,
+    /// Events of blocks created at or before this unix timestamp, rounded up to an hour
+    pub to_ts: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -41,6 +49,59 @@ pub struct SubscribeArgs {
     pub addresses: Option<HashSet<String>>,
     #[serde(default)]
     pub tokens: Option<HashSet<OriginalTokenTickRest>>,
+    /// Token event types to receive, new block and reorg events are always sent
+    #[serde(default)]
+    pub event_types: Option<HashSet<TokenEventType>>,
+    /// Skip token events with an amount below this threshold (deploys have no amount and aren't affected)
+    #[serde(default)]
+    pub min_amount: Option<Fixed128>,
+    /// Receive mints of these tokens only, other event types aren't affected
+    #[serde(default)]
+    pub mint_tokens: Option<HashSet<OriginalTokenTickRest>>,
+    /// Thresholds for an address, a token or both, replacing `min_amount` for the events they match
+    #[serde(default)]
+    pub min_amounts: Option<Vec<AmountThreshold>>,
+}
+
+impl SubscribeArgs {
+    pub fn check_min_amounts(&self) -> anyhow::Result<()> {
+        if self.min_amounts.iter().flatten().any(|x| x.address.is_none() && x.tick.is_none()) {
+            anyhow::bail!("Threshold needs an address or a tick, use min_amount for all events");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AmountThreshold {
+    pub address: Option<String>,
+    pub tick: Option<OriginalTokenTickRest>,
+    pub min_amount: Fixed128,
+}
+
+/// Value of the `type` field of token events
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, schemars::JsonSchema)]
+pub enum TokenEventType {
+    Deploy,
+    Mint,
+    DeployTransfer,
+    Send,
+    Receive,
+    SendReceive,
+}
+
+impl From<&server::TokenHistoryEvent> for TokenEventType {
+    fn from(value: &server::TokenHistoryEvent) -> Self {
+        match value {
+            server::TokenHistoryEvent::Deploy { .. } => Self::Deploy,
+            server::TokenHistoryEvent::Mint { .. } => Self::Mint,
+            server::TokenHistoryEvent::DeployTransfer { .. } => Self::DeployTransfer,
+            server::TokenHistoryEvent::Send { .. } => Self::Send,
+            server::TokenHistoryEvent::Receive { .. } => Self::Receive,
+            server::TokenHistoryEvent::SendReceive { .. } => Self::SendReceive,
+        }
+    }
 }
 
 #[derive(Serialize, schemars::JsonSchema)]
@@ -55,14 +116,595 @@ pub struct Status {
     pub version: String,
     /// Uptime of the indexer in seconds
     pub uptime_secs: u64,
+    /// Cache of token balances, `None` if `TOKEN_CACHE_MB` isn't set
+    pub token_cache: Option<TokenCacheStats>,
+    /// Hard limits of the inscription parser
+    pub parser_limits: ParserLimits,
+}
+
+/// `None` if unlimited
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct ParserLimits {
+    /// Parts of a multi-part inscription
+    pub max_partial_parts: Option<usize>,
+    /// Bytes of the content of one inscription
+    pub max_inscription_bytes: Option<usize>,
+    /// Inscriptions created by one transaction
+    pub max_inscriptions_per_tx: Option<usize>,
+    /// Envelopes in the script of one input
+    pub max_envelopes_per_input: Option<usize>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct TokenCacheStats {
+    /// Number of cached balances
+    pub accounts: usize,
+    /// Max number of cached balances within the budget
+    pub capacity: usize,
+    /// Approximate memory used by the cached balances
+    pub memory_bytes: usize,
+    /// Balance reads served from memory since startup
+    pub hits: u64,
+    /// Balance reads which went to the database since startup
+    pub misses: u64,
+    pub hit_rate: f64,
+}
+
+#[derive(Serialize, Default, schemars::JsonSchema)]
+pub struct PartialsStats {
+    /// Number of stored partials
+    pub count: u64,
+    /// Total size of stored partials in bytes
+    pub total_bytes: u64,
+    /// Partials whose outpoint is spent without continuation
+    pub spent: u64,
+    /// Height of the oldest partial with a recorded height
+    pub oldest_height: Option<u32>,
+    /// Partials written before heights were recorded
+    pub unknown_height: u64,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct WebhookArgs {
+    /// `http://` or `https://` URL receiving `POST` requests with the events
+    pub url: String,
+    pub tick: OriginalTokenTickRest,
+    /// Token event types to deliver, all if empty
+    #[serde(default)]
+    pub event_types: HashSet<TokenEventType>,
+    /// Key of the `X-Webhook-Signature` HMAC-SHA256, at least 16 characters
+    pub secret: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct WebhookCreated {
+    pub id: u64,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct Backup {
+    /// `{height}-{created}`, the directory of the checkpoint in `BACKUP_DIR`
+    pub name: String,
+    /// Last block when the checkpoint was taken
+    pub height: u32,
+    /// Unix timestamp of the checkpoint
+    pub created: u64,
+    /// Size of the files, which are hard links shared with the database until they are compacted away
+    pub size_bytes: u64,
+    /// Unix timestamp of the upload to `BACKUP_S3_URL`
+    pub uploaded: Option<u64>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct PruneBackupsArgs {
+    /// Number of newest backups to keep
+    pub keep: usize,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct PrunedBackups {
+    pub removed: Vec<String>,
+}
+
+/// Audit log query arguments
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+pub struct AdminAuditArgs {
+    /// Id of the last entry from the previous page
+    pub offset: Option<u64>,
+    /// Limit of the number of entries to return.
+    #[serde(default = "utils::page_size_default")]
+    #[validate(range(min = 1, max = 100))]
+    pub limit: usize,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct AdminAuditEntry {
+    pub id: u64,
+    /// Unix timestamp of the request
+    pub time: u64,
+    /// Value of the `X-Admin-Operator` header
+    pub operator: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    /// Request body, `secret` fields are redacted
+    pub body: Option<String>,
+    /// Response status
+    pub status: u16,
+}
+
+/// Supply audits query arguments
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+pub struct SupplyAuditArgs {
+    /// Id of the last report from the previous page
+    pub offset: Option<u64>,
+    /// Limit of the number of reports to return.
+    #[serde(default = "utils::page_size_default")]
+    #[validate(range(min = 1, max = 100))]
+    pub limit: usize,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct SupplyAudit {
+    pub id: u64,
+    /// Last block of the audited checkpoint
+    pub height: u32,
+    /// Unix timestamp of the end of the audit
+    pub created: u64,
+    pub duration_ms: u64,
+    /// Number of audited tokens
+    pub tokens: u64,
+    /// Tokens which counters differ from their history, empty if all match
+    pub discrepancies: Vec<SupplyDiscrepancy>,
+    /// Set if the audit couldn't be done
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct SupplyDiscrepancy {
+    pub tick: OriginalTokenTickRest,
+    /// Counters of the deploy
+    pub stored: SupplyCountersRest,
+    /// Counters recomputed from the history
+    pub computed: SupplyCountersRest,
 }
 
 #[derive(Serialize, schemars::JsonSchema)]
+pub struct SupplyCountersRest {
+    pub supply: Fixed128,
+    pub mint_count: u64,
+    pub transfer_count: u64,
+}
+
+impl From<SupplyCounters> for SupplyCountersRest {
+    fn from(value: SupplyCounters) -> Self {
+        Self {
+            supply: value.supply,
+            mint_count: value.mint_count,
+            transfer_count: value.transfer_count,
+        }
+    }
+}
+
+/// Rejected deploys query arguments
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+pub struct RejectedDeploysArgs {
+    /// Height of the last block from the previous page
+    pub offset: Option<u32>,
+    /// Limit of the number of deploys to return, pages end with a whole block so they can have more.
+    #[serde(default = "utils::page_size_default")]
+    #[validate(range(min = 1, max = 100))]
+    pub limit: usize,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct RejectedDeploy {
+    pub height: u32,
+    /// Unix timestamp of the block
+    pub created: u32,
+    pub tick: OriginalTokenTickRest,
+    pub genesis: RestInscriptionId,
+    pub deployer: String,
+    pub reason: RejectedDeployReason,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct Webhook {
+    pub id: u64,
+    pub url: String,
+    pub tick: OriginalTokenTickRest,
+    pub event_types: Vec<TokenEventType>,
+    /// Number of requests accepted by the receiver
+    pub delivered: u64,
+    /// Number of requests dropped after all retries failed
+    pub failed: u64,
+    /// Unix timestamp of the last request
+    pub last_attempt: Option<u64>,
+    /// Unix timestamp of the last accepted request
+    pub last_success: Option<u64>,
+    pub last_error: Option<String>,
+    /// Height of the last delivered block
+    pub last_height: Option<u32>,
+}
+
+/// Body of webhook requests, events of one block
+#[derive(Serialize)]
+pub struct WebhookPayload {
+    pub webhook_id: u64,
+    pub height: u32,
+    pub events: Vec<History>,
+}
+
+/// Line of the NDJSON sent to `REPLICATION_URL`
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplicationRecord {
+    Block {
+        height: u32,
+        blockhash: String,
+        created: u32,
+        proof: String,
+        events: Vec<History>,
+    },
+    /// Blocks above the height are rolled back and are sent again
+    Reorg { height: u32 },
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct SimulateReorgArgs {
+    /// Number of the last blocks to roll back and index again, up to the number of blocks in the reorg cache
+    pub depth: u32,
+}
+
+/// Hot-standby state, `standby` is false for a primary and after the promotion
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct StandbyStatus {
+    pub standby: bool,
+    /// Last block indexed by this indexer
+    pub height: Option<u32>,
+    /// Last block received from the primary, blocks which may still be rolled back are kept after they're verified
+    pub primary_height: Option<u32>,
+    /// Blocks up to it have the same proof of history as on the primary, since the start of the standby
+    pub verified_height: Option<u32>,
+    /// Blocks whose proof of history differed from the primary
+    pub mismatches: u64,
+    pub last_mismatch: Option<u32>,
+}
+
+impl StandbyStatus {
+    pub fn new(server: &Server) -> Self {
+        let parity = server.standby.parity();
+
+        Self {
+            standby: server.standby.is_active(),
+            height: server.db.last_block.get(()),
+            primary_height: server.db.standby_primary_hash.range(.., true).next().map(|(height, _)| height),
+            verified_height: parity.verified_height,
+            mismatches: parity.mismatches,
+            last_mismatch: parity.last_mismatch,
+        }
+    }
+}
+
+/// Report of a reorg simulation, checks are named `rollback_*` and `replay_*` by the stage they run after
+#[derive(Serialize, Clone, schemars::JsonSchema)]
+pub struct ReorgSimulation {
+    pub depth: u32,
+    /// Last block kept by the rollback
+    pub restore_height: Option<u32>,
+    /// Last block before the rollback, indexed again by the replay
+    pub tip: Option<u32>,
+    pub rollback_ms: u64,
+    pub replay_ms: u64,
+    /// Every check passed
+    pub ok: bool,
+    /// Reason the simulation didn't run or was stopped
+    pub error: Option<String>,
+    pub checks: Vec<ReorgSimulationCheck>,
+}
+
+impl ReorgSimulation {
+    pub fn failed(depth: u32, error: String) -> Self {
+        Self {
+            depth,
+            restore_height: None,
+            tip: None,
+            rollback_ms: 0,
+            replay_ms: 0,
+            ok: false,
+            error: Some(error),
+            checks: vec![],
+        }
+    }
+}
+
+/// Report of `/admin/reprocess/{height}`, checks are named by the stored data the recomputed block is compared with
+#[derive(Serialize, Clone, schemars::JsonSchema)]
+pub struct BlockReprocess {
+    pub height: u32,
+    pub hash: Option<String>,
+    /// Time to restore the state before the block in the sandbox
+    pub restore_ms: u64,
+    pub process_ms: u64,
+    /// Every check passed
+    pub ok: bool,
+    /// Reason the block couldn't be reprocessed
+    pub error: Option<String>,
+    pub checks: Vec<ReorgSimulationCheck>,
+}
+
+impl BlockReprocess {
+    pub fn failed(height: u32, error: String) -> Self {
+        Self {
+            height,
+            hash: None,
+            restore_ms: 0,
+            process_ms: 0,
+            ok: false,
+            error: Some(error),
+            checks: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Clone, schemars::JsonSchema)]
+pub struct ReorgSimulationCheck {
+    pub name: String,
+    pub ok: bool,
+    /// Expected and actual values of a failed check
+    pub details: Option<String>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct BroadcastArgs {
+    /// Raw transaction in hex
+    pub hex: String,
+    /// Return the token actions the transaction would trigger if confirmed in the next block
+    #[serde(default)]
+    pub preview: bool,
+}
+
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+pub struct EstimateTransferArgs {
+    pub tick: OriginalTokenTickRest,
+    pub amt: Fixed128,
+    /// Address which sends the tokens
+    pub address: String,
+    /// Confirmation target of the fee rate in blocks
+    #[serde(default = "utils::fee_blocks_default")]
+    #[validate(range(min = 1, max = 1008))]
+    pub blocks: u16,
+}
+
+/// Size and fee of a transaction, fees of outputs like the inscription postage aren't included
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct TxEstimate {
+    pub vsize: u64,
+    /// Not set if the node has no fee estimate
+    pub fee: Option<u64>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct InscribeEstimate {
+    pub commit: TxEstimate,
+    pub reveal: TxEstimate,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct TransferEstimate {
+    pub tick: OriginalTokenTickRest,
+    pub amt: Fixed128,
+    /// Transfer inscription of the address with exactly `amt`, which is sent without inscribing a new one
+    pub transfer: Option<TokenTransfer>,
+    /// Commit and reveal of a new transfer inscription, not set if `transfer` is
+    pub inscribe: Option<InscribeEstimate>,
+    /// Transaction sending the transfer inscription with a funding input and a change output
+    pub send: TxEstimate,
+    /// Fee rate for confirmation within `blocks` blocks in satoshis per vbyte, not set if the node has no estimate
+    pub fee_rate: Option<f64>,
+    /// Sum of the fees, not set if the node has no fee estimate
+    pub total_fee: Option<u64>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct BroadcastResult {
+    pub txid: String,
+    /// Not set if the preview isn't requested or some of the inputs aren't confirmed unspent outputs.
+    /// Other unconfirmed transactions aren't taken into account.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<Vec<ActionPreview>>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct ValidateInscriptionArgs {
+    /// Content type of the inscription, e.g. `text/plain;charset=utf-8`
+    pub content_type: String,
+    /// Body of the inscription as text
+    #[serde(default)]
+    pub content: Option<String>,
+    /// Body of the inscription in hex, instead of `content`
+    #[serde(default)]
+    pub content_hex: Option<String>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct InscriptionValidation {
+    /// Whether the body is recognized as a token operation
+    pub valid: bool,
+    /// `deploy`, `mint` or `transfer`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub op: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tick: Option<String>,
+    /// Why the body isn't a token operation, e.g. `WrongContentType` or `DecimalOverflow`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Why the operation would be skipped at the current state of the token, e.g. `AlreadyDeployed` or `SupplyMinted`.
+    /// Balances aren't checked, since the owner isn't known before broadcasting.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct TxTraceArgs {
+    /// Height of the block with the transaction, required if the transaction has no token events
+    pub height: Option<u32>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct TxTrace {
+    pub txid: String,
+    pub height: u32,
+    /// Inscriptions are parsed from every input at or after the jubilee height, only from the first one before
+    pub jubilee: bool,
+    pub inputs: Vec<InputTrace>,
+    /// Events stored for the transaction, their absence after a recognized operation means token state rejected it
+    pub events: Vec<History>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct InputTrace {
+    pub input_index: u32,
+    pub outpoint: String,
+    /// `skipped` (not scanned before the jubilee height), `none`, `partial` (continued in a later transaction),
+    /// `rejected: <limit>` (over one of the parser limits) or `inscriptions`
+    pub envelope: String,
+    pub inscriptions: Vec<InscriptionTrace>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct InscriptionTrace {
+    /// Inscription id, the genesis txid differs for multi-part inscriptions started in an earlier transaction
+    pub id: String,
+    pub content_type: Option<String>,
+    pub body_size: usize,
+    /// Reasons the inscription would be cursed by ord, e.g. `duplicate_field`, `pointer` or `reinscription`. They don't affect token indexing
+    pub curses: Vec<InscriptionCurse>,
+    pub pointer: Option<u64>,
+    /// Whether the pointer moved the inscription, pointers beyond the outputs and all pointers of coins with `ignore_pointer` are ignored
+    pub pointer_applied: bool,
+    /// Sent to fees, the inscription ends up in the coinbase and can't be a token operation
+    pub leaked: bool,
+    pub location: Option<String>,
+    pub owner: Option<String>,
+    /// Another inscription of the transaction was created at the same location. Inscriptions moved into the transaction
+    /// aren't taken into account, since the state before the block isn't kept
+    pub reinscription: bool,
+    /// Reinscription which the indexer skipped
+    pub skipped: bool,
+    pub token: TokenTrace,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct TokenTrace {
+    /// Whether the inscription was passed to token validation
+    pub parsed: bool,
+    /// `deploy`, `mint` or `transfer`
+    pub op: Option<String>,
+    pub tick: Option<String>,
+    /// Why the inscription isn't a token operation, e.g. `WrongContentType`, `op_return` or `leaked`
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct ActionPreview {
+    pub tick: OriginalTokenTickRest,
+    /// Owner of the action, the sender for sends
+    pub address: String,
+    pub action: TokenAction,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct BatchSummary {
+    pub txid: String,
+    /// Number of transfers moved by the transaction
+    pub transfers: usize,
+    pub recipients: Vec<BatchRecipient>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct BatchRecipient {
+    pub tick: OriginalTokenTickRest,
+    pub sender: String,
+    pub recipient: String,
+    /// Total amount received in the transaction
+    pub amount: Fixed128,
+    /// Number of transfers received in the transaction
+    pub transfers: usize,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct BlockDetails {
+    pub height: u32,
+    pub hash: String,
+    /// Hash of the previous block, missing for blocks indexed by older versions
+    pub prev_hash: Option<String>,
+    /// Block timestamp (in seconds since UNIX epoch)
+    pub created: u32,
+    /// Median of the timestamps of the block and the 10 blocks before it, never decreases unlike `created`
+    pub median_time: Option<u32>,
+    /// Number of transactions, missing for blocks indexed by older versions
+    pub tx_count: Option<u32>,
+    /// Block size in bytes, missing for blocks indexed by older versions
+    pub size: Option<u32>,
+    /// Number of token events in the block
+    pub events: usize,
+}
+
+#[derive(Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ProofOfHistory {
     /// Height of the block
     pub height: u32,
     /// Proof of history of the block
     pub hash: String,
+    /// Encoding version of the block events, see `/proof-of-history/spec`
+    pub version: u8,
+    /// Root of the balance tree of the block, not set for blocks without events or indexed before balance trees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance_root: Option<String>,
+    /// Proof of the previous block, set with `links=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_proof: Option<String>,
+    /// Hash of the block events, `hash = sha256(prev_proof || history_hash)`. Set with `links=true` for blocks indexed since it's stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_hash: Option<String>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct ProofOfHistorySpec {
+    /// First block encoded with v2, v1 is used for all blocks if not set
+    pub v2_height: Option<u32>,
+    /// How the proof of the block is derived from the encoding of its events
+    pub block_proof: String,
+    pub versions: Vec<PohVersionSpec>,
+    /// How the balance root of the block is derived, verifies `/address/{address}/{tick}/balance-proof`
+    pub balance_tree: String,
+    /// How `/state-digest/{height}` is derived
+    pub state_digest: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct PohVersionSpec {
+    pub version: u8,
+    pub encoding: String,
+    /// Fields of every event in the order of encoding
+    pub fields: Vec<PohFieldSpec>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct PohFieldSpec {
+    pub name: String,
+    pub encoding: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct StateDigest {
+    pub height: u32,
+    /// Digest of all non-zero balances and all tokens at the end of the block, see `state_digest` in `/proof-of-history/spec`
+    pub digest: String,
+    /// Number of hashed balances
+    pub accounts: u64,
+    /// Number of hashed tokens
+    pub tokens: u64,
 }
 
 #[derive(Deserialize, Validate, schemars::JsonSchema)]
@@ -73,6 +715,16 @@ pub struct ProofHistoryArgs {
     #[serde(default = "utils::page_size_default")]
     #[validate(range(min = 1, max = 100))]
     pub limit: usize,
+    /// Return `prev_proof` and `history_hash` of every block, so the hash chain can be verified without the events
+    #[serde(default)]
+    pub links: bool,
+}
+
+#[derive(Serialize)]
+pub struct TokenCompleted {
+    pub event_type: String,
+    pub tick: OriginalTokenTickRest,
+    pub height: u32,
 }
 
 #[derive(Serialize)]
@@ -117,23 +769,43 @@ pub struct History {
     pub height: u32,
     #[serde(flatten)]
     pub action: TokenAction,
+    /// How the inscription behind the event got to its owner. Only set by the history routes, and not for events indexed
+    /// before origins were stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<ActionOrigin>,
 }
 
 impl History {
-    pub fn new(height: u32, action: TokenHistoryDB, address_token: AddressTokenIdDB, server: &Server) -> anyhow::Result<Self> {
-        let keys = [action.address().copied(), Some(address_token.address)].into_iter().flatten();
+    /// Resolves the whole page with a single `fullhash_to_address` multi_get instead of one lookup per row
+    pub fn new_page(rows: Vec<(AddressTokenIdDB, HistoryValue)>, server: &Server) -> anyhow::Result<Vec<Self>> {
+        let addresses = server.load_addresses(rows.iter().flat_map(|(k, v)| Self::address_keys(&v.action, k)))?;
+        let origins = server.db.history_origins(&rows);
 
-        let addresses = server.load_addresses(keys)?;
+        Ok(rows
+            .into_iter()
+            .zip(origins)
+            .map(|((k, v), origin)| Self {
+                origin,
+                ..Self::new_with_addresses(v.height, v.action, k, &addresses)
+            })
+            .collect())
+    }
 
-        Ok(Self {
+    pub fn new_with_addresses(height: u32, action: TokenHistoryDB, address_token: AddressTokenIdDB, addresses: &AddressesFullHash) -> Self {
+        Self {
             height,
-            action: TokenAction::from_with_addresses(action, &addresses),
             address_token: AddressTokenId {
                 address: addresses.get(&address_token.address),
                 id: address_token.id,
                 tick: address_token.token.into(),
             },
-        })
+            action: TokenAction::from_with_addresses(action, addresses),
+            origin: None,
+        }
+    }
+
+    fn address_keys(action: &TokenHistoryDB, address_token: &AddressTokenIdDB) -> impl Iterator<Item = FullHash> {
+        [action.address().copied(), Some(address_token.address)].into_iter().flatten()
     }
 }
 
@@ -141,15 +813,88 @@ impl History {
 pub struct AddressHistory {
     #[serde(flatten)]
     pub history: History,
-    /// Block timestamp of the block in which the history was created (in seconds since UNIX epoch)
+    /// Median time past of the block in which the history was created (in seconds since UNIX epoch), unlike the block timestamp it never decreases
     pub created: u32,
+    /// Timestamp of the header of the block in which the history was created
+    pub block_time: u32,
+    /// Txid of the transaction if it moved several transfers at once, see `/txid/{txid}/batch-summary`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+}
+
+/// Page of the address history with the summary of its whole history for the tick
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct AddressHistoryPage {
+    /// Total number of events, compacted events included, regardless of the time bounds
+    pub total: u64,
+    /// Height of the first event
+    pub first_height: Option<u32>,
+    /// Height of the last event
+    pub last_height: Option<u32>,
+    pub history: Vec<AddressHistory>,
 }
 
 impl AddressHistory {
-    pub fn new(height: u32, action: TokenHistoryDB, address_token: AddressTokenIdDB, server: &Server) -> anyhow::Result<Self> {
-        let history = History::new(height, action, address_token, server)?;
-        let created = server.db.block_info.get(height).anyhow()?.created;
-        Ok(Self { history, created })
+    /// One multi_get for addresses, one for block infos and one for batches per page
+    pub fn new_page(rows: Vec<(AddressTokenIdDB, HistoryValue)>, server: &Server) -> anyhow::Result<Vec<Self>> {
+        let times = server.db.block_times(rows.iter().map(|(_, v)| v.height));
+
+        let txids = rows.iter().map(|(_, v)| v.action.is_transfer().then(|| v.action.outpoint().txid)).collect_vec();
+        let batches = server
+            .db
+            .txid_to_batch
+            .multi_get_kv(txids.iter().flatten().collect::<HashSet<_>>(), false)
+            .into_iter()
+            .map(|(txid, _)| *txid)
+            .collect::<HashSet<_>>();
+
+        History::new_page(rows, server)?
+            .into_iter()
+            .zip(txids)
+            .map(|(history, txid)| {
+                let mut item = Self::with_times(history, &times)?;
+                item.batch_id = txid.filter(|x| batches.contains(x)).map(|x| x.to_string());
+                Ok(item)
+            })
+            .collect()
+    }
+
+    /// Checkpoints are placed at the last block of their period
+    pub fn new_checkpoints_page(rows: Vec<(AddressTokenIdDB, HistoryCheckpoint)>, server: &Server) -> anyhow::Result<Vec<Self>> {
+        let times = server.db.block_times(rows.iter().map(|(_, v)| v.to_height));
+        let addresses = server.load_addresses(rows.iter().map(|(k, _)| k.address))?;
+
+        rows.into_iter()
+            .map(|(k, v)| {
+                let history = History {
+                    height: v.to_height,
+                    address_token: AddressTokenId {
+                        address: addresses.get(&k.address),
+                        id: k.id,
+                        tick: k.token.into(),
+                    },
+                    action: TokenAction::Checkpoint {
+                        from_height: v.from_height,
+                        to_height: v.to_height,
+                        events: v.events,
+                        balance: v.balance,
+                    },
+                    origin: None,
+                };
+
+                Self::with_times(history, &times)
+            })
+            .collect()
+    }
+
+    fn with_times(history: History, times: &HashMap<u32, BlockTimes>) -> anyhow::Result<Self> {
+        let times = *times.get(&history.height).anyhow_with("Block info not found")?;
+        Ok(Self {
+            history,
+            created: times.median_time,
+            block_time: times.block_time,
+            batch_id: None,
+        })
     }
 }
 
@@ -168,6 +913,13 @@ pub enum TokenAction {
     Receive { amt: Fixed128, sender: String, txid: Txid, vout: u32 },
     /// SendReceive event
     SendReceive { amt: Fixed128, txid: Txid, vout: u32 },
+    /// Roll-up of the compacted events of the period, `balance` is the total balance at its end
+    Checkpoint {
+        from_height: u32,
+        to_height: u32,
+        events: u64,
+        balance: Fixed128,
+    },
 }
 
 impl From<server::HistoryValueEvent> for TokenAction {
@@ -239,11 +991,58 @@ pub struct HoldersArgs {
     #[serde(default = "utils::first_page")]
     pub page: usize,
     pub tick: OriginalTokenTickRest,
+    /// Holders after the block at this height instead of the current ones, percents are of the supply held at that height
+    pub height: Option<u32>,
+    /// Only holders with at least this balance
+    #[serde(default, with = "::serde_with::As::<Option<DisplayFromStr>>")]
+    #[schemars(with = "Option<String>")]
+    pub min_balance: Option<Fixed128>,
+    /// Only the holder with this address or holders whose address starts with it
+    pub address: Option<String>,
 }
 
 #[derive(Deserialize, schemars::JsonSchema)]
 pub struct HoldersStatsArgs {
     pub tick: OriginalTokenTickRest,
+    /// Return [`HoldersDistribution`] instead of the list of shares
+    #[serde(default)]
+    pub distribution: bool,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum HoldersStats {
+    /// Percent of the supply held by the top 100, next 100, next 300, next 500 holders and the rest
+    Shares(Vec<Fixed128>),
+    Distribution(HoldersDistribution),
+}
+
+#[derive(Serialize, Default, schemars::JsonSchema)]
+pub struct HoldersDistribution {
+    /// Total number of holders
+    pub count: usize,
+    /// Same as the response without `distribution`
+    pub shares: Vec<Fixed128>,
+    /// Percent of the supply held by the top 10 holders
+    pub top_10_percent: String,
+    /// Percent of the supply held by the top 100 holders
+    pub top_100_percent: String,
+    /// Gini coefficient of the balances, 0 if they are equal, close to 1 if one holder has almost everything
+    pub gini: String,
+    /// Holders by balance in decimal orders of magnitude, `[0, 1)`, `[1, 10)`, `[10, 100)`, ...
+    pub histogram: Vec<BalanceBucket>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct BalanceBucket {
+    /// Inclusive lower bound of the balance
+    pub min: String,
+    /// Exclusive upper bound of the balance, `None` if unbounded
+    pub max: Option<String>,
+    /// Number of holders in the bucket
+    pub count: usize,
+    /// Percent of the supply held by the holders in the bucket
+    pub percent: String,
 }
 
 #[derive(Serialize, schemars::JsonSchema)]
@@ -262,7 +1061,7 @@ pub struct Holder {
 pub struct Holders {
     /// Number of pages
     pub pages: usize,
-    /// Total number of holders
+    /// Number of holders matching the filters, total number of holders without them
     pub count: usize,
     /// Max percent of the total supply
     pub max_percent: String,
@@ -284,10 +1083,44 @@ pub struct Token {
     pub supply: Fixed128,
     pub mint_percent: String,
     pub completed: bool,
+    /// Height of the block which completed minting
+    pub completed_height: Option<u32>,
+    /// First valid mint of the token
+    pub first_mint: Option<FirstMint>,
+    /// Number of blocks from the first mint to the block which completed minting
+    pub minted_out_in_blocks: Option<u32>,
 
     pub max: Fixed128,
     pub lim: Fixed128,
     pub dec: u8,
+
+    /// Off-chain metadata attached by the indexer operator
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extras: Option<serde_json::Value>,
+    /// Other ticks which look the same after unicode normalization and confusable characters replacement
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lookalikes: Option<Vec<OriginalTokenTickRest>>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct FirstMint {
+    pub height: u32,
+    pub txid: Txid,
+    /// Recipient of the mint
+    pub address: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct DeployerTokens {
+    pub deployer: String,
+    /// Number of deployed tokens
+    pub count: usize,
+    /// Number of deployed tokens which are fully minted
+    pub completed: usize,
+    /// Sum of the holders of the deployed tokens, an address holding several of them is counted for each
+    pub holders: u64,
+    /// Deployed tokens from the oldest one
+    pub tokens: Vec<Token>,
 }
 
 #[derive(Deserialize, Validate, schemars::JsonSchema)]
@@ -381,6 +1214,10 @@ pub enum TokenSortBy {
     /// Sort by transactions (descending)
     #[default]
     TransactionsDesc,
+    /// Sort by completion time, tokens in progress go first
+    CompletionAsc,
+    /// Sort by completion time (descending), tokens in progress go last
+    CompletionDesc,
 }
 
 #[derive(Deserialize, Default, schemars::JsonSchema)]
@@ -412,6 +1249,9 @@ pub struct TokensArgs {
     pub filter_by: TokenFilterBy,
     /// Search by token tick
     pub search: Option<String>,
+    /// Also match ticks which look like the search, e.g. with Cyrillic or fullwidth characters
+    #[serde(default)]
+    pub include_lookalikes: bool,
 }
 
 #[derive(Serialize, schemars::JsonSchema)]
@@ -445,6 +1285,126 @@ pub struct AddressTokensArgs {
     pub search: Option<String>,
 }
 
+/// Balance proof query arguments
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct BalanceProofArgs {
+    /// Proves the balance at this height, the last indexed block by default
+    pub height: Option<u32>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct BalanceProof {
+    /// Block of the last balance change at or below the requested height, whose `balance_root` the proof is verified against
+    pub height: u32,
+    pub tick: OriginalTokenTickRest,
+    /// sha256 of the script_pubkey as used in the leaf (not reversed)
+    pub script_hash: String,
+    pub balance: Fixed128,
+    pub transferable_balance: Fixed128,
+    /// Index of the leaf in the block balance tree
+    pub index: usize,
+    pub leaf: String,
+    /// Sibling hashes from the leaf to the root
+    pub path: Vec<BalanceProofStep>,
+    pub balance_root: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct BalanceProofStep {
+    pub hash: String,
+    /// The sibling is hashed before the current node
+    pub left: bool,
+}
+
+/// Balance history query arguments
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+pub struct BalanceHistoryArgs {
+    /// Height of the last item from the previous page.
+    pub after_height: Option<u32>,
+    /// Limit of the number of balance changes to return.
+    #[serde(default = "utils::page_size_default")]
+    #[validate(range(min = 1, max = 300))]
+    pub limit: usize,
+}
+
+/// Balance after a block where it changed
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct BalanceChange {
+    pub height: u32,
+    /// Median time past of the block (in seconds since UNIX epoch)
+    pub created: u32,
+    /// Timestamp of the block header
+    pub block_time: u32,
+    /// Total (available + transferable) balance
+    pub balance: Fixed128,
+}
+
+/// Address balance deltas query arguments
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+pub struct AddressDeltasArgs {
+    /// First block height (inclusive)
+    pub from_height: u32,
+    /// Last block height (inclusive), the last indexed block by default
+    pub to_height: Option<u32>,
+}
+
+impl AddressDeltasArgs {
+    pub const MAX_BLOCKS: u32 = 10_000;
+
+    pub fn heights(&self, last_height: u32) -> anyhow::Result<RangeInclusive<u32>> {
+        let to_height = self.to_height.unwrap_or(last_height).min(last_height);
+
+        if self.from_height > to_height {
+            anyhow::bail!("from_height must not be greater than to_height ({to_height})");
+        }
+
+        if to_height - self.from_height >= Self::MAX_BLOCKS {
+            anyhow::bail!("Range must not exceed {} blocks", Self::MAX_BLOCKS);
+        }
+
+        Ok(self.from_height..=to_height)
+    }
+}
+
+/// Balance changes of the address in the block
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct BlockDeltas {
+    pub height: u32,
+    /// Median time past of the block (in seconds since UNIX epoch)
+    pub created: u32,
+    /// Timestamp of the block header
+    pub block_time: u32,
+    pub deltas: Vec<TokenDelta>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct TokenDelta {
+    pub tick: OriginalTokenTickRest,
+    /// Minted and received amount
+    pub received: Fixed128,
+    /// Sent amount
+    pub sent: Fixed128,
+    /// Signed net change of the total (available + transferable) balance
+    pub delta: String,
+}
+
+impl TokenDelta {
+    pub fn new(tick: OriginalTokenTick, received: Fixed128, sent: Fixed128) -> Self {
+        let delta = if received >= sent {
+            (received - sent).to_string()
+        } else {
+            format!("-{}", sent - received)
+        };
+
+        Self {
+            tick: tick.into(),
+            received,
+            sent,
+            delta,
+        }
+    }
+}
+
 /// Address token balance response
 #[derive(Serialize, Deserialize, schemars::JsonSchema)]
 pub struct TokenBalance {
@@ -467,6 +1427,28 @@ pub struct TokenTransferProof {
     pub tick: OriginalTokenTickRest,
     /// Block height of the block in which the transfer was created
     pub height: u32,
+    /// Last indexed block when the proof was read, the transfer is unspent at it
+    pub tip_height: u32,
+    /// Hash of the block at `tip_height`, the proof is stale once it's no longer in the chain
+    pub valid_as_of: String,
+    /// Transfer outpoint is already spent by an unconfirmed transaction (only if mempool checks are enabled)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spent_in_mempool: Option<bool>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct AddressesExportArgs {
+    /// Script hash of the last row of the previous page
+    pub after: Option<String>,
+    #[serde(default = "utils::addresses_export_limit_default")]
+    #[validate(range(min = 1, max = 10_000))]
+    pub limit: usize,
+}
+
+#[derive(Serialize)]
+pub struct ExportedAddress {
+    pub scripthash: String,
+    pub address: String,
 }
 
 #[derive(Deserialize)]
@@ -474,3 +1456,309 @@ pub struct AllTickersQuery {
     #[serde(default)]
     pub block_height: Option<u32>,
 }
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct InscriptionStats {
+    /// Inscriptions with `text/plain` content type
+    pub text: u64,
+    /// Inscriptions with `application/json` content type
+    pub json: u64,
+    /// Inscriptions with `image/*` content type
+    pub image: u64,
+    /// Inscriptions with any other or missing content type
+    pub other: u64,
+    /// Total number of inscriptions
+    pub total: u64,
+}
+
+impl From<InscriptionStatsDB> for InscriptionStats {
+    fn from(value: InscriptionStatsDB) -> Self {
+        Self {
+            text: value.text,
+            json: value.json,
+            image: value.image,
+            other: value.other,
+            total: value.total(),
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+#[validate(schema(function = "validate_stats_range_args"))]
+pub struct StatsRangeArgs {
+    /// First block height (inclusive)
+    pub from: u32,
+    /// Last block height (inclusive)
+    pub to: u32,
+}
+
+const MAX_STATS_RANGE: u32 = 100_000;
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct FeeStats {
+    /// Transactions which created inscriptions
+    pub txs: u64,
+    /// Total fees paid by these transactions
+    pub fees: u64,
+    /// Average fee per transaction, `0` if there are none
+    pub avg_fee: u64,
+}
+
+impl From<FeeStatsDB> for FeeStats {
+    fn from(value: FeeStatsDB) -> Self {
+        Self {
+            txs: value.txs,
+            fees: value.fees,
+            avg_fee: value.fees.checked_div(value.txs).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct TokenStats {
+    pub tick: OriginalTokenTickRest,
+    /// Number of valid mints
+    pub mint_count: u64,
+    /// Cumulative fees of the transactions with valid mints, a transaction minting the token in several inputs is counted once
+    pub mint_fees: u64,
+}
+
+/// Everything a token page shows, see `/token/{tick}/overview`
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct TokenOverview {
+    /// Same as `/token`, the deploy inscription id is `genesis`
+    pub token: Token,
+    pub stats: TokenStats,
+    /// First page of `/holders`
+    pub holders: Holders,
+    /// Last events of the token from the newest one
+    pub events: Vec<AddressHistory>,
+}
+
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+pub struct MintActivityArgs {
+    /// Number of top minting addresses to return
+    #[serde(default = "utils::page_size_default")]
+    #[validate(range(min = 1, max = 100))]
+    pub top: usize,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct MintActivity {
+    pub tick: OriginalTokenTickRest,
+    /// Valid mints in the range of blocks
+    pub mints: u64,
+    /// Amount minted in the range of blocks
+    pub amount: Fixed128,
+    /// Number of distinct addresses which received mints in the range of blocks
+    pub minters: u64,
+    /// Blocks of the range with valid mints of the token, ascending by height
+    pub blocks: Vec<MintActivityBlock>,
+    /// Addresses which received the most mints in the range of blocks
+    pub top_minters: Vec<Minter>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct MintActivityBlock {
+    pub height: u32,
+    pub mints: u32,
+    pub amount: Fixed128,
+    /// Number of distinct addresses which received mints in the block
+    pub minters: u32,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct Minter {
+    pub address: String,
+    pub mints: u64,
+    pub amount: Fixed128,
+}
+
+fn validate_stats_range_args(args: &StatsRangeArgs) -> Result<(), validator::ValidationError> {
+    if args.from > args.to || args.to - args.from >= MAX_STATS_RANGE {
+        return Err(validator::ValidationError::new("range").with_message(format!("Range must be ascending and shorter than {MAX_STATS_RANGE} blocks").into()));
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct OutPointInfo {
+    /// Value of the output
+    pub value: u64,
+    /// Script hash (sha256 of the script_pubkey) of the output
+    pub script_hash: String,
+    /// Address of the output owner
+    pub address: String,
+    /// Offsets of the inscriptions located at the outpoint
+    pub inscription_offsets: Vec<u64>,
+    /// Active transfer inscriptions located at the outpoint
+    pub transfers: Vec<OutPointTransfer>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct OutPointTransfer {
+    /// Offset of the transfer inscription in the output
+    pub offset: u64,
+    pub tick: OriginalTokenTickRest,
+    /// Amount of the transfer
+    pub amt: Fixed128,
+    /// Block height of the block in which the transfer was created
+    pub height: u32,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, schemars::JsonSchema)]
+pub enum TrendingWindow {
+    #[serde(rename = "1h")]
+    Hour,
+    #[default]
+    #[serde(rename = "24h")]
+    Day,
+}
+
+impl TrendingWindow {
+    pub fn hours(self) -> u32 {
+        match self {
+            TrendingWindow::Hour => 1,
+            TrendingWindow::Day => 24,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+pub struct TrendingTokensArgs {
+    /// Time window of the queries
+    #[serde(default)]
+    pub window: TrendingWindow,
+    /// Limit of the number of tokens to return.
+    #[serde(default = "utils::page_size_default")]
+    #[validate(range(min = 1, max = 100))]
+    pub limit: usize,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct TrendingToken {
+    pub tick: OriginalTokenTickRest,
+    /// Number of API queries for the token in the window
+    pub queries: u64,
+}
+
+/// Inscription history query arguments
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+pub struct InscriptionHistoryArgs {
+    /// Number of movements to skip
+    #[serde(default)]
+    pub offset: usize,
+    /// Limit of the number of movements to return.
+    #[serde(default = "utils::page_size_default")]
+    #[validate(range(min = 1, max = 100))]
+    pub limit: usize,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InscriptionMoveKindRest {
+    Created,
+    Transferred,
+    /// Moved to the coinbase as a fee
+    Leaked,
+}
+
+impl From<InscriptionMoveKind> for InscriptionMoveKindRest {
+    fn from(value: InscriptionMoveKind) -> Self {
+        match value {
+            InscriptionMoveKind::Created => Self::Created,
+            InscriptionMoveKind::Transferred => Self::Transferred,
+            InscriptionMoveKind::Leaked => Self::Leaked,
+        }
+    }
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct InscriptionMove {
+    pub kind: InscriptionMoveKindRest,
+    /// Block height of the movement
+    pub height: u32,
+    /// New location of the inscription in the `txid:vout:offset` format
+    pub location: String,
+    /// Address of the new owner
+    pub address: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct InscriptionLocation {
+    pub id: String,
+    /// Current location of the inscription in the `txid:vout:offset` format, `None` if it isn't indexed
+    pub location: Option<String>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct InscriptionCurses {
+    pub id: String,
+    /// Reasons ord would curse the inscription, they don't affect token indexing except skipped reinscriptions before the jubilee
+    pub curses: Vec<InscriptionCurse>,
+}
+
+/// Leaked inscriptions query arguments
+#[derive(Deserialize, Validate, schemars::JsonSchema)]
+pub struct LeakedArgs {
+    /// Height of the last block from the previous page
+    pub offset: Option<u32>,
+    /// Limit of the number of inscriptions to return, pages end with a whole block so they can have more.
+    #[serde(default = "utils::page_size_default")]
+    #[validate(range(min = 1, max = 100))]
+    pub limit: usize,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LeakedKindRest {
+    /// Created without an output to land in
+    Creation,
+    /// Moved without an output to land in
+    Move,
+}
+
+impl From<LeakedKind> for LeakedKindRest {
+    fn from(value: LeakedKind) -> Self {
+        match value {
+            LeakedKind::Creation => Self::Creation,
+            LeakedKind::Move => Self::Move,
+        }
+    }
+}
+
+/// Inscription created or moved into the fees, which the miner of the block receives in the coinbase
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct LeakedInscription {
+    pub height: u32,
+    pub kind: LeakedKindRest,
+    /// Transaction which spent the inscription into the fees
+    pub txid: String,
+    pub input: u32,
+    /// Spent output and the offset of the inscription in it in the `txid:vout:offset` format
+    pub from: String,
+    /// Address of the spent output
+    pub address: String,
+    /// Location in the coinbase transaction in the `txid:vout:offset` format
+    pub location: String,
+    /// Ids of the moved inscriptions, known only if `INSCRIPTION_HISTORY` is enabled. Leaked creations have no ids
+    pub inscriptions: Vec<RestInscriptionId>,
+}
+
+impl LeakedInscription {
+    pub fn new(height: u32, value: LeakedInscriptionDB, addresses: &AddressesFullHash) -> Self {
+        let location = |x: Location| format!("{}:{}:{}", x.outpoint.txid, x.outpoint.vout, x.offset);
+
+        Self {
+            height,
+            kind: value.kind.into(),
+            txid: value.txid.to_string(),
+            input: value.input,
+            from: location(value.from),
+            address: addresses.get(&value.owner),
+            location: location(value.location),
+            inscriptions: value.inscriptions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -5,3 +5,11 @@ pub fn page_size_default() -> usize {
 pub fn first_page() -> usize {
     1
 }
+
+pub fn fee_blocks_default() -> u16 {
+    6
+}
+
+pub fn addresses_export_limit_default() -> usize {
+    1_000
+}
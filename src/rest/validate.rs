@@ -0,0 +1,63 @@
+use bellscoin::hashes::hex::FromHex;
+
+use super::*;
+
+pub async fn validate_inscription(State(server): State<Arc<Server>>, Json(args): Json<types::ValidateInscriptionArgs>) -> ApiResult<impl IntoApiResponse> {
+    let content = match (args.content, args.content_hex) {
+        (Some(content), None) => content.into_bytes(),
+        (None, Some(hex)) => Vec::<u8>::from_hex(hex.trim()).bad_request("Invalid content hex")?,
+        _ => Err::<Vec<u8>, _>(anyhow::anyhow!("Either content or content_hex must be set")).bad_request_from_error()?,
+    };
+
+    let brc4 = match TokenCache::try_parse(&args.content_type, &content) {
+        Ok(brc4) => brc4,
+        Err(e) => {
+            return Ok(Json(types::InscriptionValidation {
+                valid: false,
+                op: None,
+                tick: None,
+                error: Some(match e {
+                    Brc4ParseErr::Unknown(msg) => msg,
+                    e => format!("{e:?}"),
+                }),
+                warnings: vec![],
+            }));
+        }
+    };
+
+    let (op, value) = brc4.op_value();
+    let value = value.internal(INTERNAL)?;
+
+    let tick = value.tick();
+    let meta = server.db.token_to_meta.get(LowerCaseTokenTick::from(tick));
+
+    // Same checks as `TokenCache::process_token_actions`
+    let warnings = match (value, meta) {
+        (Brc4Value::Deploy { .. }, Some(_)) => vec![Brc4ActionErr::AlreadyDeployed],
+        (Brc4Value::Deploy { .. }, None) => vec![],
+        (Brc4Value::Mint { .. } | Brc4Value::Transfer { .. }, None) => vec![Brc4ActionErr::NotDeployed],
+        (Brc4Value::Mint { amt, .. }, Some(meta)) => [
+            (amt.scale() > meta.proto.dec, Brc4ActionErr::ReachDecBound),
+            (amt > meta.proto.lim, Brc4ActionErr::ReachLimBound),
+            (meta.proto.supply == meta.proto.max, Brc4ActionErr::SupplyMinted),
+        ]
+        .into_iter()
+        .filter(|(failed, _)| *failed)
+        .map(|(_, e)| e)
+        .collect(),
+        (Brc4Value::Transfer { amt, .. }, Some(meta)) => (amt.scale() > meta.proto.dec).then_some(Brc4ActionErr::ReachDecBound).into_iter().collect(),
+    };
+
+    Ok(Json(types::InscriptionValidation {
+        valid: true,
+        op: Some(op.to_string()),
+        tick: Some(tick.to_string()),
+        error: None,
+        warnings: warnings.into_iter().map(|e| format!("{e:?}")).collect(),
+    }))
+}
+
+pub fn validate_inscription_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Checks whether an inscription body would be indexed as a token deploy, mint or transfer, without broadcasting it")
+        .tag("transaction")
+}
@@ -0,0 +1,80 @@
+use std::sync::atomic::Ordering;
+
+use dashmap::DashMap;
+
+use super::*;
+
+/// Buckets older than this are removed on flush
+const ACCESS_STATS_RETENTION_HOURS: u32 = 24;
+
+/// REST query counters per tick. <br/>
+/// Hits only touch in-memory atomics, `flush` moves them into hourly buckets of the `token_access_stats` CF.
+#[derive(Default)]
+pub struct TokenAccessStats {
+    counters: DashMap<OriginalTokenTick, AtomicU64>,
+}
+
+impl TokenAccessStats {
+    pub fn hit(&self, tick: OriginalTokenTick) {
+        if let Some(counter) = self.counters.get(&tick) {
+            counter.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        self.counters.entry(tick).or_default().fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn flush(&self, db: &DB) {
+        let hour = Self::current_hour();
+
+        let keys = self
+            .counters
+            .iter()
+            .map(|x| (*x.key(), x.value().swap(0, Ordering::Relaxed)))
+            .filter(|(_, count)| *count > 0)
+            .map(|(tick, count)| (TokenAccessKey { hour, tick }, count))
+            .collect_vec();
+
+        if !keys.is_empty() {
+            let stored = db.token_access_stats.multi_get(keys.iter().map(|x| &x.0));
+            db.token_access_stats
+                .extend(keys.into_iter().zip(stored).map(|((key, count), stored)| (key, count + stored.unwrap_or_default())));
+        }
+
+        let outdated = TokenAccessKey {
+            hour: hour.saturating_sub(ACCESS_STATS_RETENTION_HOURS),
+            tick: OriginalTokenTick::default(),
+        };
+        let to_remove = db.token_access_stats.range(..&outdated, false).map(|x| x.0).collect_vec();
+        db.token_access_stats.remove_batch(to_remove);
+    }
+
+    /// Most queried ticks for the last `hours`, including counters which aren't flushed yet
+    pub fn trending(&self, db: &DB, hours: u32, limit: usize) -> Vec<(OriginalTokenTick, u64)> {
+        let from = TokenAccessKey {
+            hour: (Self::current_hour() + 1).saturating_sub(hours),
+            tick: OriginalTokenTick::default(),
+        };
+
+        let mut counts = HashMap::<OriginalTokenTick, u64>::new();
+
+        for (key, count) in db.token_access_stats.range(&from.., false) {
+            *counts.entry(key.tick).or_default() += count;
+        }
+
+        for x in self.counters.iter() {
+            *counts.entry(*x.key()).or_default() += x.value().load(Ordering::Relaxed);
+        }
+
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .sorted_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)))
+            .take(limit)
+            .collect()
+    }
+
+    fn current_hour() -> u32 {
+        (std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() / 3600) as u32
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use super::*;
+
+/// Stored next to the checkpoint as `{name}.json`, a checkpoint without it is still being created
+#[derive(Serialize, Deserialize)]
+struct BackupMeta {
+    height: u32,
+    created: u64,
+    uploaded: Option<u64>,
+}
+
+/// RocksDB checkpoints in `BACKUP_DIR`, requested by `POST /admin/backups` and `BACKUP_INTERVAL_HOURS`. <br/>
+/// The indexer takes them between blocks, so a backup never has a partially written block and indexing only pauses for the checkpoint itself.
+#[derive(Default)]
+pub struct Backups {
+    pending: AtomicBool,
+    /// Creation and removal of backups, uploads only read them
+    lock: parking_lot::Mutex<()>,
+}
+
+impl Backups {
+    pub fn enabled() -> bool {
+        BACKUP_DIR.is_some()
+    }
+
+    /// `false` if another backup is pending
+    pub fn request(&self) -> bool {
+        !self.pending.swap(true, Ordering::SeqCst)
+    }
+
+    /// Called by the indexer between blocks
+    pub fn take(&self) -> bool {
+        self.pending.swap(false, Ordering::SeqCst)
+    }
+
+    /// Hard links the SST files, so a backup only takes space once they are compacted away from the live database
+    pub fn create(&self, db: &DB) -> anyhow::Result<String> {
+        let dir = Self::dir()?;
+        let _lock = self.lock.lock();
+
+        std::fs::create_dir_all(dir)?;
+
+        let height = db.last_block.get(()).unwrap_or_default();
+        let created = unix_now();
+        let name = format!("{height}-{created}");
+
+        let started = Instant::now();
+        db.rocks().checkpoint(&dir.join(&name).to_string_lossy())?;
+
+        let meta = BackupMeta {
+            height,
+            created,
+            uploaded: None,
+        };
+        std::fs::write(Self::meta_path(dir, &name), serde_json::to_vec(&meta)?)?;
+
+        info!("Backup {} of block {} created in {:?}", name, height, started.elapsed());
+
+        Ok(name)
+    }
+
+    /// Newest first
+    pub fn list(&self) -> anyhow::Result<Vec<rest::types::Backup>> {
+        let dir = Self::dir()?;
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut backups = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|x| x != "json") {
+                continue;
+            }
+
+            let Some(name) = path.file_stem().map(|x| x.to_string_lossy().into_owned()) else {
+                continue;
+            };
+            if !dir.join(&name).is_dir() {
+                continue;
+            }
+
+            let Ok(meta) = serde_json::from_slice::<BackupMeta>(&std::fs::read(&path)?) else {
+                warn!("Invalid backup metadata {}", path.display());
+                continue;
+            };
+
+            backups.push(rest::types::Backup {
+                size_bytes: dir_size(&dir.join(&name))?,
+                name,
+                height: meta.height,
+                created: meta.created,
+                uploaded: meta.uploaded,
+            });
+        }
+
+        backups.sort_by_key(|x| std::cmp::Reverse(x.created));
+
+        Ok(backups)
+    }
+
+    /// `false` if there is no such backup
+    pub fn remove(&self, name: &str) -> anyhow::Result<bool> {
+        let dir = Self::dir()?;
+        // Names are `{height}-{created}`, anything else could escape the backup directory
+        if name.is_empty() || !name.chars().all(|x| x.is_ascii_digit() || x == '-') {
+            return Ok(false);
+        }
+
+        let _lock = self.lock.lock();
+
+        let meta_path = Self::meta_path(dir, name);
+        if !meta_path.exists() {
+            return Ok(false);
+        }
+
+        // Metadata goes first, so a partially removed backup isn't listed
+        std::fs::remove_file(meta_path)?;
+        if dir.join(name).exists() {
+            std::fs::remove_dir_all(dir.join(name))?;
+        }
+
+        info!("Backup {} removed", name);
+
+        Ok(true)
+    }
+
+    /// Removes all backups but the newest `keep`, returns the removed names
+    pub fn prune(&self, keep: usize) -> anyhow::Result<Vec<String>> {
+        let mut removed = vec![];
+        for backup in self.list()?.into_iter().skip(keep) {
+            if self.remove(&backup.name)? {
+                removed.push(backup.name);
+            }
+        }
+
+        Ok(removed)
+    }
+
+    pub fn mark_uploaded(&self, name: &str) -> anyhow::Result<()> {
+        let meta_path = Self::meta_path(Self::dir()?, name);
+
+        let _lock = self.lock.lock();
+        let mut meta = serde_json::from_slice::<BackupMeta>(&std::fs::read(&meta_path)?)?;
+        meta.uploaded = Some(unix_now());
+        std::fs::write(meta_path, serde_json::to_vec(&meta)?)?;
+
+        Ok(())
+    }
+
+    pub fn dir() -> anyhow::Result<&'static Path> {
+        BACKUP_DIR.as_deref().map(Path::new).ok_or_else(|| anyhow::anyhow!("Backups are disabled, BACKUP_DIR isn't set"))
+    }
+
+    fn meta_path(dir: &Path, name: &str) -> PathBuf {
+        dir.join(format!("{name}.json"))
+    }
+}
+
+/// Checkpoints are flat directories
+fn dir_size(path: &Path) -> anyhow::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        size += entry?.metadata()?.len();
+    }
+
+    Ok(size)
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()
+}
@@ -1,43 +1,101 @@
 use super::*;
 
+mod access_stats;
+mod backups;
+mod outbox;
+mod reorg_simulation;
+mod reprocess;
+mod request_metrics;
+mod shutdown;
+mod spot_audit;
+mod standby;
 mod structs;
+mod supply_audit;
 pub mod threads;
+mod write_throttle;
+pub use access_stats::TokenAccessStats;
+pub use backups::Backups;
+pub use outbox::Outbox;
+pub use reorg_simulation::ReorgSimulations;
+pub use reprocess::ReprocessRequests;
+pub use request_metrics::RequestMetrics;
+pub use shutdown::ShutdownCoordinator;
+pub use spot_audit::SpotAudits;
+pub use standby::{Standby, StandbyParity};
 pub use structs::*;
+pub use supply_audit::SupplyAudits;
+pub use write_throttle::WriteThrottle;
 
 pub struct Server {
     pub db: Arc<DB>,
     pub event_sender: tokio::sync::broadcast::Sender<ServerEvent>,
-    pub raw_event_sender: kanal::Sender<RawServerEvent>,
+    pub outbox: Outbox,
     pub token: WaitToken,
     pub holders: Arc<Holders>,
     pub indexer: Arc<nint_blk::Indexer>,
     pub client: Arc<nint_blk::Client>,
     pub start_time: std::time::Instant,
+    /// Held by the indexer while it writes a block, so a snapshot taken under it never has a partially written block,
+    /// which the cold instance may have between its batch and the one of the main instance
+    pub block_writes: parking_lot::Mutex<()>,
+    pub access_stats: TokenAccessStats,
+    pub reorg_in_progress: ReorgInProgress,
+    pub write_throttle: WriteThrottle,
+    pub token_accounts: TokenAccountCache,
+    pub reorg_simulations: ReorgSimulations,
+    pub reprocess_requests: ReprocessRequests,
+    pub backups: Backups,
+    pub request_metrics: RequestMetrics,
+    pub supply_audits: SupplyAudits,
+    pub spot_audits: SpotAudits,
+    pub standby: Standby,
 }
 
 impl Server {
-    pub fn new(db_path: &str) -> anyhow::Result<(kanal::Receiver<RawServerEvent>, tokio::sync::broadcast::Sender<ServerEvent>, Self)> {
-        let (raw_tx, raw_rx) = kanal::unbounded();
+    pub fn new(db_path: &str) -> anyhow::Result<(tokio::sync::broadcast::Sender<ServerEvent>, Self)> {
         let (tx, _) = tokio::sync::broadcast::channel(30_000);
         let token = WaitToken::default();
-        let db = Arc::new(DB::open(db_path));
-
-        let coin = match (*BLOCKCHAIN, *NETWORK) {
-            (Blockchain::Bellscoin, Network::Bellscoin) => "bellscoin",
-            (Blockchain::Bellscoin, Network::Testnet) => "bellscoin-testnet",
-            (Blockchain::Dogecoin, Network::Bellscoin) => "dogecoin",
-            (Blockchain::Dogecoin, Network::Testnet) => "dogecoin-testnet",
-            (Blockchain::Pepecoin, Network::Bellscoin) => "pepecoin",
-            (Blockchain::Pepecoin, Network::Testnet) => "pepecoin-testnet",
-            _ => "bellscoin",
+
+        // The pools are shared by the instances, so they're sized before the first one is opened
+        if ROCKSDB_FLUSH_THREADS.is_some() || ROCKSDB_COMPACTION_THREADS.is_some() || *ROCKSDB_LOW_PRIORITY_COMPACTION {
+            RocksDB::set_background_threads(rocksdb_wrapper::BackgroundThreads {
+                flush: ROCKSDB_FLUSH_THREADS.unwrap_or(1),
+                compaction: ROCKSDB_COMPACTION_THREADS.unwrap_or(1),
+                lower_compaction_priority: *ROCKSDB_LOW_PRIORITY_COMPACTION,
+            });
         }
-        .to_string();
 
-        let coin = nint_blk::CoinType::from_str(&coin).unwrap();
+        if COLD_DB_PATH.is_some() && !COLD_TABLES.iter().all(|x| DB::COLD_TABLES_ALLOWED.contains(&x.as_str())) {
+            anyhow::bail!("COLD_TABLES may only contain {}", DB::COLD_TABLES_ALLOWED.join(", "));
+        }
 
-        let last_height = db.last_block.get(()).unwrap_or_default();
+        let db = Arc::new(match COLD_DB_PATH.as_deref() {
+            Some(cold_path) => DB::open_tiered(db_path, cold_path, &COLD_TABLES),
+            None => DB::open(db_path),
+        });
 
-        let client = Arc::new(nint_blk::Client::new(&URL, nint_blk::Auth::UserPass(USER.to_string(), PASS.to_string()), coin, token.clone()).unwrap());
+        let coin = *COIN;
+
+        let client = Arc::new(nint_blk::Client::new(&URL, RPC_AUTH.clone(), coin, token.clone()).unwrap());
+        let block_format = client.probe_block_format()?;
+        info!("Node returns blocks with getblock format {:?}", block_format);
+
+        Self::verify_commit_marker(&db)?;
+        Self::verify_consistency(&db)?;
+        Self::verify_last_block(&db, &client)?;
+        db.backfill_token_skeletons();
+        db.backfill_deployer_tokens();
+        db.backfill_held_tokens();
+        db.backfill_token_completion_heights();
+        db.backfill_token_first_mints();
+        db.backfill_time_buckets();
+        db.backfill_median_times();
+        db.backfill_history_stats();
+        db.backfill_inscription_locations();
+        db.init_history_origin_height();
+        Self::check_shutdown_marker(&db);
+
+        let last_height = db.last_block.get(()).unwrap_or_default();
 
         let indexer = nint_blk::Indexer {
             coin,
@@ -50,59 +108,249 @@ impl Server {
             token: token.clone(),
             index_dir_path: INDEX_DIR.clone(),
             client: client.clone(),
+            validate_blocks: *VALIDATE_BLOCKS,
+            prefetch_blocks: *BLK_PREFETCH_BLOCKS,
         };
 
         let server = Self {
             holders: Arc::new(Holders::init(&db)),
-            raw_event_sender: raw_tx.clone(),
+            outbox: Outbox::new(&db),
             token,
             event_sender: tx.clone(),
             indexer: Arc::new(indexer),
             db,
             client,
             start_time: std::time::Instant::now(),
+            block_writes: parking_lot::Mutex::new(()),
+            access_stats: TokenAccessStats::default(),
+            reorg_in_progress: ReorgInProgress::default(),
+            write_throttle: WriteThrottle::default(),
+            token_accounts: TokenAccountCache::new(*TOKEN_CACHE_MB),
+            reorg_simulations: ReorgSimulations::default(),
+            reprocess_requests: ReprocessRequests::default(),
+            backups: Backups::default(),
+            request_metrics: RequestMetrics::default(),
+            supply_audits: SupplyAudits::default(),
+            spot_audits: SpotAudits::default(),
+            standby: Standby::default(),
         };
 
-        Ok((raw_rx, tx, server))
+        server.token_accounts.warm_up(&server.db);
+
+        Ok((tx, server))
     }
 
-    pub fn load_addresses(&self, keys: impl IntoIterator<Item = FullHash>) -> anyhow::Result<AddressesFullHash> {
-        let keys = keys.into_iter().collect::<HashSet<_>>();
+    /// Server over another database sharing the node client, its events have no subscribers and its balances aren't cached
+    pub fn sandbox(&self, db: Arc<DB>) -> Self {
+        let (event_sender, _) = tokio::sync::broadcast::channel(1);
 
-        Ok(AddressesFullHash::new(
-            self.db.fullhash_to_address.multi_get_kv(keys.iter(), false).into_iter().map(|(k, v)| (*k, v)).collect(),
-        ))
+        Self {
+            holders: Arc::new(Holders::init(&db)),
+            outbox: Outbox::new(&db),
+            token: self.token.clone(),
+            event_sender,
+            indexer: self.indexer.clone(),
+            db,
+            client: self.client.clone(),
+            start_time: std::time::Instant::now(),
+            block_writes: parking_lot::Mutex::new(()),
+            access_stats: TokenAccessStats::default(),
+            reorg_in_progress: ReorgInProgress::default(),
+            write_throttle: WriteThrottle::default(),
+            token_accounts: TokenAccountCache::new(None),
+            reorg_simulations: ReorgSimulations::default(),
+            reprocess_requests: ReprocessRequests::default(),
+            backups: Backups::default(),
+            request_metrics: RequestMetrics::default(),
+            supply_audits: SupplyAudits::default(),
+            spot_audits: SpotAudits::default(),
+            standby: Standby::default(),
+        }
     }
 
-    pub fn generate_history_hash(prev_history_hash: sha256::Hash, history: &[(AddressTokenIdDB, HistoryValue)], addresses: &AddressesFullHash) -> anyhow::Result<sha256::Hash> {
-        let current_hash = if history.is_empty() {
-            *DEFAULT_HASH
-        } else {
-            let mut buffer = Vec::<u8>::new();
-
-            for (address_token, action) in history {
-                let rest = rest::types::History {
-                    height: action.height,
-                    action: rest::types::TokenAction::from_with_addresses(action.action.clone(), addresses),
-                    address_token: rest::types::AddressTokenId {
-                        address: addresses.get(&address_token.address),
-                        id: address_token.id,
-                        tick: address_token.token.into(),
-                    },
-                };
-                let bytes = serde_json::to_vec(&rest)?;
-                buffer.extend(bytes);
+    /// Reports whether the previous run stopped through [`ShutdownCoordinator`], the marker is removed until the next clean shutdown
+    fn check_shutdown_marker(db: &DB) {
+        let last_block = db.last_block.get(());
+
+        match db.shutdown_marker.get(()) {
+            Some(marker) if Some(marker) == last_block => info!("Previous shutdown was clean at height {}", marker),
+            Some(marker) => warn!("Shutdown marker at height {} doesn't match the last block {:?}", marker, last_block),
+            None if last_block.is_some() => warn!("Previous shutdown wasn't clean, resuming from the last committed block {:?}", last_block),
+            None => {}
+        }
+
+        db.shutdown_marker.remove(());
+    }
+
+    /// `last_block` and `last_committed_block_marker` are written in the batch with the data and the journal of the block, so a crash
+    /// leaves them equal. They differ if blocks were indexed by a binary which predates the marker and writes a block in many batches,
+    /// those blocks are rolled back through the journal. <br/>
+    /// The marker of a database indexed before it was stored is set to the last block.
+    fn verify_commit_marker(db: &DB) -> anyhow::Result<()> {
+        let last_block = db.last_block.get(());
+        let Some(marker) = db.last_committed_block_marker.get(()) else {
+            if let Some(last_block) = last_block {
+                db.commit_block(last_block);
             }
+            return Ok(());
+        };
 
-            sha256::Hash::hash(&buffer)
+        if last_block.is_none_or(|last_block| last_block < marker) {
+            anyhow::bail!("Last block {last_block:?} is below the last committed block {marker}, restore a backup or reindex");
+        }
+
+        let Some(tip) = last_block.filter(|tip| *tip > marker) else {
+            return Ok(());
         };
 
-        let new_hash = {
-            let mut buffer = prev_history_hash.as_byte_array().to_vec();
-            buffer.extend_from_slice(current_hash.as_byte_array());
-            sha256::Hash::hash(&buffer)
+        let mut journal = ReorgCache::load(db);
+        if !journal.blocks.first_key_value().is_some_and(|(height, _)| *height <= marker + 1) {
+            anyhow::bail!("Blocks above the last committed block {marker} (last indexed {tip}) aren't in the reorg journal, restore a backup or reindex");
+        }
+
+        warn!("Blocks {}..={} aren't committed, rolling back to the last committed block {}", marker + 1, tip, marker);
+        // REST isn't started yet, so there is nobody to notify
+        journal.restore(db, marker, &ReorgInProgress::default())?;
+
+        Ok(())
+    }
+
+    /// Proofs of history must exist for every height from `START_HEIGHT` to the last block and `last_history_id` must be the
+    /// last id of `block_events`, otherwise a block was written partially and the next proofs would be chained to stale state. <br/>
+    /// The index is rolled back to the last consistent height through the reorg journal, and leftovers of an unfinished block
+    /// above the last one are removed, so its history ids are assigned again when it's indexed.
+    fn verify_consistency(db: &DB) -> anyhow::Result<()> {
+        let Some(last_height) = db.last_block.get(()).filter(|height| *height >= *START_HEIGHT) else {
+            return Ok(());
         };
 
-        Ok(new_hash)
+        let mut expected = *START_HEIGHT;
+        for (height, _) in db.proof_of_history.range(&*START_HEIGHT..=&last_height, false) {
+            if height != expected {
+                break;
+            }
+            expected += 1;
+        }
+
+        let mut consistent = last_height;
+        if expected <= last_height {
+            warn!("Proof of history of block {} is missing, last indexed block is {}", expected, last_height);
+            consistent = expected.checked_sub(1).filter(|height| *height >= *START_HEIGHT).anyhow_with("No block is consistent, reindex is required")?;
+        }
+
+        let last_history_id = db.last_history_id.get(()).unwrap_or_default();
+        while let Some((height, id)) = Self::last_event_id(db, consistent) {
+            if id <= last_history_id {
+                break;
+            }
+
+            warn!("Block {} has history id {} above the last history id {}", height, id, last_history_id);
+            consistent = height.checked_sub(1).filter(|height| *height >= *START_HEIGHT).anyhow_with("No block is consistent, reindex is required")?;
+        }
+
+        if consistent < last_height {
+            let mut journal = ReorgCache::load(db);
+            if !journal.blocks.first_key_value().is_some_and(|(height, _)| *height <= consistent + 1) {
+                anyhow::bail!(
+                    "Index is inconsistent above height {consistent} (last indexed {last_height}) and the reorg journal doesn't cover it, \
+                     restore a backup or reindex"
+                );
+            }
+
+            warn!("Index is inconsistent above height {}, rolling back {} blocks", consistent, last_height - consistent);
+            // REST isn't started yet, so there is nobody to notify
+            journal.restore(db, consistent, &ReorgInProgress::default())?;
+        }
+
+        // Every other table of a block is written in one batch with `last_block`. The cold instance is written in its own batch
+        // before it, so history of a block whose main batch was lost by a crash is left above the last block, see `DB::COLD_TABLES_ALLOWED`
+        let last_height = db.last_block.get(()).unwrap_or_default();
+        let unfinished = db.block_events.range(&(last_height + 1).., false).collect_vec();
+        if !unfinished.is_empty() {
+            warn!("Removing history of {} unfinished blocks above the last block {}", unfinished.len(), last_height);
+            db.address_token_to_history.remove_batch(unfinished.iter().flat_map(|(_, events)| events.iter()));
+            db.block_events.remove_batch(unfinished.iter().map(|(height, _)| height));
+        }
+
+        let last_id = Self::last_event_id(db, last_height).map(|(_, id)| id).unwrap_or_default();
+        if db.last_history_id.get(()).unwrap_or_default() != last_id {
+            warn!("Last history id is reset to {}, the id of the last event of block {}", last_id, last_height);
+            db.last_history_id.set((), last_id);
+        }
+
+        Ok(())
+    }
+
+    /// Height and id of the last event up to the height
+    fn last_event_id(db: &DB, height: u32) -> Option<(u32, u64)> {
+        db.block_events
+            .range(..=&height, true)
+            .find_map(|(height, events)| events.iter().map(|event| event.id).max().map(|id| (height, id)))
+    }
+
+    /// Makes sure the last indexed block is still part of the node's chain. <br/>
+    /// If the node was re-synced or points to a different chain, walks back over stored block hashes to find the fork point.
+    fn verify_last_block(db: &DB, client: &nint_blk::Client) -> anyhow::Result<()> {
+        let Some(last_height) = db.last_block.get(()) else {
+            return Ok(());
+        };
+
+        let best_height = client.get_block_count()? as u32;
+
+        let mut height = last_height.min(best_height);
+        let fork_height = loop {
+            let Some(stored) = db.block_info.get(height) else {
+                anyhow::bail!("Block info for height {height} is missing, can't verify indexed chain");
+            };
+
+            if client.get_block_hash(height as u64)? == stored.hash.to_raw_hash() {
+                break height;
+            }
+
+            if height == 0 || last_height - height >= REORG_CACHE_MAX_LEN as u32 {
+                anyhow::bail!("Indexed chain diverged from the node deeper than {REORG_CACHE_MAX_LEN} blocks below {last_height}");
+            }
+
+            warn!("Stored block {} doesn't match the node's chain", height);
+            height -= 1;
+        };
+
+        if fork_height == best_height && best_height < last_height {
+            anyhow::bail!("Node is behind the index (node tip {best_height}, last indexed {last_height}), wait for the node to sync");
+        }
+
+        if fork_height != last_height {
+            let mut journal = ReorgCache::load(db);
+
+            if !journal.blocks.first_key_value().is_some_and(|(height, _)| *height <= fork_height + 1) {
+                anyhow::bail!(
+                    "Indexed chain diverged from the node at height {fork_height} (last indexed {last_height}, node tip {best_height}). \
+                     Blocks above the fork point aren't covered by the reorg journal, reindex is required"
+                );
+            }
+
+            warn!("Indexed chain diverged from the node at height {}, rolling back {} blocks", fork_height, last_height - fork_height);
+            // REST isn't started yet, so there is nobody to notify
+            journal.restore(db, fork_height, &ReorgInProgress::default())?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `None` if mempool checks are disabled
+    pub fn spent_in_mempool(&self, outpoint: &OutPoint) -> anyhow::Result<Option<bool>> {
+        if !*MEMPOOL_CHECK {
+            return Ok(None);
+        }
+
+        Ok(Some(self.client.is_spent_in_mempool(&outpoint.txid.to_raw_hash(), outpoint.vout)?))
+    }
+
+    pub fn load_addresses(&self, keys: impl IntoIterator<Item = FullHash>) -> anyhow::Result<AddressesFullHash> {
+        let keys = keys.into_iter().collect::<HashSet<_>>();
+
+        Ok(AddressesFullHash::new(
+            self.db.fullhash_to_address.multi_get_kv(keys.iter(), false).into_iter().map(|(k, v)| (*k, v)).collect(),
+        ))
     }
 }
@@ -0,0 +1,52 @@
+use std::sync::atomic::Ordering;
+
+use super::*;
+
+/// Persisted queue of the history events of indexed blocks. <br/>
+/// The indexer appends a batch per block to the `event_outbox` CF, consumers read batches after their offset in the `outbox_offsets` CF
+/// and acknowledge them once delivered, so a consumer restarts from the first unacknowledged batch.
+/// Delivery is at-least-once: a batch may be delivered again if the consumer stops before acknowledging it, events are identified by their id.
+pub struct Outbox {
+    next_seq: AtomicU64,
+}
+
+impl Outbox {
+    pub const EVENT_SENDER: &'static str = "event_sender";
+    pub const WEBHOOK_SENDER: &'static str = "webhook_sender";
+    /// Every consumer of the outbox, a consumer without an offset yet hasn't acknowledged any batch
+    const CONSUMERS: [&'static str; 2] = [Self::EVENT_SENDER, Self::WEBHOOK_SENDER];
+
+    pub fn new(db: &DB) -> Self {
+        let next_seq = db.event_outbox.range(.., true).next().map(|(seq, _)| seq + 1).unwrap_or_default();
+
+        Self {
+            next_seq: AtomicU64::new(next_seq),
+        }
+    }
+
+    pub fn push(&self, db: &DB, events: RawServerEvent) {
+        if events.is_empty() {
+            return;
+        }
+
+        db.event_outbox.set(self.next_seq.fetch_add(1, Ordering::Relaxed), events);
+    }
+
+    /// Batches which aren't acknowledged by the consumer yet
+    pub fn pending(&self, db: &DB, consumer: &str, limit: usize) -> Vec<(u64, RawServerEvent)> {
+        let offset = db.outbox_offsets.get(consumer.to_string()).unwrap_or_default();
+        db.event_outbox.range(&offset.., false).take(limit).collect()
+    }
+
+    /// Moves the consumer offset past `seq` and removes batches acknowledged by every registered consumer
+    pub fn ack(&self, db: &DB, consumer: &str, seq: u64) {
+        db.outbox_offsets.set(consumer.to_string(), seq + 1);
+
+        let consumers = Self::CONSUMERS.map(str::to_string);
+        let offsets = db.outbox_offsets.multi_get(consumers.iter());
+        let delivered = offsets.into_iter().map(Option::unwrap_or_default).min().unwrap_or_default();
+
+        let to_remove = db.event_outbox.range(..&delivered, false).map(|(seq, _)| seq).collect_vec();
+        db.event_outbox.remove_batch(to_remove);
+    }
+}
@@ -0,0 +1,36 @@
+use super::*;
+
+/// Requests of `/admin/simulate-reorg`, run by the indexer between blocks, and the report of the last one
+#[derive(Default)]
+pub struct ReorgSimulations {
+    pending: parking_lot::Mutex<Option<u32>>,
+    last: parking_lot::Mutex<Option<rest::types::ReorgSimulation>>,
+}
+
+impl ReorgSimulations {
+    /// `false` if another simulation is pending
+    pub fn request(&self, depth: u32) -> bool {
+        let mut pending = self.pending.lock();
+        if pending.is_some() {
+            return false;
+        }
+
+        *pending = Some(depth);
+        true
+    }
+
+    /// Depth of the requested simulation, it stays pending until [`Self::finish`]
+    pub fn pending(&self) -> Option<u32> {
+        *self.pending.lock()
+    }
+
+    /// Stores the report and accepts the next request
+    pub fn finish(&self, report: rest::types::ReorgSimulation) {
+        *self.last.lock() = Some(report);
+        self.pending.lock().take();
+    }
+
+    pub fn last(&self) -> Option<rest::types::ReorgSimulation> {
+        self.last.lock().clone()
+    }
+}
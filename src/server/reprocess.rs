@@ -0,0 +1,25 @@
+use super::*;
+
+/// Requests of `/admin/reprocess/{height}`, run by the indexer between blocks one at a time
+#[derive(Default)]
+pub struct ReprocessRequests {
+    pending: parking_lot::Mutex<Option<(u32, tokio::sync::oneshot::Sender<rest::types::BlockReprocess>)>>,
+}
+
+impl ReprocessRequests {
+    /// `None` if another request is pending, the report is sent once the indexer gets to it
+    pub fn request(&self, height: u32) -> Option<tokio::sync::oneshot::Receiver<rest::types::BlockReprocess>> {
+        let mut pending = self.pending.lock();
+        if pending.is_some() {
+            return None;
+        }
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        *pending = Some((height, tx));
+        Some(rx)
+    }
+
+    pub fn take(&self) -> Option<(u32, tokio::sync::oneshot::Sender<rest::types::BlockReprocess>)> {
+        self.pending.lock().take()
+    }
+}
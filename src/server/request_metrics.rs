@@ -0,0 +1,65 @@
+use std::fmt::Write;
+
+use super::*;
+
+/// Upper bounds of the latency buckets in milliseconds, slower requests only count in the `+Inf` bucket
+const BUCKETS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+#[derive(Default)]
+struct RouteLatency {
+    /// Requests by the first bucket of `BUCKETS_MS` they fit in, not cumulative
+    buckets: [u64; BUCKETS_MS.len()],
+    count: u64,
+    sum: Duration,
+    /// Requests slower than `SLOW_QUERY_MS`
+    slow: u64,
+}
+
+/// Latency histograms of REST requests by method and route, exported by `/metrics` in the Prometheus text format. <br/>
+/// Routes are the templates (e.g. `/address/{address}/history`), so the number of series doesn't grow with the queried values.
+#[derive(Default)]
+pub struct RequestMetrics {
+    routes: parking_lot::Mutex<BTreeMap<(String, String), RouteLatency>>,
+}
+
+impl RequestMetrics {
+    pub fn record(&self, method: &str, route: &str, latency: Duration, slow: bool) {
+        let mut routes = self.routes.lock();
+        let entry = routes.entry((route.to_string(), method.to_string())).or_default();
+
+        if let Some(bucket) = BUCKETS_MS.iter().position(|x| latency <= Duration::from_millis(*x)) {
+            entry.buckets[bucket] += 1;
+        }
+        entry.count += 1;
+        entry.sum += latency;
+        entry.slow += slow as u64;
+    }
+
+    pub fn render(&self) -> String {
+        let routes = self.routes.lock();
+        let mut out = String::new();
+
+        out.push_str("# HELP bel20_http_request_duration_seconds Latency of REST requests by route\n");
+        out.push_str("# TYPE bel20_http_request_duration_seconds histogram\n");
+        for ((route, method), latency) in routes.iter() {
+            let labels = format!("method=\"{method}\",route=\"{route}\"");
+
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS_MS.iter().zip(latency.buckets) {
+                cumulative += count;
+                let _ = writeln!(out, "bel20_http_request_duration_seconds_bucket{{{labels},le=\"{}\"}} {cumulative}", *bound as f64 / 1000.0);
+            }
+            let _ = writeln!(out, "bel20_http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}", latency.count);
+            let _ = writeln!(out, "bel20_http_request_duration_seconds_sum{{{labels}}} {}", latency.sum.as_secs_f64());
+            let _ = writeln!(out, "bel20_http_request_duration_seconds_count{{{labels}}} {}", latency.count);
+        }
+
+        out.push_str("# HELP bel20_http_slow_requests_total REST requests slower than SLOW_QUERY_MS by route\n");
+        out.push_str("# TYPE bel20_http_slow_requests_total counter\n");
+        for ((route, method), latency) in routes.iter() {
+            let _ = writeln!(out, "bel20_http_slow_requests_total{{method=\"{method}\",route=\"{route}\"}} {}", latency.slow);
+        }
+
+        out
+    }
+}
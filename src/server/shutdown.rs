@@ -0,0 +1,85 @@
+use super::*;
+
+type Worker = (&'static str, std::thread::JoinHandle<anyhow::Result<()>>);
+
+/// Stops the indexer in a fixed order: stop intake → finalize batches → write commit marker → fsync → close. <br/>
+/// Nothing is written to the database after it's synced, so a restart after a clean shutdown never replays a half-written block.
+pub struct ShutdownCoordinator {
+    server: Arc<Server>,
+    /// Threads accepting new work (REST), stopped first
+    intake: Vec<Worker>,
+    /// Threads writing to the database (indexer and background jobs), joined once the intake is stopped
+    writers: Vec<Worker>,
+}
+
+impl ShutdownCoordinator {
+    const STEPS: usize = 5;
+
+    pub fn new(server: Arc<Server>) -> Self {
+        Self {
+            server,
+            intake: vec![],
+            writers: vec![],
+        }
+    }
+
+    pub fn intake(&mut self, name: &'static str, handle: std::thread::JoinHandle<anyhow::Result<()>>) {
+        self.intake.push((name, handle));
+    }
+
+    pub fn writer(&mut self, name: &'static str, handle: std::thread::JoinHandle<anyhow::Result<()>>) {
+        self.writers.push((name, handle));
+    }
+
+    /// Waits for the token to be cancelled (by a signal or a stopped thread) and runs the shutdown sequence. <br/>
+    /// Returns the results of all threads in the order they were joined.
+    pub fn run(self) -> Vec<anyhow::Result<()>> {
+        while !self.server.token.is_cancelled() {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        let mut results = vec![];
+
+        Self::step(1, "stop intake");
+        for (name, handle) in self.intake {
+            results.push(Self::join(name, handle));
+        }
+
+        // The indexer stops between blocks, so the batch of the last block and its reorg cache entries are complete
+        Self::step(2, "finalize batches");
+        for (name, handle) in self.writers {
+            results.push(Self::join(name, handle));
+        }
+
+        let db = &self.server.db;
+        let last_block = db.last_block.get(());
+
+        Self::step(3, "write commit marker");
+        match last_block {
+            Some(height) => db.shutdown_marker.set((), height),
+            None => info!("Nothing is indexed yet, skipping the marker"),
+        }
+
+        Self::step(4, "fsync");
+        db.flush_all();
+        db.rocks().sync_wal();
+
+        Self::step(5, "close");
+        if Arc::try_unwrap(self.server).is_err() {
+            warn!("Server is still referenced, the database is closed on exit");
+        }
+
+        info!("Shutdown complete, last committed block {:?}", last_block);
+
+        results
+    }
+
+    fn step(n: usize, name: &str) {
+        info!("Shutdown [{}/{}]: {}", n, Self::STEPS, name);
+    }
+
+    fn join(name: &str, handle: std::thread::JoinHandle<anyhow::Result<()>>) -> anyhow::Result<()> {
+        info!("Waiting for {} to stop", name);
+        handle.join().map_err(|_| anyhow::anyhow!("{name} thread panicked"))?
+    }
+}
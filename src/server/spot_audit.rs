@@ -0,0 +1,44 @@
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::*;
+
+/// Results of the spot audits of random past blocks done by [`SpotAuditor`](super::threads::SpotAuditor), exported by `/metrics`
+/// so mismatches can be alerted on
+#[derive(Default)]
+pub struct SpotAudits {
+    audited: AtomicU64,
+    mismatched: AtomicU64,
+    /// Height of the last block which didn't match, 0 if none
+    last_mismatch: AtomicU64,
+}
+
+impl SpotAudits {
+    pub fn record(&self, height: u32, ok: bool) {
+        self.audited.fetch_add(1, Ordering::Relaxed);
+        if !ok {
+            self.mismatched.fetch_add(1, Ordering::Relaxed);
+            self.last_mismatch.store(height as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP bel20_spot_audit_blocks_total Past blocks verified against the node by SPOT_AUDIT_INTERVAL_SECS\n");
+        out.push_str("# TYPE bel20_spot_audit_blocks_total counter\n");
+        let _ = writeln!(out, "bel20_spot_audit_blocks_total {}", self.audited.load(Ordering::Relaxed));
+
+        out.push_str("# HELP bel20_spot_audit_mismatches_total Audited blocks whose stored data doesn't match the node\n");
+        out.push_str("# TYPE bel20_spot_audit_mismatches_total counter\n");
+        let _ = writeln!(out, "bel20_spot_audit_mismatches_total {}", self.mismatched.load(Ordering::Relaxed));
+
+        out.push_str("# HELP bel20_spot_audit_last_mismatch_height Height of the last mismatched block, 0 if none\n");
+        out.push_str("# TYPE bel20_spot_audit_last_mismatch_height gauge\n");
+        let _ = writeln!(out, "bel20_spot_audit_last_mismatch_height {}", self.last_mismatch.load(Ordering::Relaxed));
+
+        out
+    }
+}
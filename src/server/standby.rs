@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::*;
+
+/// Line of the replication NDJSON received from the primary, events aren't needed since the standby indexes the blocks itself
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PrimaryRecord {
+    Block { height: u32, blockhash: BlockHash, proof: sha256::Hash },
+    Reorg { height: u32 },
+}
+
+/// Hot-standby mode enabled by `STANDBY`. <br/>
+/// The standby indexes from its own node like the primary, receives the primary's replication stream (`REPLICATION_URL` of the primary
+/// pointing to `/standby/replication`) and compares the primary's proofs of history with its own, see
+/// [`StandbyVerifier`](super::threads::StandbyVerifier). Until it's promoted, admin writes are rejected and the jobs with external
+/// effects (webhooks, replication) wait, so it can take over with one call when the primary dies.
+pub struct Standby {
+    active: AtomicBool,
+    parity: parking_lot::Mutex<StandbyParity>,
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct StandbyParity {
+    /// Blocks up to it have the same proof of history as the primary
+    pub verified_height: Option<u32>,
+    pub mismatches: u64,
+    pub last_mismatch: Option<u32>,
+}
+
+impl Default for Standby {
+    fn default() -> Self {
+        Self {
+            active: AtomicBool::new(*STANDBY),
+            parity: Default::default(),
+        }
+    }
+}
+
+impl Standby {
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// `false` if the indexer isn't a standby or is already promoted
+    pub fn promote(&self) -> bool {
+        self.active.swap(false, Ordering::SeqCst)
+    }
+
+    pub fn parity(&self) -> StandbyParity {
+        *self.parity.lock()
+    }
+
+    /// Stores the primary's blocks of the NDJSON batch, returns number of records
+    pub fn ingest(&self, db: &DB, body: &[u8]) -> anyhow::Result<usize> {
+        let records = body
+            .split(|x| *x == b'\n')
+            .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+            .map(serde_json::from_slice::<PrimaryRecord>)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for record in &records {
+            match *record {
+                PrimaryRecord::Block { height, blockhash, proof } => {
+                    db.standby_primary_hash.set(height, blockhash);
+                    db.standby_primary_proof.set(height, proof);
+                }
+                PrimaryRecord::Reorg { height } => {
+                    let to_remove = db.standby_primary_hash.range(&(height + 1).., false).map(|(height, _)| height).collect_vec();
+                    db.standby_primary_proof.remove_batch(to_remove.iter());
+                    db.standby_primary_hash.remove_batch(to_remove);
+
+                    let mut parity = self.parity.lock();
+                    parity.verified_height = parity.verified_height.map(|x| x.min(height));
+                }
+            }
+        }
+
+        Ok(records.len())
+    }
+
+    /// Compares the primary's proofs with the own ones from the last verified block. Blocks of another fork on either side
+    /// are skipped until both indexers follow the same chain.
+    pub fn verify(&self, db: &DB) {
+        let Some(last_block) = db.last_block.get(()) else {
+            return;
+        };
+
+        let from = self.parity.lock().verified_height.map(|x| x + 1).unwrap_or(*START_HEIGHT);
+        for (height, primary_hash) in db.standby_primary_hash.range(&from..=&last_block, false) {
+            if db.block_info.get(height).map(|x| x.hash) != Some(primary_hash) {
+                break;
+            }
+
+            let primary_proof = db.standby_primary_proof.get(height);
+            let proof = db.proof_of_history.get(height);
+
+            let mut parity = self.parity.lock();
+            if primary_proof != proof {
+                if parity.last_mismatch != Some(height) {
+                    error!("Proof of history of block {} differs from the primary: {:?}, primary {:?}", height, proof, primary_proof);
+                    parity.mismatches += 1;
+                    parity.last_mismatch = Some(height);
+                }
+                break;
+            }
+            parity.verified_height = Some(height);
+        }
+    }
+}
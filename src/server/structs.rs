@@ -5,6 +5,7 @@ pub enum ServerEvent {
     NewHistory(AddressTokenIdEvent, HistoryValueEvent),
     Reorg(u32, u32),
     NewBlock(u32, sha256::Hash, BlockHash),
+    TokenCompleted(OriginalTokenTick, u32),
 }
 
 pub type RawServerEvent = Vec<(AddressTokenIdDB, HistoryValue)>;
@@ -33,6 +34,13 @@ pub enum TokenHistoryEvent {
 }
 
 impl TokenHistoryEvent {
+    pub fn amount(&self) -> Option<Fixed128> {
+        match self {
+            Self::Deploy { .. } => None,
+            Self::Mint { amt, .. } | Self::DeployTransfer { amt, .. } | Self::Send { amt, .. } | Self::Receive { amt, .. } | Self::SendReceive { amt, .. } => Some(*amt),
+        }
+    }
+
     fn into_event(value: TokenHistoryDB, addresses: &AddressesFullHash) -> Self {
         match value {
             TokenHistoryDB::Deploy { max, lim, dec, txid, vout } => Self::Deploy { max, lim, dec, txid, vout },
@@ -0,0 +1,131 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::*;
+
+/// Recomputes supply, mint count and transfer count of every token from its history and compares them with the stored deploy,
+/// requested by `POST /admin/supply-audit` and `SUPPLY_AUDIT_INTERVAL_HOURS`. <br/>
+/// The indexer takes a checkpoint of the database between blocks, which is scanned by
+/// [`SupplyAuditor`](super::threads::SupplyAuditor) while indexing continues.
+#[derive(Default)]
+pub struct SupplyAudits {
+    pending: AtomicBool,
+    /// Height of the checkpoint which isn't audited yet
+    ready: parking_lot::Mutex<Option<u32>>,
+}
+
+impl SupplyAudits {
+    /// `false` if another audit is pending or running
+    pub fn request(&self) -> bool {
+        let ready = self.ready.lock();
+        ready.is_none() && !self.pending.swap(true, Ordering::SeqCst)
+    }
+
+    /// Checked by the indexer between blocks, the request stays pending until the checkpoint is taken
+    pub fn pending(&self) -> bool {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// Hard links the SST files like backups, so the checkpoint costs little until it's removed by the audit
+    pub fn checkpoint(&self, db: &DB) -> anyhow::Result<()> {
+        let path = Self::path();
+        if std::path::Path::new(&path).exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+
+        let result = db.rocks().checkpoint(&path);
+        let mut ready = self.ready.lock();
+        self.pending.store(false, Ordering::SeqCst);
+        result?;
+
+        *ready = Some(db.last_block.get(()).unwrap_or_default());
+
+        Ok(())
+    }
+
+    /// Audits the checkpoint taken by the indexer, `None` if there is none. Fails only if it's interrupted by shutdown.
+    pub fn run(&self, token: &WaitToken) -> Option<anyhow::Result<SupplyAuditDB>> {
+        let height = (*self.ready.lock())?;
+
+        let started = Instant::now();
+        let db = DB::open(&Self::path());
+
+        let mut report = SupplyAuditDB {
+            height,
+            created: 0,
+            duration_ms: 0,
+            tokens: 0,
+            discrepancies: vec![],
+            error: None,
+        };
+
+        let result = Self::audit(&db, token).map(|(tokens, discrepancies)| {
+            report.tokens = tokens;
+            report.discrepancies = discrepancies;
+        });
+        drop(db);
+
+        report.created = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+        report.duration_ms = started.elapsed().as_millis() as u64;
+
+        if let Err(e) = std::fs::remove_dir_all(Self::path()) {
+            warn!("Failed to remove supply audit checkpoint: {}", e);
+        }
+        *self.ready.lock() = None;
+
+        Some(result.map(|_| report))
+    }
+
+    /// Number of audited tokens and the ones which differ from their history
+    fn audit(db: &DB, token: &WaitToken) -> anyhow::Result<(u64, Vec<SupplyDiscrepancyDB>)> {
+        let mut computed = HashMap::<LowerCaseTokenTick, (OriginalTokenTick, SupplyCounters)>::new();
+        for (key, value) in db.address_token_to_history.iter() {
+            if token.is_cancelled() {
+                anyhow::bail!("Supply audit interrupted by shutdown");
+            }
+
+            let (_, counters) = computed.entry(key.token.into()).or_insert_with(|| (key.token, SupplyCounters::default()));
+            match value.action {
+                TokenHistoryDB::Mint { amt, .. } => {
+                    counters.supply += amt;
+                    counters.mint_count += 1;
+                }
+                TokenHistoryDB::DeployTransfer { .. } => counters.transfer_count += 1,
+                _ => {}
+            }
+        }
+
+        let mut tokens = 0;
+        let mut discrepancies = vec![];
+        for (tick, meta) in db.token_to_meta.iter() {
+            tokens += 1;
+
+            let stored = SupplyCounters {
+                supply: meta.proto.supply,
+                mint_count: meta.proto.mint_count,
+                transfer_count: meta.proto.transfer_count,
+            };
+            let computed = computed.remove(&tick).map(|x| x.1).unwrap_or_default();
+
+            if stored != computed {
+                discrepancies.push(SupplyDiscrepancyDB {
+                    tick: meta.proto.tick,
+                    stored,
+                    computed,
+                });
+            }
+        }
+
+        // History of tokens which aren't deployed
+        discrepancies.extend(computed.into_values().map(|(tick, computed)| SupplyDiscrepancyDB {
+            tick,
+            stored: SupplyCounters::default(),
+            computed,
+        }));
+
+        Ok((tokens, discrepancies))
+    }
+
+    fn path() -> String {
+        format!("{}-supply-audit", *DB_PATH)
+    }
+}
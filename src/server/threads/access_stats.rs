@@ -0,0 +1,26 @@
+use super::*;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct AccessStatsFlusher {
+    pub server: Arc<Server>,
+}
+
+impl AccessStatsFlusher {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let mut last_flush = Instant::now();
+
+        while !self.server.token.is_cancelled() {
+            if last_flush.elapsed() >= FLUSH_INTERVAL {
+                self.server.access_stats.flush(&self.server.db);
+                last_flush = Instant::now();
+            }
+
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        self.server.access_stats.flush(&self.server.db);
+
+        Ok(())
+    }
+}
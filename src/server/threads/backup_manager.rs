@@ -0,0 +1,190 @@
+use bellscoin::hashes::{hmac, HashEngine};
+
+use super::*;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// SST files are up to a few hundred megabytes
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Requests a backup every `BACKUP_INTERVAL_HOURS`, uploads new backups to `BACKUP_S3_URL` and keeps the newest `BACKUP_KEEP`. <br/>
+/// Backups are created by the indexer between blocks, see [`Backups`]. A failed upload is retried on the next check,
+/// and backups which aren't uploaded yet are never pruned.
+pub struct BackupManager {
+    pub server: Arc<Server>,
+}
+
+impl BackupManager {
+    pub fn run(&self) -> anyhow::Result<()> {
+        if !Backups::enabled() {
+            return Ok(());
+        }
+
+        let uploader = S3Uploader::new()?;
+
+        let mut last_check: Option<Instant> = None;
+
+        while !self.server.token.is_cancelled() {
+            if last_check.is_none_or(|x| x.elapsed() >= CHECK_INTERVAL) {
+                if let Err(e) = self.check(uploader.as_ref()) {
+                    warn!("Backup check failed: {}", e);
+                }
+                last_check = Some(Instant::now());
+            }
+
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        Ok(())
+    }
+
+    fn check(&self, uploader: Option<&S3Uploader>) -> anyhow::Result<()> {
+        let backups = &self.server.backups;
+        let list = backups.list()?;
+
+        if let Some(hours) = *BACKUP_INTERVAL_HOURS {
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+            if list.first().is_none_or(|x| now.saturating_sub(x.created) >= hours * 60 * 60) {
+                backups.request();
+            }
+        }
+
+        if let Some(uploader) = uploader {
+            for backup in list.iter().rev().filter(|x| x.uploaded.is_none()) {
+                if self.server.token.is_cancelled() {
+                    return Ok(());
+                }
+
+                uploader.upload(&self.server.token, &backup.name)?;
+                backups.mark_uploaded(&backup.name)?;
+                info!("Backup {} uploaded", backup.name);
+            }
+        }
+
+        if let Some(keep) = *BACKUP_KEEP {
+            let list = backups.list()?;
+            for backup in list.iter().skip(keep).filter(|x| uploader.is_none() || x.uploaded.is_some()) {
+                backups.remove(&backup.name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Uploads the files of a backup as `{BACKUP_S3_URL}/{name}/{file}` with AWS Signature Version 4
+struct S3Uploader {
+    client: reqwest::blocking::Client,
+    url: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Uploader {
+    fn new() -> anyhow::Result<Option<Self>> {
+        let Some(url) = BACKUP_S3_URL.clone() else {
+            return Ok(None);
+        };
+
+        let (Some(access_key), Some(secret_key)) = (BACKUP_S3_ACCESS_KEY.clone(), BACKUP_S3_SECRET_KEY.clone()) else {
+            anyhow::bail!("BACKUP_S3_URL requires BACKUP_S3_ACCESS_KEY and BACKUP_S3_SECRET_KEY");
+        };
+
+        Ok(Some(Self {
+            client: reqwest::blocking::Client::builder().timeout(UPLOAD_TIMEOUT).build()?,
+            url: url.trim_end_matches('/').to_string(),
+            access_key,
+            secret_key,
+        }))
+    }
+
+    fn upload(&self, token: &WaitToken, name: &str) -> anyhow::Result<()> {
+        let dir = Backups::dir()?.join(name);
+
+        for entry in std::fs::read_dir(dir)? {
+            if token.is_cancelled() {
+                anyhow::bail!("Upload of backup {name} interrupted by shutdown");
+            }
+
+            let path = entry?.path();
+            let file_name = path.file_name().map(|x| x.to_string_lossy().into_owned()).unwrap_or_default();
+            self.put(&format!("{}/{name}/{file_name}", self.url), &path)?;
+        }
+
+        Ok(())
+    }
+
+    fn put(&self, url: &str, path: &std::path::Path) -> anyhow::Result<()> {
+        let url = reqwest::Url::parse(url)?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => anyhow::bail!("BACKUP_S3_URL has no host"),
+        };
+
+        let (date, time) = amz_date(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs());
+        let scope = format!("{date}/{}/s3/aws4_request", *BACKUP_S3_REGION);
+
+        // The body is streamed from the file, so its hash isn't signed
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "PUT\n{}\n\nhost:{host}\nx-amz-content-sha256:UNSIGNED-PAYLOAD\nx-amz-date:{time}\n\n{signed_headers}\nUNSIGNED-PAYLOAD",
+            url.path()
+        );
+        let string_to_sign = format!("AWS4-HMAC-SHA256\n{time}\n{scope}\n{}", sha256::Hash::hash(canonical_request.as_bytes()));
+
+        let mut key = hmac_sha256(format!("AWS4{}", self.secret_key).as_bytes(), &date);
+        for part in [BACKUP_S3_REGION.as_str(), "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part);
+        }
+        let signature = hmac_sha256(&key, &string_to_sign).iter().map(|x| format!("{x:02x}")).collect::<String>();
+
+        let file = std::fs::File::open(path)?;
+        let len = file.metadata()?.len();
+
+        let response = self
+            .client
+            .put(url)
+            .header("x-amz-content-sha256", "UNSIGNED-PAYLOAD")
+            .header("x-amz-date", &time)
+            .header(
+                "authorization",
+                format!("AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}", self.access_key),
+            )
+            .body(reqwest::blocking::Body::sized(file, len))
+            .send()?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Upload of {} failed with {}: {}", path.display(), response.status(), response.text().unwrap_or_default());
+        }
+
+        Ok(())
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> [u8; 32] {
+    let mut engine = hmac::HmacEngine::<sha256::Hash>::new(key);
+    engine.input(data.as_bytes());
+
+    hmac::Hmac::from_engine(engine).to_byte_array()
+}
+
+/// `YYYYMMDD` and `YYYYMMDDTHHMMSSZ` of the unix time, see <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn amz_date(secs: u64) -> (String, String) {
+    let days = secs / 86_400;
+    let secs_of_day = secs % 86_400;
+
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as u64;
+
+    let date = format!("{year:04}{month:02}{day:02}");
+    let time = format!("{date}T{:02}{:02}{:02}Z", secs_of_day / 3_600, secs_of_day % 3_600 / 60, secs_of_day % 60);
+
+    (date, time)
+}
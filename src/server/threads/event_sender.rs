@@ -1,58 +1,59 @@
 use super::*;
 
+/// Name of the consumer in the `outbox_offsets` CF
+const CONSUMER: &str = Outbox::EVENT_SENDER;
+/// Batches (one per block) read from the outbox at once
+const BATCH_LIMIT: usize = 100;
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Delivers history events from the persisted [`Outbox`] to the subscribers of `/events`
 #[derive(Clone)]
 pub struct EventSender {
     pub server: Arc<Server>,
     pub event_tx: tokio::sync::broadcast::Sender<ServerEvent>,
-    pub raw_event_tx: kanal::Receiver<RawServerEvent>,
 }
 
 impl EventSender {
     pub fn run(&self) -> anyhow::Result<()> {
-        'outer: loop {
-            let mut events = vec![];
-
-            loop {
-                match self.raw_event_tx.try_recv() {
-                    Ok(Some(v)) => {
-                        events.extend(v);
-                    }
-                    Ok(None) => {
-                        if events.is_empty() {
-                            if self.server.token.is_cancelled() {
-                                break 'outer;
-                            }
-
-                            std::thread::sleep(Duration::from_millis(50));
-                            continue;
-                        }
-                        break;
-                    }
-                    Err(_) => {
-                        if events.is_empty() {
-                            break 'outer;
-                        }
-                    }
-                }
-            }
+        while !self.server.token.is_cancelled() {
+            let batches = self.server.outbox.pending(&self.server.db, CONSUMER, BATCH_LIMIT);
+
+            let Some(last_seq) = batches.last().map(|(seq, _)| *seq) else {
+                std::thread::sleep(Duration::from_millis(50));
+                continue;
+            };
 
-            let keys = events.iter().flat_map(|(k, v)| [Some(k.address), v.action.address().copied()]).flatten().collect_vec();
-
-            let addresses = self.server.load_addresses(keys)?;
-
-            for (k, v) in events {
-                self.event_tx
-                    .send(ServerEvent::NewHistory(
-                        AddressTokenIdEvent {
-                            address: addresses.get(&k.address),
-                            token: k.token.into(),
-                            id: k.id,
-                        },
-                        HistoryValueEvent::into_event(v, &addresses),
-                    ))
-                    .ok();
+            // Unacknowledged batches are read again, so a failure doesn't lose events
+            if let Err(e) = self.send(batches.into_iter().flat_map(|(_, events)| events).collect()) {
+                error!("Failed to send events, retrying in {}s: {e:?}", RETRY_DELAY.as_secs());
+                std::thread::sleep(RETRY_DELAY);
+                continue;
             }
+
+            self.server.outbox.ack(&self.server.db, CONSUMER, last_seq);
+        }
+
+        Ok(())
+    }
+
+    fn send(&self, events: RawServerEvent) -> anyhow::Result<()> {
+        let keys = events.iter().flat_map(|(k, v)| [Some(k.address), v.action.address().copied()]).flatten().collect_vec();
+
+        let addresses = self.server.load_addresses(keys)?;
+
+        for (k, v) in events {
+            self.event_tx
+                .send(ServerEvent::NewHistory(
+                    AddressTokenIdEvent {
+                        address: addresses.get(&k.address),
+                        token: k.token.into(),
+                        id: k.id,
+                    },
+                    HistoryValueEvent::into_event(v, &addresses),
+                ))
+                .ok();
         }
+
         Ok(())
     }
 }
@@ -0,0 +1,103 @@
+use super::*;
+
+const COMPACTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Rolls up per-address token history older than `HISTORY_COMPACTION_DEPTH` blocks into balance checkpoints,
+/// one per `HISTORY_CHECKPOINT_INTERVAL` blocks, which the address history returns in place of the events. <br/>
+/// Only the per-address view is rolled up: the events stay in `address_token_to_history`, so block events, proof of history
+/// and the outpoint and token id indexes keep referring to them. Deploy events are returned as is.
+pub struct HistoryCompactor {
+    pub server: Arc<Server>,
+}
+
+impl HistoryCompactor {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let Some(depth) = *HISTORY_COMPACTION_DEPTH else {
+            return Ok(());
+        };
+
+        let mut last_compaction: Option<Instant> = None;
+
+        while !self.server.token.is_cancelled() {
+            if last_compaction.is_none_or(|x| x.elapsed() >= COMPACTION_INTERVAL) {
+                self.compact(depth);
+                last_compaction = Some(Instant::now());
+            }
+
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        Ok(())
+    }
+
+    fn compact(&self, depth: u32) {
+        let db = &self.server.db;
+        let Some(last_block) = db.last_block.get(()) else {
+            return;
+        };
+
+        let interval = *HISTORY_CHECKPOINT_INTERVAL;
+        // Only whole periods are compacted, so a checkpoint never receives new events
+        let cutoff = last_block.saturating_sub(depth) / interval * interval;
+        let mut from = db.history_compacted_height.get(()).unwrap_or_default();
+
+        let mut compacted = 0;
+        while from < cutoff && !self.server.token.is_cancelled() {
+            let to = (from / interval + 1) * interval;
+            let checkpoints = self.compact_period(from, to);
+            compacted += checkpoints.iter().map(|(_, checkpoint)| checkpoint.events).sum::<u64>();
+
+            // Checkpoints of the period and the progress are written together, so a period is never rolled up twice
+            db.rocks().atomic(|| {
+                db.address_token_to_checkpoint.extend(checkpoints);
+                db.history_compacted_height.set((), to);
+            });
+            from = to;
+        }
+
+        if compacted > 0 {
+            info!("Compacted {compacted} history events below height {from}");
+        }
+    }
+
+    /// Checkpoints of the (address, tick) pairs with events in the blocks `from..to`, keyed by the last event of each pair
+    fn compact_period(&self, from: u32, to: u32) -> Vec<(AddressTokenIdDB, HistoryCheckpoint)> {
+        let db = &self.server.db;
+
+        let keys = db.block_events.range(&from..&to, false).flat_map(|(_, keys)| keys).collect_vec();
+        let mut groups = BTreeMap::<AddressToken, Vec<(AddressTokenIdDB, HistoryValue)>>::new();
+        for (key, value) in db.address_token_to_history.multi_get_kv(keys.iter(), true) {
+            if !matches!(value.action, TokenHistoryDB::Deploy { .. }) {
+                groups.entry((*key).into()).or_default().push((*key, value));
+            }
+        }
+
+        groups
+            .into_values()
+            .map(|mut rows| {
+                rows.sort_unstable_by_key(|(key, _)| key.id);
+                let first = rows[0].0;
+                let last = rows[rows.len() - 1].0;
+
+                let start = AddressTokenIdDB { id: 0, ..first };
+                let mut balance = db
+                    .address_token_to_checkpoint
+                    .range(&start..&first, true)
+                    .next()
+                    .map(|(_, checkpoint)| checkpoint.balance)
+                    .unwrap_or_default();
+                for (_, value) in &rows {
+                    value.action.apply_to_balance(&mut balance);
+                }
+
+                let checkpoint = HistoryCheckpoint {
+                    from_height: from,
+                    to_height: to - 1,
+                    events: rows.len() as u64,
+                    balance,
+                };
+                (last, checkpoint)
+            })
+            .collect()
+    }
+}
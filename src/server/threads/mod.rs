@@ -1,4 +1,22 @@
 use super::*;
 
+pub mod access_stats;
+pub mod backup_manager;
 pub mod event_sender;
+pub mod history_compactor;
+pub mod partials_collector;
+pub mod replication;
+pub mod spot_auditor;
+pub mod standby_verifier;
+pub mod supply_auditor;
+pub mod webhook_sender;
+pub use access_stats::AccessStatsFlusher;
+pub use backup_manager::BackupManager;
 pub use event_sender::EventSender;
+pub use history_compactor::HistoryCompactor;
+pub use partials_collector::PartialsCollector;
+pub use replication::ReplicationSink;
+pub use spot_auditor::SpotAuditor;
+pub use standby_verifier::StandbyVerifier;
+pub use supply_auditor::SupplyAuditor;
+pub use webhook_sender::WebhookSender;
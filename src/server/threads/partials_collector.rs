@@ -0,0 +1,68 @@
+use super::*;
+
+const COLLECTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const CHUNK_SIZE: usize = 10_000;
+
+/// Removes partials of multi-part inscriptions whose outpoint was spent without continuation
+/// more than `PARTIALS_GC_DEPTH` blocks ago.
+pub struct PartialsCollector {
+    pub server: Arc<Server>,
+}
+
+impl PartialsCollector {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let Some(depth) = *PARTIALS_GC_DEPTH else {
+            return Ok(());
+        };
+
+        let mut last_collection: Option<Instant> = None;
+
+        while !self.server.token.is_cancelled() {
+            if last_collection.is_none_or(|x| x.elapsed() >= COLLECTION_INTERVAL) {
+                self.collect(depth);
+                last_collection = Some(Instant::now());
+            }
+
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        Ok(())
+    }
+
+    fn collect(&self, depth: u32) {
+        let db = &self.server.db;
+
+        let Some(last_block) = db.last_block.get(()) else {
+            return;
+        };
+
+        let mut removed = 0;
+
+        for chunk in &db.outpoint_to_partials.iter().map(|(outpoint, _)| outpoint).chunks(CHUNK_SIZE) {
+            if self.server.token.is_cancelled() {
+                return;
+            }
+
+            let outpoints = chunk.collect_vec();
+            let prevouts = db.prevouts.multi_get(outpoints.iter());
+            // Partials written before heights were recorded are treated as the oldest ones
+            let heights = db.partials_height.multi_get(outpoints.iter());
+
+            let to_remove = outpoints
+                .into_iter()
+                .zip(prevouts)
+                .zip(heights)
+                .filter(|((_, prevout), height)| prevout.is_none() && height.unwrap_or_default().saturating_add(depth) < last_block)
+                .map(|((outpoint, _), _)| outpoint)
+                .collect_vec();
+
+            removed += to_remove.len();
+            db.partials_height.remove_batch(to_remove.iter());
+            db.outpoint_to_partials.remove_batch(to_remove);
+        }
+
+        if removed > 0 {
+            info!("Removed {removed} abandoned inscription partials");
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use super::*;
+
+/// Blocks sent in one request
+const BATCH_BLOCKS: u32 = 100;
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Streams committed blocks with their token events to `REPLICATION_URL` as height-ordered NDJSON, one line per block. <br/>
+/// A batch is acknowledged by a 2xx response and the sink resumes after the last acknowledged block on restart, so delivery is at-least-once.
+/// When the indexer rolls back acknowledged blocks, a `reorg` line with the last block kept is sent before the new ones.
+pub struct ReplicationSink {
+    pub server: Arc<Server>,
+}
+
+impl ReplicationSink {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let Some(url) = REPLICATION_URL.as_ref() else {
+            return Ok(());
+        };
+
+        let client = reqwest::blocking::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+
+        while !self.server.token.is_cancelled() {
+            if self.server.reorg_in_progress.height().is_some() || self.server.standby.is_active() {
+                std::thread::sleep(Duration::from_millis(50));
+                continue;
+            }
+
+            match self.replicate(&client, url) {
+                Ok(true) => {}
+                Ok(false) => std::thread::sleep(Duration::from_millis(50)),
+                Err(e) => {
+                    warn!("Replication to {} failed, retrying: {:?}", url, e);
+                    std::thread::sleep(RETRY_DELAY);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends the next batch, `false` if the sink is up to date
+    fn replicate(&self, client: &reqwest::blocking::Client, url: &str) -> anyhow::Result<bool> {
+        let db = &self.server.db;
+
+        if let Some(height) = self.fork_height() {
+            let mut body = serde_json::to_vec(&rest::types::ReplicationRecord::Reorg { height })?;
+            body.push(b'\n');
+            Self::post(client, url, body)?;
+
+            let to_remove = db.replication_acked.range(&(height + 1).., false).map(|(height, _)| height).collect_vec();
+            db.replication_acked.remove_batch(to_remove);
+
+            return Ok(true);
+        }
+
+        let Some(last_block) = db.last_block.get(()) else {
+            return Ok(false);
+        };
+
+        let from = db.replication_acked.range(.., true).next().map(|(height, _)| height + 1).unwrap_or(*START_HEIGHT);
+        if from > last_block {
+            return Ok(false);
+        }
+
+        let to = last_block.min(from + BATCH_BLOCKS - 1);
+        let mut body = vec![];
+        let mut hashes = vec![];
+
+        for height in from..=to {
+            let block_info = db.block_info.get(height).ok_or_else(|| anyhow::anyhow!("Block {height} isn't indexed"))?;
+            let proof = db.proof_of_history.get(height).ok_or_else(|| anyhow::anyhow!("Block {height} has no proof of history"))?;
+
+            serde_json::to_writer(
+                &mut body,
+                &rest::types::ReplicationRecord::Block {
+                    height,
+                    blockhash: block_info.hash.to_string(),
+                    created: block_info.created,
+                    proof: proof.to_string(),
+                    events: self.block_events(height)?,
+                },
+            )?;
+            body.push(b'\n');
+
+            hashes.push((height, block_info.hash));
+        }
+
+        // Blocks rolled back while the batch was read are sent once the rollback is over
+        if self.server.reorg_in_progress.height().is_some() || hashes.iter().any(|(height, hash)| db.block_info.get(*height).map(|x| x.hash) != Some(*hash)) {
+            return Ok(false);
+        }
+
+        Self::post(client, url, body)?;
+
+        db.replication_acked.extend(hashes);
+
+        // Only blocks which may be rolled back are kept
+        let outdated = to.saturating_sub(REORG_CACHE_MAX_LEN as u32);
+        let to_remove = db.replication_acked.range(..&outdated, false).map(|(height, _)| height).collect_vec();
+        db.replication_acked.remove_batch(to_remove);
+
+        Ok(true)
+    }
+
+    /// Last acknowledged block which is still in the chain if the latest one was rolled back
+    fn fork_height(&self) -> Option<u32> {
+        let db = &self.server.db;
+        let is_current = |height: u32, hash: BlockHash| db.block_info.get(height).is_some_and(|x| x.hash == hash);
+
+        let acked = db.replication_acked.range(.., true).collect_vec();
+        let (last_height, last_hash) = *acked.first()?;
+        if is_current(last_height, last_hash) {
+            return None;
+        }
+
+        let fork_height = acked.iter().find(|(height, hash)| is_current(*height, *hash)).map(|(height, _)| *height);
+
+        // Deeper than the kept blocks, the sink restarts from the oldest one
+        Some(fork_height.unwrap_or_else(|| acked.last().map(|(height, _)| height.saturating_sub(1)).unwrap_or_default()))
+    }
+
+    fn block_events(&self, height: u32) -> anyhow::Result<Vec<rest::types::History>> {
+        let keys = self.server.db.block_events.get(height).unwrap_or_default();
+
+        let rows = self
+            .server
+            .db
+            .address_token_to_history
+            .multi_get_kv(keys.iter(), true)
+            .into_iter()
+            .map(|(k, v)| (*k, v))
+            .collect_vec();
+
+        rest::types::History::new_page(rows, &self.server)
+    }
+
+    fn post(client: &reqwest::blocking::Client, url: &str, body: Vec<u8>) -> anyhow::Result<()> {
+        let response = client.post(url).header(reqwest::header::CONTENT_TYPE, "application/x-ndjson").body(body).send()?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Sink responded with {}", response.status());
+        }
+
+        Ok(())
+    }
+}
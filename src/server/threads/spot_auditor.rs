@@ -0,0 +1,157 @@
+use std::hash::{BuildHasher, Hasher};
+
+use super::*;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Every `SPOT_AUDIT_INTERVAL_SECS` picks a random block below the reorg depth, requests it from the node and checks the stored data against it,
+/// to catch silent disk corruption and regressions of inscription parsing. <br/>
+/// The state before old blocks isn't kept, so token validation can't be replayed. Instead the block hash is compared with the node,
+/// the stored deploys, mints and transfer inscriptions are matched with the inscriptions parsed again from the block, and the proof
+/// of history is recomputed from the stored events. Parsing needs the spent outputs, which requires `txindex` on the node.
+pub struct SpotAuditor {
+    pub server: Arc<Server>,
+}
+
+impl SpotAuditor {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let Some(interval) = *SPOT_AUDIT_INTERVAL_SECS else {
+            return Ok(());
+        };
+        let interval = Duration::from_secs(interval);
+        let mut last_audit = Instant::now();
+
+        while !self.server.token.is_cancelled() {
+            if last_audit.elapsed() < interval {
+                std::thread::sleep(CHECK_INTERVAL);
+                continue;
+            }
+            last_audit = Instant::now();
+
+            let Some(height) = self.pick_height() else {
+                continue;
+            };
+
+            match self.audit(height) {
+                Ok(checks) => {
+                    let failed = checks.iter().filter(|x| !x.ok).collect_vec();
+                    self.server.spot_audits.record(height, failed.is_empty());
+
+                    if failed.is_empty() {
+                        info!("Spot audit of block {} matches the node", height);
+                    } else {
+                        for check in failed {
+                            error!("Spot audit of block {}: {} doesn't match: {}", height, check.name, check.details.as_deref().unwrap_or_default());
+                        }
+                    }
+                }
+                Err(e) => warn!("Spot audit of block {} failed: {}", height, e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Random block which can't be reorged anymore, `None` until there is one
+    fn pick_height(&self) -> Option<u32> {
+        let last = self.server.db.last_block.get(())?.checked_sub(REORG_CACHE_MAX_LEN as u32)?;
+        let first = *START_HEIGHT;
+        let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+
+        (last >= first).then(|| first + (random % (last - first + 1) as u64) as u32)
+    }
+
+    fn audit(&self, height: u32) -> anyhow::Result<Vec<rest::types::ReorgSimulationCheck>> {
+        let db = &self.server.db;
+
+        let mut checks = vec![];
+        let mut check = |name: &str, ok: bool, details: String| {
+            checks.push(rest::types::ReorgSimulationCheck {
+                name: name.to_string(),
+                ok,
+                details: (!ok).then_some(details),
+            });
+        };
+
+        let stored_hash = db.block_info.get(height).anyhow_with("Block isn't indexed")?.hash;
+        let hash = self.server.client.get_block_hash(height as u64)?;
+        check("block_hash", hash == stored_hash.to_raw_hash(), format!("node {hash}, stored {stored_hash}"));
+        if hash != stored_hash.to_raw_hash() {
+            return Ok(checks);
+        }
+
+        let keys = db.block_events.get(height).unwrap_or_default();
+        let history = db.address_token_to_history.multi_get_kv(keys.iter(), true).into_iter().map(|(k, v)| (*k, v)).collect_vec();
+
+        let prev_proof = db.proof_of_history.get(height.checked_sub(1).unwrap_or_default()).unwrap_or(*DEFAULT_HASH);
+        let proof = db.proof_of_history.get(height).anyhow_with("Block has no proof of history")?;
+        if let Some(events_hash) = db.proof_of_history_events_hash.get(height) {
+            let linked = poh::link(prev_proof, events_hash);
+            check("proof_of_history", linked == proof, format!("recomputed {linked}, stored {proof}"));
+
+            // V1 hashes the events in the order they were created, which isn't stored
+            if PohVersion::from_db(db.proof_of_history_version.get(height)) == PohVersion::V2 {
+                let addresses = self.server.load_addresses(history.iter().flat_map(|(k, v)| [Some(k.address), v.action.address().copied()]).flatten())?;
+                let recomputed = poh::block_proof(prev_proof, &history, &addresses, PohVersion::V2)?.events_hash;
+                check("events_hash", recomputed == events_hash, format!("recomputed {recomputed}, stored {events_hash}"));
+            }
+        }
+
+        let block = self.server.client.try_get_block(&hash)?;
+        let txids = block.txs.iter().map(|tx| Txid::from(tx.hash)).collect::<HashSet<_>>();
+
+        let missing = history.iter().filter(|(_, v)| !txids.contains(&v.action.outpoint().txid)).map(|(k, _)| k.id).collect_vec();
+        check("event_txids", missing.is_empty(), format!("transactions of events {missing:?} aren't in the block"));
+
+        let mut differ = vec![];
+        let created = history.iter().filter(|(_, v)| Self::created_by_inscription(&v.action)).into_group_map_by(|(_, v)| v.action.outpoint().txid);
+        for (txid, events) in created {
+            if !txids.contains(&txid) {
+                continue;
+            }
+
+            let trace = crate::inscriptions::trace_tx(&self.server, height, &block, txid)?;
+            let inscriptions = trace.inputs.iter().flat_map(|input| input.inscriptions.iter()).collect_vec();
+
+            for (key, value) in events {
+                let (op, candidates) = match value.action {
+                    TokenHistoryDB::Deploy { txid, vout, .. } => {
+                        let id = InscriptionId { txid, index: vout }.to_string();
+                        ("deploy", inscriptions.iter().filter(|x| x.id == id).collect_vec())
+                    }
+                    TokenHistoryDB::Mint { txid, vout, .. } | TokenHistoryDB::DeployTransfer { txid, vout, .. } => {
+                        let op = if matches!(value.action, TokenHistoryDB::Mint { .. }) { "mint" } else { "transfer" };
+                        let outpoint = OutPoint { txid, vout };
+                        let candidates = inscriptions
+                            .iter()
+                            .filter(|x| x.location.as_deref().and_then(|x| Location::from_str(x).ok()).is_some_and(|x| x.outpoint == outpoint))
+                            .collect_vec();
+                        (op, candidates)
+                    }
+                    _ => continue,
+                };
+
+                // Multi-part inscriptions started in earlier transactions aren't parsed again
+                if candidates.is_empty() {
+                    continue;
+                }
+
+                let tick = key.token.to_string().to_lowercase();
+                let matched = candidates
+                    .iter()
+                    .any(|x| x.token.parsed && x.token.op.as_deref() == Some(op) && x.token.tick.as_ref().is_some_and(|x| x.to_lowercase() == tick));
+                if !matched {
+                    differ.push(key.id);
+                }
+            }
+        }
+        check("token_operations", differ.is_empty(), format!("inscriptions of events {differ:?} parse into other operations"));
+
+        Ok(checks)
+    }
+
+    /// Events of token inscriptions created in the block, moves of transfers depend on the state before the block
+    fn created_by_inscription(action: &TokenHistoryDB) -> bool {
+        matches!(action, TokenHistoryDB::Deploy { .. } | TokenHistoryDB::Mint { .. } | TokenHistoryDB::DeployTransfer { .. })
+    }
+}
@@ -0,0 +1,37 @@
+use super::*;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Compares the proofs of history received from the primary with the own ones while the indexer is a [`Standby`]. <br/>
+/// Only the blocks which may still be rolled back are kept once they are verified.
+pub struct StandbyVerifier {
+    pub server: Arc<Server>,
+}
+
+impl StandbyVerifier {
+    pub fn run(&self) -> anyhow::Result<()> {
+        while !self.server.token.is_cancelled() && self.server.standby.is_active() {
+            if self.server.reorg_in_progress.height().is_none() {
+                self.server.standby.verify(&self.server.db);
+                self.prune();
+            }
+
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+
+        Ok(())
+    }
+
+    fn prune(&self) {
+        let db = &self.server.db;
+
+        let Some(verified) = self.server.standby.parity().verified_height else {
+            return;
+        };
+
+        let outdated = verified.saturating_sub(REORG_CACHE_MAX_LEN as u32);
+        let to_remove = db.standby_primary_hash.range(..&outdated, false).map(|(height, _)| height).collect_vec();
+        db.standby_primary_proof.remove_batch(to_remove.iter());
+        db.standby_primary_hash.remove_batch(to_remove);
+    }
+}
@@ -0,0 +1,52 @@
+use super::*;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Requests a supply audit every `SUPPLY_AUDIT_INTERVAL_HOURS` and audits the checkpoints taken by the indexer, see [`SupplyAudits`]
+pub struct SupplyAuditor {
+    pub server: Arc<Server>,
+}
+
+impl SupplyAuditor {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let mut last_check: Option<Instant> = None;
+
+        while !self.server.token.is_cancelled() {
+            if last_check.is_none_or(|x| x.elapsed() >= CHECK_INTERVAL) {
+                self.schedule();
+                last_check = Some(Instant::now());
+            }
+
+            match self.server.supply_audits.run(&self.server.token) {
+                Some(Ok(report)) => self.save(report),
+                Some(Err(e)) => warn!("Supply audit failed: {}", e),
+                None => std::thread::sleep(Duration::from_millis(50)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn schedule(&self) {
+        let Some(hours) = *SUPPLY_AUDIT_INTERVAL_HOURS else {
+            return;
+        };
+
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+        let last = self.server.db.supply_audit.range(.., true).next().map(|(_, report)| report.created);
+        if last.is_none_or(|x| now.saturating_sub(x) >= hours * 60 * 60) {
+            self.server.supply_audits.request();
+        }
+    }
+
+    fn save(&self, report: SupplyAuditDB) {
+        match (&report.error, report.discrepancies.len()) {
+            (Some(error), _) => warn!("Supply audit of block {} skipped: {}", report.height, error),
+            (None, 0) => info!("Supply audit of block {}: {} tokens match their history", report.height, report.tokens),
+            (None, count) => error!("Supply audit of block {}: {} of {} tokens differ from their history", report.height, count, report.tokens),
+        }
+
+        let id = self.server.db.supply_audit.range(.., true).next().map(|(id, _)| id + 1).unwrap_or_default();
+        self.server.db.supply_audit.set(id, report);
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::atomic::Ordering;
+
+use bellscoin::hashes::{hmac, HashEngine};
+
+use super::*;
+
+/// Name of the consumer in the `outbox_offsets` CF
+const CONSUMER: &str = Outbox::WEBHOOK_SENDER;
+/// Batches (one per block) read from the outbox at once
+const BATCH_LIMIT: usize = 100;
+const ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+static NONCE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Posts token events from the persisted [`Outbox`] to the webhooks registered via `/admin/webhooks`, one request per webhook and block. <br/>
+/// Requests are signed with HMAC-SHA256 of `{timestamp}.{nonce}.{body}`, receivers should reject stale timestamps and repeated nonces.
+/// A request which fails [`ATTEMPTS`] times is dropped and counted in the webhook status, so a dead receiver doesn't hold back the others.
+pub struct WebhookSender {
+    pub server: Arc<Server>,
+}
+
+impl WebhookSender {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let client = reqwest::blocking::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+
+        while !self.server.token.is_cancelled() {
+            let batches = self.server.outbox.pending(&self.server.db, CONSUMER, BATCH_LIMIT);
+
+            let Some(last_seq) = batches.last().map(|(seq, _)| *seq) else {
+                std::thread::sleep(Duration::from_millis(50));
+                continue;
+            };
+
+            let webhooks = self.server.db.webhooks.iter().collect_vec();
+
+            // The primary delivers them until the standby is promoted
+            if !webhooks.is_empty() && !self.server.standby.is_active() {
+                for (_, events) in batches {
+                    self.deliver(&client, &webhooks, events)?;
+                }
+            }
+
+            // A batch interrupted by shutdown is delivered again on restart
+            if self.server.token.is_cancelled() {
+                break;
+            }
+
+            self.server.outbox.ack(&self.server.db, CONSUMER, last_seq);
+        }
+
+        Ok(())
+    }
+
+    fn deliver(&self, client: &reqwest::blocking::Client, webhooks: &[(u64, WebhookDB)], events: RawServerEvent) -> anyhow::Result<()> {
+        let Some(height) = events.first().map(|(_, v)| v.height) else {
+            return Ok(());
+        };
+
+        let ticks: HashSet<_> = webhooks.iter().map(|(_, webhook)| LowerCaseTokenTick::from(webhook.tick)).collect();
+        let events = events.into_iter().filter(|(k, _)| ticks.contains(&LowerCaseTokenTick::from(k.token))).collect_vec();
+
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let keys = events.iter().flat_map(|(k, v)| [Some(k.address), v.action.address().copied()]).flatten().collect_vec();
+        let addresses = self.server.load_addresses(keys)?;
+
+        let events = events
+            .into_iter()
+            .map(|(k, v)| {
+                let address_token = AddressTokenIdEvent {
+                    address: addresses.get(&k.address),
+                    token: k.token.into(),
+                    id: k.id,
+                };
+
+                (address_token, HistoryValueEvent::into_event(v, &addresses))
+            })
+            .collect_vec();
+
+        for (id, webhook) in webhooks {
+            if self.server.token.is_cancelled() {
+                break;
+            }
+
+            let tick = LowerCaseTokenTick::from(webhook.tick);
+            let matching = events
+                .iter()
+                .filter(|(k, v)| LowerCaseTokenTick::from(k.token) == tick && (webhook.event_types.is_empty() || webhook.event_types.contains(&(&v.action).into())))
+                .map(|(k, v)| rest::types::History {
+                    address_token: k.clone().into(),
+                    height: v.height,
+                    action: v.clone().into(),
+                    origin: None,
+                })
+                .collect_vec();
+
+            if matching.is_empty() {
+                continue;
+            }
+
+            let body = serde_json::to_vec(&rest::types::WebhookPayload {
+                webhook_id: *id,
+                height,
+                events: matching,
+            })?;
+
+            self.post(client, *id, webhook, height, &body);
+        }
+
+        Ok(())
+    }
+
+    fn post(&self, client: &reqwest::blocking::Client, id: u64, webhook: &WebhookDB, height: u32, body: &[u8]) {
+        let mut result = Ok(());
+
+        for attempt in 0..ATTEMPTS {
+            if attempt > 0 {
+                std::thread::sleep(RETRY_DELAY);
+            }
+
+            result = Self::send(client, id, webhook, body);
+            if result.is_ok() || self.server.token.is_cancelled() {
+                break;
+            }
+        }
+
+        // Removed while the request was being delivered
+        if self.server.db.webhooks.get(id).is_none() {
+            return;
+        }
+
+        let now = unix_now();
+        let mut status = self.server.db.webhook_status.get(id).unwrap_or_default();
+        status.last_attempt = Some(now);
+
+        match result {
+            Ok(()) => {
+                status.delivered += 1;
+                status.last_success = Some(now);
+                status.last_height = Some(height);
+            }
+            Err(e) => {
+                warn!("Webhook {} failed to deliver block {}: {:?}", id, height, e);
+                status.failed += 1;
+                status.last_error = Some(e.to_string());
+            }
+        }
+
+        self.server.db.webhook_status.set(id, status);
+    }
+
+    fn send(client: &reqwest::blocking::Client, id: u64, webhook: &WebhookDB, body: &[u8]) -> anyhow::Result<()> {
+        let timestamp = unix_now();
+        let nonce = nonce(id);
+        let signature = sign(&webhook.secret, timestamp, &nonce, body);
+
+        let response = client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Webhook-Id", id)
+            .header("X-Webhook-Timestamp", timestamp)
+            .header("X-Webhook-Nonce", &nonce)
+            .header("X-Webhook-Signature", format!("sha256={signature}"))
+            .body(body.to_vec())
+            .send()?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Receiver responded with {}", response.status());
+        }
+
+        Ok(())
+    }
+}
+
+/// HMAC-SHA256 of `{timestamp}.{nonce}.{body}`
+pub fn sign(secret: &str, timestamp: u64, nonce: &str, body: &[u8]) -> hmac::Hmac<sha256::Hash> {
+    let mut engine = hmac::HmacEngine::<sha256::Hash>::new(secret.as_bytes());
+    engine.input(format!("{timestamp}.{nonce}.").as_bytes());
+    engine.input(body);
+
+    hmac::Hmac::from_engine(engine)
+}
+
+/// Unique per request, including retries
+fn nonce(id: u64) -> String {
+    let mut buffer = id.to_be_bytes().to_vec();
+    buffer.extend(NONCE_COUNTER.fetch_add(1, Ordering::Relaxed).to_be_bytes());
+    buffer.extend(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos().to_be_bytes());
+
+    sha256::Hash::hash(&buffer).to_string()[..32].to_string()
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()
+}
@@ -0,0 +1,80 @@
+use std::sync::atomic::Ordering;
+
+use super::*;
+
+/// Queries within this window count as live traffic
+const LIVE_QUERIES_WINDOW: Duration = Duration::from_secs(5);
+/// Write rate is measured over windows of this length
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+const MAX_SLEEP: Duration = Duration::from_secs(1);
+/// Compaction debt above which writes are slowed down further
+const PENDING_COMPACTION_LIMIT: u64 = 16 * 1024 * 1024 * 1024;
+/// Divider of `MAX_WRITE_MBPS` while RocksDB stalls writes or is behind on compactions
+const STALL_RATE_DIVIDER: u64 = 4;
+
+/// Bounds the write rate of the indexer to `MAX_WRITE_MBPS` while REST queries are being served,
+/// so compactions caused by bulk sync don't spike query latency. <br/>
+/// The limit is lowered further while RocksDB reports write stalls or a large compaction debt.
+pub struct WriteThrottle {
+    started: Instant,
+    /// Milliseconds since `started` of the last REST query
+    last_query: AtomicU64,
+    /// Start of the current window and bytes written before it
+    window: parking_lot::Mutex<Option<(Instant, u64)>>,
+}
+
+impl Default for WriteThrottle {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            last_query: AtomicU64::new(u64::MAX),
+            window: parking_lot::Mutex::new(None),
+        }
+    }
+}
+
+impl WriteThrottle {
+    pub fn on_query(&self) {
+        self.last_query.store(self.started.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn live_queries(&self) -> bool {
+        let last_query = self.last_query.load(Ordering::Relaxed);
+        last_query != u64::MAX && self.started.elapsed().as_millis() as u64 - last_query <= LIVE_QUERIES_WINDOW.as_millis() as u64
+    }
+
+    /// Called by the indexer after every block
+    pub fn throttle(&self, db: &RocksDB, token: &WaitToken) {
+        let Some(max_write_mbps) = *MAX_WRITE_MBPS else {
+            return;
+        };
+
+        let written = db.written_bytes();
+        let mut window = self.window.lock();
+
+        let Some((window_start, window_written)) = (*window).filter(|_| self.live_queries()) else {
+            *window = Some((Instant::now(), written));
+            return;
+        };
+
+        let stalled = db.property_int("rocksdb.is-write-stopped") > 0
+            || db.property_int("rocksdb.actual-delayed-write-rate") > 0
+            || db.property_int_sum("rocksdb.estimate-pending-compaction-bytes") > PENDING_COMPACTION_LIMIT;
+
+        let max_write_mbps = if stalled { (max_write_mbps / STALL_RATE_DIVIDER).max(1) } else { max_write_mbps };
+        let allowed = Duration::from_secs_f64((written - window_written) as f64 / (max_write_mbps * 1024 * 1024) as f64);
+        let elapsed = window_start.elapsed();
+
+        if allowed > elapsed {
+            let sleep = (allowed - elapsed).min(MAX_SLEEP);
+            let until = Instant::now() + sleep;
+            while Instant::now() < until && !token.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+
+        if window_start.elapsed() >= RATE_WINDOW {
+            *window = Some((Instant::now(), db.written_bytes()));
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::{num::NonZeroUsize, sync::atomic::Ordering};
+
+use super::*;
+
+/// Approximate memory of a cached account, including the bookkeeping of the LRU
+const ENTRY_SIZE: usize = std::mem::size_of::<AddressToken>() + std::mem::size_of::<TokenBalance>() + 64;
+/// Accounts read from the database at once while warming up
+const WARMUP_CHUNK: usize = 10_000;
+
+/// Recently active token balances kept in memory within the `TOKEN_CACHE_MB` budget, other ones are read from the database. <br/>
+/// Balances written by every block go through the cache and it's cleared on reorgs, so it never serves stale values.
+/// Without a budget every read goes to the database.
+pub struct TokenAccountCache {
+    accounts: Option<parking_lot::Mutex<lru::LruCache<AddressToken, TokenBalance>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl TokenAccountCache {
+    pub fn new(budget_mb: Option<usize>) -> Self {
+        Self {
+            accounts: budget_mb
+                .and_then(|mb| NonZeroUsize::new(mb * 1024 * 1024 / ENTRY_SIZE))
+                .map(|capacity| parking_lot::Mutex::new(lru::LruCache::new(capacity))),
+            hits: AtomicU64::default(),
+            misses: AtomicU64::default(),
+        }
+    }
+
+    /// Fills the cache with the accounts of the latest events, so the first blocks after a restart don't miss
+    pub fn warm_up(&self, db: &DB) {
+        let Some(capacity) = self.accounts.as_ref().map(|x| x.lock().cap().get()) else {
+            return;
+        };
+
+        let mut seen = HashSet::new();
+        let mut keys = vec![];
+
+        'blocks: for (_, events) in db.block_events.range(.., true) {
+            for event in events {
+                let key = AddressToken {
+                    address: event.address,
+                    token: event.token,
+                };
+
+                if seen.insert(key) {
+                    keys.push(key);
+                }
+
+                if keys.len() >= capacity {
+                    break 'blocks;
+                }
+            }
+        }
+
+        // The most recent accounts are inserted last, so they are evicted last
+        for chunk in keys.rchunks(WARMUP_CHUNK) {
+            self.update(&db.load_token_accounts(chunk.to_vec()).into_iter().collect_vec());
+        }
+
+        info!("Token cache warmed up with {} accounts", self.len());
+    }
+
+    pub fn load(&self, db: &DB, keys: Vec<AddressToken>) -> HashMap<AddressToken, TokenBalance> {
+        let Some(accounts) = &self.accounts else {
+            return db.load_token_accounts(keys);
+        };
+
+        let mut result = HashMap::with_capacity(keys.len());
+        let mut missing = vec![];
+
+        {
+            let mut accounts = accounts.lock();
+            for key in keys {
+                match accounts.get(&key) {
+                    Some(balance) => {
+                        result.insert(key, balance.clone());
+                    }
+                    None => missing.push(key),
+                }
+            }
+        }
+
+        self.hits.fetch_add(result.len() as u64, Ordering::Relaxed);
+        self.misses.fetch_add(missing.len() as u64, Ordering::Relaxed);
+
+        let loaded = db.load_token_accounts(missing);
+        {
+            let mut accounts = accounts.lock();
+            for (key, balance) in &loaded {
+                accounts.put(*key, balance.clone());
+            }
+        }
+
+        result.extend(loaded);
+        result
+    }
+
+    /// Balances written to the database
+    pub fn update(&self, balances: &[(AddressToken, TokenBalance)]) {
+        if let Some(accounts) = &self.accounts {
+            let mut accounts = accounts.lock();
+            for (key, balance) in balances {
+                accounts.put(*key, balance.clone());
+            }
+        }
+    }
+
+    /// Called after balances are restored by a reorg
+    pub fn clear(&self) {
+        if let Some(accounts) = &self.accounts {
+            accounts.lock().clear();
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.accounts.as_ref().map(|x| x.lock().len()).unwrap_or_default()
+    }
+
+    /// `None` if the cache is disabled
+    pub fn stats(&self) -> Option<rest::types::TokenCacheStats> {
+        let accounts = self.accounts.as_ref()?.lock();
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+
+        Some(rest::types::TokenCacheStats {
+            accounts: accounts.len(),
+            capacity: accounts.cap().get(),
+            memory_bytes: accounts.len() * ENTRY_SIZE,
+            hits,
+            misses,
+            hit_rate: if hits + misses == 0 { 0.0 } else { hits as f64 / (hits + misses) as f64 },
+        })
+    }
+}
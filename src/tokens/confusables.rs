@@ -0,0 +1,59 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// Latin lookalikes of lowercase characters which NFKC keeps distinct
+const CONFUSABLES: &[(char, char)] = &[
+    // Cyrillic
+    ('а', 'a'),
+    ('в', 'b'),
+    ('е', 'e'),
+    ('ё', 'e'),
+    ('һ', 'h'),
+    ('н', 'h'),
+    ('і', 'i'),
+    ('ј', 'j'),
+    ('к', 'k'),
+    ('ӏ', 'l'),
+    ('м', 'm'),
+    ('о', 'o'),
+    ('р', 'p'),
+    ('ԛ', 'q'),
+    ('ѕ', 's'),
+    ('с', 'c'),
+    ('т', 't'),
+    ('у', 'y'),
+    ('ԝ', 'w'),
+    ('х', 'x'),
+    ('ԁ', 'd'),
+    // Greek
+    ('α', 'a'),
+    ('β', 'b'),
+    ('ε', 'e'),
+    ('ζ', 'z'),
+    ('η', 'h'),
+    ('ι', 'i'),
+    ('κ', 'k'),
+    ('μ', 'm'),
+    ('ν', 'v'),
+    ('ο', 'o'),
+    ('ρ', 'p'),
+    ('τ', 't'),
+    ('υ', 'u'),
+    ('χ', 'x'),
+    // Latin
+    ('ı', 'i'),
+    ('ɡ', 'g'),
+    ('ɑ', 'a'),
+    // Digits
+    ('0', 'o'),
+    ('1', 'l'),
+];
+
+/// Form of the tick which is equal for visually identical ticks:
+/// NFKC normalized, lowercased and with confusable characters replaced by their latin lookalikes
+pub fn tick_skeleton(tick: &[u8]) -> String {
+    String::from_utf8_lossy(tick)
+        .nfkc()
+        .flat_map(char::to_lowercase)
+        .map(|c| CONFUSABLES.iter().find(|(from, _)| *from == c).map(|(_, to)| *to).unwrap_or(c))
+        .collect()
+}
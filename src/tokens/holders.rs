@@ -1,11 +1,17 @@
+use dashmap::DashMap;
+
 use super::*;
 
 #[derive(Eq, PartialEq, Clone, Ord, PartialOrd, Serialize, Deserialize, Debug)]
 pub struct SortedByBalance(pub Fixed128, pub FullHash);
 
+/// Holders sharded by tick. <br/>
+/// Each tick keeps its set behind an `Arc`, so REST readers take a cheap snapshot
+/// and writers only copy the set of a single tick if a snapshot of it is still alive.
+#[derive(Default)]
 pub struct Holders {
-    balances: parking_lot::RwLock<HashMap<OriginalTokenTick, BTreeSet<SortedByBalance>>>,
-    stats: parking_lot::RwLock<HashMap<OriginalTokenTick, usize>>,
+    balances: DashMap<OriginalTokenTick, Arc<BTreeSet<SortedByBalance>>>,
+    stats: DashMap<OriginalTokenTick, usize>,
 }
 
 enum Action {
@@ -15,7 +21,7 @@ enum Action {
 
 impl Holders {
     pub fn init(db: &DB) -> Self {
-        let holders = HashMap::<OriginalTokenTick, _>::from_iter(
+        let holders = DashMap::<OriginalTokenTick, _>::from_iter(
             db.address_token_to_balance
                 .iter()
                 .filter(|(_, v)| !v.balance.is_zero() || !v.transferable_balance.is_zero())
@@ -23,19 +29,17 @@ impl Holders {
                 .sorted_unstable_by_key(|(tick, _)| *tick)
                 .chunk_by(|(tick, _)| *tick)
                 .into_iter()
-                .map(|(k, v)| (k, v.map(|(_, v)| v).collect::<BTreeSet<_>>())),
+                .map(|(k, v)| (k, Arc::new(v.map(|(_, v)| v).collect::<BTreeSet<_>>()))),
         );
 
-        let stats = holders.iter().map(|(tick, holders)| (*tick, holders.len())).collect();
+        let stats = holders.iter().map(|x| (*x.key(), x.value().len())).collect();
 
-        Self {
-            balances: parking_lot::RwLock::new(holders),
-            stats: parking_lot::RwLock::new(stats),
-        }
+        Self { balances: holders, stats }
     }
 
-    pub fn get_holders(&self, tick: &OriginalTokenTick) -> Option<BTreeSet<SortedByBalance>> {
-        self.balances.read().get(tick).cloned()
+    /// Snapshot of the holders of the tick, it isn't affected by further changes
+    pub fn get_holders(&self, tick: &OriginalTokenTick) -> Option<Arc<BTreeSet<SortedByBalance>>> {
+        self.balances.get(tick).map(|x| x.value().clone())
     }
 
     /// hack because i cant throw -amt cause of type
@@ -48,27 +52,32 @@ impl Holders {
         self.change(key, prev_balance, amt, Action::Increase)
     }
 
+    /// Ticks which had holders since the start
+    pub fn ticks(&self) -> Vec<OriginalTokenTick> {
+        self.balances.iter().map(|x| *x.key()).collect()
+    }
+
     pub fn holders_by_tick(&self, tick: &OriginalTokenTick) -> Option<usize> {
-        self.stats.read().get(tick).cloned()
+        self.stats.get(tick).map(|x| *x)
     }
 
     pub fn stats(&self) -> HashMap<OriginalTokenTick, usize> {
-        self.stats.read().clone()
+        self.stats.iter().map(|x| (*x.key(), *x.value())).collect()
     }
 
     fn change(&self, key: &AddressToken, acc: &TokenBalance, amt: Fixed128, action: Action) {
         // used to prevent footgun with balance (not to forget to add transferable)
         let old_balance = acc.balance + acc.transferable_balance;
-        let mut balances = self.balances.write();
+        let mut balances = self.balances.entry(key.token).or_default();
 
-        let v = balances.entry(key.token).or_default();
+        let v = Arc::make_mut(&mut balances);
 
         let existed = v.remove(&SortedByBalance(old_balance, key.address));
 
         match action {
             Action::Increase => {
                 if !existed {
-                    self.stats.write().entry(key.token).and_modify(|x| *x += 1).or_insert(1);
+                    self.stats.entry(key.token).and_modify(|x| *x += 1).or_insert(1);
                 }
 
                 v.insert(SortedByBalance(old_balance + amt, key.address));
@@ -78,7 +87,7 @@ impl Holders {
                 if !bal.is_zero() {
                     v.insert(SortedByBalance(bal, key.address));
                 } else {
-                    self.stats.write().entry(key.token).and_modify(|x| *x -= 1);
+                    self.stats.entry(key.token).and_modify(|x| *x -= 1);
                 }
             }
         }
@@ -1,11 +1,17 @@
 use super::*;
 
+mod account_cache;
+mod confusables;
 mod holders;
 mod parser;
+mod policy;
 mod proto;
 mod structs;
 
-pub use holders::Holders;
+pub use account_cache::TokenAccountCache;
+pub use confusables::tick_skeleton;
+pub use holders::{Holders, SortedByBalance};
 pub use parser::{HistoryTokenAction, TokenCache};
+pub use policy::{RejectedDeployReason, TickPolicy};
 pub use proto::*;
 pub use structs::*;
@@ -13,6 +13,7 @@ pub enum HistoryTokenAction {
         recipient: FullHash,
         txid: Txid,
         vout: u32,
+        origin: ActionOrigin,
     },
     Mint {
         tick: OriginalTokenTick,
@@ -20,6 +21,7 @@ pub enum HistoryTokenAction {
         recipient: FullHash,
         txid: Txid,
         vout: u32,
+        origin: ActionOrigin,
     },
     DeployTransfer {
         tick: OriginalTokenTick,
@@ -27,6 +29,7 @@ pub enum HistoryTokenAction {
         recipient: FullHash,
         txid: Txid,
         vout: u32,
+        origin: ActionOrigin,
     },
     Send {
         tick: OriginalTokenTick,
@@ -35,6 +38,7 @@ pub enum HistoryTokenAction {
         sender: FullHash,
         txid: Txid,
         vout: u32,
+        origin: ActionOrigin,
     },
 }
 
@@ -48,6 +52,15 @@ impl HistoryTokenAction {
         }
     }
 
+    pub fn origin(&self) -> ActionOrigin {
+        match self {
+            HistoryTokenAction::Deploy { origin, .. }
+            | HistoryTokenAction::Mint { origin, .. }
+            | HistoryTokenAction::DeployTransfer { origin, .. }
+            | HistoryTokenAction::Send { origin, .. } => *origin,
+        }
+    }
+
     pub fn recipient(&self) -> FullHash {
         match self {
             HistoryTokenAction::Mint { recipient, .. } => *recipient,
@@ -57,6 +70,64 @@ impl HistoryTokenAction {
         }
     }
 
+    /// Fees of the mint transactions by token, a transaction minting the token in several inputs is counted once
+    pub fn mint_fees(actions: &[Self], tx_fees: &HashMap<Txid, u64>) -> Vec<(LowerCaseTokenTick, u64)> {
+        let mut fees = HashMap::<LowerCaseTokenTick, u64>::new();
+        let mut seen = HashSet::new();
+
+        for action in actions {
+            if let HistoryTokenAction::Mint { tick, txid, .. } = action {
+                if seen.insert((*txid, *tick)) {
+                    *fees.entry(tick.into()).or_default() += tx_fees.get(txid).copied().unwrap_or_default();
+                }
+            }
+        }
+
+        fees.into_iter().collect()
+    }
+
+    /// Valid mints by token with the mints of every recipient, recipients are sorted by address
+    pub fn mint_activity(actions: &[Self]) -> Vec<(OriginalTokenTick, MintActivityDB)> {
+        let mut activity = HashMap::<OriginalTokenTick, (MintActivityDB, BTreeMap<FullHash, (u32, Fixed128)>)>::new();
+
+        for action in actions {
+            if let HistoryTokenAction::Mint { tick, amt, recipient, .. } = action {
+                let (total, minters) = activity.entry(*tick).or_default();
+                total.mints += 1;
+                total.amount += *amt;
+
+                let minter = minters.entry(*recipient).or_default();
+                minter.0 += 1;
+                minter.1 += *amt;
+            }
+        }
+
+        activity
+            .into_iter()
+            .map(|(tick, (mut total, minters))| {
+                total.minters = minters.into_iter().map(|(address, (mints, amount))| (address, mints, amount)).collect();
+                (tick, total)
+            })
+            .collect()
+    }
+
+    /// First valid mint of every token minted in the block
+    pub fn first_mints(actions: &[Self], height: u32) -> Vec<(LowerCaseTokenTick, FirstMintDB)> {
+        let mut first_mints = HashMap::<LowerCaseTokenTick, FirstMintDB>::new();
+
+        for action in actions {
+            if let HistoryTokenAction::Mint { tick, recipient, txid, .. } = action {
+                first_mints.entry(tick.into()).or_insert(FirstMintDB {
+                    height,
+                    txid: *txid,
+                    minter: *recipient,
+                });
+            }
+        }
+
+        first_mints.into_iter().collect()
+    }
+
     pub fn sender(&self) -> Option<FullHash> {
         match self {
             HistoryTokenAction::Send { sender, .. } => Some(*sender),
@@ -81,6 +152,15 @@ pub struct TokenCache {
 
     /// All transfer actions that are valid. Used to write to the db.
     pub valid_transfers: BTreeMap<Location, (FullHash, TransferProtoDB)>,
+
+    /// Tokens fully minted by the processed actions
+    pub completed: Vec<OriginalTokenTick>,
+
+    /// Fees of the transactions which created inscriptions in the block. Used to sum up mint fees of tokens.
+    pub tx_fees: HashMap<Txid, u64>,
+
+    /// Deploys blocked by the tick policy. Written to the db for audit.
+    pub rejected_deploys: Vec<RejectedDeployDB>,
 }
 
 impl TokenCache {
@@ -102,7 +182,8 @@ impl TokenCache {
         token_cache
     }
 
-    fn try_parse(content_type: &str, content: &[u8]) -> Result<Brc4, Brc4ParseErr> {
+    /// Parses the inscription body the same way for indexing and for `/validate/inscription`
+    pub fn try_parse(content_type: &str, content: &[u8]) -> Result<Brc4, Brc4ParseErr> {
         // Dogecoin wonky bugfix
         if *BLOCKCHAIN == Blockchain::Dogecoin {
             if !content_type.starts_with("text/plain") && !content_type.starts_with("application/json") {
@@ -118,7 +199,15 @@ impl TokenCache {
             return Err(Brc4ParseErr::InvalidUtf8);
         };
 
-        let data = serde_json::from_str::<serde_json::Value>(&data).map_err(|_| Brc4ParseErr::WrongProtocol)?;
+        let mut data = serde_json::from_str::<serde_json::Value>(&data).map_err(|_| Brc4ParseErr::WrongProtocol)?;
+
+        // Tokens of a registered coin with its own protocol name follow the rules of BLOCKCHAIN
+        if let Some(protocol) = COIN.protocol {
+            match data.as_object_mut().and_then(|x| x.get_mut("p")) {
+                Some(p) if p.as_str() == Some(protocol) => *p = BLOCKCHAIN.protocol().into(),
+                _ => return Err(Brc4ParseErr::WrongProtocol),
+            }
+        }
 
         let brc4 = serde_json::from_str::<Brc4>(&serde_json::to_string(&data).map_err(|_| Brc4ParseErr::WrongProtocol)?).map_err(|error| match error.to_string().as_str() {
             "Invalid decimal: empty" => Brc4ParseErr::DecimalEmpty,
@@ -176,6 +265,17 @@ impl TokenCache {
             Brc4::Deploy { proto } => {
                 let v = proto.value().ok()?;
 
+                if let Some(reason) = TICK_POLICY.check(v.tick) {
+                    self.rejected_deploys.push(RejectedDeployDB {
+                        tick: v.tick,
+                        genesis: inc.genesis,
+                        deployer: inc.owner,
+                        created,
+                        reason,
+                    });
+                    return None;
+                }
+
                 self.token_actions.push(TokenAction::Deploy {
                     genesis: inc.genesis,
                     proto: DeployProtoDB {
@@ -192,6 +292,7 @@ impl TokenCache {
                         transactions: 1,
                     },
                     owner: inc.owner,
+                    origin: inc.origin,
                 })
             }
             Brc4::Mint { proto } => {
@@ -200,6 +301,7 @@ impl TokenCache {
                     proto: proto.value().ok()?,
                     txid: inc.location.outpoint.txid,
                     vout: inc.location.outpoint.vout,
+                    origin: inc.origin,
                 });
             }
             Brc4::Transfer { proto } => {
@@ -209,6 +311,7 @@ impl TokenCache {
                     proto: proto.value().ok()?,
                     txid: inc.location.outpoint.txid,
                     vout: inc.location.outpoint.vout,
+                    origin: inc.origin,
                 });
                 self.all_transfers.insert(inc.location, TransferProtoDB::from_proto(proto.clone(), height).ok()?);
                 return Some(proto);
@@ -224,6 +327,7 @@ impl TokenCache {
             recipient,
             txid,
             vout,
+            origin: ActionOrigin::Normal,
         });
     }
 
@@ -233,10 +337,22 @@ impl TokenCache {
             recipient: *OP_RETURN_HASH,
             txid,
             vout,
+            origin: ActionOrigin::Normal,
+        });
+    }
+
+    /// Transfer moved into the fees, its amount returns to the sender
+    pub fn leaked_transfer(&mut self, location: Location, sender: FullHash, txid: Txid) {
+        self.token_actions.push(TokenAction::Transferred {
+            transfer_location: location,
+            recipient: sender,
+            txid,
+            vout: 0,
+            origin: ActionOrigin::LeakedReturn,
         });
     }
 
-    pub fn load_tokens_data(&mut self, db: &DB) -> anyhow::Result<()> {
+    pub fn load_tokens_data(&mut self, db: &DB, accounts: &TokenAccountCache) -> anyhow::Result<()> {
         let (tickers, users) = self.fill_tickers_and_users();
 
         self.tokens = db
@@ -256,12 +372,17 @@ impl TokenCache {
             })
             .collect();
 
-        self.token_accounts = db.load_token_accounts(keys);
+        self.token_accounts = accounts.load(db, keys);
 
         Ok(())
     }
 
-    fn fill_tickers_and_users(&mut self) -> (Tickers, Users) {
+    /// Ticks of the parsed actions, the token state of other ticks isn't read
+    pub fn ticks(&self) -> HashSet<LowerCaseTokenTick> {
+        self.fill_tickers_and_users().0
+    }
+
+    fn fill_tickers_and_users(&self) -> (Tickers, Users) {
         let mut tickers: Tickers = HashSet::new();
         let mut users: Users = HashSet::new();
 
@@ -318,7 +439,7 @@ impl TokenCache {
 
         for action in self.token_actions.drain(..) {
             match action {
-                TokenAction::Deploy { genesis, proto, owner } => {
+                TokenAction::Deploy { genesis, proto, owner, origin } => {
                     let DeployProtoDB { tick, max, lim, dec, .. } = proto.clone();
                     if let std::collections::hash_map::Entry::Vacant(e) = self.tokens.entry(tick.into()) {
                         e.insert(TokenMeta { genesis, proto });
@@ -331,10 +452,11 @@ impl TokenCache {
                             recipient: owner,
                             txid: genesis.txid,
                             vout: genesis.index,
+                            origin,
                         });
                     }
                 }
-                TokenAction::Mint { owner, proto, txid, vout } => {
+                TokenAction::Mint { owner, proto, txid, vout, origin } => {
                     let MintProtoWrapper { tick, amt } = proto;
                     let Some(token) = self.tokens.get_mut(&tick.into()) else {
                         continue;
@@ -365,6 +487,10 @@ impl TokenCache {
                     *supply += amt;
                     *transactions += 1;
 
+                    if *supply == *max {
+                        self.completed.push(*tick);
+                    }
+
                     let key = AddressToken { address: owner, token: *tick };
 
                     holders.increase(&key, self.token_accounts.get(&key).unwrap_or(&TokenBalance::default()), amt);
@@ -377,6 +503,7 @@ impl TokenCache {
                         recipient: key.address,
                         txid,
                         vout,
+                        origin,
                     });
                 }
                 TokenAction::Transfer {
@@ -385,6 +512,7 @@ impl TokenCache {
                     proto,
                     txid,
                     vout,
+                    origin,
                 } => {
                     let Some(mut data) = self.all_transfers.remove(&location) else {
                         // skip cause is it transfer already spent
@@ -430,6 +558,7 @@ impl TokenCache {
                         recipient: key.address,
                         txid,
                         vout,
+                        origin,
                     });
 
                     self.valid_transfers.insert(location, (key.address, data));
@@ -441,6 +570,7 @@ impl TokenCache {
                     recipient,
                     txid,
                     vout,
+                    origin,
                 } => {
                     let Some((sender, TransferProtoDB { tick, amt, .. })) = self.valid_transfers.remove(&transfer_location) else {
                         // skip cause transfer has been already spent
@@ -478,6 +608,7 @@ impl TokenCache {
                         sender,
                         txid,
                         vout,
+                        origin,
                     });
                 }
             }
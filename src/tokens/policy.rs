@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use super::*;
+
+/// Contents of `TICK_POLICY_FILE`, e.g. `{"deny": ["abcd"], "allow": []}`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TickPolicyFile {
+    #[serde(default)]
+    deny: Vec<String>,
+    #[serde(default)]
+    allow: Vec<String>,
+}
+
+/// Why a deploy was blocked by the tick policy, new variants go to the end
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectedDeployReason {
+    /// The tick or a lookalike of it is in the deny list
+    Denied,
+    /// The allow list isn't empty and the tick isn't in it
+    NotAllowed,
+}
+
+/// Ticks which can't be deployed, checked when deploys are parsed. <br/>
+/// Denied ticks also block their lookalikes (see [`tick_skeleton`]) and take precedence over the allow list,
+/// a non-empty allow list blocks every tick which isn't in it.
+#[derive(Default)]
+pub struct TickPolicy {
+    /// Skeletons of the denied ticks
+    deny: HashSet<String>,
+    allow: HashSet<LowerCaseTokenTick>,
+}
+
+impl TickPolicy {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file: TickPolicyFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+        let parse = |tick: &String| OriginalTokenTick::from_str(tick).map_err(|_| anyhow::anyhow!("{tick:?} isn't a 4 byte tick"));
+
+        Ok(Self {
+            deny: file.deny.iter().map(|x| parse(x).map(|tick| tick_skeleton(&tick.0))).try_collect()?,
+            allow: file.allow.iter().map(|x| parse(x).map(LowerCaseTokenTick::from)).try_collect()?,
+        })
+    }
+
+    /// `None` if the tick can be deployed
+    pub fn check(&self, tick: OriginalTokenTick) -> Option<RejectedDeployReason> {
+        if self.deny.contains(&tick_skeleton(&tick.0)) {
+            return Some(RejectedDeployReason::Denied);
+        }
+
+        if !self.allow.is_empty() && !self.allow.contains(&LowerCaseTokenTick::from(tick)) {
+            return Some(RejectedDeployReason::NotAllowed);
+        }
+
+        None
+    }
+}
@@ -192,6 +192,25 @@ pub enum Brc4Value {
     Deploy { tick: OriginalTokenTick, max: Fixed128, lim: Fixed128, dec: u8 },
 }
 
+impl Brc4Value {
+    pub fn tick(&self) -> OriginalTokenTick {
+        match self {
+            Self::Mint { tick, .. } | Self::Transfer { tick, .. } | Self::Deploy { tick, .. } => *tick,
+        }
+    }
+}
+
+impl Brc4 {
+    /// Value of the `op` field and the parsed operation
+    pub fn op_value(&self) -> (&'static str, anyhow::Result<Brc4Value>) {
+        match self {
+            Self::Deploy { proto } => ("deploy", Brc4Value::try_from(proto)),
+            Self::Mint { proto } => ("mint", Brc4Value::try_from(proto)),
+            Self::Transfer { proto } => ("transfer", Brc4Value::try_from(proto)),
+        }
+    }
+}
+
 impl TryFrom<&DeployProto> for Brc4Value {
     type Error = anyhow::Error;
 
@@ -254,9 +254,20 @@ impl FromStr for InscriptionId {
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum TokenAction {
     /// Deploy new token action.
-    Deploy { genesis: InscriptionId, proto: DeployProtoDB, owner: FullHash },
+    Deploy {
+        genesis: InscriptionId,
+        proto: DeployProtoDB,
+        owner: FullHash,
+        origin: ActionOrigin,
+    },
     /// Mint new token action.
-    Mint { owner: FullHash, proto: MintProtoWrapper, txid: Txid, vout: u32 },
+    Mint {
+        owner: FullHash,
+        proto: MintProtoWrapper,
+        txid: Txid,
+        vout: u32,
+        origin: ActionOrigin,
+    },
     /// Transfer token action.
     Transfer {
         location: Location,
@@ -264,6 +275,7 @@ pub enum TokenAction {
         proto: MintProtoWrapper,
         txid: Txid,
         vout: u32,
+        origin: ActionOrigin,
     },
     /// Founded move of transfer action.
     Transferred {
@@ -274,6 +286,7 @@ pub enum TokenAction {
         recipient: FullHash,
         txid: Txid,
         vout: u32,
+        origin: ActionOrigin,
     },
 }
 
@@ -282,6 +295,9 @@ pub enum TokenAction {
 pub struct TokenTransfer {
     pub outpoint: crate::rest::OutPoint,
     pub amount: Fixed128,
+    /// Outpoint is already spent by an unconfirmed transaction (only if mempool checks are enabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spent_in_mempool: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -299,6 +315,10 @@ pub struct InscriptionTemplate {
     pub value: u64,
     pub content: Option<Vec<u8>>,
     pub leaked: bool,
+    /// Curses known before the location is checked for a reinscription
+    pub curses: Vec<InscriptionCurse>,
+    /// Origin of the token actions of the inscription
+    pub origin: ActionOrigin,
 }
 
 pub(crate) struct DeserializeFromStr<T: FromStr>(pub(crate) T);
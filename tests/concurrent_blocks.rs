@@ -0,0 +1,155 @@
+//! Indexes the same synthetic chain served by `mock-node` with and without `CONCURRENT_BLOCKS` and compares the proofs of history and
+//! the balances. The statics of the indexer are initialized by the first access, so every run is a child process of the test.
+
+use std::{borrow::Cow, path::Path, process::Command, sync::Arc};
+
+use bel20_core::{db::DB, Indexer, Server};
+use mock_node::{Chain, Tx};
+
+/// Set for the child processes, `DB_PATH` to index the chain into
+const CHILD_ENV: &str = "CONCURRENT_BLOCKS_TEST_CHILD";
+const TEST_NAME: &str = "concurrent_blocks_match_sequential";
+const WINDOW: &str = "4";
+/// Added to the tip reported with the blocks, so all of them are deeper than the reorg window
+const REORG_DEPTH: u64 = 1_000;
+
+/// Two tokens minted in the same and in separate blocks, and transfers whose sends spend outputs of the previous block
+fn chain() -> Chain {
+    let mut chain = Chain::new();
+    chain.push_block(vec![Tx::Inscribe {
+        body: mock_node::deploy("aaaa", 21_000, 1_000),
+        owner: 1,
+    }]);
+    chain.push_block(vec![Tx::Inscribe {
+        body: mock_node::deploy("bbbb", 21_000, 1_000),
+        owner: 2,
+    }]);
+    chain.push_block(vec![
+        Tx::Inscribe {
+            body: mock_node::mint("aaaa", 1_000),
+            owner: 1,
+        },
+        Tx::Inscribe {
+            body: mock_node::mint("aaaa", 500),
+            owner: 2,
+        },
+    ]);
+    chain.push_block(vec![Tx::Inscribe {
+        body: mock_node::mint("bbbb", 1_000),
+        owner: 2,
+    }]);
+    chain.push_block(vec![
+        Tx::Inscribe {
+            body: mock_node::mint("bbbb", 300),
+            owner: 3,
+        },
+        Tx::Inscribe {
+            body: mock_node::mint("aaaa", 200),
+            owner: 3,
+        },
+    ]);
+    let transfer = chain.push_block(vec![Tx::Inscribe {
+        body: mock_node::transfer("aaaa", 100),
+        owner: 1,
+    }]);
+    chain.push_block(vec![Tx::Send { inscription: transfer[0], to: 3 }]);
+    let transfer = chain.push_block(vec![Tx::Inscribe {
+        body: mock_node::transfer("bbbb", 50),
+        owner: 2,
+    }]);
+    chain.push_block(vec![Tx::Send { inscription: transfer[0], to: 1 }]);
+    chain.push_block(vec![Tx::Inscribe {
+        body: mock_node::mint("aaaa", 100),
+        owner: 4,
+    }]);
+    chain
+}
+
+/// Child process: indexes the chain into `db_path` with `CONCURRENT_BLOCKS` of the environment
+fn index(db_path: &str) {
+    let chain = chain();
+    let tip = chain.height();
+    let rpc_url = chain.serve().unwrap();
+
+    for (key, value) in [
+        ("BLOCKCHAIN", "bells"),
+        ("NETWORK", "testnet"),
+        ("RPC_URL", rpc_url.as_str()),
+        ("RPC_USER", "user"),
+        ("RPC_PASS", "pass"),
+        ("DB_PATH", db_path),
+    ] {
+        std::env::set_var(key, value);
+    }
+
+    let (_events, server) = Server::new(db_path).unwrap();
+    let server = Arc::new(server);
+
+    // Queued before indexing, so the windows of `CONCURRENT_BLOCKS` are filled
+    let (tx, rx) = nint_blk::block_channel();
+    for height in 1..=tip {
+        let hash = server.client.get_block_hash(height).unwrap();
+        let block = server.client.get_block(&hash).unwrap();
+        let id = nint_blk::BlockId { height, hash };
+        tx.send(nint_blk::BlockEvent {
+            id,
+            block,
+            reorg_len: 0,
+            tip: tip + REORG_DEPTH,
+        })
+        .unwrap();
+    }
+    drop(tx);
+
+    // Returns once the channel is closed after the last block
+    Indexer::new(server.clone()).run_from(rx).unwrap();
+    server.db.flush_all();
+}
+
+fn run_child(db_path: &Path, concurrent_blocks: Option<&str>) {
+    std::fs::remove_dir_all(db_path).ok();
+
+    let mut command = Command::new(std::env::current_exe().unwrap());
+    command.args(["--exact", TEST_NAME, "--nocapture"]).env(CHILD_ENV, db_path);
+    match concurrent_blocks {
+        Some(window) => command.env("CONCURRENT_BLOCKS", window),
+        None => command.env_remove("CONCURRENT_BLOCKS"),
+    };
+
+    let status = command.status().unwrap();
+    assert!(status.success(), "Indexing with CONCURRENT_BLOCKS={concurrent_blocks:?} failed: {status}");
+}
+
+fn rows(db: &DB, table: &str) -> Vec<(Cow<'static, [u8]>, Cow<'static, [u8]>)> {
+    db.rocks().table::<Cow<'static, [u8]>, Cow<'static, [u8]>>(table).iter().collect()
+}
+
+#[test]
+fn concurrent_blocks_match_sequential() {
+    if let Ok(db_path) = std::env::var(CHILD_ENV) {
+        index(&db_path);
+        return;
+    }
+
+    let dir = std::env::temp_dir().join(format!("bel20-concurrent-blocks-test-{}", std::process::id()));
+    let sequential = dir.join("sequential");
+    let concurrent = dir.join("concurrent");
+    run_child(&sequential, None);
+    run_child(&concurrent, Some(WINDOW));
+
+    let sequential = DB::open(&sequential.to_string_lossy());
+    let concurrent = DB::open(&concurrent.to_string_lossy());
+
+    let tip = chain().height() as u32;
+    assert_eq!(sequential.last_block.get(()), Some(tip));
+    assert_eq!(concurrent.last_block.get(()), Some(tip));
+
+    for table in ["PROOF_OF_HISTORY", "ADDRESS_TOKEN_TO_BALANCE"] {
+        let expected = rows(&sequential, table);
+        assert!(!expected.is_empty(), "{table} is empty");
+        assert!(rows(&concurrent, table) == expected, "{table} differs with CONCURRENT_BLOCKS={WINDOW}");
+    }
+
+    drop((sequential, concurrent));
+    std::fs::remove_dir_all(dir).ok();
+}